@@ -1,7 +1,7 @@
-use coinbase_rs::{Public, MAIN_URL};
+use coinbase_rs::{Client, MAIN_URL};
 
 #[tokio::main]
 async fn main() {
-    let client = Public::new(MAIN_URL);
+    let client = Client::new(MAIN_URL);
     println!("Server time is {:?}", client.current_time().await.unwrap());
 }
@@ -1,10 +1,10 @@
-use coinbase_rs::{Public, MAIN_URL};
+use coinbase_rs::{Client, MAIN_URL};
 use futures::pin_mut;
 use futures::stream::StreamExt;
 
 #[tokio::main]
 async fn main() {
-    let client = Public::new(MAIN_URL);
+    let client = Client::new(MAIN_URL);
 
     let currencies = client.currencies();
     pin_mut!(currencies);
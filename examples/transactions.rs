@@ -1,4 +1,4 @@
-use coinbase_rs::{Private, Uuid, MAIN_URL};
+use coinbase_rs::{Client, Uuid, MAIN_URL};
 use futures::pin_mut;
 use futures::stream::StreamExt;
 use std::str::FromStr;
@@ -8,7 +8,7 @@ pub const SECRET: &str = "<put secret here>";
 
 #[tokio::main]
 async fn main() {
-    let client = Private::new(MAIN_URL, KEY, SECRET);
+    let client = Client::new_with_auth(MAIN_URL, KEY, SECRET);
 
     let accounts = client.accounts();
     pin_mut!(accounts);
@@ -0,0 +1,254 @@
+//! Alerts on price thresholds and moves, fed by polling spot prices.
+//!
+//! ```no_run
+//! # async fn run() {
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::alerts::{AlertRule, PriceWatcher};
+//! use coinbase_rs::{Client, MAIN_URL};
+//! use futures::stream::StreamExt;
+//!
+//! let client = Client::new(MAIN_URL);
+//! let watcher = PriceWatcher::new(Duration::from_secs(30))
+//!     .rule("BTC-USD", AlertRule::Above("100000".parse().unwrap()))
+//!     .rule("ETH-USD", AlertRule::PercentMove("5".parse().unwrap()));
+//! let alerts = watcher.watch(&client);
+//! futures::pin_mut!(alerts);
+//! while let Some(alert) = alerts.next().await {
+//!     match alert {
+//!         Ok(alert) => println!("{} at {}", alert.product, alert.price),
+//!         Err(error) => eprintln!("price unavailable: {error}"),
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use bigdecimal::{BigDecimal, Zero};
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{Client, Result};
+
+/// Condition on the price of a product.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlertRule {
+    /// The price reaches or exceeds a threshold.
+    Above(BigDecimal),
+    /// The price reaches or falls below a threshold.
+    Below(BigDecimal),
+    /// The price moves by at least this many percent, up or down, from the price it was last
+    /// compared to: the first price seen, then the price of the last alert.
+    PercentMove(BigDecimal),
+}
+
+/// A rule that triggered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub product: String,
+    pub rule: AlertRule,
+    pub price: BigDecimal,
+}
+
+/// Polls the spot prices of the products with rules and yields an [`Alert`] whenever a rule
+/// triggers.
+///
+/// Threshold rules only trigger again once the price has moved back past the threshold by the
+/// hysteresis, so a price hovering around a threshold doesn't flap.
+#[derive(Clone, Debug)]
+pub struct PriceWatcher {
+    interval: Duration,
+    hysteresis: BigDecimal,
+    rules: Vec<(String, AlertRule)>,
+}
+
+impl PriceWatcher {
+    /// Creates a watcher polling every `interval`, with a hysteresis of 0.5%.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            hysteresis: BigDecimal::new(5.into(), 1),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds `rule` for the currency pair `product`, e.g. `BTC-USD`.
+    pub fn rule(self, product: &str, rule: AlertRule) -> Self {
+        let mut _self = self;
+        _self.rules.push((product.to_string(), rule));
+        _self
+    }
+
+    /// Percent of a threshold the price must move back by before its rule is re-armed.
+    pub fn hysteresis(self, percent: BigDecimal) -> Self {
+        let mut _self = self;
+        _self.hysteresis = percent;
+        _self
+    }
+
+    /// Yields alerts until dropped or the [`Shutdown`](crate::Shutdown) of `client` is
+    /// triggered. Failures to get a price are yielded as errors without
+    /// stopping the watcher, which tries again at the next poll.
+    pub fn watch<'a>(&self, client: &'a Client) -> impl Stream<Item = Result<Alert>> + 'a {
+        let mut rules: Vec<_> = self
+            .rules
+            .iter()
+            .map(|(product, rule)| RuleState::new(product, rule.clone(), &self.hysteresis))
+            .collect();
+        let mut products: Vec<String> = rules.iter().map(|r| r.product.clone()).collect();
+        products.sort();
+        products.dedup();
+        let prices = products.into_iter().map(|product| {
+            client
+                .spot_price_stream(&product, self.interval)
+                .map(move |price| (product.clone(), price))
+                .boxed()
+        });
+        stream::select_all(prices).flat_map(move |(product, price)| {
+            let alerts: Vec<Result<Alert>> = match price {
+                Ok(price) => rules
+                    .iter_mut()
+                    .filter(|rule| rule.product == product)
+                    .filter_map(|rule| rule.check(&price.amount))
+                    .map(Ok)
+                    .collect(),
+                Err(error) => vec![Err(error)],
+            };
+            stream::iter(alerts)
+        })
+    }
+}
+
+/// A rule with what it needs to remember between prices.
+struct RuleState {
+    product: String,
+    rule: AlertRule,
+    /// Price a threshold rule re-arms at.
+    rearm: Option<BigDecimal>,
+    armed: bool,
+    /// Price a move is measured from.
+    reference: Option<BigDecimal>,
+}
+
+impl RuleState {
+    fn new(product: &str, rule: AlertRule, hysteresis: &BigDecimal) -> Self {
+        let band = |threshold: &BigDecimal| threshold * hysteresis / BigDecimal::from(100);
+        let rearm = match &rule {
+            AlertRule::Above(threshold) => Some(threshold - band(threshold)),
+            AlertRule::Below(threshold) => Some(threshold + band(threshold)),
+            AlertRule::PercentMove(_) => None,
+        };
+        Self {
+            product: product.to_string(),
+            rule,
+            rearm,
+            armed: true,
+            reference: None,
+        }
+    }
+
+    fn check(&mut self, price: &BigDecimal) -> Option<Alert> {
+        let triggered = match (&self.rule, &self.rearm) {
+            (AlertRule::Above(threshold), Some(rearm)) => {
+                if price < rearm {
+                    self.armed = true;
+                }
+                self.fire(price >= threshold)
+            }
+            (AlertRule::Below(threshold), Some(rearm)) => {
+                if price > rearm {
+                    self.armed = true;
+                }
+                self.fire(price <= threshold)
+            }
+            (AlertRule::PercentMove(percent), _) => match &self.reference {
+                Some(reference) if !reference.is_zero() => {
+                    let moved = (price - reference).abs() * BigDecimal::from(100) / reference;
+                    let triggered = &moved >= percent;
+                    if triggered {
+                        self.reference = Some(price.clone());
+                    }
+                    triggered
+                }
+                _ => {
+                    self.reference = Some(price.clone());
+                    false
+                }
+            },
+            _ => false,
+        };
+        triggered.then(|| Alert {
+            product: self.product.clone(),
+            rule: self.rule.clone(),
+            price: price.clone(),
+        })
+    }
+
+    /// Triggers if the threshold is crossed while armed, disarming the rule.
+    fn fire(&mut self, crossed: bool) -> bool {
+        let triggered = crossed && self.armed;
+        if triggered {
+            self.armed = false;
+        }
+        triggered
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::StreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn decimal(value: &str) -> BigDecimal {
+        value.parse().unwrap()
+    }
+
+    fn alerts(rule: AlertRule, prices: &[&str]) -> Vec<usize> {
+        let mut state = RuleState::new("BTC-USD", rule, &decimal("1"));
+        prices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, price)| state.check(&decimal(price)).map(|_| i))
+            .collect()
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let prices = ["99", "100", "101", "99.5", "100", "98", "100.5"];
+        assert_eq!(alerts(AlertRule::Above(decimal("100")), &prices), [1, 6]);
+        let prices = ["101", "100", "100.5", "99", "101.5", "100"];
+        assert_eq!(alerts(AlertRule::Below(decimal("100")), &prices), [1, 5]);
+    }
+
+    #[test]
+    fn test_percent_move() {
+        let prices = ["100", "104", "95", "99", "104.5"];
+        assert_eq!(
+            alerts(AlertRule::PercentMove(decimal("5")), &prices),
+            [2, 4]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch() {
+        let mock = MockTransport::new();
+        for price in ["90", "110", "120"] {
+            mock.push_json(
+                "/v2/prices/BTC-USD/spot",
+                &format!(r#"{{"data":{{"amount":"{price}","currency":"USD"}}}}"#),
+            );
+        }
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_transport(mock);
+        let watcher =
+            PriceWatcher::new(Duration::ZERO).rule("BTC-USD", AlertRule::Above(decimal("100")));
+
+        let alerts: Vec<_> = watcher.watch(&client).take(2).collect().await;
+        assert_eq!(alerts[0].as_ref().unwrap().price, decimal("110"));
+        assert!(alerts[1].is_err());
+    }
+}
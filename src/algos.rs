@@ -0,0 +1,307 @@
+//! Execution algorithms slicing a large Exchange order into smaller child orders.
+//!
+//! A [`Slicer`] places the children one after another with
+//! [`OrderEvents::execute`](crate::order_events::OrderEvents::execute), canceling what a child
+//! hasn't filled when its time is up, and yields the aggregate [`Progress`] after each child.
+//! Children go through the client's rate limiter like any other request.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::algos::{ParentOrder, Slicer};
+//! use coinbase_rs::exchange::{ExchangeClient, OrderSide};
+//! use futures::stream::TryStreamExt;
+//!
+//! let exchange = ExchangeClient::new("<key>", "<secret>", "<passphrase>");
+//! let parent = ParentOrder::market(OrderSide::Buy, "BTC-USD", "2".parse().unwrap());
+//! let progress = Slicer::twap(12, Duration::from_secs(3600))
+//!     .increment("0.00000001".parse().unwrap())
+//!     .jitter(0.5)
+//!     .run(&exchange, &parent);
+//! futures::pin_mut!(progress);
+//! while let Some(progress) = progress.try_next().await? {
+//!     println!("filled {} of 2", progress.filled_size);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use async_stream::try_stream;
+use bigdecimal::{BigDecimal, Signed, Zero};
+use futures::stream::Stream;
+use rand::Rng;
+
+use crate::amount;
+use crate::exchange::{ExchangeClient, NewOrder};
+use crate::order::OrderSide;
+use crate::order_events::{ExecutionReport, OrderEvents};
+use crate::Result;
+
+/// The order to slice, placed as limit children if it has a price and market children if not.
+#[derive(Clone, Debug)]
+pub struct ParentOrder {
+    side: OrderSide,
+    product_id: String,
+    size: BigDecimal,
+    price: Option<BigDecimal>,
+}
+
+impl ParentOrder {
+    /// Buys or sells `size` of `product` at `price` or better.
+    pub fn limit(side: OrderSide, product: &str, price: BigDecimal, size: BigDecimal) -> Self {
+        Self {
+            side,
+            product_id: product.to_string(),
+            size,
+            price: Some(price),
+        }
+    }
+
+    /// Buys or sells `size` of `product` at the best available prices.
+    pub fn market(side: OrderSide, product: &str, size: BigDecimal) -> Self {
+        Self {
+            side,
+            product_id: product.to_string(),
+            size,
+            price: None,
+        }
+    }
+
+    fn child(&self, size: BigDecimal) -> NewOrder {
+        match &self.price {
+            Some(price) => NewOrder::limit(self.side, &self.product_id, price.clone(), size),
+            None => NewOrder::market(self.side, &self.product_id, size),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Algo {
+    Twap {
+        slices: u32,
+        duration: Duration,
+    },
+    Iceberg {
+        visible: BigDecimal,
+        timeout: Duration,
+    },
+}
+
+/// Aggregate execution of a parent order after one of its children.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// The child order that just finished.
+    pub child: ExecutionReport,
+    /// Amount of the base currency filled by all children so far.
+    pub filled_size: BigDecimal,
+    /// Amount of the parent order left to fill.
+    pub remaining: BigDecimal,
+    /// Quote currency per unit of the base currency over all fills, `None` without fills.
+    pub average_price: Option<BigDecimal>,
+    pub fees: BigDecimal,
+}
+
+/// Slices a parent order into child orders, see the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct Slicer {
+    algo: Algo,
+    poll_interval: Duration,
+    increment: Option<BigDecimal>,
+    jitter: f64,
+}
+
+impl Slicer {
+    /// Time-weighted average price: places `slices` children of equal size, one at the start of
+    /// each of `slices` equal periods of `duration`. A child still open at the end of its
+    /// period is canceled and what it didn't fill is spread over the remaining children.
+    pub fn twap(slices: u32, duration: Duration) -> Self {
+        Self::new(Algo::Twap {
+            slices: slices.max(1),
+            duration,
+        })
+    }
+
+    /// Iceberg: shows at most `visible` of the order at a time, placing the next child once
+    /// the previous one is done. A child still open after `timeout` is canceled, and slicing
+    /// stops once a child fills nothing, e.g. because the price moved away from the limit.
+    pub fn iceberg(visible: BigDecimal, timeout: Duration) -> Self {
+        Self::new(Algo::Iceberg { visible, timeout })
+    }
+
+    fn new(algo: Algo) -> Self {
+        Self {
+            algo,
+            poll_interval: Duration::from_secs(1),
+            increment: None,
+            jitter: 0.0,
+        }
+    }
+
+    /// How often the open child order is checked, 1 second by default.
+    pub fn poll_interval(self, poll_interval: Duration) -> Self {
+        let mut _self = self;
+        _self.poll_interval = poll_interval;
+        _self
+    }
+
+    /// Rounds child sizes down to a multiple of `increment`, the product's base increment.
+    pub fn increment(self, increment: BigDecimal) -> Self {
+        let mut _self = self;
+        _self.increment = Some(increment);
+        _self
+    }
+
+    /// Delays each TWAP child by a random fraction, up to `jitter` between 0 and 1, of its
+    /// period, so the children don't follow a predictable schedule.
+    pub fn jitter(self, jitter: f64) -> Self {
+        let mut _self = self;
+        _self.jitter = jitter.clamp(0.0, 1.0);
+        _self
+    }
+
+    /// Places the children, yielding the progress after each one. The stream ends when the
+    /// parent order is filled or the algorithm gives up, and with the first error.
+    pub fn run<'a>(
+        &self,
+        client: &'a ExchangeClient,
+        parent: &ParentOrder,
+    ) -> impl Stream<Item = Result<Progress>> + 'a {
+        let slicer = self.clone();
+        let parent = parent.clone();
+        let events = OrderEvents::new(self.poll_interval);
+        try_stream! {
+            let mut totals = Totals::new(parent.size.clone());
+            match &slicer.algo {
+                Algo::Twap { slices, duration } => {
+                    let period = *duration / *slices;
+                    let started = crate::rt::Instant::now();
+                    for slice in 0..*slices {
+                        let share = &totals.remaining / BigDecimal::from(slices - slice);
+                        let size = slicer.round(&share);
+                        let end = period * (slice + 1);
+                        if size.is_positive() {
+                            let fraction = slicer.jitter * rand::thread_rng().gen::<f64>();
+                            crate::rt::sleep(period.mul_f64(fraction)).await;
+                            let deadline = end.saturating_sub(started.elapsed());
+                            let order = parent.child(size);
+                            let child = events.execute(client, &order, deadline).await?;
+                            yield totals.add(child);
+                        }
+                        crate::rt::sleep(end.saturating_sub(started.elapsed())).await;
+                    }
+                }
+                Algo::Iceberg { visible, timeout } => loop {
+                    let size = slicer.round(visible.min(&totals.remaining));
+                    if !size.is_positive() {
+                        break;
+                    }
+                    let child = events.execute(client, &parent.child(size), *timeout).await?;
+                    let filled = child.filled_size.is_positive();
+                    yield totals.add(child);
+                    if !filled {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    fn round(&self, size: &BigDecimal) -> BigDecimal {
+        match &self.increment {
+            Some(increment) => amount::to_increment(size, increment),
+            None => size.clone(),
+        }
+    }
+}
+
+/// Fills of all children so far.
+struct Totals {
+    filled_size: BigDecimal,
+    remaining: BigDecimal,
+    value: BigDecimal,
+    fees: BigDecimal,
+}
+
+impl Totals {
+    fn new(size: BigDecimal) -> Self {
+        Self {
+            filled_size: BigDecimal::zero(),
+            remaining: size,
+            value: BigDecimal::zero(),
+            fees: BigDecimal::zero(),
+        }
+    }
+
+    fn add(&mut self, child: ExecutionReport) -> Progress {
+        if let Some(price) = &child.average_price {
+            self.value += price * &child.filled_size;
+        }
+        self.filled_size += &child.filled_size;
+        self.remaining -= &child.filled_size;
+        self.fees += &child.fees;
+        Progress {
+            filled_size: self.filled_size.clone(),
+            remaining: self.remaining.clone(),
+            average_price: (!self.filled_size.is_zero()).then(|| &self.value / &self.filled_size),
+            fees: self.fees.clone(),
+            child,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::TryStreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{Client, MAIN_URL};
+
+    fn order(id: &str, status: &str, filled_size: &str, executed_value: &str) -> String {
+        format!(
+            r#"{{"id":"{id}","product_id":"BTC-USD","side":"buy","type":"market",
+                "size":"0.005","created_at":"2024-01-01T00:00:00Z","status":"{status}",
+                "filled_size":"{filled_size}","executed_value":"{executed_value}",
+                "fill_fees":"0.5","settled":false}}"#
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_twap() {
+        let mock = MockTransport::new();
+        mock.push_json("/orders", &order("a", "pending", "0", "0"))
+            .push_json("/orders/a", &order("a", "done", "0.005", "150"))
+            .push_json("/orders", &order("b", "pending", "0", "0"))
+            .push_json("/orders/b", &order("b", "done", "0.005", "160"));
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", "c2VjcmV0", "passphrase");
+
+        let parent = ParentOrder::market(OrderSide::Buy, "BTC-USD", "0.01".parse().unwrap());
+        let started = tokio::time::Instant::now();
+        let progress: Vec<Progress> = Slicer::twap(2, Duration::from_secs(60))
+            .increment("0.001".parse().unwrap())
+            .run(&exchange, &parent)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(started.elapsed(), Duration::from_secs(60));
+
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[0].remaining, "0.005".parse().unwrap());
+        let last = &progress[1];
+        assert_eq!(last.filled_size, "0.01".parse().unwrap());
+        assert!(last.remaining.is_zero());
+        assert_eq!(last.average_price, Some("31000".parse().unwrap()));
+        assert_eq!(last.fees, "1".parse().unwrap());
+
+        let requests = mock.requests();
+        let body: serde_json::Value = serde_json::from_slice(requests[2].body()).unwrap();
+        assert_eq!(body["size"], "0.005");
+        assert_eq!(body["type"], "market");
+    }
+}
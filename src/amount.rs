@@ -0,0 +1,183 @@
+//! Rounding and formatting of amounts to the precision a currency or product accepts.
+//!
+//! Coinbase rejects amounts with more decimal places than a currency's `exponent`, and order
+//! sizes that aren't a multiple of the product's increment, so amounts computed client side
+//! should go through these helpers before being sent.
+//!
+//! ```
+//! use coinbase_rs::amount;
+//!
+//! let size = "0.123456789".parse().unwrap();
+//! assert_eq!(amount::truncate(&size, 8).to_string(), "0.12345678");
+//! assert_eq!(amount::to_increment(&size, &"0.001".parse().unwrap()).to_string(), "0.123");
+//! assert_eq!(amount::format(&"1.5".parse().unwrap(), 2), "1.50");
+//! assert_eq!(
+//!     amount::format_with(&"1234.5".parse().unwrap(), 2, &amount::NumberFormat::DE),
+//!     "1.234,50"
+//! );
+//! ```
+
+use bigdecimal::{BigDecimal, Signed};
+
+/// Rounds `amount` to `exponent` decimal places, half away from zero.
+pub fn round(amount: &BigDecimal, exponent: usize) -> BigDecimal {
+    let exponent = exponent as i64;
+    let truncated = amount.with_scale(exponent);
+    let remainder = amount - &truncated;
+    let half = BigDecimal::new(5.into(), exponent + 1);
+    if remainder.abs() < half {
+        truncated
+    } else if amount.is_negative() {
+        truncated - BigDecimal::new(1.into(), exponent)
+    } else {
+        truncated + BigDecimal::new(1.into(), exponent)
+    }
+}
+
+/// Drops the decimal places of `amount` beyond `exponent`, rounding toward zero so the result
+/// never exceeds what was available.
+pub fn truncate(amount: &BigDecimal, exponent: usize) -> BigDecimal {
+    amount.with_scale(exponent as i64)
+}
+
+/// Whether `amount` has no more than `exponent` decimal places.
+pub fn fits(amount: &BigDecimal, exponent: usize) -> bool {
+    truncate(amount, exponent) == *amount
+}
+
+/// Rounds `amount` toward zero to a multiple of `increment`, e.g. a product's base or quote
+/// increment. The result has as many decimal places as `increment`.
+pub fn to_increment(amount: &BigDecimal, increment: &BigDecimal) -> BigDecimal {
+    if !increment.is_positive() {
+        return amount.clone();
+    }
+    let (_, scale) = increment.as_bigint_and_exponent();
+    let steps = (amount / increment).with_scale(0);
+    (steps * increment).with_scale(scale.max(0))
+}
+
+/// Formats `amount` rounded to exactly `exponent` decimal places, keeping trailing zeros.
+pub fn format(amount: &BigDecimal, exponent: usize) -> String {
+    round(amount, exponent)
+        .with_scale(exponent as i64)
+        .to_string()
+}
+
+/// Separators used to display amounts in a locale.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NumberFormat {
+    /// Separates the integer part from the decimal places.
+    pub decimal: char,
+    /// Separates groups of three digits of the integer part, if any.
+    pub grouping: Option<char>,
+}
+
+impl NumberFormat {
+    /// `1234.5`, the format of the API.
+    pub const PLAIN: NumberFormat = NumberFormat {
+        decimal: '.',
+        grouping: None,
+    };
+    /// `1,234.5`, e.g. English.
+    pub const EN: NumberFormat = NumberFormat {
+        decimal: '.',
+        grouping: Some(','),
+    };
+    /// `1.234,5`, e.g. German, Spanish and Italian.
+    pub const DE: NumberFormat = NumberFormat {
+        decimal: ',',
+        grouping: Some('.'),
+    };
+    /// `1 234,5` with a narrow no-break space, e.g. French.
+    pub const FR: NumberFormat = NumberFormat {
+        decimal: ',',
+        grouping: Some('\u{202f}'),
+    };
+    /// `1'234.5`, e.g. Swiss German.
+    pub const CH: NumberFormat = NumberFormat {
+        decimal: '.',
+        grouping: Some('\''),
+    };
+}
+
+/// Formats `amount` like [`format`], with the separators of `number_format`.
+pub fn format_with(amount: &BigDecimal, exponent: usize, number_format: &NumberFormat) -> String {
+    let plain = format(amount, exponent);
+    let (sign, digits) = match plain.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", plain.as_str()),
+    };
+    let (integer, decimals) = match digits.split_once('.') {
+        Some((integer, decimals)) => (integer, Some(decimals)),
+        None => (digits, None),
+    };
+    let mut formatted = sign.to_string();
+    for (i, digit) in integer.chars().enumerate() {
+        if let Some(grouping) = number_format.grouping {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.push(grouping);
+            }
+        }
+        formatted.push(digit);
+    }
+    if let Some(decimals) = decimals {
+        formatted.push(number_format.decimal);
+        formatted.push_str(decimals);
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decimal(value: &str) -> BigDecimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_round() {
+        assert_eq!(round(&decimal("1.005"), 2), decimal("1.01"));
+        assert_eq!(round(&decimal("1.0049"), 2), decimal("1"));
+        assert_eq!(round(&decimal("-1.005"), 2), decimal("-1.01"));
+        assert_eq!(round(&decimal("12"), 0), decimal("12"));
+        assert_eq!(truncate(&decimal("0.999999999"), 8), decimal("0.99999999"));
+        assert_eq!(truncate(&decimal("-0.129"), 2), decimal("-0.12"));
+        assert!(fits(&decimal("1.50"), 1));
+        assert!(!fits(&decimal("0.001"), 2));
+    }
+
+    #[test]
+    fn test_to_increment() {
+        assert_eq!(
+            to_increment(&decimal("0.123456789"), &decimal("0.00000001")).to_string(),
+            "0.12345678"
+        );
+        assert_eq!(
+            to_increment(&decimal("101.37"), &decimal("0.25")).to_string(),
+            "101.25"
+        );
+        assert_eq!(to_increment(&decimal("17"), &decimal("5")), decimal("15"));
+        assert_eq!(to_increment(&decimal("1.5"), &decimal("0")), decimal("1.5"));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(&decimal("1.5"), 8), "1.50000000");
+        assert_eq!(format(&decimal("0.125"), 2), "0.13");
+        assert_eq!(format(&decimal("3.7"), 0), "4");
+        assert_eq!(
+            format_with(&decimal("1234567.891"), 2, &NumberFormat::EN),
+            "1,234,567.89"
+        );
+        assert_eq!(
+            format_with(&decimal("-1234.5"), 8, &NumberFormat::DE),
+            "-1.234,50000000"
+        );
+        assert_eq!(format_with(&decimal("999"), 0, &NumberFormat::FR), "999");
+        assert_eq!(
+            format_with(&decimal("123456"), 1, &NumberFormat::CH),
+            "123'456.0"
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Command line client for the Coinbase API.
+//!
+//! Credentials are read from `COINBASE_API_KEY` and `COINBASE_API_SECRET`, or
+//! `COINBASE_OAUTH_TOKEN`, and otherwise from the system keyring where `coinbase login` stores
+//! them.
+
+use std::error::Error;
+use std::io::Read;
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use clap::{Parser, Subcommand, ValueEnum};
+use coinbase_rs::private::{PlaceBuy, SendMoney};
+use coinbase_rs::{Client, DateTime, Environment, Uuid};
+use futures::TryStreamExt;
+
+const KEYRING_SERVICE: &str = "coinbase-rs";
+const KEYRING_KEY: &str = "api-key";
+const KEYRING_SECRET: &str = "api-secret";
+
+#[derive(Parser)]
+#[command(
+    name = "coinbase",
+    version,
+    about = "Command line client for the Coinbase API"
+)]
+struct Cli {
+    /// Talk to the sandbox environment instead of production.
+    #[arg(long, global = true)]
+    sandbox: bool,
+
+    /// Base URL of the API, overriding the environment.
+    #[arg(long, global = true, env = "COINBASE_BASE_URL")]
+    base_url: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Store an API key in the system keyring, reading the PEM encoded secret from stdin.
+    Login {
+        /// API key name.
+        key: String,
+    },
+    /// Remove the API key stored in the system keyring.
+    Logout,
+    /// Show the API server time.
+    Time,
+    /// List known currencies.
+    Currencies,
+    /// Show the price of a currency pair such as BTC-USD.
+    Price {
+        pair: String,
+        #[arg(long, value_enum, default_value_t = Side::Spot)]
+        side: Side,
+    },
+    /// Show the exchange rates of a currency.
+    Rates { currency: String },
+    /// List accounts.
+    Accounts,
+    /// List the balances of accounts that hold funds.
+    Balances,
+    /// List the transactions of an account, newest first.
+    Transactions {
+        account: Uuid,
+        /// Only list transactions created at or after this RFC 3339 time.
+        #[arg(long)]
+        since: Option<DateTime>,
+        /// Only list transactions created before this RFC 3339 time.
+        #[arg(long)]
+        until: Option<DateTime>,
+    },
+    /// List the addresses of an account.
+    Addresses { account: Uuid },
+    /// Buy an amount, in the account's currency or a fiat currency to spend, into an account.
+    Buy {
+        account: Uuid,
+        amount: String,
+        currency: String,
+        /// Payment method to pay with instead of the default one.
+        #[arg(long)]
+        payment_method: Option<String>,
+    },
+    /// Send funds from an account to a crypto address or email address.
+    Send {
+        account: Uuid,
+        to: String,
+        amount: String,
+        currency: String,
+        #[arg(long)]
+        description: Option<String>,
+        /// Network to send on, for currencies that exist on several.
+        #[arg(long)]
+        network: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Side {
+    Buy,
+    Sell,
+    Spot,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(error) = run(Cli::parse()).await {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let base_url = match cli.base_url {
+        Some(base_url) => base_url,
+        None if cli.sandbox => Environment::Sandbox.base_url().to_string(),
+        None => Environment::Production.base_url().to_string(),
+    };
+    let client = Client::new(&base_url);
+
+    match cli.command {
+        Command::Login { key } => {
+            let mut secret = String::new();
+            std::io::stdin().read_to_string(&mut secret)?;
+            keyring::Entry::new(KEYRING_SERVICE, KEYRING_KEY)?.set_password(&key)?;
+            keyring::Entry::new(KEYRING_SERVICE, KEYRING_SECRET)?.set_password(secret.trim())?;
+        }
+        Command::Logout => {
+            keyring::Entry::new(KEYRING_SERVICE, KEYRING_KEY)?.delete_password()?;
+            keyring::Entry::new(KEYRING_SERVICE, KEYRING_SECRET)?.delete_password()?;
+        }
+        Command::Time => {
+            let time = client.current_time().await?;
+            println!("{}", serde_json::to_string(&time)?);
+        }
+        Command::Currencies => {
+            let currencies: Vec<_> = client.currencies().try_concat().await?;
+            for currency in currencies {
+                println!("{}\t{}", currency.id, currency.name);
+            }
+        }
+        Command::Price { pair, side } => {
+            let price = match side {
+                Side::Buy => client.buy_price(&pair).await?,
+                Side::Sell => client.sell_price(&pair).await?,
+                Side::Spot => client.spot_price(&pair, None).await?,
+            };
+            println!("{} {}", price.amount, price.currency);
+        }
+        Command::Rates { currency } => {
+            let rates = client.exchange_rates(&currency).await?;
+            let mut rates: Vec<_> = rates.rates.into_iter().collect();
+            rates.sort_by(|a, b| a.0.cmp(&b.0));
+            for (currency, rate) in rates {
+                println!("{currency}\t{rate}");
+            }
+        }
+        Command::Accounts => {
+            let client = authenticate(client)?;
+            let accounts: Vec<_> = client.accounts().try_concat().await?;
+            for account in accounts {
+                println!(
+                    "{}\t{}\t{}",
+                    account.id, account.currency.code, account.name
+                );
+            }
+        }
+        Command::Balances => {
+            let client = authenticate(client)?;
+            let accounts: Vec<_> = client.funded_accounts().try_concat().await?;
+            for account in accounts {
+                println!("{} {}", account.balance.amount, account.balance.currency);
+            }
+        }
+        Command::Transactions {
+            account,
+            since,
+            until,
+        } => {
+            let client = authenticate(client)?;
+            let transactions: Vec<_> = client
+                .transactions_between(&account, since, until)
+                .try_concat()
+                .await?;
+            for transaction in transactions {
+                println!(
+                    "{}\t{}\t{} {}",
+                    transaction.id,
+                    transaction.status,
+                    transaction.amount.amount,
+                    transaction.amount.currency
+                );
+            }
+        }
+        Command::Addresses { account } => {
+            let client = authenticate(client)?;
+            let addresses: Vec<_> = client.list_addresses(&account).try_concat().await?;
+            for address in addresses {
+                println!("{}\t{}\t{}", address.id, address.network, address.address);
+            }
+        }
+        Command::Buy {
+            account,
+            amount,
+            currency,
+            payment_method,
+        } => {
+            let client = authenticate(client)?;
+            let mut buy = PlaceBuy::new(BigDecimal::from_str(&amount)?, &currency);
+            if let Some(payment_method) = payment_method {
+                buy = buy.payment_method(&payment_method);
+            }
+            let buy = client.place_buy(&account, &buy).await?;
+            println!(
+                "{}\t{}\t{} {}",
+                buy.id, buy.status, buy.amount.amount, buy.amount.currency
+            );
+        }
+        Command::Send {
+            account,
+            to,
+            amount,
+            currency,
+            description,
+            network,
+        } => {
+            let client = authenticate(client)?;
+            let mut send = SendMoney::new(&to, BigDecimal::from_str(&amount)?, &currency);
+            if let Some(description) = description {
+                send = send.description(&description);
+            }
+            if let Some(network) = network {
+                send = send.network(&network);
+            }
+            let transaction = client.send_money(&account, &send).await?;
+            println!("{}\t{}", transaction.id, transaction.status);
+        }
+    }
+    Ok(())
+}
+
+/// Adds the credentials found in the environment or the system keyring to `client`.
+fn authenticate(client: Client) -> Result<Client, Box<dyn Error>> {
+    if let Ok(token) = std::env::var("COINBASE_OAUTH_TOKEN") {
+        return Ok(client.with_oauth_token(&token));
+    }
+    if let (Ok(key), Ok(secret)) = (
+        std::env::var("COINBASE_API_KEY"),
+        std::env::var("COINBASE_API_SECRET"),
+    ) {
+        return Ok(client.with_credentials(&key, &secret));
+    }
+    let key = keyring::Entry::new(KEYRING_SERVICE, KEYRING_KEY)?.get_password();
+    let secret = keyring::Entry::new(KEYRING_SERVICE, KEYRING_SECRET)?.get_password();
+    match (key, secret) {
+        (Ok(key), Ok(secret)) => Ok(client.with_credentials(&key, &secret)),
+        _ => Err(
+            "no credentials, set COINBASE_API_KEY and COINBASE_API_SECRET or run `coinbase login`"
+                .into(),
+        ),
+    }
+}
@@ -0,0 +1,398 @@
+//! Synchronous wrappers around [`Client`](crate::Client), for scripts and codebases that don't
+//! use async Rust.
+//!
+//! ```no_run
+//! use coinbase_rs::{blocking::Client, MAIN_URL};
+//!
+//! let client = Client::new(MAIN_URL);
+//! println!("BTC is at {}", client.spot_price("BTC-USD", None).unwrap().amount);
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream::{Stream, TryStreamExt};
+use http::Method;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::runtime::{self, Runtime};
+use uuid::Uuid;
+
+use crate::brokerage::{BrokerageAccount, RetailPortfolio};
+use crate::market::{Candle, Granularity, Trade};
+use crate::order_book::OrderBook;
+use crate::portfolio::Portfolio;
+use crate::private::{
+    Account, Address, AuthInfo, Buy, ListParams, Notification, PaymentMethod, PlaceBuy, PlaceSell,
+    PlaceWithdrawal, Quote, Scope, Sell, SendMoney, Transaction, Withdrawal,
+};
+use crate::public::{Asset, Currency, CurrencyPrice, ExchangeRates, SupportedNetwork, Time};
+use crate::tax::{CostBasis, LotMethod};
+use crate::{DateTime, Result};
+
+/// Blocking client running an async [`Client`](crate::Client) on an internal single threaded
+/// runtime.
+///
+/// Endpoints returning pages in the async client return all items at once. Methods must not be
+/// called from within an async runtime, where blocking would stall other tasks; they panic if
+/// they are.
+#[derive(Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl From<crate::Client> for Client {
+    /// Wraps a client configured with the async API, e.g. through
+    /// [`Coinbase::builder`](crate::Coinbase::builder).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime can't be started.
+    fn from(inner: crate::Client) -> Self {
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime");
+        Self {
+            inner,
+            runtime: Arc::new(runtime),
+        }
+    }
+}
+
+impl Client {
+    pub fn new(uri: &str) -> Self {
+        crate::Client::new(uri).into()
+    }
+
+    /// Creates a client signing requests with the API key name `key` and the PEM encoded
+    /// private key `secret`.
+    pub fn new_with_auth(uri: &str, key: &str, secret: &str) -> Self {
+        crate::Client::new_with_auth(uri, key, secret).into()
+    }
+
+    /// The wrapped async client.
+    pub fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    /// See [`Client::currencies`](crate::Client::currencies).
+    pub fn currencies(&self) -> Result<Vec<Currency>> {
+        self.collect(self.inner.currencies())
+    }
+
+    /// See [`Client::exchange_rates`](crate::Client::exchange_rates).
+    pub fn exchange_rates(&self, currency: &str) -> Result<ExchangeRates> {
+        self.runtime.block_on(self.inner.exchange_rates(currency))
+    }
+
+    /// See [`Client::buy_price`](crate::Client::buy_price).
+    pub fn buy_price(&self, pair: &str) -> Result<CurrencyPrice> {
+        self.runtime.block_on(self.inner.buy_price(pair))
+    }
+
+    /// See [`Client::sell_price`](crate::Client::sell_price).
+    pub fn sell_price(&self, currency_pair: &str) -> Result<CurrencyPrice> {
+        self.runtime.block_on(self.inner.sell_price(currency_pair))
+    }
+
+    /// See [`Client::spot_price`](crate::Client::spot_price).
+    pub fn spot_price(
+        &self,
+        currency_pair: &str,
+        date: Option<chrono::NaiveDate>,
+    ) -> Result<CurrencyPrice> {
+        self.runtime
+            .block_on(self.inner.spot_price(currency_pair, date))
+    }
+
+    /// See [`Client::assets`](crate::Client::assets).
+    pub fn assets(&self) -> Result<Vec<Asset>> {
+        self.runtime.block_on(self.inner.assets())
+    }
+
+    /// See [`Client::asset`](crate::Client::asset).
+    pub fn asset(&self, currency: &str) -> Result<Asset> {
+        self.runtime.block_on(self.inner.asset(currency))
+    }
+
+    /// See [`Client::supported_networks`](crate::Client::supported_networks).
+    pub fn supported_networks(&self, currency: &str) -> Result<Vec<SupportedNetwork>> {
+        self.runtime
+            .block_on(self.inner.supported_networks(currency))
+    }
+
+    /// See [`Client::current_time`](crate::Client::current_time).
+    pub fn current_time(&self) -> Result<Time> {
+        self.runtime.block_on(self.inner.current_time())
+    }
+
+    /// See [`Client::ping`](crate::Client::ping).
+    pub fn ping(&self) -> Result<Duration> {
+        self.runtime.block_on(self.inner.ping())
+    }
+
+    /// See [`Client::ping_authenticated`](crate::Client::ping_authenticated).
+    pub fn ping_authenticated(&self) -> Result<Duration> {
+        self.runtime.block_on(self.inner.ping_authenticated())
+    }
+
+    /// See [`Client::auth_info`](crate::Client::auth_info).
+    pub fn auth_info(&self) -> Result<AuthInfo> {
+        self.runtime.block_on(self.inner.auth_info())
+    }
+
+    /// See [`Client::require_scopes`](crate::Client::require_scopes).
+    pub fn require_scopes(&self, scopes: &[Scope]) -> Result<()> {
+        self.runtime.block_on(self.inner.require_scopes(scopes))
+    }
+
+    /// See [`Client::candles`](crate::Client::candles).
+    pub fn candles(
+        &self,
+        product: &str,
+        granularity: Granularity,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<Vec<Candle>> {
+        self.runtime
+            .block_on(self.inner.candles(product, granularity, start, end))
+    }
+
+    /// See [`Client::fetch_candles_range`](crate::Client::fetch_candles_range).
+    pub fn fetch_candles_range(
+        &self,
+        product: &str,
+        granularity: Granularity,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<Vec<Candle>> {
+        self.collect(
+            self.inner
+                .fetch_candles_range(product, granularity, start, end),
+        )
+    }
+
+    /// See [`Client::market_trades`](crate::Client::market_trades).
+    pub fn market_trades(&self, product: &str, limit: u32) -> Result<Vec<Trade>> {
+        self.runtime
+            .block_on(self.inner.market_trades(product, limit))
+    }
+
+    /// See [`Client::product_book`](crate::Client::product_book).
+    pub fn product_book(&self, product: &str, limit: Option<u32>) -> Result<OrderBook> {
+        self.runtime
+            .block_on(self.inner.product_book(product, limit))
+    }
+
+    /// See [`Client::account`](crate::Client::account).
+    pub fn account(&self, account_id: &Uuid) -> Result<Account> {
+        self.runtime.block_on(self.inner.account(account_id))
+    }
+
+    /// See [`Client::accounts`](crate::Client::accounts).
+    pub fn accounts(&self) -> Result<Vec<Account>> {
+        self.collect(self.inner.accounts())
+    }
+
+    /// See [`Client::accounts_with_params`](crate::Client::accounts_with_params).
+    pub fn accounts_with_params(&self, params: &ListParams) -> Result<Vec<Account>> {
+        self.collect(self.inner.accounts_with_params(params))
+    }
+
+    /// See [`Client::funded_accounts`](crate::Client::funded_accounts).
+    pub fn funded_accounts(&self) -> Result<Vec<Account>> {
+        self.collect(self.inner.funded_accounts())
+    }
+
+    /// See [`Client::portfolios`](crate::Client::portfolios).
+    pub fn portfolios(&self) -> Result<Vec<RetailPortfolio>> {
+        self.runtime.block_on(self.inner.portfolios())
+    }
+
+    /// See [`Client::brokerage_accounts`](crate::Client::brokerage_accounts).
+    pub fn brokerage_accounts(&self) -> Result<Vec<BrokerageAccount>> {
+        self.collect(self.inner.brokerage_accounts())
+    }
+
+    /// See [`Client::transactions`](crate::Client::transactions).
+    pub fn transactions(&self, account_id: &Uuid) -> Result<Vec<Transaction>> {
+        self.collect(self.inner.transactions(account_id))
+    }
+
+    /// See [`Client::transactions_with_params`](crate::Client::transactions_with_params).
+    pub fn transactions_with_params(
+        &self,
+        account_id: &Uuid,
+        params: &ListParams,
+    ) -> Result<Vec<Transaction>> {
+        self.collect(self.inner.transactions_with_params(account_id, params))
+    }
+
+    /// See [`Client::transactions_between`](crate::Client::transactions_between).
+    pub fn transactions_between(
+        &self,
+        account_id: &Uuid,
+        start: Option<DateTime>,
+        end: Option<DateTime>,
+    ) -> Result<Vec<Transaction>> {
+        self.collect(self.inner.transactions_between(account_id, start, end))
+    }
+
+    /// See [`Client::list_addresses`](crate::Client::list_addresses).
+    pub fn list_addresses(&self, account_id: &Uuid) -> Result<Vec<Address>> {
+        self.collect(self.inner.list_addresses(account_id))
+    }
+
+    /// See [`Client::update_address`](crate::Client::update_address).
+    pub fn update_address(
+        &self,
+        account_id: &Uuid,
+        address_id: &str,
+        name: &str,
+    ) -> Result<Address> {
+        self.runtime
+            .block_on(self.inner.update_address(account_id, address_id, name))
+    }
+
+    /// See [`Client::cancel_transaction`](crate::Client::cancel_transaction).
+    pub fn cancel_transaction(&self, account_id: &Uuid, transaction_id: &Uuid) -> Result<()> {
+        self.runtime
+            .block_on(self.inner.cancel_transaction(account_id, transaction_id))
+    }
+
+    /// See [`Client::cancel_vault_withdrawal`](crate::Client::cancel_vault_withdrawal).
+    pub fn cancel_vault_withdrawal(&self, account_id: &Uuid, transaction_id: &Uuid) -> Result<()> {
+        self.runtime.block_on(
+            self.inner
+                .cancel_vault_withdrawal(account_id, transaction_id),
+        )
+    }
+
+    /// See [`Client::send_money`](crate::Client::send_money).
+    pub fn send_money(&self, account_id: &Uuid, send: &SendMoney) -> Result<Transaction> {
+        self.runtime
+            .block_on(self.inner.send_money(account_id, send))
+    }
+
+    /// See [`Client::portfolio`](crate::Client::portfolio).
+    pub fn portfolio(&self, currency: &str) -> Result<Portfolio> {
+        self.runtime.block_on(self.inner.portfolio(currency))
+    }
+
+    /// See [`Client::cost_basis`](crate::Client::cost_basis).
+    pub fn cost_basis(
+        &self,
+        account_id: &Uuid,
+        fiat: &str,
+        method: LotMethod,
+    ) -> Result<CostBasis> {
+        self.runtime
+            .block_on(self.inner.cost_basis(account_id, fiat, method))
+    }
+
+    /// See [`Client::notifications`](crate::Client::notifications).
+    pub fn notifications(&self) -> Result<Vec<Notification>> {
+        self.collect(self.inner.notifications())
+    }
+
+    /// See [`Client::payment_methods`](crate::Client::payment_methods).
+    pub fn payment_methods(&self) -> Result<Vec<PaymentMethod>> {
+        self.collect(self.inner.payment_methods())
+    }
+
+    /// See [`Client::payment_method`](crate::Client::payment_method).
+    pub fn payment_method(&self, payment_method_id: &str) -> Result<PaymentMethod> {
+        self.runtime
+            .block_on(self.inner.payment_method(payment_method_id))
+    }
+
+    /// See [`Client::place_buy`](crate::Client::place_buy).
+    pub fn place_buy(&self, account_id: &Uuid, buy: &PlaceBuy) -> Result<Buy> {
+        self.runtime.block_on(self.inner.place_buy(account_id, buy))
+    }
+
+    /// See [`Client::quote_buy`](crate::Client::quote_buy).
+    pub fn quote_buy(&self, account_id: &Uuid, buy: &PlaceBuy) -> Result<Quote> {
+        self.runtime.block_on(self.inner.quote_buy(account_id, buy))
+    }
+
+    /// See [`Client::commit_buy`](crate::Client::commit_buy).
+    pub fn commit_buy(&self, account_id: &Uuid, quote: &Quote) -> Result<Buy> {
+        self.runtime
+            .block_on(self.inner.commit_buy(account_id, quote))
+    }
+
+    /// See [`Client::place_sell`](crate::Client::place_sell).
+    pub fn place_sell(&self, account_id: &Uuid, sell: &PlaceSell) -> Result<Sell> {
+        self.runtime
+            .block_on(self.inner.place_sell(account_id, sell))
+    }
+
+    /// See [`Client::commit_sell`](crate::Client::commit_sell).
+    pub fn commit_sell(&self, account_id: &Uuid, sell: &Sell) -> Result<Sell> {
+        self.runtime
+            .block_on(self.inner.commit_sell(account_id, sell))
+    }
+
+    /// See [`Client::withdraw`](crate::Client::withdraw).
+    pub fn withdraw(&self, account_id: &Uuid, withdrawal: &PlaceWithdrawal) -> Result<Withdrawal> {
+        self.runtime
+            .block_on(self.inner.withdraw(account_id, withdrawal))
+    }
+
+    /// See [`Client::get_raw`](crate::Client::get_raw).
+    pub fn get_raw(&self, path: &str) -> Result<Value> {
+        self.runtime.block_on(self.inner.get_raw(path))
+    }
+
+    /// See [`Client::post_raw`](crate::Client::post_raw).
+    pub fn post_raw(&self, path: &str, body: &Value) -> Result<Value> {
+        self.runtime.block_on(self.inner.post_raw(path, body))
+    }
+
+    /// See [`Client::call`](crate::Client::call).
+    pub fn call<T>(&self, method: Method, path: &str, body: Option<&Value>) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.runtime.block_on(self.inner.call(method, path, body))
+    }
+
+    fn collect<T>(&self, pages: impl Stream<Item = Result<Vec<T>>>) -> Result<Vec<T>> {
+        self.runtime.block_on(pages.try_concat())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    #[test]
+    fn test_blocking_calls() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/currencies",
+            r#"{"pagination":{"limit":1,"order":"asc","next_uri":"/v2/currencies?starting_after=BTC"},
+                "data":[{"id":"BTC","name":"Bitcoin","min_size":"0.00000001"}]}"#,
+        )
+        .push_json(
+            "/v2/currencies?starting_after=BTC",
+            r#"{"pagination":{"limit":1,"order":"asc","next_uri":null},
+                "data":[{"id":"ETH","name":"Ether","min_size":"0.00000001"}]}"#,
+        )
+        .push_json(
+            "/v2/prices/BTC-USD/spot",
+            r#"{"data":{"amount":"1020.25","currency":"USD"}}"#,
+        );
+        let client = Client::from(crate::Client::new(MAIN_URL).with_transport(mock));
+
+        let currencies = client.currencies().unwrap();
+        assert_eq!(currencies.len(), 2);
+        assert_eq!(currencies[1].id, "ETH");
+        assert_eq!(client.spot_price("BTC-USD", None).unwrap().currency, "USD");
+    }
+}
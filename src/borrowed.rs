@@ -0,0 +1,229 @@
+//! Borrowed variants of the high-volume market data models.
+//!
+//! The `*_body` methods return the undeserialized [`Body`] of a response, which
+//! [`parse`](Body::parse) deserializes into the `*Ref` types of this module. Their fields are
+//! `&str` slices of the body, so a page of candles or trades costs one allocation per list instead
+//! of several per item, for callers converting into their own types anyway.
+//!
+//! ```no_run
+//! # async fn run(client: coinbase_rs::Client) -> coinbase_rs::Result<()> {
+//! use coinbase_rs::borrowed::MarketTradesRef;
+//!
+//! let body = client.market_trades_body("BTC-USD", 1000).await?.data;
+//! let trades = body.parse::<MarketTradesRef>()?.trades;
+//! let volume: f64 = trades.iter().filter_map(|t| t.size.parse::<f64>().ok()).sum();
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::Bytes;
+use http::StatusCode;
+use serde::Deserialize;
+
+use crate::error::body_excerpt;
+use crate::market::{self, Granularity};
+use crate::order::OrderSide;
+use crate::{order_book, ApiResponse, CBError, Client, DateTime, Result};
+
+/// The body of a successful response, decoded but not deserialized.
+#[derive(Clone, Debug)]
+pub struct Body {
+    status: StatusCode,
+    bytes: Bytes,
+}
+
+impl Body {
+    /// Deserializes the body into `T`, which may borrow from it.
+    pub fn parse<'a, T: Deserialize<'a>>(&'a self) -> Result<T> {
+        serde_json::from_slice(&self.bytes).map_err(|source| CBError::Deserialize {
+            status: self.status,
+            body: body_excerpt(&self.bytes),
+            source,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Borrowed [`Candle`](crate::market::Candle).
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CandleRef<'a> {
+    /// Start of the interval in seconds since the epoch.
+    pub start: &'a str,
+    pub low: &'a str,
+    pub high: &'a str,
+    pub open: &'a str,
+    pub close: &'a str,
+    pub volume: &'a str,
+}
+
+impl CandleRef<'_> {
+    /// Start of the interval, `None` if the server sent an invalid timestamp.
+    pub fn start_time(&self) -> Option<DateTime> {
+        chrono::DateTime::from_timestamp(self.start.parse().ok()?, 0)
+    }
+}
+
+/// Body of a [`candles_body`](Client::candles_body) response.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CandlesRef<'a> {
+    #[serde(borrow)]
+    pub candles: Vec<CandleRef<'a>>,
+}
+
+/// Borrowed [`Trade`](crate::market::Trade).
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TradeRef<'a> {
+    pub trade_id: &'a str,
+    pub product_id: &'a str,
+    pub price: &'a str,
+    pub size: &'a str,
+    /// RFC 3339 time of the trade.
+    pub time: &'a str,
+    /// Side of the taker.
+    pub side: OrderSide,
+}
+
+/// Body of a [`market_trades_body`](Client::market_trades_body) response.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MarketTradesRef<'a> {
+    #[serde(borrow)]
+    pub trades: Vec<TradeRef<'a>>,
+    /// Empty when the book has no bids.
+    #[serde(default)]
+    pub best_bid: &'a str,
+    /// Empty when the book has no asks.
+    #[serde(default)]
+    pub best_ask: &'a str,
+}
+
+/// A price level of an order book.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LevelRef<'a> {
+    pub price: &'a str,
+    pub size: &'a str,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PriceBookRef<'a> {
+    pub product_id: &'a str,
+    #[serde(borrow)]
+    pub bids: Vec<LevelRef<'a>>,
+    #[serde(borrow)]
+    pub asks: Vec<LevelRef<'a>>,
+    pub time: Option<&'a str>,
+}
+
+/// Body of a [`product_book_body`](Client::product_book_body) response.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProductBookRef<'a> {
+    #[serde(borrow)]
+    pub pricebook: PriceBookRef<'a>,
+}
+
+impl Client {
+    /// Like [`candles`](Self::candles), returning the body to be parsed into [`CandlesRef`].
+    pub async fn candles_body(
+        &self,
+        product: &str,
+        granularity: Granularity,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<ApiResponse<Body>> {
+        let uri = market::candles_uri(product, granularity, start, end);
+        self.body(&uri).await
+    }
+
+    /// Like [`market_trades`](Self::market_trades), returning the body to be parsed into
+    /// [`MarketTradesRef`].
+    pub async fn market_trades_body(&self, product: &str, limit: u32) -> Result<ApiResponse<Body>> {
+        self.body(&market::market_trades_uri(product, limit)).await
+    }
+
+    /// Like [`product_book`](Self::product_book), returning the body to be parsed into
+    /// [`ProductBookRef`].
+    pub async fn product_book_body(
+        &self,
+        product: &str,
+        limit: Option<u32>,
+    ) -> Result<ApiResponse<Body>> {
+        self.body(&order_book::product_book_uri(product, limit))
+            .await
+    }
+
+    async fn body(&self, uri: &str) -> Result<ApiResponse<Body>> {
+        let response = self.make_request_body(self.request(uri)).await?;
+        Ok(ApiResponse {
+            data: Body {
+                status: response.meta.status,
+                bytes: response.data,
+            },
+            meta: response.meta,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    #[tokio::test]
+    async fn test_borrowed_models() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/api/v3/brokerage/market/products/BTC-USD/ticker?limit=2",
+            r#"{"trades":[
+                {"trade_id":"34b080bf","product_id":"BTC-USD","price":"140.91","size":"4",
+                 "time":"2021-05-31T09:59:59Z","side":"BUY","exchange":"coinbase"},
+                {"trade_id":"34b080c0","product_id":"BTC-USD","price":"140.9","size":"0.5",
+                 "time":"2021-05-31T09:59:58Z","side":"SELL","exchange":"coinbase"}],
+                "best_bid":"140.89","best_ask":"140.92"}"#,
+        )
+        .push_json(
+            "/api/v3/brokerage/market/product_book?product_id=BTC-USD&limit=1",
+            r#"{"pricebook":{"product_id":"BTC-USD","bids":[{"price":"99","size":"2"}],
+                "asks":[{"price":"101","size":"1"}],"time":"2024-01-01T00:00:00Z"}}"#,
+        );
+        let client = Client::new(MAIN_URL).with_transport(mock);
+
+        let body = client.market_trades_body("BTC-USD", 2).await.unwrap().data;
+        let trades = body.parse::<MarketTradesRef>().unwrap();
+        assert_eq!(trades.trades.len(), 2);
+        assert_eq!(trades.trades[1].price, "140.9");
+        assert_eq!(trades.best_ask, "140.92");
+
+        let body = client
+            .product_book_body("BTC-USD", Some(1))
+            .await
+            .unwrap()
+            .data;
+        let book = body.parse::<ProductBookRef>().unwrap().pricebook;
+        assert_eq!(
+            book.bids,
+            [LevelRef {
+                price: "99",
+                size: "2"
+            }]
+        );
+
+        assert!(matches!(
+            body.parse::<CandlesRef>(),
+            Err(CBError::Deserialize { .. })
+        ));
+    }
+
+    #[test]
+    fn test_candle_start_time() {
+        let json = r#"{"candles":[{"start":"1639508050","low":"1","high":"2","open":"1","close":"2","volume":"10"}]}"#;
+        let body = Body {
+            status: StatusCode::OK,
+            bytes: Bytes::from(json),
+        };
+        let candles = body.parse::<CandlesRef>().unwrap().candles;
+        assert_eq!(candles[0].start_time().unwrap().timestamp(), 1639508050);
+    }
+}
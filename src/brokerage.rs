@@ -0,0 +1,501 @@
+//! Authenticated endpoints of the Advanced Trade API.
+//!
+//! Account and order queries act on the key's default portfolio unless the client is pinned to
+//! another one with [`Client::with_retail_portfolio`]:
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::{Client, MAIN_URL};
+//! use futures::stream::TryStreamExt;
+//!
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! for portfolio in client.portfolios().await? {
+//!     let accounts: Vec<_> = client
+//!         .clone()
+//!         .with_retail_portfolio(&portfolio.uuid)
+//!         .brokerage_accounts()
+//!         .try_concat()
+//!         .await?;
+//!     println!("{}: {} accounts", portfolio.name, accounts.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use async_stream::try_stream;
+use bigdecimal::{BigDecimal, Signed};
+use futures::stream::{Stream, StreamExt};
+use http::Method;
+use uritemplate::UriTemplate;
+
+use crate::amount;
+use crate::{ApiResponse, CBError, Client, DateTime, Result};
+
+/// Most accounts returned by a single request.
+const ACCOUNTS_LIMIT: u32 = 250;
+
+/// A portfolio of the user, holding its own accounts and orders.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RetailPortfolio {
+    pub uuid: String,
+    pub name: String,
+    /// `DEFAULT` or `CONSUMER`.
+    pub r#type: String,
+    pub deleted: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct Portfolios {
+    portfolios: Vec<RetailPortfolio>,
+}
+
+/// An amount as the Advanced Trade API sends it.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Amount {
+    pub value: BigDecimal,
+    pub currency: String,
+}
+
+/// A currency account of a portfolio.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BrokerageAccount {
+    pub uuid: String,
+    pub name: String,
+    pub currency: String,
+    pub available_balance: Amount,
+    /// Amount held by open orders.
+    pub hold: Option<Amount>,
+    pub default: bool,
+    pub active: bool,
+    pub ready: bool,
+    pub r#type: String,
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
+    pub retail_portfolio_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BrokerageAccounts {
+    accounts: Vec<BrokerageAccount>,
+    has_next: bool,
+    cursor: Option<String>,
+}
+
+/// Outcome of cancelling one order of a [`cancel_orders`](Client::cancel_orders) batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CancelResult {
+    pub order_id: String,
+    /// Why the order wasn't canceled, `None` if it was.
+    pub failure: Option<CancelFailure>,
+}
+
+impl CancelResult {
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Why an order couldn't be canceled.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelFailure {
+    InvalidCancelRequest,
+    /// No open order has the id.
+    UnknownCancelOrder,
+    CommanderRejectedCancelOrder,
+    /// A cancellation of the order is already in progress.
+    DuplicateCancelRequest,
+    InvalidCancelProductId,
+    InvalidCancelFcmTradingSession,
+    NotAllowedToCancel,
+    OrderIsFullyFilled,
+    OrderIsBeingReplaced,
+    /// A reason the API doesn't name, or one added after this version.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Debug)]
+struct CancelResults {
+    results: Vec<RawCancelResult>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RawCancelResult {
+    success: bool,
+    failure_reason: Option<CancelFailure>,
+    order_id: String,
+}
+
+/// Fee rates of a pricing tier, as fractions of the order value.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FeeTier {
+    pub pricing_tier: String,
+    /// Rate of orders adding liquidity, e.g. resting limit orders.
+    pub maker_fee_rate: BigDecimal,
+    /// Rate of orders taking liquidity, e.g. market orders.
+    pub taker_fee_rate: BigDecimal,
+}
+
+/// Trading summary of the user, with the fee tier their volume reached.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TransactionSummary {
+    pub fee_tier: FeeTier,
+}
+
+/// A product with the sizes and increments its orders must respect.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BrokerageProduct {
+    pub product_id: String,
+    /// Price of the last trade.
+    pub price: BigDecimal,
+    pub base_increment: BigDecimal,
+    pub quote_increment: BigDecimal,
+    pub base_min_size: BigDecimal,
+    pub base_max_size: BigDecimal,
+    pub quote_min_size: BigDecimal,
+    pub quote_max_size: BigDecimal,
+    pub status: String,
+    pub trading_disabled: bool,
+}
+
+/// Largest buy a budget in the quote currency covers, fees included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuySize {
+    /// Order size in the base currency, a multiple of the base increment.
+    pub base_size: BigDecimal,
+    /// Value of the order before fees.
+    pub value: BigDecimal,
+    /// Fee charged on the value, rounded up to the quote increment.
+    pub fee: BigDecimal,
+    /// Value and fee, never more than the budget.
+    pub total: BigDecimal,
+}
+
+impl BuySize {
+    /// Sizes a buy of `product` at `price` paying `fee_rate`, e.g. the taker rate of the
+    /// user's [`FeeTier`] for a market order. Fails with [`CBError::InvalidAmount`] if the
+    /// budget doesn't cover the product's minimum size.
+    pub fn for_budget(
+        budget: &BigDecimal,
+        price: &BigDecimal,
+        fee_rate: &BigDecimal,
+        product: &BrokerageProduct,
+    ) -> Result<Self> {
+        if !price.is_positive() {
+            return Err(CBError::InvalidAmount(format!(
+                "no price to size {} orders at",
+                product.product_id
+            )));
+        }
+        let one = BigDecimal::from(1);
+        let size = budget / (price * (&one + fee_rate));
+        let mut base_size = amount::to_increment(
+            &size.min(product.base_max_size.clone()),
+            &product.base_increment,
+        );
+        // Rounding the fee up may push the total over the budget, the fee is then paid first.
+        loop {
+            let sized = Self::new(base_size.clone(), price, fee_rate, &product.quote_increment);
+            if base_size < product.base_min_size || sized.value < product.quote_min_size {
+                return Err(CBError::InvalidAmount(format!(
+                    "{budget} doesn't cover the minimum order size of {}",
+                    product.product_id
+                )));
+            }
+            if sized.total <= *budget {
+                return Ok(sized);
+            }
+            let size =
+                amount::to_increment(&((budget - &sized.fee) / price), &product.base_increment);
+            base_size = size.min(base_size - &product.base_increment);
+        }
+    }
+
+    fn new(
+        base_size: BigDecimal,
+        price: &BigDecimal,
+        fee_rate: &BigDecimal,
+        quote_increment: &BigDecimal,
+    ) -> Self {
+        let value = price * &base_size;
+        let fee = &value * fee_rate;
+        let mut rounded = amount::to_increment(&fee, quote_increment);
+        if rounded < fee {
+            rounded += quote_increment;
+        }
+        Self {
+            total: &value + &rounded,
+            base_size,
+            value,
+            fee: rounded,
+        }
+    }
+}
+
+impl Client {
+    ///
+    /// **List portfolios**
+    ///
+    /// Get a list of all portfolios of a user.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_getportfolios
+    ///
+    pub async fn portfolios(&self) -> Result<Vec<RetailPortfolio>> {
+        self.portfolios_with_meta()
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`portfolios`](Self::portfolios), also returning the response metadata.
+    pub async fn portfolios_with_meta(&self) -> Result<ApiResponse<Vec<RetailPortfolio>>> {
+        let request = self.authenticated_request("/api/v3/brokerage/portfolios");
+        let response = self.make_request::<Portfolios>(request).await?;
+        Ok(ApiResponse {
+            data: response.data.portfolios,
+            meta: response.meta,
+        })
+    }
+
+    ///
+    /// **List accounts**
+    ///
+    /// Get a list of authenticated Advanced Trade accounts for the current user, of the
+    /// portfolio set with [`with_retail_portfolio`](Self::with_retail_portfolio) if any.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_getaccounts
+    ///
+    pub fn brokerage_accounts<'a>(
+        &'a self,
+    ) -> impl Stream<Item = Result<Vec<BrokerageAccount>>> + 'a {
+        self.brokerage_accounts_with_meta()
+            .map(|page| page.map(|page| page.data))
+    }
+
+    /// Like [`brokerage_accounts`](Self::brokerage_accounts), also returning the metadata of
+    /// each page.
+    pub fn brokerage_accounts_with_meta<'a>(
+        &'a self,
+    ) -> impl Stream<Item = Result<ApiResponse<Vec<BrokerageAccount>>>> + 'a {
+        try_stream! {
+            let mut cursor: Option<String> = None;
+            loop {
+                let mut uri = format!("/api/v3/brokerage/accounts?limit={ACCOUNTS_LIMIT}");
+                if let Some(portfolio) = &self.retail_portfolio {
+                    uri += &format!("&retail_portfolio_id={portfolio}");
+                }
+                if let Some(cursor) = &cursor {
+                    uri += &format!("&cursor={cursor}");
+                }
+                let request = self.authenticated_request(&uri);
+                let response = self.make_page_request::<BrokerageAccounts>(request).await?;
+                let page = response.data;
+                let delay = response.meta.rate_limit.page_delay();
+                yield ApiResponse {
+                    data: page.accounts,
+                    meta: response.meta,
+                };
+                match page.cursor.filter(|cursor| page.has_next && !cursor.is_empty()) {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+                if !self.pace(delay).await {
+                    break;
+                }
+            }
+        }
+    }
+
+    ///
+    /// **Get transaction summary**
+    ///
+    /// Get the user's trading volume and fees, with the fee tier they reached.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_gettransactionsummary
+    ///
+    pub async fn transaction_summary(&self) -> Result<TransactionSummary> {
+        let request = self.authenticated_request("/api/v3/brokerage/transaction_summary");
+        Ok(self.make_request(request).await?.data)
+    }
+
+    ///
+    /// **Get product**
+    ///
+    /// Get a product with its last price and order size constraints.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_getproduct
+    ///
+    pub async fn brokerage_product(&self, product_id: &str) -> Result<BrokerageProduct> {
+        let uri = UriTemplate::new("/api/v3/brokerage/products/{product}")
+            .set("product", product_id)
+            .build();
+        Ok(self
+            .make_request(self.authenticated_request(&uri))
+            .await?
+            .data)
+    }
+
+    ///
+    /// Sizes the largest market buy of `product_id` that `budget`, in the quote currency,
+    /// pays for after taker fees and rounding to the product increments, so the order isn't
+    /// rejected for insufficient funds. See [`BuySize::for_budget`] to size limit orders.
+    ///
+    pub async fn max_buy_size(&self, product_id: &str, budget: &BigDecimal) -> Result<BuySize> {
+        let summary = self.transaction_summary().await?;
+        let product = self.brokerage_product(product_id).await?;
+        BuySize::for_budget(
+            budget,
+            &product.price,
+            &summary.fee_tier.taker_fee_rate,
+            &product,
+        )
+    }
+
+    ///
+    /// **Cancel orders**
+    ///
+    /// Initiates cancel requests for one or more orders, returning whether each one was
+    /// canceled, so only the failed ones need to be retried.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_cancelorders
+    ///
+    pub async fn cancel_orders(&self, order_ids: &[&str]) -> Result<Vec<CancelResult>> {
+        let body = serde_json::json!({ "order_ids": order_ids });
+        let request = self
+            .authenticated_request("/api/v3/brokerage/orders/batch_cancel")
+            .method(Method::POST)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(&body)?);
+        let response = self.make_request::<CancelResults>(request).await?;
+        Ok(response
+            .data
+            .results
+            .into_iter()
+            .map(|result| CancelResult {
+                failure: if result.success {
+                    None
+                } else {
+                    Some(result.failure_reason.unwrap_or(CancelFailure::Unknown))
+                },
+                order_id: result.order_id,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::TryStreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn account(currency: &str, value: &str) -> String {
+        format!(
+            r#"{{"uuid":"{currency}-uuid","name":"{currency} Wallet","currency":"{currency}",
+                "available_balance":{{"value":"{value}","currency":"{currency}"}},
+                "default":true,"active":true,"created_at":"2024-01-01T00:00:00Z",
+                "updated_at":"2024-01-01T00:00:00Z","deleted_at":null,
+                "type":"ACCOUNT_TYPE_CRYPTO","ready":true,
+                "hold":{{"value":"0","currency":"{currency}"}},
+                "retail_portfolio_id":"b87a2d3f"}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_brokerage_accounts() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/api/v3/brokerage/portfolios",
+            r#"{"portfolios":[{"name":"Trading","uuid":"b87a2d3f","type":"CONSUMER","deleted":false}]}"#,
+        )
+        .push_json(
+            "/api/v3/brokerage/accounts?limit=250&retail_portfolio_id=b87a2d3f",
+            &format!(
+                r#"{{"accounts":[{}],"has_next":true,"cursor":"789100","size":1}}"#,
+                account("BTC", "0.5")
+            ),
+        )
+        .push_json(
+            "/api/v3/brokerage/accounts?limit=250&retail_portfolio_id=b87a2d3f&cursor=789100",
+            &format!(
+                r#"{{"accounts":[{}],"has_next":false,"cursor":"","size":1}}"#,
+                account("ETH", "2")
+            ),
+        );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+
+        let portfolios = client.portfolios().await.unwrap();
+        assert_eq!(portfolios[0].name, "Trading");
+        let accounts: Vec<_> = client
+            .with_retail_portfolio(&portfolios[0].uuid)
+            .brokerage_accounts()
+            .try_concat()
+            .await
+            .unwrap();
+        let currencies: Vec<_> = accounts.iter().map(|a| a.currency.as_str()).collect();
+        assert_eq!(currencies, ["BTC", "ETH"]);
+        assert_eq!(accounts[1].available_balance.value, "2".parse().unwrap());
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_orders() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/api/v3/brokerage/orders/batch_cancel",
+            r#"{"results":[
+                {"success":true,"failure_reason":"UNKNOWN_CANCEL_FAILURE_REASON","order_id":"a"},
+                {"success":false,"failure_reason":"UNKNOWN_CANCEL_ORDER","order_id":"b"},
+                {"success":false,"failure_reason":"SOMETHING_NEW","order_id":"c"}]}"#,
+        );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+
+        let results = client.cancel_orders(&["a", "b", "c"]).await.unwrap();
+        assert!(results[0].is_success());
+        assert_eq!(results[1].failure, Some(CancelFailure::UnknownCancelOrder));
+        assert_eq!(results[2].failure, Some(CancelFailure::Unknown));
+        let body: serde_json::Value = serde_json::from_slice(mock.requests()[0].body()).unwrap();
+        assert_eq!(body, serde_json::json!({"order_ids": ["a", "b", "c"]}));
+    }
+
+    #[tokio::test]
+    async fn test_max_buy_size() {
+        let product = r#"{"product_id":"BTC-USD","price":"30000","base_increment":"0.00000001",
+            "quote_increment":"0.01","base_min_size":"0.00000001","base_max_size":"3400",
+            "quote_min_size":"1","quote_max_size":"150000000","status":"online",
+            "trading_disabled":false}"#;
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/api/v3/brokerage/transaction_summary",
+            r#"{"total_volume":0,"total_fees":0,"fee_tier":{"pricing_tier":"Advanced 1",
+                "usd_from":"0","usd_to":"1000","taker_fee_rate":"0.006",
+                "maker_fee_rate":"0.004"}}"#,
+        )
+        .push_json("/api/v3/brokerage/products/BTC-USD", product);
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+
+        let budget = "100".parse().unwrap();
+        let size = client.max_buy_size("BTC-USD", &budget).await.unwrap();
+        // 0.00331345 would cost 99.4035 plus 0.60 fees.
+        assert_eq!(size.base_size, "0.00331333".parse().unwrap());
+        assert_eq!(size.fee, "0.6".parse().unwrap());
+        assert_eq!(size.total, "99.9999".parse().unwrap());
+
+        let product: BrokerageProduct = serde_json::from_str(product).unwrap();
+        let sized = BuySize::for_budget(
+            &"0.5".parse().unwrap(),
+            &product.price,
+            &"0.004".parse().unwrap(),
+            &product,
+        );
+        assert!(matches!(sized, Err(CBError::InvalidAmount(_))));
+    }
+}
@@ -0,0 +1,350 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::ResponseCache;
+use crate::retry::Exponential;
+#[cfg(feature = "tokio")]
+use crate::TlsOptions;
+use crate::{
+    CircuitBreaker, Client, ConnectionOptions, DebugLog, Environment, HttpTransport, Metrics,
+    Middleware, Proxy, RateBudget, RateLimit, RetryPolicy, Shutdown, Timeouts, MAIN_URL,
+};
+
+/// Entry point for configuring a client, see [`Coinbase::builder`].
+pub struct Coinbase;
+
+impl Coinbase {
+    /// Returns a builder for a client talking to the production API with default settings.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+}
+
+/// Collects the configuration of a client in one place.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use coinbase_rs::retry::Exponential;
+/// use coinbase_rs::{Coinbase, Timeouts};
+///
+/// let client = Coinbase::builder()
+///     .credentials("<key>", "<secret>")
+///     .timeouts(Timeouts::default().request(Some(Duration::from_secs(5))))
+///     .retry_policy(Exponential::default().max_attempts(5))
+///     .user_agent("my-app/1.0")
+///     .build();
+/// ```
+pub struct ClientBuilder {
+    base_url: String,
+    credentials: Option<(String, String)>,
+    oauth_token: Option<String>,
+    retail_portfolio: Option<String>,
+    timeouts: Timeouts,
+    retry: Arc<dyn RetryPolicy>,
+    rate_limit: Option<RateLimit>,
+    rate_budget: Option<RateBudget>,
+    circuit_breaker: Option<CircuitBreaker>,
+    clock_correction: Option<Duration>,
+    debug_log: Option<DebugLog>,
+    shutdown: Option<Shutdown>,
+    resumable_streams: bool,
+    user_agent: Option<String>,
+    user_agent_suffix: Option<String>,
+    #[cfg(feature = "tokio")]
+    tls: TlsOptions,
+    proxy: Option<Option<Proxy>>,
+    connection: ConnectionOptions,
+    middleware: Vec<Arc<dyn Middleware>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    cache: Option<ResponseCache>,
+    transport: Option<Arc<dyn HttpTransport>>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: MAIN_URL.to_string(),
+            credentials: None,
+            oauth_token: None,
+            retail_portfolio: None,
+            timeouts: Timeouts::default(),
+            retry: Arc::new(Exponential::default()),
+            rate_limit: Some(RateLimit::default()),
+            rate_budget: None,
+            circuit_breaker: None,
+            clock_correction: None,
+            debug_log: None,
+            shutdown: None,
+            resumable_streams: false,
+            user_agent: None,
+            user_agent_suffix: None,
+            #[cfg(feature = "tokio")]
+            tls: TlsOptions::default(),
+            proxy: None,
+            connection: ConnectionOptions::default(),
+            middleware: Vec::new(),
+            metrics: None,
+            cache: None,
+            transport: None,
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Base URL of the API, [`MAIN_URL`] by default.
+    pub fn base_url(self, base_url: &str) -> Self {
+        let mut _self = self;
+        _self.base_url = base_url.to_string();
+        _self
+    }
+
+    /// Points the client at the base URL of `environment`.
+    pub fn environment(self, environment: Environment) -> Self {
+        self.base_url(environment.base_url())
+    }
+
+    /// API key name and PEM encoded private key used to sign requests.
+    pub fn credentials(self, key: &str, secret: &str) -> Self {
+        let mut _self = self;
+        _self.credentials = Some((key.to_string(), secret.to_string()));
+        _self
+    }
+
+    /// OAuth2 access token authorizing requests, used instead of [`credentials`](Self::credentials).
+    pub fn oauth_token(self, token: &str) -> Self {
+        let mut _self = self;
+        _self.oauth_token = Some(token.to_string());
+        _self
+    }
+
+    /// Portfolio Advanced Trade account and order queries are pinned to, see
+    /// [`Client::with_retail_portfolio`].
+    pub fn retail_portfolio(self, portfolio: &str) -> Self {
+        let mut _self = self;
+        _self.retail_portfolio = Some(portfolio.to_string());
+        _self
+    }
+
+    /// Connect, request and total timeouts.
+    pub fn timeouts(self, timeouts: Timeouts) -> Self {
+        let mut _self = self;
+        _self.timeouts = timeouts;
+        _self
+    }
+
+    /// Policy used to retry failed requests.
+    pub fn retry_policy<P: RetryPolicy + 'static>(self, retry: P) -> Self {
+        let mut _self = self;
+        _self.retry = Arc::new(retry);
+        _self
+    }
+
+    /// Limit on how fast requests are sent, `None` disables rate limiting.
+    pub fn rate_limit(self, limit: Option<RateLimit>) -> Self {
+        let mut _self = self;
+        _self.rate_limit = limit;
+        _self
+    }
+
+    /// Budget shared with other clients, replacing the [`rate_limit`](Self::rate_limit).
+    pub fn rate_budget(self, budget: &RateBudget) -> Self {
+        let mut _self = self;
+        _self.rate_budget = Some(budget.clone());
+        _self
+    }
+
+    /// Circuit breaker failing requests fast while a host keeps failing, `None` by default.
+    pub fn circuit_breaker(self, breaker: Option<CircuitBreaker>) -> Self {
+        let mut _self = self;
+        _self.circuit_breaker = breaker;
+        _self
+    }
+
+    /// Interval at which the offset of the server's clock, which JWTs are dated by, is measured
+    /// again, `None` by default. See [`Client::with_clock_correction`].
+    pub fn clock_correction(self, refresh: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.clock_correction = refresh;
+        _self
+    }
+
+    /// Logging of every request and response with secrets redacted, `None` by default.
+    pub fn debug_log(self, debug_log: Option<DebugLog>) -> Self {
+        let mut _self = self;
+        _self.debug_log = debug_log;
+        _self
+    }
+
+    /// Signal ending the paginated and polling streams, none by default.
+    pub fn shutdown(self, shutdown: &Shutdown) -> Self {
+        let mut _self = self;
+        _self.shutdown = Some(shutdown.clone());
+        _self
+    }
+
+    /// Whether paginated streams request a page again after a transient error instead of
+    /// ending, off by default. See [`Client::with_resumable_streams`].
+    pub fn resumable_streams(self, resume: bool) -> Self {
+        let mut _self = self;
+        _self.resumable_streams = resume;
+        _self
+    }
+
+    /// `User-Agent` sent with every request, replacing the crate's own.
+    pub fn user_agent(self, user_agent: &str) -> Self {
+        let mut _self = self;
+        _self.user_agent = Some(user_agent.to_string());
+        _self
+    }
+
+    /// Product identifier appended to the `User-Agent`, e.g. `myapp/1.2` to send
+    /// `coinbase-rs/<version> myapp/1.2`.
+    pub fn user_agent_suffix(self, suffix: &str) -> Self {
+        let mut _self = self;
+        _self.user_agent_suffix = Some(suffix.to_string());
+        _self
+    }
+
+    /// Trusted root certificates, client certificate and other TLS settings.
+    #[cfg(feature = "tokio")]
+    pub fn tls(self, tls: TlsOptions) -> Self {
+        let mut _self = self;
+        _self.tls = tls;
+        _self
+    }
+
+    /// Outbound proxy, `None` connects directly. By default it is read from the environment.
+    pub fn proxy(self, proxy: Option<Proxy>) -> Self {
+        let mut _self = self;
+        _self.proxy = Some(proxy);
+        _self
+    }
+
+    /// HTTP/2, connection pool and keep-alive settings.
+    pub fn connection_options(self, connection: ConnectionOptions) -> Self {
+        let mut _self = self;
+        _self.connection = connection;
+        _self
+    }
+
+    /// Adds a hook that sees every request and response.
+    pub fn middleware<M: Middleware + 'static>(self, middleware: M) -> Self {
+        let mut _self = self;
+        _self.middleware.push(Arc::new(middleware));
+        _self
+    }
+
+    /// Receiver of request counts, latencies, rate limiting and retries.
+    pub fn metrics<M: Metrics + 'static>(self, metrics: M) -> Self {
+        let mut _self = self;
+        _self.metrics = Some(Arc::new(metrics));
+        _self
+    }
+
+    /// Cache answering repeated requests to public endpoints, see [`Client::with_cache`].
+    pub fn cache(self, cache: ResponseCache) -> Self {
+        let mut _self = self;
+        _self.cache = Some(cache);
+        _self
+    }
+
+    /// Transport used instead of the default [`HyperTransport`](crate::HyperTransport).
+    pub fn transport<T: HttpTransport + 'static>(self, transport: T) -> Self {
+        let mut _self = self;
+        _self.transport = Some(Arc::new(transport));
+        _self
+    }
+
+    pub fn build(self) -> Client {
+        let mut client = Client::new(&self.base_url)
+            .with_timeouts(self.timeouts)
+            .with_retry_policy(self.retry)
+            .with_rate_limit(self.rate_limit)
+            .with_circuit_breaker(self.circuit_breaker)
+            .with_clock_correction(self.clock_correction)
+            .with_resumable_streams(self.resumable_streams)
+            .with_debug_log(self.debug_log)
+            .with_connection_options(self.connection);
+        #[cfg(feature = "tokio")]
+        {
+            client = client.with_tls_options(self.tls);
+        }
+        if let Some(shutdown) = &self.shutdown {
+            client = client.with_shutdown(shutdown);
+        }
+        if let Some(budget) = &self.rate_budget {
+            client = client.with_rate_budget(budget);
+        }
+        if let Some((key, secret)) = self.credentials {
+            client = client.with_credentials(&key, &secret);
+        }
+        if let Some(token) = self.oauth_token {
+            client = client.with_oauth_token(&token);
+        }
+        if let Some(portfolio) = self.retail_portfolio {
+            client = client.with_retail_portfolio(&portfolio);
+        }
+        if let Some(proxy) = self.proxy {
+            client = client.with_proxy(proxy);
+        }
+        if let Some(user_agent) = self.user_agent {
+            client = client.with_user_agent(&user_agent);
+        }
+        if let Some(suffix) = self.user_agent_suffix {
+            client = client.with_user_agent_suffix(&suffix);
+        }
+        for middleware in self.middleware {
+            client = client.with_middleware(middleware);
+        }
+        if let Some(metrics) = self.metrics {
+            client = client.with_metrics(metrics);
+        }
+        if let Some(cache) = self.cache {
+            client = client.with_cache(cache);
+        }
+        if let Some(transport) = self.transport {
+            client = client.with_transport(transport);
+        }
+        client
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+
+    #[tokio::test]
+    async fn test_build() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/prices/BTC-USD/spot",
+            r#"{"data":{"amount":"1020.25","currency":"USD"}}"#,
+        );
+        let client = Coinbase::builder()
+            .environment(Environment::Sandbox)
+            .user_agent("my-app/1.0")
+            .rate_limit(None)
+            .transport(mock.clone())
+            .build();
+        client.spot_price("BTC-USD", None).await.unwrap();
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.uri().host(), Some("api-sandbox.coinbase.com"));
+        assert_eq!(request.headers()["user-agent"], "my-app/1.0");
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_suffix() {
+        let mock = MockTransport::new();
+        let client = Coinbase::builder()
+            .user_agent_suffix("myapp/1.2")
+            .transport(mock.clone())
+            .build();
+        let _ = client.current_time().await;
+
+        let user_agent = concat!("coinbase-rs/", env!("CARGO_PKG_VERSION"), " myapp/1.2");
+        assert_eq!(mock.requests()[0].headers()["user-agent"], user_agent);
+    }
+}
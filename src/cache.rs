@@ -0,0 +1,184 @@
+//! Opt-in caching of public reference data.
+//!
+//! Exchange rates, currencies and product metadata change rarely, so repeated lookups within a
+//! few seconds can be answered from memory instead of spending rate limit budget. Only
+//! unauthenticated `GET` requests of the endpoint classes given a TTL are cached.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::cache::{EndpointClass, ResponseCache};
+//! use coinbase_rs::{Client, MAIN_URL};
+//!
+//! let cache = ResponseCache::new()
+//!     .ttl(EndpointClass::ExchangeRates, Duration::from_secs(10))
+//!     .ttl(EndpointClass::Currencies, Duration::from_secs(3600));
+//! let client = Client::new(MAIN_URL).with_cache(cache);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{response, HeaderMap, Method, Response, StatusCode};
+
+use crate::request;
+use crate::rt::Instant;
+
+/// Group of public endpoints sharing a TTL.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EndpointClass {
+    /// `/v2/exchange-rates`.
+    ExchangeRates,
+    /// `/v2/currencies` and the currencies of the Exchange API.
+    Currencies,
+    /// Product lists and single products of the Advanced Trade and Exchange APIs, not their
+    /// candles, trades or books.
+    Products,
+}
+
+impl EndpointClass {
+    /// The class of the endpoint at `path`, `None` for endpoints that are never cached.
+    fn of(path: &str) -> Option<Self> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match segments[..] {
+            ["v2", "exchange-rates"] => Some(Self::ExchangeRates),
+            ["v2", "currencies", ..] | ["currencies", ..] => Some(Self::Currencies),
+            ["products"] | ["products", _] => Some(Self::Products),
+            ["api", "v3", "brokerage", "market", "products"]
+            | ["api", "v3", "brokerage", "market", "products", _] => Some(Self::Products),
+            _ => None,
+        }
+    }
+}
+
+/// Responses of public endpoints kept for the TTL of their [`EndpointClass`]. Clones share the
+/// cached responses, like clients sharing a cache through [`Client::with_cache`](crate::Client::with_cache).
+#[derive(Clone, Debug, Default)]
+pub struct ResponseCache {
+    ttls: HashMap<EndpointClass, Duration>,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    stored: Instant,
+    ttl: Duration,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl ResponseCache {
+    /// A cache without TTLs, which caches nothing until [`ttl`](Self::ttl) is set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps responses of endpoints of `class` for `ttl`.
+    pub fn ttl(self, class: EndpointClass, ttl: Duration) -> Self {
+        let mut _self = self;
+        _self.ttls.insert(class, ttl);
+        _self
+    }
+
+    /// Drops every cached response.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// How long the response to `request` may be kept, `None` if it isn't cached.
+    pub(crate) fn ttl_for(&self, request: &request::Builder) -> Option<Duration> {
+        if request.method_ref() != Method::GET || request.is_authenticated() {
+            return None;
+        }
+        let class = EndpointClass::of(request.uri_ref().path())?;
+        self.ttls.get(&class).copied()
+    }
+
+    /// The response cached for `key` if it is still fresh.
+    pub(crate) fn get(&self, key: &str) -> Option<(response::Parts, Bytes)> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get(key)
+            .filter(|entry| entry.stored.elapsed() < entry.ttl)?;
+        let (mut parts, ()) = Response::new(()).into_parts();
+        parts.status = entry.status;
+        parts.headers = entry.headers.clone();
+        Some((parts, entry.body.clone()))
+    }
+
+    pub(crate) fn insert(&self, key: String, ttl: Duration, parts: &response::Parts, body: &Bytes) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.stored.elapsed() < entry.ttl);
+        entries.insert(
+            key,
+            Entry {
+                stored: Instant::now(),
+                ttl,
+                status: parts.status,
+                headers: parts.headers.clone(),
+                body: body.clone(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{Client, MAIN_URL};
+
+    #[test]
+    fn test_endpoint_class() {
+        assert_eq!(
+            EndpointClass::of("/v2/exchange-rates"),
+            Some(EndpointClass::ExchangeRates)
+        );
+        assert_eq!(
+            EndpointClass::of("/currencies/BTC"),
+            Some(EndpointClass::Currencies)
+        );
+        assert_eq!(
+            EndpointClass::of("/api/v3/brokerage/market/products/BTC-USD"),
+            Some(EndpointClass::Products)
+        );
+        assert_eq!(
+            EndpointClass::of("/api/v3/brokerage/market/products/BTC-USD/candles"),
+            None
+        );
+        assert_eq!(EndpointClass::of("/v2/prices/BTC-USD/spot"), None);
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let rates = r#"{"data":{"currency":"USD","rates":{"EUR":"0.9"}}}"#;
+        let mock = MockTransport::new();
+        mock.push_json("/v2/exchange-rates?currency=USD", rates)
+            .push_json("/v2/exchange-rates?currency=EUR", rates)
+            .push_json("/v2/exchange-rates?currency=USD", rates);
+        let cache = ResponseCache::new().ttl(EndpointClass::ExchangeRates, Duration::from_secs(60));
+        let client = Client::new(MAIN_URL)
+            .with_cache(cache.clone())
+            .with_transport(mock.clone());
+
+        client.exchange_rates("USD").await.unwrap();
+        client.exchange_rates("USD").await.unwrap();
+        client.clone().exchange_rates("EUR").await.unwrap();
+        assert_eq!(mock.requests().len(), 2);
+
+        cache.clear();
+        let rates = client.exchange_rates("USD").await.unwrap();
+        assert_eq!(rates.rates["EUR"], "0.9".parse().unwrap());
+        assert_eq!(mock.requests().len(), 3);
+
+        // Classes without a TTL are not cached.
+        let time = r#"{"data":{"iso":"2015-06-23T18:02:51Z","epoch":1435082571}}"#;
+        mock.push_json("/v2/time", time).push_json("/v2/time", time);
+        client.current_time().await.unwrap();
+        client.current_time().await.unwrap();
+        assert_eq!(mock.requests().len(), 5);
+    }
+}
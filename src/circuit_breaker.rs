@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use std::sync::Mutex;
+
+use crate::rt::Instant;
+use crate::transport::TransportErrorKind;
+use crate::{CBError, Result};
+
+/// Stops sending requests to a host that keeps failing.
+///
+/// After `failure_threshold` consecutive server errors (5xx) or timeouts the circuit of the host
+/// opens and requests fail immediately with [`CBError::CircuitOpen`], instead of adding retries
+/// to an outage. Once `open_for` has passed a single probe request is let through: the circuit
+/// closes if it succeeds and opens again if it fails.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Number of consecutive failures opening the circuit.
+    pub fn failure_threshold(self, failure_threshold: u32) -> Self {
+        let mut _self = self;
+        _self.failure_threshold = failure_threshold.max(1);
+        _self
+    }
+
+    /// How long the circuit stays open before a probe request is sent.
+    pub fn open_for(self, open_for: Duration) -> Self {
+        let mut _self = self;
+        _self.open_for = open_for;
+        _self
+    }
+}
+
+/// Circuit state of every host a client talked to.
+#[derive(Debug)]
+pub(crate) struct Breaker {
+    config: CircuitBreaker,
+    hosts: Mutex<HashMap<String, State>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe request is in flight.
+    HalfOpen,
+}
+
+impl Breaker {
+    pub(crate) fn new(config: CircuitBreaker) -> Self {
+        Self {
+            config,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Admits a request to `host`, or fails with how long the circuit stays open. The outcome
+    /// of the request is recorded through the returned permit.
+    pub(crate) fn acquire<'a>(&'a self, host: &'a str) -> Result<Permit<'a>> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts
+            .entry(host.to_string())
+            .or_insert(State::Closed { failures: 0 });
+        let permit = |probe| Permit {
+            breaker: self,
+            host,
+            probe,
+        };
+        let retry_in = match *state {
+            State::Closed { .. } => return Ok(permit(false)),
+            State::Open { until } => {
+                let now = Instant::now();
+                if now >= until {
+                    *state = State::HalfOpen;
+                    return Ok(permit(true));
+                }
+                until.duration_since(now)
+            }
+            State::HalfOpen => Duration::ZERO,
+        };
+        Err(CBError::CircuitOpen {
+            host: host.to_string(),
+            retry_in,
+        })
+    }
+
+    fn record<T>(&self, host: &str, result: &Result<T>) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts
+            .entry(host.to_string())
+            .or_insert(State::Closed { failures: 0 });
+        *state = match (is_failure(result), *state) {
+            (Some(false), _) => State::Closed { failures: 0 },
+            (Some(true), State::Closed { failures })
+                if failures + 1 < self.config.failure_threshold =>
+            {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (Some(true), _) => State::Open {
+                until: Instant::now() + self.config.open_for,
+            },
+            // The request never reached the server, so a pending probe is given up.
+            (None, State::HalfOpen) => State::Open {
+                until: Instant::now(),
+            },
+            (None, state) => state,
+        };
+    }
+}
+
+/// Admission of a request by [`Breaker::acquire`].
+pub(crate) struct Permit<'a> {
+    breaker: &'a Breaker,
+    host: &'a str,
+    /// Whether the request is the probe of a half open circuit.
+    probe: bool,
+}
+
+impl Permit<'_> {
+    /// Records the outcome of the admitted request.
+    pub(crate) fn record<T>(self, result: &Result<T>) {
+        let mut _self = self;
+        _self.breaker.record(_self.host, result);
+        _self.probe = false;
+    }
+}
+
+impl Drop for Permit<'_> {
+    /// A probe dropped before its outcome was recorded, e.g. by a timeout or a cancelled
+    /// stream, is given up, so the next request probes again instead of the circuit staying
+    /// half open for good.
+    fn drop(&mut self) {
+        if !self.probe {
+            return;
+        }
+        let mut hosts = self.breaker.hosts.lock().unwrap();
+        if let Some(state @ State::HalfOpen) = hosts.get_mut(self.host) {
+            *state = State::Open {
+                until: Instant::now(),
+            };
+        }
+    }
+}
+
+/// Whether `result` shows the server failing, `None` if it says nothing about the server.
+fn is_failure<T>(result: &Result<T>) -> Option<bool> {
+    match result {
+        Ok(_) => Some(false),
+        Err(CBError::Http(e)) => match e.kind() {
+            TransportErrorKind::Connect
+            | TransportErrorKind::Timeout
+            | TransportErrorKind::Incomplete => Some(true),
+            TransportErrorKind::Other => None,
+        },
+        Err(CBError::Timeout) => Some(true),
+        Err(CBError::Coinbase { status, .. } | CBError::Status { status, .. }) => {
+            Some(status.is_server_error())
+        }
+        Err(CBError::RateLimited { .. }) => Some(false),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::StatusCode;
+
+    use super::*;
+    use crate::retry::{Exponential, NoRetry};
+    use crate::testing::{FaultInjector, MockTransport};
+    use crate::{Client, MAIN_URL};
+
+    const SPOT: &str = "/v2/prices/BTC-USD/spot";
+
+    #[tokio::test]
+    async fn test_opens_after_failures() {
+        let mock = MockTransport::new();
+        mock.push_status(SPOT, StatusCode::SERVICE_UNAVAILABLE, "")
+            .push_status(SPOT, StatusCode::BAD_GATEWAY, "");
+        let client = Client::new(MAIN_URL)
+            .with_retry_policy(Exponential::default().base_delay(Duration::ZERO))
+            .with_circuit_breaker(Some(
+                CircuitBreaker::default()
+                    .failure_threshold(2)
+                    .open_for(Duration::from_secs(60)),
+            ))
+            .with_transport(mock.clone());
+
+        // The second failure opens the circuit, so the third attempt is never sent.
+        let result = client.spot_price("BTC-USD", None).await;
+        assert!(
+            matches!(result, Err(CBError::CircuitOpen { ref host, .. }) if host == "api.coinbase.com")
+        );
+        assert_eq!(mock.requests().len(), 2);
+
+        let result = client.clone().spot_price("BTC-USD", None).await;
+        assert!(
+            matches!(result, Err(CBError::CircuitOpen { retry_in, .. }) if retry_in > Duration::from_secs(50))
+        );
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_probe_closes() {
+        let spot = r#"{"data":{"amount":"1020.25","currency":"USD"}}"#;
+        let mock = MockTransport::new();
+        mock.push_status(SPOT, StatusCode::INTERNAL_SERVER_ERROR, "")
+            .push_json(SPOT, spot)
+            .push_status(SPOT, StatusCode::NOT_FOUND, "")
+            .push_json(SPOT, spot);
+        let client = Client::new(MAIN_URL)
+            .with_retry_policy(NoRetry)
+            .with_circuit_breaker(Some(
+                CircuitBreaker::default()
+                    .failure_threshold(1)
+                    .open_for(Duration::ZERO),
+            ))
+            .with_transport(mock.clone());
+
+        assert!(client.spot_price("BTC-USD", None).await.is_err());
+        client.spot_price("BTC-USD", None).await.unwrap();
+        // Client errors don't count as failures.
+        assert!(matches!(
+            client.spot_price("BTC-USD", None).await,
+            Err(CBError::Status { .. } | CBError::Coinbase { .. })
+        ));
+        client.spot_price("BTC-USD", None).await.unwrap();
+        assert_eq!(mock.requests().len(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dropped_probe() {
+        let spot = r#"{"data":{"amount":"1020.25","currency":"USD"}}"#;
+        let mock = MockTransport::new();
+        mock.push_status(SPOT, StatusCode::INTERNAL_SERVER_ERROR, "")
+            .push_json(SPOT, spot)
+            .push_json(SPOT, spot);
+        let client = Client::new(MAIN_URL)
+            .with_retry_policy(NoRetry)
+            .with_circuit_breaker(Some(
+                CircuitBreaker::default()
+                    .failure_threshold(1)
+                    .open_for(Duration::ZERO),
+            ))
+            .with_transport(FaultInjector::new(mock.clone()).latency(1.0, Duration::from_secs(5)));
+
+        assert!(client.spot_price("BTC-USD", None).await.is_err());
+        // The probe is dropped while in flight, e.g. by a deadline.
+        let probe = client.spot_price("BTC-USD", None);
+        assert!(tokio::time::timeout(Duration::from_secs(1), probe)
+            .await
+            .is_err());
+        client.spot_price("BTC-USD", None).await.unwrap();
+    }
+}
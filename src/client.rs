@@ -0,0 +1,1262 @@
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_stream::try_stream;
+use bytes::Bytes;
+use futures::stream::Stream;
+use http::{header, response, HeaderMap, StatusCode, Uri};
+#[cfg(feature = "tokio")]
+use hyper_util::client::legacy::connect::Connect;
+
+use crate::cache::ResponseCache;
+use crate::circuit_breaker::Breaker;
+use crate::clock::ServerClock;
+use crate::compression::{decoder, decompress, ACCEPT_ENCODING};
+use crate::debug_log::DebugLog;
+use crate::metrics::{Metrics, NoMetrics};
+use crate::middleware::Middleware;
+use crate::otel::HttpSpan;
+use crate::public::Response;
+use crate::rate_limit::{Priority, RateLimiter};
+use crate::response::{ApiResponse, ResponseMeta};
+use crate::retry::{Attempt, Exponential};
+use crate::timeouts::with_timeout;
+use crate::trace::{self, RequestSpan};
+use crate::transport::{
+    default_transport, ConnectionOptions, HttpTransport, NoTransport, TransportError,
+    TransportErrorKind,
+};
+use crate::{
+    error::body_excerpt, request, CBError, CircuitBreaker, Environment, Proxy, RateBudget,
+    RateLimit, Result, RetryPolicy, Shutdown, Timeouts,
+};
+#[cfg(feature = "tokio")]
+use crate::{HyperTransport, TlsOptions};
+
+/// Client for the Coinbase API.
+///
+/// Public endpoints such as prices and currencies always work. Endpoints acting on an account
+/// need credentials, given to [`new_with_auth`](Self::new_with_auth),
+/// [`with_credentials`](Self::with_credentials) or [`with_oauth_token`](Self::with_oauth_token),
+/// and fail with
+/// [`CBError::MissingCredentials`] without them.
+///
+/// Clients are cheap to clone and can be shared between tasks: clones share the connection
+/// pool, rate limiter, middleware and metrics. Settings changed on a clone only apply to it.
+#[derive(Clone)]
+pub struct Client {
+    pub(crate) uri: String,
+    transport: Arc<dyn HttpTransport>,
+    custom_transport: bool,
+    retry: Arc<dyn RetryPolicy>,
+    limiter: Arc<RateLimiter>,
+    priority: Option<Priority>,
+    breaker: Option<Arc<Breaker>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    debug_log: Option<DebugLog>,
+    metrics: Arc<dyn Metrics>,
+    cache: Option<ResponseCache>,
+    timeouts: Timeouts,
+    proxy: Option<Proxy>,
+    connection: ConnectionOptions,
+    #[cfg(feature = "tokio")]
+    tls: TlsOptions,
+    user_agent: String,
+    credentials: Option<Credentials>,
+    clock: Option<Arc<ServerClock>>,
+    shutdown: Option<Shutdown>,
+    resume_streams: bool,
+    pub(crate) retail_portfolio: Option<String>,
+}
+
+/// How requests to endpoints acting on an account are authorized.
+#[derive(Clone)]
+enum Credentials {
+    /// API key name and PEM encoded private key signing every request.
+    Key(String, String),
+    /// OAuth2 access token sent as a bearer token.
+    OAuth(String),
+    /// Exchange API key, base64 encoded secret and passphrase signing every request.
+    #[cfg(feature = "exchange")]
+    Exchange {
+        key: String,
+        secret: String,
+        passphrase: String,
+    },
+    /// Prime API key, secret and passphrase signing every request.
+    #[cfg(feature = "prime")]
+    Prime {
+        key: String,
+        secret: String,
+        passphrase: String,
+    },
+}
+
+impl Client {
+    pub fn new(uri: &str) -> Self {
+        let timeouts = Timeouts::default();
+        let proxy = Proxy::from_env();
+        let connection = ConnectionOptions::default();
+        let uri = uri.to_string();
+
+        let mut client = Self {
+            uri,
+            transport: Arc::new(NoTransport),
+            connection,
+            #[cfg(feature = "tokio")]
+            tls: TlsOptions::default(),
+            user_agent: request::USER_AGENT.to_string(),
+            custom_transport: false,
+            timeouts,
+            proxy,
+            retry: Arc::new(Exponential::default()),
+            limiter: Arc::new(RateLimiter::new(Some(RateLimit::default()))),
+            priority: None,
+            breaker: None,
+            middleware: Vec::new(),
+            debug_log: None,
+            metrics: Arc::new(NoMetrics),
+            cache: None,
+            credentials: None,
+            clock: None,
+            shutdown: None,
+            resume_streams: false,
+            retail_portfolio: None,
+        };
+        client.rebuild_transport();
+        client
+    }
+
+    /// Creates a client signing requests with the API key name `key` and the PEM encoded
+    /// private key `secret`.
+    pub fn new_with_auth(uri: &str, key: &str, secret: &str) -> Self {
+        Self::new(uri).with_credentials(key, secret)
+    }
+
+    /// Creates a client talking to the sandbox environment, see [`Environment::Sandbox`].
+    pub fn new_sandbox() -> Self {
+        Self::new(Environment::Sandbox.base_url())
+    }
+
+    /// Creates a client opening connections with `connector` instead of the default TLS
+    /// connector, e.g. to reach a test server on a unix socket, resolve names differently or
+    /// present a client certificate. The connector is responsible for TLS.
+    #[cfg(feature = "tokio")]
+    pub fn new_with_connector<C>(uri: &str, connector: C) -> Self
+    where
+        C: Connect + Clone + Send + Sync + 'static,
+    {
+        let transport = HyperTransport::with_connector(connector, &ConnectionOptions::default());
+        Self::new(uri).with_transport(transport)
+    }
+
+    /// Signs requests with the API key name `key` and the PEM encoded private key `secret`,
+    /// enabling the endpoints that act on an account.
+    pub fn with_credentials(self, key: &str, secret: &str) -> Self {
+        let mut _self = self;
+        _self.credentials = Some(Credentials::Key(key.to_string(), secret.to_string()));
+        _self
+    }
+
+    /// Dates the JWTs signing requests by the server's clock instead of the local one, measuring
+    /// the offset from `/v2/time` again once it is older than `refresh`. Machines with a skewed
+    /// clock otherwise get sporadic 401s for tokens that look expired or not yet valid. `None`,
+    /// the default, uses the local clock. Clones made afterwards share the measured offset.
+    pub fn with_clock_correction(self, refresh: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.clock = refresh.map(|refresh| Arc::new(ServerClock::new(refresh)));
+        _self
+    }
+
+    /// Authorizes requests with an OAuth2 access token instead of an API key, e.g. in a browser
+    /// where private keys should not be shipped.
+    pub fn with_oauth_token(self, token: &str) -> Self {
+        let mut _self = self;
+        _self.credentials = Some(Credentials::OAuth(token.to_string()));
+        _self
+    }
+
+    /// Pins Advanced Trade account and order queries to the portfolio with the UUID
+    /// `portfolio`, for keys with access to several portfolios. Without it the API uses the
+    /// key's default portfolio.
+    pub fn with_retail_portfolio(self, portfolio: &str) -> Self {
+        let mut _self = self;
+        _self.retail_portfolio = Some(portfolio.to_string());
+        _self
+    }
+
+    /// Signs requests the way the Exchange API expects, see [`crate::exchange`].
+    #[cfg(feature = "exchange")]
+    pub(crate) fn with_exchange_credentials(
+        self,
+        key: &str,
+        secret: &str,
+        passphrase: &str,
+    ) -> Self {
+        let mut _self = self;
+        _self.credentials = Some(Credentials::Exchange {
+            key: key.to_string(),
+            secret: secret.to_string(),
+            passphrase: passphrase.to_string(),
+        });
+        _self
+    }
+
+    /// Signs requests the way the Prime API expects, see [`crate::prime`].
+    #[cfg(feature = "prime")]
+    pub(crate) fn with_prime_credentials(self, key: &str, secret: &str, passphrase: &str) -> Self {
+        let mut _self = self;
+        _self.credentials = Some(Credentials::Prime {
+            key: key.to_string(),
+            secret: secret.to_string(),
+            passphrase: passphrase.to_string(),
+        });
+        _self
+    }
+
+    /// Ends the paginated and polling streams of this client at their next page or poll once
+    /// `shutdown` is triggered, see [`Shutdown`]. Clones made afterwards watch it too.
+    pub fn with_shutdown(self, shutdown: &Shutdown) -> Self {
+        let mut _self = self;
+        _self.shutdown = Some(shutdown.clone());
+        _self
+    }
+
+    /// Keeps paginated streams going through transient failures, e.g. to let a sync of
+    /// thousands of pages survive an outage. A page whose request fails with an error the retry
+    /// policy would retry is then requested again, however often it fails, backing off further
+    /// every time up to a minute; only errors the policy gives up on right away end the stream.
+    /// Off by default, when a page failing once its retries are used up ends the stream.
+    pub fn with_resumable_streams(self, resume: bool) -> Self {
+        let mut _self = self;
+        _self.resume_streams = resume;
+        _self
+    }
+
+    /// Replaces the policy used to retry failed requests.
+    pub fn with_retry_policy<P: RetryPolicy + 'static>(self, retry: P) -> Self {
+        let mut _self = self;
+        _self.retry = Arc::new(retry);
+        _self
+    }
+
+    /// Replaces the limit on how fast requests are sent, `None` disables rate limiting.
+    pub fn with_rate_limit(self, limit: Option<RateLimit>) -> Self {
+        let mut _self = self;
+        _self.limiter = Arc::new(RateLimiter::new(limit));
+        _self
+    }
+
+    /// Queues the requests of this client at `priority` when they wait for the rate limiter,
+    /// instead of by their method. Tag e.g. a clone running a long pagination job with
+    /// [`Priority::Low`] so it yields to order placement on the other clones.
+    pub fn with_priority(self, priority: Priority) -> Self {
+        let mut _self = self;
+        _self.priority = Some(priority);
+        _self
+    }
+
+    /// Fails requests fast while a host keeps failing, `None`, the default, disables the circuit
+    /// breaker. Clones made afterwards share the state of the circuits.
+    pub fn with_circuit_breaker(self, breaker: Option<CircuitBreaker>) -> Self {
+        let mut _self = self;
+        _self.breaker = breaker.map(|breaker| Arc::new(Breaker::new(breaker)));
+        _self
+    }
+
+    /// Draws requests from `budget`, shared with every other client using it.
+    pub fn with_rate_budget(self, budget: &RateBudget) -> Self {
+        let mut _self = self;
+        _self.limiter = budget.limiter.clone();
+        _self
+    }
+
+    /// The rate limit budget of this client, to share it with clients created separately.
+    pub fn rate_budget(&self) -> RateBudget {
+        RateBudget {
+            limiter: self.limiter.clone(),
+        }
+    }
+
+    /// Adds a hook that sees every request before it is sent and every response received.
+    pub fn with_middleware<M: Middleware + 'static>(self, middleware: M) -> Self {
+        let mut _self = self;
+        _self.middleware.push(Arc::new(middleware));
+        _self
+    }
+
+    /// Logs every request and response with credentials and account identifiers redacted,
+    /// `None`, the default, disables logging.
+    pub fn with_debug_log(self, debug_log: Option<DebugLog>) -> Self {
+        let mut _self = self;
+        _self.debug_log = debug_log;
+        _self
+    }
+
+    /// Answers repeated requests to public endpoints from `cache` while they are fresh. Clients
+    /// given clones of the same cache share its responses.
+    pub fn with_cache(self, cache: ResponseCache) -> Self {
+        let mut _self = self;
+        _self.cache = Some(cache);
+        _self
+    }
+
+    /// Reports request counts, latencies, rate limiting and retries to `metrics`.
+    pub fn with_metrics<M: Metrics + 'static>(self, metrics: M) -> Self {
+        let mut _self = self;
+        _self.metrics = Arc::new(metrics);
+        _self
+    }
+
+    /// Replaces the connect, request and total timeouts.
+    pub fn with_timeouts(self, timeouts: Timeouts) -> Self {
+        let mut _self = self;
+        _self.timeouts = timeouts;
+        _self.rebuild_transport();
+        _self
+    }
+
+    /// Replaces the outbound proxy, which by default is read from the `HTTPS_PROXY` environment
+    /// variable. `None` connects directly.
+    pub fn with_proxy(self, proxy: Option<Proxy>) -> Self {
+        let mut _self = self;
+        _self.proxy = proxy;
+        _self.rebuild_transport();
+        _self
+    }
+
+    /// Replaces the HTTP/2, connection pool and keep-alive settings.
+    pub fn with_connection_options(self, connection: ConnectionOptions) -> Self {
+        let mut _self = self;
+        _self.connection = connection;
+        _self.rebuild_transport();
+        _self
+    }
+
+    /// Replaces the trusted root certificates, client certificate and other TLS settings.
+    #[cfg(feature = "tokio")]
+    pub fn with_tls_options(self, tls: TlsOptions) -> Self {
+        let mut _self = self;
+        _self.tls = tls;
+        _self.rebuild_transport();
+        _self
+    }
+
+    /// Replaces the `User-Agent` sent with every request, `coinbase-rs/<version>` by default.
+    pub fn with_user_agent(self, user_agent: &str) -> Self {
+        let mut _self = self;
+        _self.user_agent = user_agent.to_string();
+        _self
+    }
+
+    /// Appends a product identifier such as `myapp/1.2` to the `User-Agent`, which helps
+    /// Coinbase support to find an integration's requests.
+    pub fn with_user_agent_suffix(self, suffix: &str) -> Self {
+        let mut _self = self;
+        _self.user_agent = format!("{} {}", _self.user_agent, suffix);
+        _self
+    }
+
+    /// Sends requests through `transport` instead of the default
+    /// [`HyperTransport`](crate::transport::HyperTransport). The
+    /// proxy, connect timeout, connection and TLS options are settings of the default transport
+    /// and no longer apply.
+    pub fn with_transport<T: HttpTransport + 'static>(self, transport: T) -> Self {
+        let mut _self = self;
+        _self.transport = Arc::new(transport);
+        _self.custom_transport = true;
+        _self
+    }
+
+    /// Returns a client sending requests to `base_url` instead, e.g. a recording proxy or a
+    /// regional mirror, for a single call:
+    ///
+    /// ```no_run
+    /// # async fn run(client: coinbase_rs::Client) -> coinbase_rs::Result<()> {
+    /// let price = client.at("http://localhost:8080").spot_price("BTC-USD", None).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The returned client shares everything else, including the connection pool and the rate
+    /// limiter, with this one.
+    pub fn at(&self, base_url: &str) -> Self {
+        let mut client = self.clone();
+        client.uri = base_url.to_string();
+        client
+    }
+
+    fn rebuild_transport(&mut self) {
+        if !self.custom_transport {
+            self.transport = default_transport(
+                self.timeouts.connect,
+                self.proxy.clone(),
+                &self.connection,
+                #[cfg(feature = "tokio")]
+                &self.tls,
+            );
+        }
+    }
+
+    /// Sends `request`, retrying it as the policy allows, and deserializes the whole response
+    /// body into `T`.
+    pub(crate) async fn make_request<T>(&self, request: request::Builder) -> Result<ApiResponse<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let (mut parts, body) = self.fetch(request).await?;
+        let status = parts.status;
+        // Compressed bodies are only left encoded when nothing needs to inspect them.
+        if let Some(data) = deserialize_encoded::<T>(&parts.headers, &body) {
+            return Ok(ApiResponse {
+                data,
+                meta: ResponseMeta::new(status, &parts.headers),
+            });
+        }
+        let body = decompress(&mut parts, body)
+            .map_err(|e| TransportError::new(TransportErrorKind::Other, e))?;
+        // Empty responses such as 204 No Content deserialize like `null`, e.g. into `()`.
+        let json: &[u8] = if body.is_empty() { b"null" } else { &body };
+        let data = serde_json::from_slice::<T>(json).map_err(|source| CBError::Deserialize {
+            status,
+            body: body_excerpt(&body),
+            source,
+        })?;
+        Ok(ApiResponse {
+            data,
+            meta: ResponseMeta::new(status, &parts.headers),
+        })
+    }
+
+    /// Sends `request` like [`make_request`](Self::make_request), returning the decoded body of
+    /// the successful response instead of deserializing it.
+    pub(crate) async fn make_request_body(
+        &self,
+        request: request::Builder,
+    ) -> Result<ApiResponse<Bytes>> {
+        let (mut parts, body) = self.fetch(request).await?;
+        let body = decompress(&mut parts, body)
+            .map_err(|e| TransportError::new(TransportErrorKind::Other, e))?;
+        Ok(ApiResponse {
+            data: body,
+            meta: ResponseMeta::new(parts.status, &parts.headers),
+        })
+    }
+
+    /// Requests a page of a paginated stream like [`make_request`](Self::make_request). With
+    /// [resumable streams](Self::with_resumable_streams), the page is requested again after a
+    /// transient error until it arrives, the error is permanent or the [`Shutdown`] of the
+    /// client is triggered.
+    pub(crate) async fn make_page_request<T>(
+        &self,
+        request: request::Builder,
+    ) -> Result<ApiResponse<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let mut resumes = 0;
+        let mut previous_delay = None;
+        loop {
+            let error = match self.make_request(request.clone()).await {
+                Err(error) if self.resume_streams => error,
+                result => return result,
+            };
+            resumes += 1;
+            let attempt = |number, previous_delay| {
+                Attempt::new(number, request.method_ref(), previous_delay)
+                    .with_idempotency_key(request.has_idempotency_key())
+            };
+            // Errors the policy would retry at all are resumed. Once it runs out of attempts
+            // for the resumes, their delay keeps doubling up to a cap instead.
+            let delay = match self.retry.decide(&attempt(1, None), &error) {
+                Some(first) => self
+                    .retry
+                    .decide(&attempt(resumes, previous_delay), &error)
+                    .or_else(|| previous_delay.map(|delay| (delay * 2).min(MAX_RESUME_DELAY)))
+                    .unwrap_or(first),
+                None => return Err(error),
+            };
+            trace::resume(delay, &error);
+            if !self.pace(Some(delay)).await {
+                return Err(error);
+            }
+            previous_delay = Some(delay);
+        }
+    }
+
+    /// Sends `request`, retrying it as the policy allows, and returns the successful response,
+    /// from the cache if a fresh one is cached.
+    async fn fetch(&self, request: request::Builder) -> Result<(response::Parts, Bytes)> {
+        let cache = self
+            .cache
+            .as_ref()
+            .and_then(|cache| Some((cache, cache.ttl_for(&request)?)));
+        let key = request.uri_ref().to_string();
+        if let Some((cache, _)) = cache {
+            if let Some(cached) = cache.get(&key) {
+                return Ok(cached);
+            }
+        }
+
+        let span = RequestSpan::new(request.method_ref(), request.uri_ref());
+        let mut attempts = 0;
+        let result = with_timeout(
+            self.timeouts.total,
+            self.send_with_retries(&request, &span, &mut attempts),
+        )
+        .await;
+        span.finish(attempts);
+        if let (Some((cache, ttl)), Ok((parts, body))) = (cache, &result) {
+            cache.insert(key, ttl, parts, body);
+        }
+        result
+    }
+
+    async fn send_with_retries(
+        &self,
+        request: &request::Builder,
+        span: &RequestSpan,
+        attempts: &mut u32,
+    ) -> Result<(response::Parts, Bytes)> {
+        let host = request.uri_ref().host().unwrap_or_default();
+        let mut attempt = 1;
+        let mut previous_delay = None;
+        loop {
+            *attempts = attempt;
+            let permit = match &self.breaker {
+                Some(breaker) => Some(breaker.acquire(host)?),
+                None => None,
+            };
+            let result = self.send_request(request.clone(), span).await;
+            if let Some(permit) = permit {
+                permit.record(&result);
+            }
+            match result {
+                Err(error) => match self.retry.decide(
+                    &Attempt::new(attempt, request.method_ref(), previous_delay)
+                        .with_idempotency_key(request.has_idempotency_key()),
+                    &error,
+                ) {
+                    Some(delay) => {
+                        span.retry(attempt, delay, &error);
+                        self.metrics.retry(
+                            request.method_ref(),
+                            request.uri_ref().path(),
+                            attempt,
+                            delay,
+                        );
+                        crate::rt::sleep(delay).await;
+                        previous_delay = Some(delay);
+                        attempt += 1;
+                    }
+                    None => return Err(error),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends `request` once. The body of a successful response is still compressed when there
+    /// is no middleware to show it to, so it can be deserialized while being decoded.
+    async fn send_request(
+        &self,
+        request: request::Builder,
+        span: &RequestSpan,
+    ) -> Result<(response::Parts, Bytes)> {
+        if request.is_authenticated() && self.credentials.is_none() {
+            return Err(CBError::MissingCredentials);
+        }
+        let priority = self
+            .priority
+            .unwrap_or_else(|| Priority::for_method(request.method_ref()));
+        self.limiter.acquire(priority).await;
+
+        // Signed once admitted, so signatures are dated when the request is actually sent
+        // rather than before waiting for the rate limiter.
+        let request = match (request.is_authenticated(), &self.credentials) {
+            (false, _) => request,
+            (true, Some(Credentials::Key(key, secret))) => request
+                .auth(key, secret)
+                .clock_offset(self.server_clock_offset().await),
+            (true, Some(Credentials::OAuth(token))) => request.bearer(token),
+            #[cfg(feature = "exchange")]
+            (
+                true,
+                Some(Credentials::Exchange {
+                    key,
+                    secret,
+                    passphrase,
+                }),
+            ) => {
+                let offset = self.server_clock_offset().await;
+                crate::exchange::sign(request, key, secret, passphrase, offset)?
+            }
+            #[cfg(feature = "prime")]
+            (
+                true,
+                Some(Credentials::Prime {
+                    key,
+                    secret,
+                    passphrase,
+                }),
+            ) => {
+                let offset = self.server_clock_offset().await;
+                crate::prime::sign(request, key, secret, passphrase, offset)?
+            }
+            (true, None) => return Err(CBError::MissingCredentials),
+        };
+
+        let method = request.method_ref().clone();
+        let path = request.uri_ref().path().to_string();
+        let mut request = request
+            .header(header::ACCEPT_ENCODING.as_str(), ACCEPT_ENCODING)
+            .header(header::USER_AGENT.as_str(), &self.user_agent)
+            .try_build()?;
+        let http_span = HttpSpan::start(&mut request);
+        for middleware in &self.middleware {
+            middleware.on_request(&mut request);
+        }
+        let uri = request.uri().clone();
+        if let Some(debug_log) = &self.debug_log {
+            debug_log.request(&request);
+        }
+
+        let started = crate::rt::Instant::now();
+        self.metrics.request_started(&method, &path);
+        let response = with_timeout(self.timeouts.request, async {
+            Ok(self.transport.send(request).await?)
+        })
+        .await;
+        let status = response.as_ref().ok().map(|response| response.status());
+        http_span.finish(response.as_ref().map(|response| response.status()));
+        self.metrics
+            .request_finished(&method, &path, status, started.elapsed());
+
+        let body = response.and_then(|response| {
+            let (mut parts, body) = response.into_parts();
+            if self.middleware.is_empty() && self.debug_log.is_none() && parts.status.is_success() {
+                return Ok((parts, body, true));
+            }
+            let body = decompress(&mut parts, body)
+                .map_err(|e| TransportError::new(TransportErrorKind::Other, e))?;
+            Ok((parts, body, false))
+        });
+        if let Some(debug_log) = &self.debug_log {
+            let response = match &body {
+                Ok((parts, body, _)) => Ok((parts, &body[..])),
+                Err(error) => Err(error),
+            };
+            debug_log.response(&method, &uri, started.elapsed(), response);
+        }
+        let (parts, body, encoded) = body?;
+        if encoded {
+            span.status(parts.status);
+            return Ok((parts, body));
+        }
+        for middleware in &self.middleware {
+            middleware.on_response(&parts, &body);
+        }
+
+        let status = parts.status;
+        span.status(status);
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry_after(&parts.headers);
+            self.metrics.rate_limited(&path, retry_after);
+            return Err(CBError::RateLimited { retry_after });
+        }
+
+        if !status.is_success() {
+            return Err(match serde_json::from_slice(&body) {
+                Ok(error) => CBError::Coinbase { status, error },
+                Err(_) => CBError::Status {
+                    status,
+                    body: body_excerpt(&body),
+                },
+            });
+        }
+        Ok((parts, body))
+    }
+
+    pub(crate) fn get_stream<'a, U>(
+        &'a self,
+        request: request::Builder,
+    ) -> impl Stream<Item = Result<ApiResponse<U>>> + 'a
+    where
+        U: Send + 'static,
+        U: serde::de::DeserializeOwned,
+        U: std::marker::Unpin,
+    {
+        try_stream! {
+            let initial_request = request.clone();
+            let ApiResponse { data: mut result, meta } =
+                self.make_page_request::<Response<U>>(initial_request).await?;
+            let mut page = 1;
+            trace::page(page, result.next_uri());
+            let mut delay = meta.rate_limit.page_delay();
+            yield ApiResponse { data: result.data, meta };
+
+            while let Some(ref next_uri) = result.pagination.and_then(|p| p.next_uri) {
+                if !self.pace(delay).await {
+                    break;
+                }
+                let uri: Uri = (self.uri.to_string() + next_uri).parse().unwrap();
+                let request = request.clone().uri(uri);
+                let response = self.make_page_request(request).await?;
+                result = response.data;
+                page += 1;
+                trace::page(page, result.next_uri());
+                delay = response.meta.rate_limit.page_delay();
+                yield ApiResponse { data: result.data, meta: response.meta };
+            }
+        }
+    }
+
+    pub(crate) async fn get_with_meta<U>(&self, uri: &str) -> Result<ApiResponse<U>>
+    where
+        U: Send + 'static,
+        U: serde::de::DeserializeOwned,
+    {
+        let response = self.make_request::<Response<U>>(self.request(uri)).await?;
+        Ok(ApiResponse {
+            data: response.data.data,
+            meta: response.meta,
+        })
+    }
+
+    /// Measures how far the server's clock is ahead of the local one, half the round trip
+    /// included, and dates JWTs by it from now on if [clock
+    /// correction](Self::with_clock_correction) is enabled. Clients of the Exchange or Prime
+    /// API ask the v2 API of their environment, as those hosts don't serve `/v2/time`.
+    pub async fn sync_clock(&self) -> Result<chrono::Duration> {
+        let sent_at = chrono::Utc::now();
+        let started = crate::rt::Instant::now();
+        let request = self.v2_request("/v2/time");
+        let response = Box::pin(self.make_request::<Response<crate::public::Time>>(request));
+        let time = response.await?.data.data;
+        let round_trip = chrono::Duration::from_std(started.elapsed()).unwrap_or_default();
+        let offset = time.iso - (sent_at + round_trip / 2);
+        if let Some(clock) = &self.clock {
+            clock.set(offset);
+        }
+        Ok(offset)
+    }
+
+    /// The offset signatures are dated by, zero without clock correction, measured again if
+    /// stale. While the server time can't be fetched the last offset is kept for another
+    /// refresh interval.
+    async fn server_clock_offset(&self) -> chrono::Duration {
+        let Some(clock) = &self.clock else {
+            return chrono::Duration::zero();
+        };
+        if let Some(offset) = clock.offset() {
+            return offset;
+        }
+        match self.sync_clock().await {
+            Ok(offset) => offset,
+            Err(_) => {
+                let offset = clock.last_offset().unwrap_or_default();
+                clock.set(offset);
+                offset
+            }
+        }
+    }
+
+    pub(crate) fn request(&self, uri: &str) -> request::Builder {
+        let uri: Uri = (self.uri.to_string() + uri).parse().unwrap();
+        request::Builder::new().uri(uri)
+    }
+
+    /// Builds a request to the Exchange API of the client's environment. Clients pointed at
+    /// any other URL, e.g. a test server, send it there.
+    pub(crate) fn exchange_request(&self, uri: &str) -> request::Builder {
+        let base = match self.uri.as_str() {
+            crate::MAIN_URL => crate::EXCHANGE_URL,
+            crate::SANDBOX_URL => crate::EXCHANGE_SANDBOX_URL,
+            other => other,
+        };
+        let uri: Uri = (base.to_string() + uri).parse().unwrap();
+        request::Builder::new().uri(uri)
+    }
+
+    /// Builds a request to the v2 API of the client's environment, for clients pointed at the
+    /// Exchange or Prime API. Clients pointed at any other URL send it there.
+    fn v2_request(&self, uri: &str) -> request::Builder {
+        let base = match self.uri.as_str() {
+            crate::EXCHANGE_URL => crate::MAIN_URL,
+            crate::EXCHANGE_SANDBOX_URL => crate::SANDBOX_URL,
+            #[cfg(feature = "prime")]
+            crate::prime::PRIME_URL => crate::MAIN_URL,
+            other => other,
+        };
+        let uri: Uri = (base.to_string() + uri).parse().unwrap();
+        request::Builder::new().uri(uri)
+    }
+
+    pub(crate) fn has_credentials(&self) -> bool {
+        self.credentials.is_some()
+    }
+
+    /// Builds a request to an endpoint that needs credentials.
+    pub(crate) fn authenticated_request(&self, uri: &str) -> request::Builder {
+        self.request(uri).authenticated()
+    }
+
+    /// Waits before requesting the next page of a listing, see
+    /// [`RateLimitStatus::page_delay`](crate::RateLimitStatus::page_delay). Returns `false`
+    /// instead once the [`Shutdown`] of the client is triggered, ending the listing.
+    pub(crate) async fn pace(&self, delay: Option<Duration>) -> bool {
+        match (&self.shutdown, delay) {
+            (Some(shutdown), Some(delay)) => shutdown.sleep(delay).await,
+            (Some(shutdown), None) => !shutdown.is_triggered(),
+            (None, Some(delay)) => {
+                crate::rt::sleep(delay).await;
+                true
+            }
+            (None, None) => true,
+        }
+    }
+
+    /// Waits until the next poll of a polling stream. Returns `false` instead once the
+    /// [`Shutdown`] of the client is triggered, ending the stream.
+    pub(crate) async fn idle(&self, interval: Duration) -> bool {
+        self.pace(Some(interval)).await
+    }
+}
+
+/// Longest wait before a resumable stream requests a failing page again.
+const MAX_RESUME_DELAY: Duration = Duration::from_secs(60);
+
+/// Deserializes a compressed body while decoding it, so the decoded JSON, often several times
+/// the size of the compressed body for large pages, is never held in memory as a whole. `None`
+/// for uncompressed bodies and bodies that fail to decode or deserialize, which are decoded as
+/// a whole to report the error, i.e. a second time.
+///
+/// The compressed body itself is still received in full first, as transports hand over whole
+/// bodies, so this saves memory but doesn't yield anything before the response is complete.
+fn deserialize_encoded<T>(headers: &HeaderMap, body: &[u8]) -> Option<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let decoder = decoder(headers, body)?;
+    serde_json::from_reader(BufReader::new(decoder)).ok()
+}
+
+/// Parses a `Retry-After` header given either as delay-seconds or as an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+
+    #[tokio::test]
+    async fn test_shared_between_tasks() {
+        fn assert_send_sync<T: Clone + Send + Sync + 'static>() {}
+        assert_send_sync::<Client>();
+
+        let mock = MockTransport::new();
+        for _ in 0..4 {
+            mock.push_json(
+                "/v2/prices/BTC-USD/spot",
+                r#"{"data":{"amount":"1020.25","currency":"USD"}}"#,
+            );
+        }
+        let client = Client::new(crate::MAIN_URL).with_transport(mock.clone());
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.spot_price("BTC-USD", None).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(mock.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_base_url_override() {
+        let mock = MockTransport::new();
+        let body = r#"{"data":{"amount":"1020.25","currency":"USD"}}"#;
+        mock.push_json("/v2/prices/BTC-USD/spot", body)
+            .push_json("/v2/prices/BTC-USD/spot", body);
+        let client = Client::new(crate::MAIN_URL).with_transport(mock.clone());
+        client
+            .at("http://localhost:8080")
+            .spot_price("BTC-USD", None)
+            .await
+            .unwrap();
+        client.spot_price("BTC-USD", None).await.unwrap();
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].uri().authority().unwrap(), "localhost:8080");
+        assert_eq!(requests[1].uri().host(), Some("api.coinbase.com"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_credentials() {
+        let mock = MockTransport::new();
+        let client = Client::new(crate::MAIN_URL).with_transport(mock.clone());
+        let accounts: Vec<_> = client.accounts().collect().await;
+        assert!(matches!(accounts[..], [Err(CBError::MissingCredentials)]));
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_key() {
+        let mock = MockTransport::new();
+        let client = Client::new(crate::MAIN_URL)
+            .with_credentials("<key>", "not a pem")
+            .with_transport(mock.clone());
+        let accounts: Vec<_> = client.accounts().collect().await;
+        assert!(matches!(
+            accounts[..],
+            [Err(CBError::Auth(crate::error::AuthError::InvalidPem(_)))]
+        ));
+        assert!(mock.requests().is_empty());
+    }
+
+    #[test]
+    fn test_sign_relative_uri() {
+        let request = request::Builder::new_with_auth("<key>", "<secret>")
+            .uri("/v2/accounts".parse().unwrap())
+            .try_build();
+        assert!(matches!(
+            request,
+            Err(crate::error::AuthError::RelativeUri(ref uri)) if uri == "/v2/accounts"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_oauth_token() {
+        let mock = MockTransport::new();
+        mock.push_json("/v2/accounts", r#"{"data":[]}"#);
+        let client = Client::new(crate::MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+        let accounts: Vec<_> = client.accounts().collect().await;
+        assert!(matches!(accounts[..], [Ok(_)]));
+        assert_eq!(
+            mock.requests()[0].headers()["authorization"],
+            "Bearer <token>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clock_correction() {
+        use p256::pkcs8::LineEnding;
+
+        let secret = p256::SecretKey::random(&mut rand::rngs::OsRng);
+        let secret = secret.to_sec1_pem(LineEnding::LF).unwrap();
+        let server_time = chrono::Utc::now() + chrono::Duration::hours(1);
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/time",
+            &format!(
+                r#"{{"data":{{"iso":"{}","epoch":{}}}}}"#,
+                server_time.to_rfc3339(),
+                server_time.timestamp()
+            ),
+        )
+        .push_json("/v2/accounts", r#"{"data":[]}"#)
+        .push_json("/v2/accounts", r#"{"data":[]}"#);
+        let client = Client::new(crate::MAIN_URL)
+            .with_credentials("<key>", &secret)
+            .with_clock_correction(Some(Duration::from_secs(60)))
+            .with_transport(mock.clone());
+
+        let _: Vec<_> = client.accounts().collect().await;
+        let _: Vec<_> = client.accounts().collect().await;
+        let requests = mock.requests();
+        let paths: Vec<_> = requests.iter().map(|r| r.uri().path()).collect();
+        assert_eq!(paths, ["/v2/time", "/v2/accounts", "/v2/accounts"]);
+        let authorization = requests[2].headers()["authorization"].to_str().unwrap();
+        let claims = authorization.split('.').nth(1).unwrap();
+        let claims = base64::decode_config(claims, base64::URL_SAFE_NO_PAD).unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(&claims).unwrap();
+        let issued_at = claims["iat"].as_i64().unwrap();
+        assert!((issued_at - server_time.timestamp()).abs() <= 5);
+        assert_eq!(claims["exp"].as_i64().unwrap(), issued_at + 120);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_custom_connector() {
+        use std::net::SocketAddr;
+        use std::task::{Context, Poll};
+
+        use hyper_util::client::legacy::connect::HttpConnector;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+        use tower_service::Service;
+
+        /// Connects every host name to a local address.
+        #[derive(Clone)]
+        struct Loopback {
+            http: HttpConnector,
+            addr: SocketAddr,
+        }
+
+        impl Service<Uri> for Loopback {
+            type Response = <HttpConnector as Service<Uri>>::Response;
+            type Error = <HttpConnector as Service<Uri>>::Error;
+            type Future = <HttpConnector as Service<Uri>>::Future;
+
+            fn poll_ready(
+                &mut self,
+                cx: &mut Context<'_>,
+            ) -> Poll<std::result::Result<(), Self::Error>> {
+                self.http.poll_ready(cx)
+            }
+
+            fn call(&mut self, _uri: Uri) -> Self::Future {
+                self.http
+                    .call(format!("http://{}", self.addr).parse().unwrap())
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+            let body = r#"{"data":{"amount":"1020.25","currency":"USD"}}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let connector = Loopback {
+            http: HttpConnector::new(),
+            addr,
+        };
+        let client = Client::new_with_connector("http://api.coinbase.test", connector);
+        let price = client.spot_price("BTC-USD", None).await.unwrap();
+        assert_eq!(price.currency, "USD");
+    }
+
+    #[tokio::test]
+    async fn test_compressed_response() {
+        use std::io::Write;
+
+        fn gzip(body: &str) -> http::Response<bytes::Bytes> {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            encoder.write_all(body.as_bytes()).unwrap();
+            http::Response::builder()
+                .header(header::CONTENT_ENCODING, "gzip")
+                .header("CB-AFTER", "42")
+                .body(encoder.finish().unwrap().into())
+                .unwrap()
+        }
+
+        let mock = MockTransport::new();
+        mock.push(
+            "/v2/prices/BTC-USD/spot",
+            gzip(r#"{"data":{"amount":"1020.25","currency":"USD"}}"#),
+        )
+        .push("/v2/prices/BTC-USD/spot", gzip(r#"{"data":{"amount":"#));
+        let client = Client::new(crate::MAIN_URL).with_transport(mock.clone());
+
+        let response = client.spot_price_with_meta("BTC-USD", None).await.unwrap();
+        assert_eq!(response.data.amount, "1020.25".parse().unwrap());
+        assert_eq!(response.meta.after.as_deref(), Some("42"));
+
+        match client.spot_price("BTC-USD", None).await {
+            Err(CBError::Deserialize { body, .. }) => assert_eq!(body, r#"{"data":{"amount":"#),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_paced_pages() {
+        let page = |next_uri: &str, remaining: u32| {
+            let reset = chrono::Utc::now().timestamp() + 10;
+            http::Response::builder()
+                .header("x-ratelimit-limit", "100")
+                .header("x-ratelimit-remaining", remaining.to_string())
+                .header("x-ratelimit-reset", reset.to_string())
+                .body(bytes::Bytes::from(format!(
+                    r#"{{"pagination":{{"limit":1,"order":"asc","next_uri":{next_uri}}},
+                        "data":[{{"id":"BTC","name":"Bitcoin","min_size":"0.00000001"}}]}}"#
+                )))
+                .unwrap()
+        };
+        let mock = MockTransport::new();
+        mock.push(
+            "/v2/currencies?limit=100",
+            page(r#""/v2/currencies?limit=100&starting_after=BTC""#, 50),
+        )
+        .push(
+            "/v2/currencies?limit=100&starting_after=BTC",
+            page(r#""/v2/currencies?limit=100&starting_after=ETH""#, 0),
+        )
+        .push(
+            "/v2/currencies?limit=100&starting_after=ETH",
+            page("null", 0),
+        );
+        let client = Client::new(crate::MAIN_URL).with_transport(mock.clone());
+
+        // Plenty of requests are left after the first page, none after the second.
+        let start = crate::rt::Instant::now();
+        let pages: Vec<_> = client.currencies().collect().await;
+        assert_eq!(pages.len(), 3);
+        assert!(start.elapsed() >= Duration::from_secs(9));
+        assert!(start.elapsed() < Duration::from_secs(11));
+        assert!(mock.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown() {
+        let page = |next_uri: &str| {
+            format!(
+                r#"{{"pagination":{{"limit":1,"order":"asc","next_uri":{next_uri}}},
+                    "data":[{{"id":"BTC","name":"Bitcoin","min_size":"0.00000001"}}]}}"#
+            )
+        };
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/currencies?limit=100",
+            &page(r#""/v2/currencies?limit=100&starting_after=BTC""#),
+        )
+        .push_json(
+            "/v2/currencies?limit=100&starting_after=BTC",
+            &page(r#""/v2/currencies?limit=100&starting_after=ETH""#),
+        )
+        .push_json(
+            "/v2/prices/BTC-USD/spot",
+            r#"{"data":{"amount":"1020.25","currency":"USD"}}"#,
+        );
+        let shutdown = Shutdown::new();
+        let client = Client::new(crate::MAIN_URL)
+            .with_shutdown(&shutdown)
+            .with_transport(mock.clone());
+
+        // The page in flight is yielded, the next one is never requested.
+        let pages = client.currencies();
+        futures::pin_mut!(pages);
+        assert!(pages.next().await.unwrap().is_ok());
+        shutdown.trigger();
+        assert!(pages.next().await.is_none());
+
+        // A polling stream ends instead of waiting for its next poll.
+        let prices = client.spot_price_stream("BTC-USD", Duration::from_secs(60));
+        let started = crate::rt::Instant::now();
+        let prices: Vec<_> = prices.collect().await;
+        assert_eq!(prices.len(), 1);
+        assert!(started.elapsed() < Duration::from_secs(60));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resumable_streams() {
+        let page = |next_uri: &str| {
+            format!(
+                r#"{{"pagination":{{"limit":1,"order":"asc","next_uri":{next_uri}}},
+                    "data":[{{"id":"BTC","name":"Bitcoin","min_size":"0.00000001"}}]}}"#
+            )
+        };
+        let next = "/v2/currencies?limit=100&starting_after=BTC";
+        let mock = MockTransport::new();
+        mock.push_json("/v2/currencies?limit=100", &page(&format!("\"{next}\"")));
+        for _ in 0..3 {
+            mock.push_status(next, StatusCode::SERVICE_UNAVAILABLE, "");
+        }
+        mock.push_json(next, &page("null")).push_status(
+            "/v2/currencies?limit=100",
+            StatusCode::NOT_FOUND,
+            "",
+        );
+        let client = Client::new(crate::MAIN_URL)
+            .with_retry_policy(Exponential::default().max_attempts(2))
+            .with_resumable_streams(true)
+            .with_transport(mock.clone());
+
+        // The second page fails its two attempts and is requested again.
+        let pages: Vec<_> = client.currencies().collect().await;
+        assert_eq!(pages.len(), 2);
+        assert!(pages.iter().all(|page| page.is_ok()));
+        assert_eq!(mock.requests().len(), 5);
+
+        // Permanent errors still end the stream.
+        let pages: Vec<_> = client.currencies().collect().await;
+        assert!(matches!(pages[..], [Err(CBError::Status { .. })]));
+        assert!(mock.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resume_backoff() {
+        /// Records when each request is sent.
+        #[derive(Default)]
+        struct Sent(std::sync::Mutex<Vec<crate::rt::Instant>>);
+
+        impl Middleware for Sent {
+            fn on_request(&self, _request: &mut http::Request<Bytes>) {
+                self.0.lock().unwrap().push(crate::rt::Instant::now());
+            }
+        }
+
+        let path = "/v2/currencies?limit=100";
+        let mock = MockTransport::new();
+        for _ in 0..8 {
+            mock.push_status(path, StatusCode::SERVICE_UNAVAILABLE, "");
+        }
+        mock.push_json(path, r#"{"data":[]}"#);
+        let sent = Arc::new(Sent::default());
+        let client = Client::new(crate::MAIN_URL)
+            .with_retry_policy(
+                Exponential::default()
+                    .max_attempts(2)
+                    .base_delay(Duration::from_secs(1))
+                    .jitter(0.0),
+            )
+            .with_resumable_streams(true)
+            .with_middleware(sent.clone())
+            .with_transport(mock.clone());
+
+        let pages: Vec<_> = client.currencies().collect().await;
+        assert!(pages[0].is_ok());
+        let sent = sent.0.lock().unwrap();
+        let waits: Vec<_> = sent
+            .windows(2)
+            .map(|sent| (sent[1] - sent[0]).as_secs())
+            .collect();
+        // Retries within a resume wait 1 s, resumes back off further and further.
+        assert_eq!(waits, [1, 1, 1, 2, 1, 4, 1, 8]);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(header::RETRY_AFTER, "30".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(30)));
+
+        headers.insert(
+            header::RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), None);
+
+        let later = chrono::Utc::now() + chrono::Duration::seconds(120);
+        headers.insert(header::RETRY_AFTER, later.to_rfc2822().parse().unwrap());
+        assert!(retry_after(&headers).unwrap() > Duration::from_secs(100));
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::rt::Instant;
+
+/// Offset of the server's clock from the local one, measured every `refresh`, that JWTs are
+/// dated with so a skewed local clock doesn't get them rejected as expired.
+#[derive(Debug)]
+pub(crate) struct ServerClock {
+    refresh: Duration,
+    measured: Mutex<Option<(chrono::Duration, Instant)>>,
+}
+
+impl ServerClock {
+    pub(crate) fn new(refresh: Duration) -> Self {
+        Self {
+            refresh,
+            measured: Mutex::new(None),
+        }
+    }
+
+    /// The last offset measured, `None` if it is older than the refresh interval.
+    pub(crate) fn offset(&self) -> Option<chrono::Duration> {
+        let measured = *self.measured.lock().unwrap();
+        measured
+            .filter(|(_, at)| at.elapsed() < self.refresh)
+            .map(|(offset, _)| offset)
+    }
+
+    /// The last offset measured, however old, e.g. to keep signing while the server time
+    /// can't be fetched.
+    pub(crate) fn last_offset(&self) -> Option<chrono::Duration> {
+        self.measured.lock().unwrap().map(|(offset, _)| offset)
+    }
+
+    pub(crate) fn set(&self, offset: chrono::Duration) {
+        *self.measured.lock().unwrap() = Some((offset, Instant::now()));
+    }
+}
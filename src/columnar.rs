@@ -0,0 +1,258 @@
+//! Arrow record batches and Parquet files of candles and trades, behind the `parquet` feature.
+//!
+//! A batch of [`Candle`]s or [`Trade`]s converts to a [`RecordBatch`] in one go, ready for
+//! Polars, DataFusion or any other Arrow consumer. Prices and sizes become `Float64` columns
+//! and times `Timestamp(Microsecond, "UTC")` ones. A [`ParquetWriter`] appends batches to a
+//! Parquet file.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use std::fs::File;
+//!
+//! use chrono::{TimeZone, Utc};
+//! use coinbase_rs::columnar::ParquetWriter;
+//! use coinbase_rs::market::Granularity;
+//! use coinbase_rs::{Client, MAIN_URL};
+//!
+//! let client = Client::new(MAIN_URL);
+//! let mut writer = ParquetWriter::new(File::create("btc-usd.parquet")?)?;
+//! let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+//! let end = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+//! client
+//!     .write_candles_parquet("BTC-USD", Granularity::OneHour, start, end, &mut writer)
+//!     .await?;
+//! println!("wrote {} candles", writer.rows());
+//! writer.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::Write;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use futures::stream::StreamExt;
+use parquet::arrow::ArrowWriter;
+
+use crate::market::{Candle, Granularity, Trade};
+use crate::{CBError, Client, DateTime, Result};
+
+/// A record with a fixed Arrow schema.
+pub trait Columnar: Sized {
+    fn schema() -> SchemaRef;
+
+    /// The columns of `rows`, in the order of the [`schema`](Self::schema).
+    fn columns(rows: &[Self]) -> Vec<ArrayRef>;
+
+    fn record_batch(rows: &[Self]) -> Result<RecordBatch> {
+        RecordBatch::try_new(Self::schema(), Self::columns(rows)).map_err(storage)
+    }
+}
+
+impl Columnar for Candle {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field("start"),
+            Field::new("low", DataType::Float64, false),
+            Field::new("high", DataType::Float64, false),
+            Field::new("open", DataType::Float64, false),
+            Field::new("close", DataType::Float64, false),
+            Field::new("volume", DataType::Float64, false),
+        ]))
+    }
+
+    fn columns(candles: &[Self]) -> Vec<ArrayRef> {
+        vec![
+            timestamps(candles.iter().map(|candle| candle.start)),
+            floats(candles.iter().map(|candle| &candle.low)),
+            floats(candles.iter().map(|candle| &candle.high)),
+            floats(candles.iter().map(|candle| &candle.open)),
+            floats(candles.iter().map(|candle| &candle.close)),
+            floats(candles.iter().map(|candle| &candle.volume)),
+        ]
+    }
+}
+
+impl Columnar for Trade {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("trade_id", DataType::Utf8, false),
+            Field::new("product_id", DataType::Utf8, false),
+            Field::new("price", DataType::Float64, false),
+            Field::new("size", DataType::Float64, false),
+            timestamp_field("time"),
+            Field::new("side", DataType::Utf8, false),
+        ]))
+    }
+
+    fn columns(trades: &[Self]) -> Vec<ArrayRef> {
+        let side: StringArray = trades
+            .iter()
+            .map(|trade| Some(trade.side.to_string()))
+            .collect();
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                trades.iter().map(|trade| &trade.trade_id),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                trades.iter().map(|trade| &trade.product_id),
+            )),
+            floats(trades.iter().map(|trade| &trade.price)),
+            floats(trades.iter().map(|trade| &trade.size)),
+            timestamps(trades.iter().map(|trade| trade.time)),
+            Arc::new(side),
+        ]
+    }
+}
+
+/// Writes batches of one [`Columnar`] record to a Parquet file.
+pub struct ParquetWriter<W: Write + Send, T: Columnar> {
+    writer: ArrowWriter<W>,
+    rows: usize,
+    record: PhantomData<T>,
+}
+
+impl<W: Write + Send, T: Columnar> ParquetWriter<W, T> {
+    /// Starts a file with the schema of `T` and default writer properties.
+    pub fn new(writer: W) -> Result<Self> {
+        Ok(Self {
+            writer: ArrowWriter::try_new(writer, T::schema(), None).map_err(storage)?,
+            rows: 0,
+            record: PhantomData,
+        })
+    }
+
+    /// Appends `rows` as one record batch.
+    pub fn write(&mut self, rows: &[T]) -> Result<()> {
+        self.writer
+            .write(&T::record_batch(rows)?)
+            .map_err(storage)?;
+        self.rows += rows.len();
+        Ok(())
+    }
+
+    /// Number of rows written so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Writes the footer of the file and returns the writer.
+    pub fn finish(self) -> Result<W> {
+        self.writer.into_inner().map_err(storage)
+    }
+}
+
+impl Client {
+    /// Writes the candles of `product` from `start` up to `end`, fetched as by
+    /// [`fetch_candles_range`](Self::fetch_candles_range), one row group per request. Returns
+    /// the number of candles written.
+    pub async fn write_candles_parquet<W: Write + Send>(
+        &self,
+        product: &str,
+        granularity: Granularity,
+        start: DateTime,
+        end: DateTime,
+        writer: &mut ParquetWriter<W, Candle>,
+    ) -> Result<usize> {
+        let chunks = self.fetch_candles_range(product, granularity, start, end);
+        futures::pin_mut!(chunks);
+        let mut count = 0;
+        while let Some(candles) = chunks.next().await {
+            let candles = candles?;
+            if candles.is_empty() {
+                continue;
+            }
+            writer.write(&candles)?;
+            count += candles.len();
+        }
+        Ok(count)
+    }
+}
+
+fn storage(error: impl std::error::Error + Send + Sync + 'static) -> CBError {
+    CBError::Storage(Box::new(error))
+}
+
+fn timestamp_field(name: &str) -> Field {
+    let unit = TimeUnit::Microsecond;
+    Field::new(name, DataType::Timestamp(unit, Some("UTC".into())), false)
+}
+
+fn timestamps(times: impl Iterator<Item = DateTime>) -> ArrayRef {
+    let micros: Vec<i64> = times.map(|time| time.timestamp_micros()).collect();
+    Arc::new(TimestampMicrosecondArray::from(micros).with_timezone("UTC"))
+}
+
+fn floats<'a>(values: impl Iterator<Item = &'a BigDecimal>) -> ArrayRef {
+    let values: Float64Array = values
+        .map(|value| Some(value.to_f64().unwrap_or(f64::NAN)))
+        .collect();
+    Arc::new(values)
+}
+
+#[cfg(test)]
+mod test {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, TimestampMicrosecondType};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+
+    fn trade(id: &str, price: &str, time: &str) -> Trade {
+        serde_json::from_str(&format!(
+            r#"{{"trade_id":"{id}","product_id":"BTC-USD","price":"{price}","size":"0.5",
+                "time":"{time}","side":"BUY"}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_batch() {
+        let trades = [
+            trade("1", "42000.5", "2024-01-01T00:00:00.25Z"),
+            trade("2", "42001", "2024-01-01T00:00:01Z"),
+        ];
+        let batch = Trade::record_batch(&trades).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), Trade::schema());
+        let prices = batch.column(2).as_primitive::<Float64Type>();
+        assert_eq!(prices.values(), &[42000.5, 42001.0]);
+        let times = batch.column(4).as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(times.value(0), 1_704_067_200_250_000);
+        assert_eq!(batch.column(5).as_string::<i32>().value(0), "BUY");
+    }
+
+    #[test]
+    fn test_parquet_writer() {
+        let mut writer = ParquetWriter::new(Vec::new()).unwrap();
+        writer
+            .write(&[trade("1", "1", "2024-01-01T00:00:00Z")])
+            .unwrap();
+        writer
+            .write(&[
+                trade("2", "2", "2024-01-01T00:00:01Z"),
+                trade("3", "3", "2024-01-01T00:00:02Z"),
+            ])
+            .unwrap();
+        assert_eq!(writer.rows(), 3);
+        let file = bytes::Bytes::from(writer.finish().unwrap());
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let ids: Vec<String> = reader
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                let ids = batch.column(0).as_string::<i32>();
+                ids.iter()
+                    .map(|id| id.unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+}
@@ -0,0 +1,514 @@
+//! Coinbase Commerce API, for merchants accepting crypto payments.
+//!
+//! [`CommerceClient`] sends its requests through a [`Client`], so Commerce calls share its
+//! transport, retries, rate limiting, middleware and errors.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::commerce::{CommerceClient, CreateCharge};
+//!
+//! let commerce = CommerceClient::new("<api key>");
+//! let charge = commerce
+//!     .create_charge(&CreateCharge::fixed_price("T-shirt", "Size M", "25.00".parse().unwrap(), "USD"))
+//!     .await?;
+//! println!("pay at {:?}", charge.hosted_url);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use async_stream::try_stream;
+use bigdecimal::BigDecimal;
+use futures::stream::Stream;
+use hmac::{Hmac, Mac};
+use http::{Method, Uri};
+use serde_json::Value;
+use sha2::Sha256;
+use thiserror::Error;
+use uritemplate::UriTemplate;
+
+use crate::{request, ApiResponse, Client, DateTime, Result};
+
+/// Base URL of the Commerce API.
+pub const COMMERCE_URL: &str = "https://api.commerce.coinbase.com";
+
+/// Version of the Commerce API the types of this module follow.
+pub const COMMERCE_VERSION: &str = "2018-03-22";
+
+/// Name of the header carrying the signature of a webhook event.
+pub const SIGNATURE_HEADER: &str = "X-CC-Webhook-Signature";
+
+/// Client for the Commerce API, authenticated with an API key from the Commerce settings.
+#[derive(Clone)]
+pub struct CommerceClient {
+    client: Client,
+    api_key: String,
+}
+
+impl CommerceClient {
+    /// Creates a client with the default transport and settings.
+    pub fn new(api_key: &str) -> Self {
+        Self::with_client(&Client::new(COMMERCE_URL), api_key)
+    }
+
+    /// Creates a client sending requests through `client`, sharing its transport, retry
+    /// policy, rate limiter, middleware and metrics. Requests go to [`COMMERCE_URL`] unless
+    /// `client` is pointed at a URL other than Coinbase's, e.g. a test server.
+    pub fn with_client(client: &Client, api_key: &str) -> Self {
+        let mut client = client.clone();
+        if client.uri == crate::MAIN_URL || client.uri == crate::SANDBOX_URL {
+            client.uri = COMMERCE_URL.to_string();
+        }
+        Self {
+            client,
+            api_key: api_key.to_string(),
+        }
+    }
+
+    ///
+    /// **Create a charge**
+    ///
+    /// Creates a charge, a payment request the customer pays at its hosted URL.
+    ///
+    /// https://docs.cdp.coinbase.com/commerce-onchain/reference/creates-a-charge
+    ///
+    pub async fn create_charge(&self, charge: &CreateCharge) -> Result<Charge> {
+        self.post("/charges", charge).await
+    }
+
+    ///
+    /// **Show a charge**
+    ///
+    /// Retrieves a charge by its code or id.
+    ///
+    /// https://docs.cdp.coinbase.com/commerce-onchain/reference/retrieve-a-charge
+    ///
+    pub async fn charge(&self, code: &str) -> Result<Charge> {
+        let uri = UriTemplate::new("/charges/{code}")
+            .set("code", code)
+            .build();
+        self.get(&uri).await
+    }
+
+    ///
+    /// **Cancel a charge**
+    ///
+    /// Cancels a charge that hasn't been paid yet.
+    ///
+    pub async fn cancel_charge(&self, code: &str) -> Result<Charge> {
+        let uri = UriTemplate::new("/charges/{code}/cancel")
+            .set("code", code)
+            .build();
+        self.post(&uri, &serde_json::json!({})).await
+    }
+
+    ///
+    /// **List charges**
+    ///
+    /// Lists the charges of the merchant, newest first.
+    ///
+    /// https://docs.cdp.coinbase.com/commerce-onchain/reference/list-charges
+    ///
+    pub fn charges(&self) -> impl Stream<Item = Result<Vec<Charge>>> + '_ {
+        self.list("/charges")
+    }
+
+    ///
+    /// **Create a checkout**
+    ///
+    /// Creates a checkout, a reusable product page every payment of which creates a charge.
+    ///
+    pub async fn create_checkout(&self, checkout: &CreateCheckout) -> Result<Checkout> {
+        self.post("/checkouts", checkout).await
+    }
+
+    ///
+    /// **Show a checkout**
+    ///
+    pub async fn checkout(&self, id: &str) -> Result<Checkout> {
+        let uri = UriTemplate::new("/checkouts/{id}").set("id", id).build();
+        self.get(&uri).await
+    }
+
+    ///
+    /// **List checkouts**
+    ///
+    /// Lists the checkouts of the merchant, newest first.
+    ///
+    pub fn checkouts(&self) -> impl Stream<Item = Result<Vec<Checkout>>> + '_ {
+        self.list("/checkouts")
+    }
+
+    fn request(&self, uri: &str) -> request::Builder {
+        self.client
+            .request(uri)
+            .header("X-CC-Api-Key", &self.api_key)
+            .header("X-CC-Version", COMMERCE_VERSION)
+    }
+
+    async fn get<U: serde::de::DeserializeOwned>(&self, uri: &str) -> Result<U> {
+        let response = self
+            .client
+            .make_request::<Data<U>>(self.request(uri))
+            .await?;
+        Ok(response.data.data)
+    }
+
+    async fn post<T, U>(&self, uri: &str, body: &T) -> Result<U>
+    where
+        T: serde::Serialize,
+        U: serde::de::DeserializeOwned,
+    {
+        let request = self
+            .request(uri)
+            .method(Method::POST)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(body)?);
+        let response = self.client.make_request::<Data<U>>(request).await?;
+        Ok(response.data.data)
+    }
+
+    /// Follows the `next_uri` of each page, which the Commerce API sends as an absolute URL.
+    fn list<'a, U>(&'a self, uri: &str) -> impl Stream<Item = Result<Vec<U>>> + 'a
+    where
+        U: serde::de::DeserializeOwned + 'a,
+    {
+        let first = self.request(uri);
+        try_stream! {
+            let mut request = first.clone();
+            loop {
+                let ApiResponse { data: page, meta } =
+                    self.client.make_page_request::<Page<U>>(request).await?;
+                yield page.data;
+                match page.pagination.next_uri.and_then(|uri| uri.parse::<Uri>().ok()) {
+                    Some(next) => request = first.clone().uri(next),
+                    None => break,
+                }
+                if !self.client.pace(meta.rate_limit.page_delay()).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Data<U> {
+    data: U,
+}
+
+#[derive(Deserialize, Debug)]
+struct Page<U> {
+    pagination: CommercePagination,
+    data: Vec<U>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommercePagination {
+    next_uri: Option<String>,
+}
+
+/// An amount of a currency.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Money {
+    pub amount: BigDecimal,
+    pub currency: String,
+}
+
+/// Parameters of a [`create_charge`](CommerceClient::create_charge) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateCharge {
+    pub name: String,
+    pub description: String,
+    /// `fixed_price` or `no_price`.
+    pub pricing_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_price: Option<Money>,
+    /// Data of the merchant kept with the charge, e.g. an order id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_url: Option<String>,
+}
+
+impl CreateCharge {
+    /// A charge for `amount` of `currency`, usually a fiat currency.
+    pub fn fixed_price(name: &str, description: &str, amount: BigDecimal, currency: &str) -> Self {
+        Self {
+            local_price: Some(Money {
+                amount,
+                currency: currency.to_string(),
+            }),
+            pricing_type: "fixed_price".to_string(),
+            ..Self::no_price(name, description)
+        }
+    }
+
+    /// A charge the customer chooses the amount of, e.g. a donation.
+    pub fn no_price(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            pricing_type: "no_price".to_string(),
+            local_price: None,
+            metadata: None,
+            redirect_url: None,
+            cancel_url: None,
+        }
+    }
+
+    pub fn metadata(self, metadata: Value) -> Self {
+        let mut _self = self;
+        _self.metadata = Some(metadata);
+        _self
+    }
+
+    /// Where the customer is sent after paying.
+    pub fn redirect_url(self, url: &str) -> Self {
+        let mut _self = self;
+        _self.redirect_url = Some(url.to_string());
+        _self
+    }
+
+    /// Where the customer is sent after canceling.
+    pub fn cancel_url(self, url: &str) -> Self {
+        let mut _self = self;
+        _self.cancel_url = Some(url.to_string());
+        _self
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Charge {
+    pub id: String,
+    /// Short code identifying the charge, e.g. in its hosted URL.
+    pub code: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub pricing_type: String,
+    /// Amount due in the local currency and each accepted cryptocurrency, keyed by `local` or
+    /// the name of the currency.
+    #[serde(default)]
+    pub pricing: HashMap<String, Money>,
+    /// Page the customer pays the charge at.
+    pub hosted_url: Option<String>,
+    pub created_at: Option<DateTime>,
+    pub expires_at: Option<DateTime>,
+    pub confirmed_at: Option<DateTime>,
+    /// Status changes of the charge, oldest first.
+    #[serde(default)]
+    pub timeline: Vec<TimelineEntry>,
+    pub metadata: Option<Value>,
+}
+
+impl Charge {
+    /// The latest status of the charge, e.g. `NEW`, `PENDING`, `COMPLETED` or `EXPIRED`.
+    pub fn status(&self) -> Option<&str> {
+        self.timeline.last().map(|entry| entry.status.as_str())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimelineEntry {
+    pub time: DateTime,
+    pub status: String,
+    /// Detail of some statuses, e.g. `OVERPAID` for `UNRESOLVED`.
+    pub context: Option<String>,
+}
+
+/// Parameters of a [`create_checkout`](CommerceClient::create_checkout) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateCheckout {
+    pub name: String,
+    pub description: String,
+    /// `fixed_price` or `no_price`.
+    pub pricing_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_price: Option<Money>,
+    /// Customer details to collect, `name` and `email`.
+    pub requested_info: Vec<String>,
+}
+
+impl CreateCheckout {
+    /// A checkout selling for `amount` of `currency`.
+    pub fn fixed_price(name: &str, description: &str, amount: BigDecimal, currency: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            pricing_type: "fixed_price".to_string(),
+            local_price: Some(Money {
+                amount,
+                currency: currency.to_string(),
+            }),
+            requested_info: Vec::new(),
+        }
+    }
+
+    /// Asks the customer for `info`, `name` or `email`.
+    pub fn request_info(self, info: &str) -> Self {
+        let mut _self = self;
+        _self.requested_info.push(info.to_string());
+        _self
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Checkout {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub pricing_type: String,
+    pub local_price: Option<Money>,
+    #[serde(default)]
+    pub requested_info: Vec<String>,
+}
+
+/// An event delivered to a webhook, e.g. `charge:confirmed`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Event {
+    pub id: String,
+    pub r#type: String,
+    pub api_version: Option<String>,
+    pub created_at: Option<DateTime>,
+    /// The resource the event is about, e.g. a [`Charge`].
+    pub data: Value,
+}
+
+impl Event {
+    /// Deserializes `data` into the resource, e.g. a [`Charge`].
+    pub fn data_as<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(T::deserialize(&self.data)?)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Delivery {
+    event: Event,
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("invalid event")]
+    Payload(#[from] serde_json::Error),
+}
+
+/// Checks the `X-CC-Webhook-Signature` header value `signature`, the hex encoded HMAC-SHA256
+/// of the raw request `body` keyed with the shared secret of the webhook subscription, and
+/// deserializes the event the body carries.
+pub fn verify_webhook(
+    secret: &str,
+    signature: &str,
+    body: &[u8],
+) -> std::result::Result<Event, WebhookError> {
+    let signature = decode_hex(signature.trim()).ok_or(WebhookError::InvalidSignature)?;
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes())
+        .map_err(|_| WebhookError::InvalidSignature)?;
+    mac.input(body);
+    mac.verify(&signature)
+        .map_err(|_| WebhookError::InvalidSignature)?;
+    Ok(serde_json::from_slice::<Delivery>(body)?.event)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::TryStreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    const CHARGE: &str = r#"{"id":"f765421f-2ed6-4d10-b3a8-9ed1b9a1c1a6","code":"66BEOV2A",
+        "name":"T-shirt","description":"Size M","pricing_type":"fixed_price",
+        "pricing":{"local":{"amount":"25.00","currency":"USD"},
+                   "bitcoin":{"amount":"0.00040000","currency":"BTC"}},
+        "hosted_url":"https://commerce.coinbase.com/charges/66BEOV2A",
+        "created_at":"2024-01-01T00:00:00Z","expires_at":"2024-01-01T01:00:00Z",
+        "timeline":[{"time":"2024-01-01T00:00:00Z","status":"NEW"}],
+        "metadata":{"order":"1042"}}"#;
+
+    #[tokio::test]
+    async fn test_charges() {
+        let mock = MockTransport::new();
+        mock.push_json("/charges", &format!(r#"{{"data":{CHARGE}}}"#))
+            .push_json(
+                "/charges",
+                &format!(
+                    r#"{{"pagination":{{"order":"desc","limit":1,
+                        "next_uri":"https://api.commerce.coinbase.com/charges?starting_after=f765"}},
+                        "data":[{CHARGE}]}}"#
+                ),
+            )
+            .push_json(
+                "/charges?starting_after=f765",
+                &format!(r#"{{"pagination":{{"next_uri":null}},"data":[{CHARGE}]}}"#),
+            );
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+        let commerce = CommerceClient::with_client(&client, "<api key>");
+
+        let charge = commerce
+            .create_charge(
+                &CreateCharge::fixed_price("T-shirt", "Size M", "25.00".parse().unwrap(), "USD")
+                    .metadata(serde_json::json!({"order": "1042"})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(charge.code, "66BEOV2A");
+        assert_eq!(charge.status(), Some("NEW"));
+        assert_eq!(charge.pricing["bitcoin"].currency, "BTC");
+
+        let charges: Vec<Charge> = commerce.charges().try_concat().await.unwrap();
+        assert_eq!(charges.len(), 2);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].uri().host(), Some("api.commerce.coinbase.com"));
+        assert_eq!(requests[0].headers()["x-cc-api-key"], "<api key>");
+        assert_eq!(requests[0].headers()["x-cc-version"], COMMERCE_VERSION);
+        let body: Value = serde_json::from_slice(requests[0].body()).unwrap();
+        assert_eq!(body["local_price"]["amount"], "25.00");
+    }
+
+    #[test]
+    fn test_verify_webhook() {
+        let body = format!(
+            r#"{{"id":"1","scheduled_for":"2024-01-01T00:00:00Z","attempt_number":1,
+                "event":{{"id":"24934862-d980-46cb-9402-43c81b0cdba6","type":"charge:created",
+                "api_version":"2018-03-22","created_at":"2024-01-01T00:00:00Z","data":{CHARGE}}}}}"#
+        );
+        let mut mac = Hmac::<Sha256>::new_varkey(b"secret").unwrap();
+        mac.input(body.as_bytes());
+        let signature: String = mac
+            .result()
+            .code()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+
+        let event = verify_webhook("secret", &signature, body.as_bytes()).unwrap();
+        assert_eq!(event.r#type, "charge:created");
+        assert_eq!(event.data_as::<Charge>().unwrap().code, "66BEOV2A");
+        assert!(matches!(
+            verify_webhook("other", &signature, body.as_bytes()),
+            Err(WebhookError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify_webhook("secret", "zz", body.as_bytes()),
+            Err(WebhookError::InvalidSignature)
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::{self, Read};
+
+use bytes::Bytes;
+use http::{header, response, HeaderMap};
+
+/// Encodings advertised in `Accept-Encoding` and decoded by `decompress`.
+pub(crate) const ACCEPT_ENCODING: &str = "gzip, br";
+
+/// Decodes a gzip or brotli response body according to its `Content-Encoding`.
+///
+/// The `Content-Encoding` and `Content-Length` headers are removed once the body is decoded, so
+/// the response looks as if it had been sent uncompressed. Bodies with no or an unknown encoding
+/// are returned unchanged.
+pub(crate) fn decompress(parts: &mut response::Parts, body: Bytes) -> io::Result<Bytes> {
+    let Some(mut decoder) = decoder(&parts.headers, &body) else {
+        return Ok(body);
+    };
+    let mut decoded = Vec::with_capacity(body.len() * 4);
+    decoder.read_to_end(&mut decoded)?;
+    parts.headers.remove(header::CONTENT_ENCODING);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Ok(decoded.into())
+}
+
+/// A reader decoding `body` according to the `Content-Encoding` in `headers`, to consume the
+/// decoded body without holding all of it in memory. `None` for bodies with no or an unknown
+/// encoding.
+pub(crate) fn decoder<'a>(headers: &HeaderMap, body: &'a [u8]) -> Option<Box<dyn Read + 'a>> {
+    let encoding = headers
+        .get(header::CONTENT_ENCODING)?
+        .to_str()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match encoding.as_str() {
+        "gzip" | "x-gzip" => Some(Box::new(flate2::read::GzDecoder::new(body))),
+        "br" => Some(Box::new(brotli::Decompressor::new(body, 4096))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use http::Response;
+
+    use super::*;
+
+    const JSON: &[u8] = br#"{"data":{"iso":"2015-06-23T18:02:51Z","epoch":1435082571}}"#;
+
+    fn response_parts(encoding: Option<&str>) -> response::Parts {
+        let mut builder = Response::builder();
+        if let Some(encoding) = encoding {
+            builder = builder.header(header::CONTENT_ENCODING, encoding);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(JSON).unwrap();
+        let body = Bytes::from(encoder.finish().unwrap());
+
+        let mut parts = response_parts(Some("gzip"));
+        assert_eq!(decompress(&mut parts, body).unwrap(), JSON);
+        assert!(parts.headers.get(header::CONTENT_ENCODING).is_none());
+    }
+
+    #[test]
+    fn test_brotli() {
+        let mut body = Vec::new();
+        {
+            let mut encoder = brotli::CompressorWriter::new(&mut body, 4096, 5, 22);
+            encoder.write_all(JSON).unwrap();
+        }
+
+        let mut parts = response_parts(Some("br"));
+        assert_eq!(decompress(&mut parts, body.into()).unwrap(), JSON);
+    }
+
+    #[test]
+    fn test_identity() {
+        let mut parts = response_parts(None);
+        assert_eq!(decompress(&mut parts, Bytes::from(JSON)).unwrap(), JSON);
+
+        let mut parts = response_parts(Some("gzip"));
+        assert!(decompress(&mut parts, Bytes::from(JSON)).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Dollar cost averaging: buying a fixed amount at a regular interval.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::dca::{Dca, MemoryStore};
+//! use coinbase_rs::private::PlaceBuy;
+//! use coinbase_rs::{Client, Uuid, MAIN_URL};
+//!
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! let account: Uuid = "af6fd33a-e20c-494a-b3f6-f91d204af4b7".parse().unwrap();
+//! let dca = Dca::new(account, PlaceBuy::new("25".parse().unwrap(), "USD"))
+//!     .interval(Duration::from_secs(7 * 24 * 60 * 60))
+//!     .jitter(Duration::from_secs(60 * 60));
+//! let error = dca.run(&client, &MemoryStore::default()).await;
+//! eprintln!("buy failed: {error}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::private::{Buy, PlaceBuy};
+use crate::{CBError, Client, DateTime, Result};
+
+/// Persists when the last buy was placed, so a restarted bot doesn't buy again too early.
+pub trait LastRunStore: Send + Sync {
+    fn load(&self) -> Option<DateTime>;
+
+    fn save(&self, at: DateTime);
+}
+
+impl<S: LastRunStore + ?Sized> LastRunStore for Arc<S> {
+    fn load(&self) -> Option<DateTime> {
+        (**self).load()
+    }
+
+    fn save(&self, at: DateTime) {
+        (**self).save(at)
+    }
+}
+
+/// Store keeping the time of the last buy in memory only.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    last_run: Mutex<Option<DateTime>>,
+}
+
+impl LastRunStore for MemoryStore {
+    fn load(&self) -> Option<DateTime> {
+        *self.last_run.lock().unwrap()
+    }
+
+    fn save(&self, at: DateTime) {
+        *self.last_run.lock().unwrap() = Some(at);
+    }
+}
+
+/// Recurring buy of a fixed amount into an account.
+#[derive(Clone, Debug)]
+pub struct Dca {
+    account: Uuid,
+    buy: PlaceBuy,
+    interval: Duration,
+    jitter: Duration,
+}
+
+impl Dca {
+    /// Places `buy` into `account` once a day.
+    pub fn new(account: Uuid, buy: PlaceBuy) -> Self {
+        Self {
+            account,
+            buy,
+            interval: Duration::from_secs(24 * 60 * 60),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Time between two buys.
+    pub fn interval(self, interval: Duration) -> Self {
+        let mut _self = self;
+        _self.interval = interval;
+        _self
+    }
+
+    /// Upper bound of a random delay added to every buy, so that bots started at the same time
+    /// don't all buy at once.
+    pub fn jitter(self, jitter: Duration) -> Self {
+        let mut _self = self;
+        _self.jitter = jitter;
+        _self
+    }
+
+    /// Time the next buy is due, given the time of the last one.
+    pub fn next_run(&self, last_run: Option<DateTime>) -> DateTime {
+        match last_run {
+            Some(last_run) => last_run + self.interval,
+            None => chrono::Utc::now(),
+        }
+    }
+
+    /// Places the buy if it is due according to `store`, then records the time it was placed.
+    /// Returns `None` if it isn't due yet.
+    pub async fn run_once<S>(&self, client: &Client, store: &S) -> Result<Option<Buy>>
+    where
+        S: LastRunStore + ?Sized,
+    {
+        let now = chrono::Utc::now();
+        if store
+            .load()
+            .is_some_and(|last_run| last_run + self.interval > now)
+        {
+            return Ok(None);
+        }
+        let buy = client.place_buy(&self.account, &self.buy).await?;
+        store.save(now);
+        Ok(Some(buy))
+    }
+
+    /// Places the buy whenever it is due, sleeping in between. Only returns when a buy fails,
+    /// with its error.
+    pub async fn run<S>(&self, client: &Client, store: &S) -> CBError
+    where
+        S: LastRunStore + ?Sized,
+    {
+        loop {
+            let due = self.next_run(store.load());
+            let wait = (due - chrono::Utc::now()).to_std().unwrap_or_default();
+            let jitter = self.jitter.mul_f64(rand::thread_rng().gen_range(0.0..=1.0));
+            crate::rt::sleep(wait + jitter).await;
+            if let Err(error) = self.run_once(client, store).await {
+                return error;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    #[tokio::test]
+    async fn test_run_once() {
+        let account = Uuid::nil();
+        let mock = MockTransport::new();
+        mock.push_json(
+            &format!("/v2/accounts/{account}/buys"),
+            r#"{"data":{"id":"67e0eaec-07d7-54c4-a72c-2e92826897df","status":"created",
+                "resource":"buy","resource_path":"/",
+                "amount":{"amount":"0.001","currency":"BTC"},"committed":true}}"#,
+        );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+        let store = MemoryStore::default();
+        let dca = Dca::new(account, PlaceBuy::new("25".parse().unwrap(), "USD"));
+
+        let buy = dca.run_once(&client, &store).await.unwrap().unwrap();
+        assert!(buy.committed);
+        assert!(store.load().is_some());
+        assert!(dca.run_once(&client, &store).await.unwrap().is_none());
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_slice(requests[0].body()).unwrap();
+        assert_eq!(body["amount"], "25");
+        assert_eq!(body["commit"], true);
+    }
+}
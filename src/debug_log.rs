@@ -0,0 +1,293 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{response, HeaderMap, Method, Request, Uri};
+use serde_json::Value;
+
+use crate::CBError;
+
+/// Placeholder logged instead of credentials and account identifiers.
+const REDACTED: &str = "[REDACTED]";
+
+/// Path segments followed by an account identifier.
+const ACCOUNT_SEGMENTS: &[&str] = &["accounts", "portfolios"];
+
+/// Suffixes of query parameters and JSON fields holding an account identifier.
+const ACCOUNT_FIELDS: &[&str] = &[
+    "account_id",
+    "account_uuid",
+    "portfolio_id",
+    "portfolio_uuid",
+];
+
+/// Logs every request sent and response received, to see what a client actually sent.
+///
+/// Each attempt logs a line with the method, URL, headers and body of the request, and one with
+/// the status, latency and body of the response, or the error it failed with. Bodies are
+/// truncated to `max_body` bytes. Credentials are redacted from the headers, and account and
+/// portfolio identifiers from URLs and JSON bodies, so logs can be shared in support requests.
+/// Lines go to standard error unless another [`sink`](Self::sink) is set.
+///
+/// ```
+/// use coinbase_rs::{Client, DebugLog, MAIN_URL};
+///
+/// let client = Client::new(MAIN_URL)
+///     .with_debug_log(Some(DebugLog::default().max_body(256).sink(|line| println!("{line}"))));
+/// ```
+#[derive(Clone)]
+pub struct DebugLog {
+    max_body: usize,
+    sink: Arc<dyn Fn(&str) + Send + Sync>,
+}
+
+impl Default for DebugLog {
+    fn default() -> Self {
+        Self {
+            max_body: 1024,
+            sink: Arc::new(|line| eprintln!("{line}")),
+        }
+    }
+}
+
+impl fmt::Debug for DebugLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DebugLog")
+            .field("max_body", &self.max_body)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DebugLog {
+    /// Number of bytes of each body that are logged.
+    pub fn max_body(self, max_body: usize) -> Self {
+        let mut _self = self;
+        _self.max_body = max_body;
+        _self
+    }
+
+    /// Receives the logged lines, e.g. to forward them to `log` or `tracing`.
+    pub fn sink<F: Fn(&str) + Send + Sync + 'static>(self, sink: F) -> Self {
+        let mut _self = self;
+        _self.sink = Arc::new(sink);
+        _self
+    }
+
+    pub(crate) fn request(&self, request: &Request<Bytes>) {
+        (self.sink)(&format!(
+            "--> {} {} {} {}",
+            request.method(),
+            redact_uri(request.uri()),
+            redact_headers(request.headers()),
+            self.body(request.uri().path(), request.body()),
+        ));
+    }
+
+    pub(crate) fn response(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        latency: Duration,
+        response: std::result::Result<(&response::Parts, &[u8]), &CBError>,
+    ) {
+        let outcome = match response {
+            Ok((parts, body)) => format!("{} {}", parts.status, self.body(uri.path(), body)),
+            Err(error) => format!("failed: {error}"),
+        };
+        (self.sink)(&format!(
+            "<-- {} {} ({} ms) {}",
+            method,
+            redact_uri(uri),
+            latency.as_millis(),
+            outcome,
+        ));
+    }
+
+    fn body(&self, path: &str, body: &[u8]) -> String {
+        if body.is_empty() {
+            return "(empty)".to_string();
+        }
+        let text = match serde_json::from_slice::<Value>(body) {
+            Ok(mut json) => {
+                redact_json(&mut json, is_account_resource(path));
+                json.to_string()
+            }
+            Err(_) => String::from_utf8_lossy(body).into_owned(),
+        };
+        if text.len() <= self.max_body {
+            return text;
+        }
+        let mut end = self.max_body;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}... ({} bytes)", &text[..end], text.len())
+    }
+}
+
+fn redact_headers(headers: &HeaderMap) -> String {
+    let headers: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| {
+            let name = name.as_str();
+            let sensitive = matches!(name, "authorization" | "cookie" | "set-cookie")
+                || ["access-key", "access-sign", "access-passphrase"]
+                    .iter()
+                    .any(|suffix| name.ends_with(suffix));
+            let value = match value.to_str() {
+                _ if sensitive => REDACTED,
+                Ok(value) => value,
+                Err(_) => "<binary>",
+            };
+            format!("{name}: {value}")
+        })
+        .collect();
+    format!("[{}]", headers.join(", "))
+}
+
+fn redact_uri(uri: &Uri) -> String {
+    let mut redacted = format!(
+        "{}://{}{}",
+        uri.scheme_str().unwrap_or("https"),
+        uri.authority().map_or("", |authority| authority.as_str()),
+        redact_path(uri.path()),
+    );
+    if let Some(query) = uri.query() {
+        let pairs: Vec<String> = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, _)) if is_account_field(key) => format!("{key}={REDACTED}"),
+                _ => pair.to_string(),
+            })
+            .collect();
+        redacted.push('?');
+        redacted.push_str(&pairs.join("&"));
+    }
+    redacted
+}
+
+/// Replaces the identifiers following `accounts` and `portfolios` segments.
+fn redact_path(path: &str) -> String {
+    let mut previous = "";
+    path.split('/')
+        .map(|segment| {
+            let redact = ACCOUNT_SEGMENTS.contains(&previous) && !segment.is_empty();
+            previous = segment;
+            if redact {
+                REDACTED
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether the response to `path` describes accounts, whose `id` and `uuid` are identifiers.
+fn is_account_resource(path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    matches!(segments[..], [.., "accounts"] | [.., "accounts", _])
+}
+
+fn is_account_field(key: &str) -> bool {
+    ACCOUNT_FIELDS.iter().any(|field| key.ends_with(field))
+}
+
+fn redact_json(json: &mut Value, accounts: bool) {
+    match json {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(_)
+                        if is_account_field(key)
+                            || (accounts && matches!(key.as_str(), "id" | "uuid")) =>
+                    {
+                        *value = Value::String(REDACTED.to_string());
+                    }
+                    Value::String(path) if path.starts_with('/') => {
+                        *path = redact_path(path);
+                    }
+                    _ => redact_json(value, accounts),
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| redact_json(value, accounts)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{Client, MAIN_URL};
+
+    #[test]
+    fn test_redact_uri() {
+        let uri: Uri = "https://api.coinbase.com/v2/accounts/2bbf394c/transactions?limit=25"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            redact_uri(&uri),
+            "https://api.coinbase.com/v2/accounts/[REDACTED]/transactions?limit=25"
+        );
+        let uri: Uri = "https://api.coinbase.com/orders?retail_portfolio_id=42&limit=1"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            redact_uri(&uri),
+            "https://api.coinbase.com/orders?retail_portfolio_id=[REDACTED]&limit=1"
+        );
+    }
+
+    #[test]
+    fn test_truncated_body() {
+        let log = DebugLog::default().max_body(8);
+        assert_eq!(log.body("/v2/time", b""), "(empty)");
+        assert_eq!(
+            log.body("/v2/time", b"not json at all"),
+            "not json... (15 bytes)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_debug_log() {
+        let id = "2bbf394c-193b-5b2a-9155-3b4732659ede";
+        let mock = MockTransport::new();
+        mock.push_json(
+            &format!("/v2/accounts/{id}"),
+            &format!(
+                r#"{{"data":{{"id":"{id}","name":"BTC Wallet","primary":true,"type":"wallet",
+                    "currency":{{"code":"BTC","name":"Bitcoin","exponent":8,"type":"crypto"}},
+                    "balance":{{"amount":"0.1","currency":"BTC"}},
+                    "created_at":null,"updated_at":null,
+                    "resource":"account","resource_path":"/v2/accounts/{id}",
+                    "allow_deposits":true,"allow_withdrawals":true}}}}"#
+            ),
+        );
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("secret-token")
+            .with_debug_log(Some(
+                DebugLog::default().sink(move |line| sink.lock().unwrap().push(line.to_string())),
+            ))
+            .with_transport(mock);
+        client.account(&id.parse().unwrap()).await.unwrap();
+
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("--> GET https://api.coinbase.com/v2/accounts/[REDACTED] ["));
+        assert!(lines[0].contains("authorization: [REDACTED]"));
+        assert!(lines[1].starts_with("<-- GET https://api.coinbase.com/v2/accounts/[REDACTED] ("));
+        assert!(lines[1].contains("200 OK"));
+        assert!(lines[1].contains(r#""name":"BTC Wallet""#));
+        assert!(lines.iter().all(|line| !line.contains("secret-token")));
+        assert!(lines.iter().all(|line| !line.contains("2bbf394c")));
+    }
+}
@@ -0,0 +1,170 @@
+//! De-duplication of records repeated across the pages of a listing.
+//!
+//! Pages can overlap when records are added while a listing is followed, or when a page is
+//! fetched again after a retry, so a record at the end of one page shows up again at the start
+//! of the next. [`DedupPages::dedup_pages`] drops those repeats by the [`Keyed::key`] of each
+//! record. The listings of accounts, transactions, and Exchange orders, fills and transfers
+//! already apply it.
+//!
+//! ```
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! use coinbase_rs::dedup::{DedupPages, Keyed};
+//! use futures::stream::{self, StreamExt};
+//!
+//! struct Trade(u64);
+//!
+//! impl Keyed for Trade {
+//!     type Key = u64;
+//!
+//!     fn key(&self) -> u64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! let page = |ids: &[u64]| Ok(ids.iter().map(|id| Trade(*id)).collect());
+//! let pages: Vec<coinbase_rs::Result<Vec<Trade>>> =
+//!     stream::iter([page(&[3, 2]), page(&[2, 1])]).dedup_pages().collect().await;
+//! assert_eq!(pages[1].as_ref().unwrap().len(), 1);
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use futures::future;
+use futures::stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::private::{Account, Transaction};
+use crate::Result;
+
+/// A record with a key identifying it in a listing.
+pub trait Keyed {
+    type Key: Eq + Hash;
+
+    fn key(&self) -> Self::Key;
+}
+
+impl Keyed for Account {
+    type Key = String;
+
+    fn key(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl Keyed for Transaction {
+    type Key = Uuid;
+
+    fn key(&self) -> Uuid {
+        self.id
+    }
+}
+
+/// Drops records repeated from the previous page.
+pub trait DedupPages<T: Keyed>: Stream<Item = Result<Vec<T>>> + Sized {
+    /// Drops the records whose key was on the page before, skipping pages left empty. Only
+    /// the keys of the last page are kept, which is where a shifted or refetched page overlaps.
+    /// Pages that were empty to begin with and errors are passed on.
+    fn dedup_pages(self) -> impl Stream<Item = Result<Vec<T>>> {
+        let mut previous: HashSet<T::Key> = HashSet::new();
+        self.filter_map(move |page| {
+            let page = match page {
+                Ok(mut records) if !records.is_empty() => {
+                    let keys = records.iter().map(Keyed::key).collect();
+                    records.retain(|record| !previous.contains(&record.key()));
+                    previous = keys;
+                    (!records.is_empty()).then_some(Ok(records))
+                }
+                page => Some(page),
+            };
+            future::ready(page)
+        })
+    }
+}
+
+impl<T: Keyed, S: Stream<Item = Result<Vec<T>>>> DedupPages<T> for S {}
+
+#[cfg(feature = "exchange")]
+mod exchange {
+    use super::*;
+    use crate::exchange::{Fill, Order, Transfer};
+
+    impl Keyed for Order {
+        type Key = String;
+
+        fn key(&self) -> String {
+            self.id.clone()
+        }
+    }
+
+    /// Trade ids are only unique within a product.
+    impl Keyed for Fill {
+        type Key = (String, u64);
+
+        fn key(&self) -> (String, u64) {
+            (self.product_id.clone(), self.trade_id)
+        }
+    }
+
+    impl Keyed for Transfer {
+        type Key = String;
+
+        fn key(&self) -> String {
+            self.id.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream;
+
+    use super::*;
+    use crate::CBError;
+
+    struct Record(u64);
+
+    impl Keyed for Record {
+        type Key = u64;
+
+        fn key(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dedup_pages() {
+        let page =
+            |ids: &[u64]| -> Result<Vec<Record>> { Ok(ids.iter().map(|id| Record(*id)).collect()) };
+        let pages: Vec<_> = stream::iter([
+            page(&[5, 4]),
+            page(&[4, 3]),
+            page(&[3]),
+            page(&[]),
+            Err(CBError::Timeout),
+            page(&[3, 2, 1]),
+        ])
+        .dedup_pages()
+        .collect()
+        .await;
+        let pages: Vec<_> = pages
+            .into_iter()
+            .map(|page| {
+                page.ok()
+                    .map(|records| records.into_iter().map(|r| r.0).collect())
+            })
+            .collect();
+        assert_eq!(
+            pages,
+            [
+                Some(vec![5u64, 4]),
+                Some(vec![3]),
+                Some(vec![]),
+                None,
+                Some(vec![2, 1])
+            ]
+        );
+    }
+}
@@ -1,24 +1,131 @@
-use std::fmt;
+use std::time::Duration;
 
+use http::StatusCode;
 use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::private::Scope;
+use crate::transport::TransportError;
+
+/// Upper bound on how much of a response body is kept in an error.
+const MAX_BODY_LEN: usize = 1024;
+
+#[derive(Serialize, Deserialize, Debug, Error)]
+#[error("{message}")]
 pub struct Error {
     message: String,
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
-    }
-}
-
 #[derive(Debug, Error)]
 pub enum CBError {
-    #[error("http error {0}")]
-    Http(#[from] super::hyper::Error),
-    #[error(transparent)]
+    #[error("http error")]
+    Http(#[from] TransportError),
+    #[error("invalid json")]
     Serde(#[from] super::serde_json::Error),
-    #[error("coinbase: {0}")]
-    Coinbase(Error),
+    #[error("failed to write")]
+    Io(#[from] std::io::Error),
+    #[error("coinbase error (status {status})")]
+    Coinbase {
+        status: StatusCode,
+        #[source]
+        error: Error,
+    },
+    #[error("credentials are required for this endpoint")]
+    MissingCredentials,
+    #[error("invalid credentials: {0}")]
+    InvalidCredentials(String),
+    #[error("failed to authenticate the request: {0}")]
+    Auth(#[from] AuthError),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("no exchange rate for {0}")]
+    UnknownCurrency(String),
+    #[error("{currency} can't be sent on the {network} network (supported: {})", supported.join(", "))]
+    UnsupportedNetwork {
+        currency: String,
+        network: String,
+        supported: Vec<String>,
+    },
+    #[error("request timed out")]
+    Timeout,
+    #[error("cancelled")]
+    Cancelled,
+    #[error("rate limited")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("circuit open for {host}, retry in {retry_in:?}")]
+    CircuitOpen { host: String, retry_in: Duration },
+    #[error("storage error: {0}")]
+    Storage(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("missing scopes: {}", scope_list(missing))]
+    MissingScopes { missing: Vec<Scope> },
+    #[error("unexpected status {status}: {body}")]
+    Status { status: StatusCode, body: String },
+    #[error("failed to deserialize response (status {status}): {body}")]
+    Deserialize {
+        status: StatusCode,
+        body: String,
+        #[source]
+        source: super::serde_json::Error,
+    },
+}
+
+/// Why a request couldn't be signed with an API key.
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("the secret is not a PEM encoded EC private key: {0}")]
+    InvalidPem(String),
+    #[error("the secret is not a P-256 key: {0}")]
+    InvalidKey(String),
+    #[error("failed to sign the token: {0}")]
+    Signing(String),
+    #[error("can't sign a request to the relative URI {0}")]
+    RelativeUri(String),
+}
+
+fn scope_list(scopes: &[Scope]) -> String {
+    let scopes: Vec<&str> = scopes.iter().map(|scope| scope.as_str()).collect();
+    scopes.join(", ")
+}
+
+/// Lossily decodes a response body, truncating it to `MAX_BODY_LEN` bytes.
+pub(crate) fn body_excerpt(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    if text.len() <= MAX_BODY_LEN {
+        return text.into_owned();
+    }
+    let mut end = MAX_BODY_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &text[..end])
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn test_source_chaining() {
+        let serde_err = serde_json::from_str::<u32>("nope").unwrap_err();
+        let err = CBError::from(serde_err);
+        assert!(err.source().is_some());
+
+        let coinbase_err: Error = serde_json::from_str(r#"{"message": "not found"}"#).unwrap();
+        let err = CBError::Coinbase {
+            status: StatusCode::NOT_FOUND,
+            error: coinbase_err,
+        };
+        assert_eq!(err.source().unwrap().to_string(), "not found");
+    }
+
+    #[test]
+    fn test_body_excerpt() {
+        assert_eq!(body_excerpt(b"short"), "short");
+
+        let long = "é".repeat(MAX_BODY_LEN);
+        let excerpt = body_excerpt(long.as_bytes());
+        assert!(excerpt.ends_with("..."));
+        assert!(excerpt.len() <= MAX_BODY_LEN + 3);
+    }
 }
@@ -0,0 +1,810 @@
+//! Coinbase Exchange, the institutional REST API.
+//!
+//! Requests are signed with an Exchange API key, its base64 encoded secret and its passphrase,
+//! and sent through a [`Client`] so they share its transport, retries, rate limiting and
+//! errors. Listings are followed page by page with the `CB-AFTER` cursor.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::exchange::{ExchangeClient, NewOrder, OrderSide};
+//!
+//! let exchange = ExchangeClient::new("<key>", "<secret>", "<passphrase>");
+//! for account in exchange.accounts().await? {
+//!     println!("{} {}", account.balance, account.currency);
+//! }
+//! let order = NewOrder::limit(OrderSide::Buy, "BTC-USD", "30000".parse().unwrap(), "0.01".parse().unwrap());
+//! let order = exchange.place_order(&order).await?;
+//! println!("{} {}", order.id, order.status);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_stream::try_stream;
+use bigdecimal::BigDecimal;
+use futures::stream::Stream;
+use hmac::{Hmac, Mac};
+use http::Method;
+use serde_json::Value;
+use sha2::Sha256;
+use uritemplate::UriTemplate;
+use uuid::Uuid;
+
+use crate::dedup::DedupPages;
+use crate::order;
+pub use crate::order::{OrderSide, OrderStatus, OrderType, TimeInForce};
+use crate::private::SendMoney;
+use crate::{request, ApiResponse, CBError, Client, DateTime, Result};
+
+/// Most items returned by a single page of a listing.
+const PAGE_LIMIT: u32 = 100;
+
+/// Client for the Exchange API.
+#[derive(Clone)]
+pub struct ExchangeClient {
+    pub(crate) client: Client,
+}
+
+impl ExchangeClient {
+    /// Creates a client for the production Exchange with the default transport and settings.
+    pub fn new(key: &str, secret: &str, passphrase: &str) -> Self {
+        Self::with_client(&Client::new(crate::EXCHANGE_URL), key, secret, passphrase)
+    }
+
+    /// Creates a client sending requests through `client`, sharing its transport, retry
+    /// policy, rate limiter, middleware and metrics. A client for [`MAIN_URL`](crate::MAIN_URL)
+    /// or [`SANDBOX_URL`](crate::SANDBOX_URL) talks to the matching Exchange deployment, any
+    /// other URL, e.g. of a test server, is used as is.
+    pub fn with_client(client: &Client, key: &str, secret: &str, passphrase: &str) -> Self {
+        let mut client = client
+            .clone()
+            .with_exchange_credentials(key, secret, passphrase);
+        if client.uri == crate::MAIN_URL {
+            client.uri = crate::EXCHANGE_URL.to_string();
+        } else if client.uri == crate::SANDBOX_URL {
+            client.uri = crate::EXCHANGE_SANDBOX_URL.to_string();
+        }
+        Self { client }
+    }
+
+    ///
+    /// **Get all accounts for a profile**
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getaccounts
+    ///
+    pub async fn accounts(&self) -> Result<Vec<ExchangeAccount>> {
+        self.get("/accounts").await
+    }
+
+    ///
+    /// **Get a single account by id**
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getaccount
+    ///
+    pub async fn account(&self, account_id: &str) -> Result<ExchangeAccount> {
+        let uri = UriTemplate::new("/accounts/{account}")
+            .set("account", account_id)
+            .build();
+        self.get(&uri).await
+    }
+
+    ///
+    /// **Get all orders**
+    ///
+    /// Lists orders, newest first, by default the open, pending and active ones of every
+    /// product.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getorders
+    ///
+    pub fn orders<'a>(
+        &'a self,
+        params: &ListOrdersParams,
+    ) -> impl Stream<Item = Result<Vec<Order>>> + 'a {
+        self.list("/orders", params.query()).dedup_pages()
+    }
+
+    ///
+    /// **Get single order**
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getorder
+    ///
+    pub async fn order(&self, order_id: &str) -> Result<Order> {
+        let uri = UriTemplate::new("/orders/{order}")
+            .set("order", order_id)
+            .build();
+        self.get(&uri).await
+    }
+
+    ///
+    /// **Create a new order**
+    ///
+    /// The order carries a client order id, so a retried placement can be matched up with an
+    /// order that was already accepted.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_postorders
+    ///
+    pub async fn place_order(&self, order: &NewOrder) -> Result<Order> {
+        let request = self
+            .request("/orders")
+            .method(Method::POST)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(order)?);
+        Ok(self.client.make_request(request).await?.data)
+    }
+
+    ///
+    /// **Cancel an order**
+    ///
+    /// Returns the id of the canceled order.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_deleteorder
+    ///
+    pub async fn cancel_order(&self, order_id: &str) -> Result<String> {
+        let uri = UriTemplate::new("/orders/{order}")
+            .set("order", order_id)
+            .build();
+        let request = self.request(&uri).method(Method::DELETE);
+        Ok(self.client.make_request(request).await?.data)
+    }
+
+    ///
+    /// **Get all fills**
+    ///
+    /// Lists the fills of the orders of a product or of a single order, newest first.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getfills
+    ///
+    pub fn fills<'a>(
+        &'a self,
+        params: &ListFillsParams,
+    ) -> impl Stream<Item = Result<Vec<Fill>>> + 'a {
+        self.list("/fills", params.query()).dedup_pages()
+    }
+
+    ///
+    /// **Get all transfers**
+    ///
+    /// Lists deposits and withdrawals, newest first.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_gettransfers
+    ///
+    pub fn transfers(&self) -> impl Stream<Item = Result<Vec<Transfer>>> + '_ {
+        self.list("/transfers", Vec::new()).dedup_pages()
+    }
+
+    ///
+    /// **Get fee estimate for crypto withdrawal**
+    ///
+    /// Get the network fee of sending `send` on chain, to show it before the send is made. The
+    /// estimate depends on the currency, recipient address and network of the send.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getwithdrawfeeestimate
+    ///
+    pub async fn estimate_send_fee(&self, send: &SendMoney) -> Result<SendFee> {
+        let mut query = vec![
+            ("currency", send.currency.as_str()),
+            ("crypto_address", send.to.as_str()),
+        ];
+        if let Some(network) = &send.network {
+            query.push(("network", network));
+        }
+        let uri = UriTemplate::new("/withdrawals/fee-estimate{?query*}")
+            .set("query", query.as_slice())
+            .build();
+        self.get(&uri).await
+    }
+
+    ///
+    /// **Get product ticker**
+    ///
+    /// Returns the last trade and the best bid and ask of a product.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getproductticker
+    ///
+    pub async fn ticker(&self, product_id: &str) -> Result<Ticker> {
+        let uri = UriTemplate::new("/products/{product}/ticker")
+            .set("product", product_id)
+            .build();
+        self.get(&uri).await
+    }
+
+    fn request(&self, uri: &str) -> request::Builder {
+        self.client.authenticated_request(uri)
+    }
+
+    async fn get<U: serde::de::DeserializeOwned>(&self, uri: &str) -> Result<U> {
+        Ok(self.client.make_request(self.request(uri)).await?.data)
+    }
+
+    /// Pages through a listing until the `CB-AFTER` cursor runs out.
+    fn list<'a, U>(
+        &'a self,
+        path: &str,
+        query: Vec<(String, String)>,
+    ) -> impl Stream<Item = Result<Vec<U>>> + 'a
+    where
+        U: serde::de::DeserializeOwned + 'a,
+    {
+        let path = path.to_string();
+        try_stream! {
+            let mut after: Option<String> = None;
+            loop {
+                let mut pairs = vec![("limit".to_string(), PAGE_LIMIT.to_string())];
+                pairs.extend(query.iter().cloned());
+                if let Some(after) = &after {
+                    pairs.push(("after".to_string(), after.clone()));
+                }
+                let uri = UriTemplate::new(&format!("{path}{{?query*}}"))
+                    .set("query", pairs)
+                    .build();
+                let ApiResponse { data, meta } =
+                    self.client.make_page_request::<Vec<U>>(self.request(&uri)).await?;
+                let last = data.is_empty();
+                yield data;
+                match meta.after {
+                    Some(next) if !last && after.as_ref() != Some(&next) => after = Some(next),
+                    _ => break,
+                }
+                if !self.client.pace(meta.rate_limit.page_delay()).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Adds the Exchange API signature headers to `request`: the base64 encoded HMAC-SHA256, keyed
+/// with the decoded `secret`, of the timestamp, method, path and body.
+/// The timestamp is taken by a clock `clock_offset` ahead of the local one.
+pub(crate) fn sign(
+    request: request::Builder,
+    key: &str,
+    secret: &str,
+    passphrase: &str,
+    clock_offset: chrono::Duration,
+) -> Result<request::Builder> {
+    let timestamp = (chrono::Utc::now() + clock_offset).timestamp().to_string();
+    let signature = signature(&request, secret, &timestamp)?;
+    Ok(request
+        .header("CB-ACCESS-KEY", key)
+        .header("CB-ACCESS-SIGN", &signature)
+        .header("CB-ACCESS-TIMESTAMP", &timestamp)
+        .header("CB-ACCESS-PASSPHRASE", passphrase))
+}
+
+fn signature(request: &request::Builder, secret: &str, timestamp: &str) -> Result<String> {
+    let secret = base64::decode(secret)
+        .map_err(|_| CBError::InvalidCredentials("the secret is not base64".to_string()))?;
+    let mut mac = Hmac::<Sha256>::new_varkey(&secret)
+        .map_err(|_| CBError::InvalidCredentials("empty secret".to_string()))?;
+    let path = request
+        .uri_ref()
+        .path_and_query()
+        .map_or("/", |path| path.as_str());
+    mac.input(timestamp.as_bytes());
+    mac.input(request.method_ref().as_str().as_bytes());
+    mac.input(path.as_bytes());
+    mac.input(request.body_ref());
+    Ok(base64::encode(&mac.result().code()))
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExchangeAccount {
+    pub id: String,
+    pub currency: String,
+    pub balance: BigDecimal,
+    /// Balance not held by open orders or pending withdrawals.
+    pub available: BigDecimal,
+    pub hold: BigDecimal,
+    pub profile_id: String,
+    #[serde(default)]
+    pub trading_enabled: bool,
+}
+
+/// Filters of an [`orders`](ExchangeClient::orders) listing.
+///
+/// ```
+/// use coinbase_rs::exchange::{ListOrdersParams, OrderStatus};
+///
+/// let params = ListOrdersParams::new()
+///     .product("BTC-USD")
+///     .status(OrderStatus::Open)
+///     .status(OrderStatus::Done);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ListOrdersParams {
+    product_id: Option<String>,
+    statuses: Vec<&'static str>,
+    start_date: Option<DateTime>,
+    end_date: Option<DateTime>,
+}
+
+impl ListOrdersParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lists orders of `product`, e.g. `BTC-USD`.
+    pub fn product(self, product: &str) -> Self {
+        let mut _self = self;
+        _self.product_id = Some(product.to_string());
+        _self
+    }
+
+    /// Lists orders with `status`, in addition to the statuses given before.
+    pub fn status(self, status: OrderStatus) -> Self {
+        let mut _self = self;
+        _self.statuses.push(order::Names::exchange_name(status));
+        _self
+    }
+
+    /// Lists orders of every status.
+    pub fn all_statuses(self) -> Self {
+        let mut _self = self;
+        _self.statuses = vec!["all"];
+        _self
+    }
+
+    /// Only lists orders created at or after `start`.
+    pub fn start_date(self, start: DateTime) -> Self {
+        let mut _self = self;
+        _self.start_date = Some(start);
+        _self
+    }
+
+    /// Only lists orders created before `end`.
+    pub fn end_date(self, end: DateTime) -> Self {
+        let mut _self = self;
+        _self.end_date = Some(end);
+        _self
+    }
+
+    /// The same filters, listing the orders created at or after `start` that are done instead
+    /// of the statuses given.
+    pub(crate) fn done_since(&self, start: DateTime) -> Self {
+        let mut params = self.clone();
+        params.statuses = vec![order::Names::exchange_name(OrderStatus::Done)];
+        params.start_date = Some(start);
+        params
+    }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut query = Vec::new();
+        if let Some(product_id) = &self.product_id {
+            query.push(("product_id".to_string(), product_id.clone()));
+        }
+        for status in &self.statuses {
+            query.push(("status".to_string(), status.to_string()));
+        }
+        query.extend(date_range(self.start_date, self.end_date));
+        query
+    }
+}
+
+/// Filters of a [`fills`](ExchangeClient::fills) listing, which needs a product or an order.
+#[derive(Clone, Debug)]
+pub struct ListFillsParams {
+    product_id: Option<String>,
+    order_id: Option<String>,
+    start_date: Option<DateTime>,
+    end_date: Option<DateTime>,
+}
+
+impl ListFillsParams {
+    /// Lists the fills of the orders of `product`, e.g. `BTC-USD`.
+    pub fn product(product: &str) -> Self {
+        Self {
+            product_id: Some(product.to_string()),
+            order_id: None,
+            start_date: None,
+            end_date: None,
+        }
+    }
+
+    /// Lists the fills of a single order.
+    pub fn order(order_id: &str) -> Self {
+        Self {
+            product_id: None,
+            order_id: Some(order_id.to_string()),
+            start_date: None,
+            end_date: None,
+        }
+    }
+
+    /// Only lists fills at or after `start`.
+    pub fn start_date(self, start: DateTime) -> Self {
+        let mut _self = self;
+        _self.start_date = Some(start);
+        _self
+    }
+
+    /// Only lists fills before `end`.
+    pub fn end_date(self, end: DateTime) -> Self {
+        let mut _self = self;
+        _self.end_date = Some(end);
+        _self
+    }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut query = Vec::new();
+        if let Some(product_id) = &self.product_id {
+            query.push(("product_id".to_string(), product_id.clone()));
+        }
+        if let Some(order_id) = &self.order_id {
+            query.push(("order_id".to_string(), order_id.clone()));
+        }
+        query.extend(date_range(self.start_date, self.end_date));
+        query
+    }
+}
+
+fn date_range(start: Option<DateTime>, end: Option<DateTime>) -> Vec<(String, String)> {
+    [("start_date", start), ("end_date", end)]
+        .into_iter()
+        .filter_map(|(name, date)| Some((name.to_string(), date?.to_rfc3339())))
+        .collect()
+}
+
+/// Parameters of a [`place_order`](ExchangeClient::place_order) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct NewOrder {
+    /// Identifier chosen by the client, generated by the constructors.
+    pub client_oid: Uuid,
+    #[serde(serialize_with = "order::exchange")]
+    pub r#type: OrderType,
+    #[serde(serialize_with = "order::exchange")]
+    pub side: OrderSide,
+    pub product_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<BigDecimal>,
+    /// Amount of the base currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<BigDecimal>,
+    /// Amount of the quote currency to spend or receive, for market orders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub funds: Option<BigDecimal>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "order::exchange_option"
+    )]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_only: Option<bool>,
+}
+
+impl NewOrder {
+    /// Buys or sells `size` of `product` at `price` or better.
+    pub fn limit(side: OrderSide, product: &str, price: BigDecimal, size: BigDecimal) -> Self {
+        Self {
+            price: Some(price),
+            size: Some(size),
+            ..Self::new(OrderType::Limit, side, product)
+        }
+    }
+
+    /// Buys or sells `size` of `product` at the best available prices.
+    pub fn market(side: OrderSide, product: &str, size: BigDecimal) -> Self {
+        Self {
+            size: Some(size),
+            ..Self::new(OrderType::Market, side, product)
+        }
+    }
+
+    /// Spends or receives `funds` of the quote currency of `product` at the best available
+    /// prices.
+    pub fn market_funds(side: OrderSide, product: &str, funds: BigDecimal) -> Self {
+        Self {
+            funds: Some(funds),
+            ..Self::new(OrderType::Market, side, product)
+        }
+    }
+
+    fn new(r#type: OrderType, side: OrderSide, product: &str) -> Self {
+        Self {
+            client_oid: Uuid::new_v4(),
+            r#type,
+            side,
+            product_id: product.to_string(),
+            price: None,
+            size: None,
+            funds: None,
+            time_in_force: None,
+            post_only: None,
+        }
+    }
+
+    pub fn time_in_force(self, time_in_force: TimeInForce) -> Self {
+        let mut _self = self;
+        _self.time_in_force = Some(time_in_force);
+        _self
+    }
+
+    /// Only places the order if it rests on the book instead of taking liquidity.
+    pub fn post_only(self) -> Self {
+        let mut _self = self;
+        _self.post_only = Some(true);
+        _self
+    }
+
+    /// Replaces the generated client order id, e.g. with one persisted before placing.
+    pub fn client_oid(self, client_oid: Uuid) -> Self {
+        let mut _self = self;
+        _self.client_oid = client_oid;
+        _self
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Order {
+    pub id: String,
+    pub client_oid: Option<String>,
+    pub product_id: String,
+    pub side: OrderSide,
+    pub r#type: OrderType,
+    pub price: Option<BigDecimal>,
+    pub size: Option<BigDecimal>,
+    pub funds: Option<BigDecimal>,
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(default)]
+    pub post_only: bool,
+    pub created_at: DateTime,
+    pub done_at: Option<DateTime>,
+    pub status: OrderStatus,
+    pub filled_size: Option<BigDecimal>,
+    pub executed_value: Option<BigDecimal>,
+    pub fill_fees: Option<BigDecimal>,
+    #[serde(default)]
+    pub settled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Fill {
+    pub trade_id: u64,
+    pub order_id: String,
+    pub product_id: String,
+    pub side: OrderSide,
+    pub price: BigDecimal,
+    pub size: BigDecimal,
+    pub fee: BigDecimal,
+    /// `M` for maker, `T` for taker.
+    pub liquidity: String,
+    pub created_at: DateTime,
+    #[serde(default)]
+    pub settled: bool,
+}
+
+/// A deposit or withdrawal.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Transfer {
+    pub id: String,
+    /// `deposit`, `withdraw`, `internal_deposit` or `internal_withdraw`.
+    pub r#type: String,
+    pub amount: BigDecimal,
+    pub currency: Option<String>,
+    pub created_at: DateTime,
+    pub completed_at: Option<DateTime>,
+    pub canceled_at: Option<DateTime>,
+    pub processed_at: Option<DateTime>,
+    /// Details depending on the type, e.g. the crypto address and transaction hash.
+    #[serde(default)]
+    pub details: Value,
+}
+
+/// Estimated network fee of a send, see [`estimate_send_fee`](ExchangeClient::estimate_send_fee).
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SendFee {
+    /// Fee charged, in the currency of the send.
+    pub fee: BigDecimal,
+    /// Fee before any subsidy by Coinbase.
+    pub fee_before_subsidy: Option<BigDecimal>,
+}
+
+/// Last trade and best bid and ask of a product.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Ticker {
+    pub trade_id: u64,
+    /// Price of the last trade.
+    pub price: BigDecimal,
+    /// Size of the last trade.
+    pub size: BigDecimal,
+    pub bid: BigDecimal,
+    pub ask: BigDecimal,
+    /// Traded over the last 24 hours, in the base currency.
+    pub volume: BigDecimal,
+    pub time: DateTime,
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::TryStreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    const SECRET: &str = "c2VjcmV0";
+
+    #[tokio::test]
+    async fn test_orders() {
+        let order = |id: &str| {
+            format!(
+                r#"{{"id":"{id}","product_id":"BTC-USD","side":"buy","type":"limit",
+                    "price":"30000","size":"0.01","time_in_force":"GTC","post_only":false,
+                    "created_at":"2024-01-01T00:00:00Z","status":"open","filled_size":"0",
+                    "executed_value":"0","fill_fees":"0","settled":false}}"#
+            )
+        };
+        let page = |body: String, after: Option<&str>| {
+            let mut response = http::Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json");
+            if let Some(after) = after {
+                response = response.header("CB-AFTER", after);
+            }
+            response.body(bytes::Bytes::from(body)).unwrap()
+        };
+        let mock = MockTransport::new();
+        mock.push(
+            "/orders?limit=100&status=all",
+            page(format!("[{},{}]", order("a"), order("b")), Some("1071")),
+        )
+        .push(
+            "/orders?limit=100&status=all&after=1071",
+            page(format!("[{}]", order("c")), Some("1070")),
+        )
+        .push(
+            "/orders?limit=100&status=all&after=1070",
+            page("[]".to_string(), None),
+        )
+        .push_json("/orders", &order("d"));
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", SECRET, "passphrase");
+
+        let params = ListOrdersParams::new().all_statuses();
+        let orders: Vec<Order> = exchange.orders(&params).try_concat().await.unwrap();
+        let ids: Vec<_> = orders.iter().map(|o| o.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+
+        let new = NewOrder::limit(
+            OrderSide::Buy,
+            "BTC-USD",
+            "30000".parse().unwrap(),
+            "0.01".parse().unwrap(),
+        )
+        .time_in_force(TimeInForce::GoodTilCancelled)
+        .post_only();
+        let placed = exchange.place_order(&new).await.unwrap();
+        assert_eq!(placed.id, "d");
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].uri().host(), Some("api.exchange.coinbase.com"));
+        let post = &requests[3];
+        let body: Value = serde_json::from_slice(post.body()).unwrap();
+        assert_eq!(body["client_oid"], new.client_oid.to_string());
+        assert_eq!(body["post_only"], true);
+        assert_eq!(body["side"], "buy");
+        assert_eq!(body["type"], "limit");
+        assert_eq!(body["time_in_force"], "GTC");
+        assert_eq!(placed.status, OrderStatus::Open);
+
+        let headers = post.headers();
+        assert_eq!(headers["cb-access-key"], "key");
+        assert_eq!(headers["cb-access-passphrase"], "passphrase");
+        let timestamp = headers["cb-access-timestamp"].to_str().unwrap();
+        let mut mac = Hmac::<Sha256>::new_varkey(b"secret").unwrap();
+        mac.input(format!("{timestamp}POST/orders").as_bytes());
+        mac.input(post.body());
+        assert_eq!(
+            headers["cb-access-sign"],
+            base64::encode(&mac.result().code()).as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_clock_correction() {
+        let server_time = chrono::Utc::now() + chrono::Duration::hours(1);
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/time",
+            &format!(
+                r#"{{"data":{{"iso":"{}","epoch":{}}}}}"#,
+                server_time.to_rfc3339(),
+                server_time.timestamp()
+            ),
+        )
+        .push_json("/accounts", "[]");
+        let client = Client::new(MAIN_URL)
+            .with_clock_correction(Some(std::time::Duration::from_secs(60)))
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", SECRET, "passphrase");
+
+        exchange.accounts().await.unwrap();
+        let requests = mock.requests();
+        // The Exchange API has no `/v2/time`, so the time comes from the v2 API.
+        assert_eq!(requests[0].uri().host(), Some("api.coinbase.com"));
+        assert_eq!(requests[1].uri().host(), Some("api.exchange.coinbase.com"));
+        let timestamp = requests[1].headers()["cb-access-timestamp"]
+            .to_str()
+            .unwrap();
+        let timestamp: i64 = timestamp.parse().unwrap();
+        assert!((timestamp - server_time.timestamp()).abs() <= 5);
+    }
+
+    #[tokio::test]
+    async fn test_transfers_cursor() {
+        let transfer = |id: &str| {
+            format!(
+                r#"{{"id":"{id}","type":"withdraw","amount":"1","currency":"BTC",
+                    "created_at":"2024-01-01T00:00:00Z","completed_at":null,
+                    "canceled_at":null,"processed_at":null}}"#
+            )
+        };
+        let mut first = http::Response::new(bytes::Bytes::from(format!("[{}]", transfer("a"))));
+        first
+            .headers_mut()
+            .insert("CB-AFTER", "2024-01-01 12:00:00.5+00".parse().unwrap());
+        let mock = MockTransport::new();
+        mock.push("/transfers?limit=100", first).push_json(
+            "/transfers?limit=100&after=2024-01-01%2012%3A00%3A00.5%2B00",
+            "[]",
+        );
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", SECRET, "passphrase");
+
+        let transfers: Vec<Transfer> = exchange.transfers().try_concat().await.unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert!(mock.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_send_fee() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/withdrawals/fee-estimate?currency=USDC&crypto_address=0xd8da&network=base",
+            r#"{"fee":0.01,"fee_before_subsidy":0.05}"#,
+        );
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", SECRET, "passphrase");
+
+        let send = SendMoney::new("0xd8da", "10".parse().unwrap(), "USDC").network("base");
+        let fee = exchange.estimate_send_fee(&send).await.unwrap();
+        assert_eq!(fee.fee, "0.01".parse().unwrap());
+        assert_eq!(fee.fee_before_subsidy, Some("0.05".parse().unwrap()));
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.uri().host(), Some("api.exchange.coinbase.com"));
+        assert_eq!(request.headers()["cb-access-passphrase"], "passphrase");
+        assert!(request.headers().get("authorization").is_none());
+    }
+
+    #[test]
+    fn test_list_params() {
+        let start = "2024-01-01T00:00:00Z".parse().unwrap();
+        let params = ListOrdersParams::new()
+            .product("BTC-USD")
+            .status(OrderStatus::Open)
+            .status(OrderStatus::Done)
+            .start_date(start);
+        let uri = UriTemplate::new("/orders{?query*}")
+            .set("query", params.query())
+            .build();
+        assert_eq!(
+            uri,
+            "/orders?product_id=BTC-USD&status=open&status=done&start_date=2024-01-01T00%3A00%3A00%2B00%3A00"
+        );
+        assert_eq!(
+            ListFillsParams::order("d50ec984").query(),
+            [("order_id".to_string(), "d50ec984".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_secret() {
+        let client = Client::new(MAIN_URL).with_transport(MockTransport::new());
+        let exchange = ExchangeClient::with_client(&client, "key", "not base64!", "passphrase");
+        assert!(matches!(
+            exchange.accounts().await,
+            Err(CBError::InvalidCredentials(_))
+        ));
+    }
+}
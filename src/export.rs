@@ -0,0 +1,292 @@
+//! Export of listings to CSV or JSON Lines, e.g. for accounting.
+//!
+//! An [`Exporter`] writes [`Row`]s one at a time to any [`Write`], e.g. a file or standard
+//! output.
+//!
+//! ```no_run
+//! # #[cfg(feature = "exchange")]
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use std::fs::File;
+//!
+//! use chrono::{TimeZone, Utc};
+//! use coinbase_rs::exchange::ExchangeClient;
+//! use coinbase_rs::export::{Exporter, Format};
+//!
+//! let exchange = ExchangeClient::new("<key>", "<secret>", "<passphrase>");
+//! let mut exporter = Exporter::new(File::create("fills.csv")?, Format::Csv);
+//! let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+//! let end = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+//! exchange
+//!     .export_fills(&["BTC-USD", "ETH-USD"], start, end, &mut exporter)
+//!     .await?;
+//! println!("exported {} fills", exporter.rows());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::Result;
+
+/// File format of an export.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Comma separated values with a header line, quoted as in RFC 4180.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+/// A record that can be exported.
+pub trait Row: Serialize {
+    /// Names of the CSV columns.
+    const COLUMNS: &'static [&'static str];
+
+    /// Values of the CSV columns, in the order of [`COLUMNS`](Self::COLUMNS).
+    fn values(&self) -> Vec<String>;
+}
+
+/// Writes rows in one [`Format`].
+#[derive(Debug)]
+pub struct Exporter<W: Write> {
+    writer: W,
+    format: Format,
+    rows: usize,
+}
+
+impl<W: Write> Exporter<W> {
+    pub fn new(writer: W, format: Format) -> Self {
+        Self {
+            writer,
+            format,
+            rows: 0,
+        }
+    }
+
+    /// Writes `row`, preceded by the CSV header if it is the first one.
+    pub fn write<R: Row>(&mut self, row: &R) -> Result<()> {
+        match self.format {
+            Format::Csv => {
+                if self.rows == 0 {
+                    self.write_csv(R::COLUMNS.iter().map(|column| column.to_string()))?;
+                }
+                self.write_csv(row.values().into_iter())?;
+            }
+            Format::JsonLines => {
+                serde_json::to_writer(&mut self.writer, row)?;
+                self.writer.write_all(b"\n")?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Number of rows written so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Flushes the writer and returns it.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_csv(&mut self, values: impl Iterator<Item = String>) -> Result<()> {
+        let line: Vec<String> = values.map(|value| quote(&value)).collect();
+        self.writer.write_all(line.join(",").as_bytes())?;
+        self.writer.write_all(b"\r\n")?;
+        Ok(())
+    }
+}
+
+fn quote(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(feature = "exchange")]
+mod exchange {
+    use futures::stream::TryStreamExt;
+
+    use super::*;
+    use crate::exchange::{ExchangeClient, Fill, ListFillsParams};
+    use crate::DateTime;
+
+    impl Row for Fill {
+        const COLUMNS: &'static [&'static str] = &[
+            "created_at",
+            "trade_id",
+            "order_id",
+            "product_id",
+            "side",
+            "price",
+            "size",
+            "fee",
+            "liquidity",
+            "settled",
+        ];
+
+        fn values(&self) -> Vec<String> {
+            vec![
+                self.created_at.to_rfc3339(),
+                self.trade_id.to_string(),
+                self.order_id.clone(),
+                self.product_id.clone(),
+                self.side.to_string(),
+                self.price.to_string(),
+                self.size.to_string(),
+                self.fee.to_string(),
+                self.liquidity.clone(),
+                self.settled.to_string(),
+            ]
+        }
+    }
+
+    impl ExchangeClient {
+        /// Exports the fills of `products` from `start` up to `end`, oldest first. Every page
+        /// goes through the rate limiter and later pages are paced as the reported limit runs
+        /// low. Returns the number of fills written.
+        pub async fn export_fills<W: Write>(
+            &self,
+            products: &[&str],
+            start: DateTime,
+            end: DateTime,
+            exporter: &mut Exporter<W>,
+        ) -> Result<usize> {
+            let mut fills = Vec::new();
+            for product in products {
+                let params = ListFillsParams::product(product)
+                    .start_date(start)
+                    .end_date(end);
+                fills.extend(self.fills(&params).try_concat().await?);
+            }
+            fills.sort_by(|a, b| {
+                (a.created_at, &a.product_id, a.trade_id).cmp(&(
+                    b.created_at,
+                    &b.product_id,
+                    b.trade_id,
+                ))
+            });
+            for fill in &fills {
+                exporter.write(fill)?;
+            }
+            Ok(fills.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Note {
+        id: u32,
+        text: String,
+    }
+
+    impl Row for Note {
+        const COLUMNS: &'static [&'static str] = &["id", "text"];
+
+        fn values(&self) -> Vec<String> {
+            vec![self.id.to_string(), self.text.clone()]
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let notes = [
+            Note {
+                id: 1,
+                text: "plain".to_string(),
+            },
+            Note {
+                id: 2,
+                text: "a \"quoted\", text".to_string(),
+            },
+        ];
+
+        let mut exporter = Exporter::new(Vec::new(), Format::Csv);
+        for note in &notes {
+            exporter.write(note).unwrap();
+        }
+        assert_eq!(exporter.rows(), 2);
+        let csv = String::from_utf8(exporter.finish().unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "id,text\r\n1,plain\r\n2,\"a \"\"quoted\"\", text\"\r\n"
+        );
+
+        let mut exporter = Exporter::new(Vec::new(), Format::JsonLines);
+        for note in &notes {
+            exporter.write(note).unwrap();
+        }
+        let lines = String::from_utf8(exporter.finish().unwrap()).unwrap();
+        assert_eq!(
+            lines,
+            "{\"id\":1,\"text\":\"plain\"}\n{\"id\":2,\"text\":\"a \\\"quoted\\\", text\"}\n"
+        );
+    }
+
+    #[cfg(feature = "exchange")]
+    #[tokio::test]
+    async fn test_export_fills() {
+        use chrono::{TimeZone, Utc};
+
+        use crate::exchange::ExchangeClient;
+        use crate::testing::MockTransport;
+        use crate::{Client, MAIN_URL};
+
+        let fill = |trade_id: u32, product: &str, time: &str| {
+            format!(
+                r#"{{"trade_id":{trade_id},"order_id":"o","product_id":"{product}","side":"buy",
+                    "price":"10","size":"1","fee":"0.01","liquidity":"T",
+                    "created_at":"{time}","settled":true}}"#
+            )
+        };
+        let query = concat!(
+            "start_date=2024-01-01T00%3A00%3A00%2B00%3A00",
+            "&end_date=2024-02-01T00%3A00%3A00%2B00%3A00"
+        );
+        let mock = MockTransport::new();
+        mock.push_json(
+            &format!("/fills?limit=100&product_id=BTC-USD&{query}"),
+            &format!(
+                "[{},{}]",
+                fill(2, "BTC-USD", "2024-01-20T00:00:00Z"),
+                fill(1, "BTC-USD", "2024-01-05T00:00:00Z")
+            ),
+        )
+        .push_json(
+            &format!("/fills?limit=100&product_id=ETH-USD&{query}"),
+            &format!("[{}]", fill(7, "ETH-USD", "2024-01-10T00:00:00Z")),
+        );
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", "c2VjcmV0", "passphrase");
+
+        let mut exporter = Exporter::new(Vec::new(), Format::Csv);
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let count = exchange
+            .export_fills(&["BTC-USD", "ETH-USD"], start, end, &mut exporter)
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+        let csv = String::from_utf8(exporter.finish().unwrap()).unwrap();
+        let trades: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').nth(1).unwrap())
+            .collect();
+        assert_eq!(trades, ["1", "7", "2"]);
+        assert!(mock.is_empty());
+    }
+}
@@ -0,0 +1,422 @@
+//! Realistic fake models for unit tests of code built on this crate.
+//!
+//! Every builder starts from sensible defaults, a BTC wallet, a completed buy, a resting limit
+//! order or a one hour candle, so a test only spells out what it cares about. Amounts are given
+//! as strings and panic if they don't parse.
+//!
+//! ```
+//! use coinbase_rs::fixtures;
+//! use coinbase_rs::journal::JournalEntry;
+//!
+//! let account = fixtures::account().currency("ETH").balance("2.5").build();
+//! assert_eq!(account.balance.currency, "ETH");
+//!
+//! let send = fixtures::transaction()
+//!     .r#type("send")
+//!     .amount("-0.1", "BTC")
+//!     .build();
+//! assert!(JournalEntry::from_transaction(&send).unwrap().is_balanced());
+//! ```
+
+use bigdecimal::BigDecimal;
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::market::Candle;
+use crate::private::{Account, Balance, Currency, Transaction, TransactionDetails};
+use crate::DateTime;
+
+/// Id of the default account.
+pub const ACCOUNT_ID: &str = "af6fd33a-e20c-494a-b3f6-f91d204af4b7";
+
+/// 2024-01-01T00:00:00Z, when every fixture happens unless told otherwise.
+pub fn epoch() -> DateTime {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+fn decimal(amount: &str) -> BigDecimal {
+    amount
+        .parse()
+        .unwrap_or_else(|_| panic!("invalid amount {amount:?}"))
+}
+
+fn balance(amount: &str, currency: &str) -> Balance {
+    Balance {
+        amount: decimal(amount),
+        currency: currency.to_string(),
+    }
+}
+
+/// A BTC wallet holding 1 BTC.
+pub fn account() -> AccountBuilder {
+    AccountBuilder {
+        account: Account {
+            id: ACCOUNT_ID.to_string(),
+            r#type: "wallet".to_string(),
+            created_at: Some(epoch()),
+            updated_at: Some(epoch()),
+            resource: "account".to_string(),
+            resource_path: format!("/v2/accounts/{ACCOUNT_ID}"),
+            name: "BTC Wallet".to_string(),
+            primary: true,
+            currency: Currency {
+                code: "BTC".to_string(),
+                name: "BTC".to_string(),
+                exponent: 8,
+                r#type: "crypto".to_string(),
+            },
+            balance: balance("1", "BTC"),
+            allow_deposits: true,
+            allow_withdrawals: true,
+        },
+    }
+}
+
+pub struct AccountBuilder {
+    account: Account,
+}
+
+impl AccountBuilder {
+    pub fn id(self, id: &str) -> Self {
+        let mut _self = self;
+        _self.account.id = id.to_string();
+        _self.account.resource_path = format!("/v2/accounts/{id}");
+        _self
+    }
+
+    pub fn name(self, name: &str) -> Self {
+        let mut _self = self;
+        _self.account.name = name.to_string();
+        _self
+    }
+
+    /// A crypto wallet of `code`, named after it, keeping the balance amount.
+    pub fn currency(self, code: &str) -> Self {
+        let mut _self = self;
+        _self.account.name = format!("{code} Wallet");
+        _self.account.currency.code = code.to_string();
+        _self.account.currency.name = code.to_string();
+        _self.account.balance.currency = code.to_string();
+        _self
+    }
+
+    /// A fiat wallet of `code`, with 2 decimal places.
+    pub fn fiat(self, code: &str) -> Self {
+        let mut _self = self.currency(code);
+        _self.account.r#type = "fiat".to_string();
+        _self.account.currency.exponent = 2;
+        _self.account.currency.r#type = "fiat".to_string();
+        _self
+    }
+
+    pub fn balance(self, amount: &str) -> Self {
+        let mut _self = self;
+        _self.account.balance.amount = decimal(amount);
+        _self
+    }
+
+    pub fn primary(self, primary: bool) -> Self {
+        let mut _self = self;
+        _self.account.primary = primary;
+        _self
+    }
+
+    pub fn build(self) -> Account {
+        self.account
+    }
+}
+
+/// A completed buy of 1 BTC for 100 USD in the default account, with a random id.
+pub fn transaction() -> TransactionBuilder {
+    TransactionBuilder {
+        transaction: Transaction {
+            id: Uuid::new_v4(),
+            created_at: Some(epoch()),
+            updated_at: Some(epoch()),
+            r#type: "buy".to_string(),
+            resource: "transaction".to_string(),
+            resource_path: String::new(),
+            status: "completed".to_string(),
+            amount: balance("1", "BTC"),
+            native_amount: balance("100", "USD"),
+            instant_exchange: false,
+            network: None,
+            from: None,
+            details: TransactionDetails {
+                title: "Bought Bitcoin".to_string(),
+                subtitle: String::new(),
+            },
+            available_at: None,
+        },
+        account_id: ACCOUNT_ID.to_string(),
+    }
+}
+
+pub struct TransactionBuilder {
+    transaction: Transaction,
+    account_id: String,
+}
+
+impl TransactionBuilder {
+    pub fn id(self, id: Uuid) -> Self {
+        let mut _self = self;
+        _self.transaction.id = id;
+        _self
+    }
+
+    /// The account the transaction belongs to, which only shows in its resource path.
+    pub fn account_id(self, account_id: &str) -> Self {
+        let mut _self = self;
+        _self.account_id = account_id.to_string();
+        _self
+    }
+
+    /// The raw type, e.g. `send` or `staking_reward`.
+    pub fn r#type(self, r#type: &str) -> Self {
+        let mut _self = self;
+        _self.transaction.r#type = r#type.to_string();
+        _self
+    }
+
+    pub fn status(self, status: &str) -> Self {
+        let mut _self = self;
+        _self.transaction.status = status.to_string();
+        _self
+    }
+
+    /// Signed amount, negative for funds leaving the account.
+    pub fn amount(self, amount: &str, currency: &str) -> Self {
+        let mut _self = self;
+        _self.transaction.amount = balance(amount, currency);
+        _self
+    }
+
+    pub fn native_amount(self, amount: &str, currency: &str) -> Self {
+        let mut _self = self;
+        _self.transaction.native_amount = balance(amount, currency);
+        _self
+    }
+
+    /// Sets both the creation and update times.
+    pub fn created_at(self, created_at: DateTime) -> Self {
+        let mut _self = self;
+        _self.transaction.created_at = Some(created_at);
+        _self.transaction.updated_at = Some(created_at);
+        _self
+    }
+
+    pub fn title(self, title: &str) -> Self {
+        let mut _self = self;
+        _self.transaction.details.title = title.to_string();
+        _self
+    }
+
+    pub fn build(self) -> Transaction {
+        let mut transaction = self.transaction;
+        transaction.resource_path = format!(
+            "/v2/accounts/{}/transactions/{}",
+            self.account_id, transaction.id
+        );
+        transaction
+    }
+}
+
+/// A one hour BTC candle starting at [`epoch`], opening at 100 and closing at 105.
+pub fn candle() -> CandleBuilder {
+    CandleBuilder {
+        candle: Candle {
+            start: epoch(),
+            low: decimal("99"),
+            high: decimal("106"),
+            open: decimal("100"),
+            close: decimal("105"),
+            volume: decimal("10"),
+        },
+    }
+}
+
+pub struct CandleBuilder {
+    candle: Candle,
+}
+
+impl CandleBuilder {
+    pub fn start(self, start: DateTime) -> Self {
+        let mut _self = self;
+        _self.candle.start = start;
+        _self
+    }
+
+    pub fn ohlc(self, open: &str, high: &str, low: &str, close: &str) -> Self {
+        let mut _self = self;
+        _self.candle.open = decimal(open);
+        _self.candle.high = decimal(high);
+        _self.candle.low = decimal(low);
+        _self.candle.close = decimal(close);
+        _self
+    }
+
+    pub fn volume(self, volume: &str) -> Self {
+        let mut _self = self;
+        _self.candle.volume = decimal(volume);
+        _self
+    }
+
+    pub fn build(self) -> Candle {
+        self.candle
+    }
+}
+
+#[cfg(feature = "exchange")]
+pub use self::exchange::{order, OrderBuilder};
+
+#[cfg(feature = "exchange")]
+mod exchange {
+    use super::*;
+    use crate::exchange::Order;
+    use crate::order::{OrderSide, OrderStatus, OrderType, TimeInForce};
+
+    /// An open good til cancelled limit buy of 1 BTC-USD at 100, with a random id.
+    pub fn order() -> OrderBuilder {
+        OrderBuilder {
+            order: Order {
+                id: Uuid::new_v4().to_string(),
+                client_oid: None,
+                product_id: "BTC-USD".to_string(),
+                side: OrderSide::Buy,
+                r#type: OrderType::Limit,
+                price: Some(decimal("100")),
+                size: Some(decimal("1")),
+                funds: None,
+                time_in_force: Some(TimeInForce::GoodTilCancelled),
+                post_only: false,
+                created_at: epoch(),
+                done_at: None,
+                status: OrderStatus::Open,
+                filled_size: Some(decimal("0")),
+                executed_value: Some(decimal("0")),
+                fill_fees: Some(decimal("0")),
+                settled: false,
+            },
+        }
+    }
+
+    pub struct OrderBuilder {
+        order: Order,
+    }
+
+    impl OrderBuilder {
+        pub fn id(self, id: &str) -> Self {
+            let mut _self = self;
+            _self.order.id = id.to_string();
+            _self
+        }
+
+        pub fn product_id(self, product_id: &str) -> Self {
+            let mut _self = self;
+            _self.order.product_id = product_id.to_string();
+            _self
+        }
+
+        pub fn side(self, side: OrderSide) -> Self {
+            let mut _self = self;
+            _self.order.side = side;
+            _self
+        }
+
+        pub fn price(self, price: &str) -> Self {
+            let mut _self = self;
+            _self.order.price = Some(decimal(price));
+            _self
+        }
+
+        pub fn size(self, size: &str) -> Self {
+            let mut _self = self;
+            _self.order.size = Some(decimal(size));
+            _self
+        }
+
+        /// A market order without price or time in force.
+        pub fn market(self) -> Self {
+            let mut _self = self;
+            _self.order.r#type = OrderType::Market;
+            _self.order.price = None;
+            _self.order.time_in_force = None;
+            _self
+        }
+
+        pub fn status(self, status: OrderStatus) -> Self {
+            let mut _self = self;
+            _self.order.status = status;
+            _self
+        }
+
+        /// Fills the whole size at the price, or 100 for market orders, paying `fees`.
+        pub fn filled(self, fees: &str) -> Self {
+            let mut _self = self;
+            let size = _self.order.size.clone().unwrap_or_else(|| decimal("0"));
+            let price = _self.order.price.clone().unwrap_or_else(|| decimal("100"));
+            _self.order.executed_value = Some(&size * &price);
+            _self.order.filled_size = Some(size);
+            _self.order.fill_fees = Some(decimal(fees));
+            _self.order.status = OrderStatus::Done;
+            _self.order.done_at = Some(_self.order.created_at);
+            _self.order.settled = true;
+            _self
+        }
+
+        pub fn created_at(self, created_at: DateTime) -> Self {
+            let mut _self = self;
+            _self.order.created_at = created_at;
+            _self
+        }
+
+        pub fn build(self) -> Order {
+            self.order
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        let account = account().fiat("USD").balance("12.34").build();
+        assert_eq!(account.name, "USD Wallet");
+        assert_eq!(account.currency.exponent, 2);
+        assert_eq!(account.balance.amount, decimal("12.34"));
+
+        let id = Uuid::nil();
+        let transaction = transaction()
+            .id(id)
+            .account_id("a")
+            .status("pending")
+            .build();
+        assert_eq!(
+            transaction.resource_path,
+            format!("/v2/accounts/a/transactions/{id}")
+        );
+        assert_eq!(transaction.status, "pending");
+        assert_ne!(
+            super::transaction().build().id,
+            super::transaction().build().id
+        );
+
+        let candle = candle().ohlc("1", "4", "0.5", "2").build();
+        assert_eq!(candle.high, decimal("4"));
+        assert_eq!(candle.start, epoch());
+    }
+
+    #[cfg(feature = "exchange")]
+    #[test]
+    fn test_order_fixture() {
+        use crate::order::OrderStatus;
+
+        let order = order().size("2").price("50").filled("0.25").build();
+        assert_eq!(order.status, OrderStatus::Done);
+        assert_eq!(order.executed_value, Some(decimal("100")));
+        assert_eq!(order.filled_size, Some(decimal("2")));
+        assert!(order.settled);
+    }
+}
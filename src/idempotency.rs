@@ -0,0 +1,49 @@
+use std::fmt;
+
+use uuid::Uuid;
+
+/// Token that lets Coinbase recognise a repeated submission of the same money-moving request.
+///
+/// A request sent twice with the same key is only executed once, so a request can be resent
+/// after a network failure without risking a double spend. `IdempotencyKey::default()` generates
+/// a random key; use `From` to supply one of your own, e.g. a key persisted alongside the
+/// pending operation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IdempotencyKey(String);
+
+impl Default for IdempotencyKey {
+    fn default() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+}
+
+impl IdempotencyKey {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for IdempotencyKey {
+    fn from(key: String) -> Self {
+        Self(key)
+    }
+}
+
+impl From<&str> for IdempotencyKey {
+    fn from(key: &str) -> Self {
+        Self(key.to_string())
+    }
+}
+
+impl From<Uuid> for IdempotencyKey {
+    fn from(key: Uuid) -> Self {
+        Self(key.to_string())
+    }
+}
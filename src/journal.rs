@@ -0,0 +1,319 @@
+//! Double-entry journal entries of transactions, for accounting integrations.
+//!
+//! Every completed transaction becomes a [`JournalEntry`] whose [`Posting`]s debit and credit
+//! [`LedgerAccount`]s by the same amount in each currency: the wallet of the transaction's
+//! currency on one side and, depending on its kind, trading, the outside world or income on the
+//! other. Network fees of sends are split out to their own account.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::{Client, Uuid, MAIN_URL};
+//! use futures::stream::TryStreamExt;
+//!
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! let account: Uuid = "af6fd33a-e20c-494a-b3f6-f91d204af4b7".parse().unwrap();
+//! let entries: Vec<_> = client.journal(&account, None, None).try_concat().await?;
+//! for entry in &entries {
+//!     for posting in &entry.postings {
+//!         println!("{} {:?} {:?} {}", entry.at, posting.side, posting.account, posting.amount);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use bigdecimal::{BigDecimal, Signed, Zero};
+use futures::stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::private::{Balance, Transaction};
+use crate::{Client, DateTime, Result};
+
+/// What a transaction did, parsed from its `type`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TransactionKind {
+    Buy,
+    Sell,
+    /// Conversion between two currencies.
+    Trade,
+    AdvancedTradeFill,
+    /// Crypto sent to or received from an address or email.
+    Send,
+    Request,
+    /// Move between two accounts of the user.
+    Transfer,
+    FiatDeposit,
+    FiatWithdrawal,
+    ExchangeDeposit,
+    ExchangeWithdrawal,
+    VaultWithdrawal,
+    Interest,
+    StakingReward,
+    InflationReward,
+    Other(String),
+}
+
+impl TransactionKind {
+    pub fn parse(r#type: &str) -> Self {
+        match r#type {
+            "buy" => Self::Buy,
+            "sell" => Self::Sell,
+            "trade" => Self::Trade,
+            "advanced_trade_fill" => Self::AdvancedTradeFill,
+            "send" => Self::Send,
+            "request" => Self::Request,
+            "transfer" => Self::Transfer,
+            "fiat_deposit" => Self::FiatDeposit,
+            "fiat_withdrawal" => Self::FiatWithdrawal,
+            "exchange_deposit" => Self::ExchangeDeposit,
+            "exchange_withdrawal" => Self::ExchangeWithdrawal,
+            "vault_withdrawal" => Self::VaultWithdrawal,
+            "interest" => Self::Interest,
+            "staking_reward" => Self::StakingReward,
+            "inflation_reward" => Self::InflationReward,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// Account on the other side of the wallet.
+    fn counter_account(&self, currency: &str) -> LedgerAccount {
+        let currency = currency.to_string();
+        match self {
+            Self::Buy | Self::Sell | Self::Trade | Self::AdvancedTradeFill => {
+                LedgerAccount::Trading(currency)
+            }
+            Self::Interest | Self::StakingReward | Self::InflationReward => {
+                LedgerAccount::Income(currency)
+            }
+            _ => LedgerAccount::External(currency),
+        }
+    }
+}
+
+/// Account of the books, per currency.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum LedgerAccount {
+    /// Asset: the funds held on Coinbase.
+    Wallet(String),
+    /// Clearing account of buys, sells and conversions, settled against the other currency.
+    Trading(String),
+    /// Funds coming from or going to outside Coinbase, or another account of the user.
+    External(String),
+    /// Expense: fees paid.
+    Fees(String),
+    /// Income: interest and rewards.
+    Income(String),
+}
+
+impl LedgerAccount {
+    pub fn currency(&self) -> &str {
+        match self {
+            Self::Wallet(currency)
+            | Self::Trading(currency)
+            | Self::External(currency)
+            | Self::Fees(currency)
+            | Self::Income(currency) => currency,
+        }
+    }
+}
+
+impl fmt::Display for LedgerAccount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Wallet(_) => "Wallet",
+            Self::Trading(_) => "Trading",
+            Self::External(_) => "External",
+            Self::Fees(_) => "Fees",
+            Self::Income(_) => "Income",
+        };
+        write!(f, "{name}:{}", self.currency())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+/// An amount debited or credited to an account.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Posting {
+    pub account: LedgerAccount,
+    pub side: Side,
+    /// Always positive, the side tells the direction.
+    pub amount: BigDecimal,
+}
+
+impl Posting {
+    fn new(account: LedgerAccount, side: Side, amount: BigDecimal) -> Self {
+        Self {
+            account,
+            side,
+            amount,
+        }
+    }
+}
+
+/// The postings of one transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    pub transaction_id: Uuid,
+    pub at: DateTime,
+    pub kind: TransactionKind,
+    /// Value of the transaction in the user's native currency.
+    pub native_amount: Balance,
+    pub description: String,
+    pub postings: Vec<Posting>,
+}
+
+impl JournalEntry {
+    /// The entry of a completed transaction, `None` for other transactions. Sends are assumed to
+    /// include their network fee in the amount leaving the wallet.
+    pub fn from_transaction(transaction: &Transaction) -> Option<Self> {
+        if transaction.status != "completed" {
+            return None;
+        }
+        let at = transaction.created_at?;
+        let kind = TransactionKind::parse(&transaction.r#type);
+        let Balance { amount, currency } = &transaction.amount;
+        let wallet = LedgerAccount::Wallet(currency.clone());
+        let counter = kind.counter_account(currency);
+        let fee = transaction
+            .network
+            .as_ref()
+            .and_then(|network| network.transaction_fee.as_ref())
+            .filter(|fee| fee.currency == *currency && fee.amount.is_positive())
+            .map(|fee| fee.amount.clone())
+            .filter(|fee| amount.is_negative() && *fee <= amount.abs());
+
+        let mut postings = Vec::new();
+        if amount.is_positive() {
+            postings.push(Posting::new(wallet, Side::Debit, amount.clone()));
+            postings.push(Posting::new(counter, Side::Credit, amount.clone()));
+        } else if amount.is_negative() {
+            let total = amount.abs();
+            let net = match &fee {
+                Some(fee) => &total - fee,
+                None => total.clone(),
+            };
+            if !net.is_zero() {
+                postings.push(Posting::new(counter, Side::Debit, net));
+            }
+            if let Some(fee) = fee {
+                postings.push(Posting::new(
+                    LedgerAccount::Fees(currency.clone()),
+                    Side::Debit,
+                    fee,
+                ));
+            }
+            postings.push(Posting::new(wallet, Side::Credit, total));
+        }
+        Some(Self {
+            transaction_id: transaction.id,
+            at,
+            kind,
+            native_amount: transaction.native_amount.clone(),
+            description: transaction.details.title.clone(),
+            postings,
+        })
+    }
+
+    /// Whether the debits equal the credits in every currency.
+    pub fn is_balanced(&self) -> bool {
+        let mut totals: HashMap<&str, BigDecimal> = HashMap::new();
+        for posting in &self.postings {
+            let total = totals.entry(posting.account.currency()).or_default();
+            match posting.side {
+                Side::Debit => *total += &posting.amount,
+                Side::Credit => *total -= &posting.amount,
+            }
+        }
+        totals.values().all(Zero::is_zero)
+    }
+}
+
+impl Client {
+    /// Journal entries of the completed transactions of an account created from `start` up to
+    /// but excluding `end`, newest first.
+    pub fn journal<'a>(
+        &'a self,
+        account_id: &Uuid,
+        start: Option<DateTime>,
+        end: Option<DateTime>,
+    ) -> impl Stream<Item = Result<Vec<JournalEntry>>> + 'a {
+        self.transactions_between(account_id, start, end)
+            .map(|page| {
+                page.map(|transactions| {
+                    transactions
+                        .iter()
+                        .filter_map(JournalEntry::from_transaction)
+                        .collect()
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn transaction(r#type: &str, amount: &str, network: &str) -> Transaction {
+        serde_json::from_str(&format!(
+            r#"{{"id":"9dd482e4-d8ce-46f7-a261-281843bd2855","type":"{type}",
+                "status":"completed","amount":{{"amount":"{amount}","currency":"BTC"}},
+                "native_amount":{{"amount":"100","currency":"USD"}},
+                "created_at":"2024-01-01T00:00:00Z","updated_at":null,
+                "resource":"transaction","resource_path":"/","network":{network},
+                "details":{{"title":"Sent Bitcoin","subtitle":""}}}}"#
+        ))
+        .unwrap()
+    }
+
+    fn posting(account: LedgerAccount, side: Side, amount: &str) -> Posting {
+        Posting::new(account, side, amount.parse().unwrap())
+    }
+
+    #[test]
+    fn test_journal_entry() {
+        let btc = || "BTC".to_string();
+
+        let buy = JournalEntry::from_transaction(&transaction("buy", "0.5", "null")).unwrap();
+        assert_eq!(buy.kind, TransactionKind::Buy);
+        assert_eq!(
+            buy.postings,
+            [
+                posting(LedgerAccount::Wallet(btc()), Side::Debit, "0.5"),
+                posting(LedgerAccount::Trading(btc()), Side::Credit, "0.5"),
+            ]
+        );
+        assert!(buy.is_balanced());
+
+        let network = r#"{"status":"confirmed",
+            "transaction_fee":{"amount":"0.0001","currency":"BTC"}}"#;
+        let send = JournalEntry::from_transaction(&transaction("send", "-0.1", network)).unwrap();
+        assert_eq!(
+            send.postings,
+            [
+                posting(LedgerAccount::External(btc()), Side::Debit, "0.0999"),
+                posting(LedgerAccount::Fees(btc()), Side::Debit, "0.0001"),
+                posting(LedgerAccount::Wallet(btc()), Side::Credit, "0.1"),
+            ]
+        );
+        assert!(send.is_balanced());
+        assert_eq!(send.description, "Sent Bitcoin");
+
+        let reward = JournalEntry::from_transaction(&transaction("staking_reward", "0.01", "null"));
+        assert_eq!(
+            reward.unwrap().postings[1].account,
+            LedgerAccount::Income(btc())
+        );
+
+        let mut pending = transaction("send", "-0.1", "null");
+        pending.status = "pending".to_string();
+        assert!(JournalEntry::from_transaction(&pending).is_none());
+    }
+}
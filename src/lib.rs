@@ -2,28 +2,132 @@ extern crate base64;
 extern crate futures;
 extern crate hmac;
 extern crate http;
+#[cfg(feature = "tokio")]
 extern crate hyper;
-extern crate hyper_tls;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
 extern crate sha2;
+#[cfg(feature = "tokio")]
 extern crate tokio;
-extern crate tokio_stream;
 extern crate uritemplate;
 
+pub mod alerts;
+#[cfg(feature = "exchange")]
+pub mod algos;
+pub mod amount;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod borrowed;
+pub mod brokerage;
+pub mod builder;
+pub mod cache;
+mod circuit_breaker;
+pub mod client;
+mod clock;
+#[cfg(feature = "parquet")]
+pub mod columnar;
+#[cfg(feature = "commerce")]
+pub mod commerce;
+mod compression;
+pub mod dca;
+mod debug_log;
+pub mod dedup;
 pub mod error;
+#[cfg(feature = "exchange")]
+pub mod exchange;
+pub mod export;
+pub mod fixtures;
+pub mod idempotency;
+pub mod journal;
+pub mod market;
+pub mod metrics;
+pub mod middleware;
+pub mod order;
+pub mod order_book;
+#[cfg(feature = "exchange")]
+pub mod order_events;
+mod otel;
+mod payment_uri;
+pub mod portfolio;
+#[cfg(feature = "exchange")]
+pub mod positions;
+#[cfg(feature = "prime")]
+pub mod prime;
 pub mod private;
+pub mod proxy;
 pub mod public;
+pub mod rate_limit;
+pub mod rates;
+mod raw;
+pub mod reconcile;
 pub mod request;
+pub mod response;
+pub mod retry;
+mod rt;
+mod shutdown;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub mod sql;
+pub mod sync;
+pub mod tax;
+pub mod testing;
+pub mod timeouts;
+mod trace;
+pub mod transport;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
 
+pub use builder::{ClientBuilder, Coinbase};
+pub use circuit_breaker::CircuitBreaker;
+pub use client::Client;
+pub use debug_log::DebugLog;
 pub use error::CBError;
+pub use idempotency::IdempotencyKey;
+pub use metrics::Metrics;
+pub use middleware::Middleware;
+#[allow(deprecated)]
 pub use private::Private;
+pub use proxy::Proxy;
+#[allow(deprecated)]
 pub use public::Public;
+pub use rate_limit::{Priority, RateBudget, RateLimit};
+pub use response::{ApiResponse, RateLimitStatus, ResponseMeta};
+pub use retry::RetryPolicy;
+pub use shutdown::{CancellableStream, Shutdown};
+pub use timeouts::Timeouts;
+pub use transport::{ConnectionOptions, HttpTransport, TransportError};
+#[cfg(feature = "tokio")]
+pub use transport::{HyperTransport, TlsOptions};
 
 pub const MAIN_URL: &str = "https://api.coinbase.com";
 
+/// Base URL of Coinbase's sandbox, which accepts orders and withdrawals without moving real funds.
+pub const SANDBOX_URL: &str = "https://api-sandbox.coinbase.com";
+
+/// Base URL of the Exchange API, which publishes the networks each currency can be sent on.
+pub const EXCHANGE_URL: &str = "https://api.exchange.coinbase.com";
+
+/// Base URL of the Exchange API's sandbox.
+pub const EXCHANGE_SANDBOX_URL: &str = "https://api-public.sandbox.exchange.coinbase.com";
+
+/// A Coinbase deployment a client can be pointed at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Environment {
+    #[default]
+    Production,
+    Sandbox,
+}
+
+impl Environment {
+    pub fn base_url(self) -> &'static str {
+        match self {
+            Environment::Production => MAIN_URL,
+            Environment::Sandbox => SANDBOX_URL,
+        }
+    }
+}
+
 pub use uuid::Uuid;
 
 pub type DateTime = chrono::DateTime<chrono::Utc>;
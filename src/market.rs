@@ -0,0 +1,278 @@
+//! Public market data of the Advanced Trade API.
+
+use async_stream::try_stream;
+use bigdecimal::BigDecimal;
+use futures::stream::Stream;
+use serde::{Deserialize, Deserializer};
+
+use crate::order::OrderSide;
+use crate::{ApiResponse, Client, DateTime, Result};
+
+/// Most candles returned by a single request.
+pub const MAX_CANDLES: i64 = 300;
+
+/// Interval covered by one candle.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Granularity {
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+    ThirtyMinutes,
+    OneHour,
+    TwoHours,
+    SixHours,
+    OneDay,
+}
+
+impl Granularity {
+    pub fn seconds(&self) -> i64 {
+        match self {
+            Granularity::OneMinute => 60,
+            Granularity::FiveMinutes => 5 * 60,
+            Granularity::FifteenMinutes => 15 * 60,
+            Granularity::ThirtyMinutes => 30 * 60,
+            Granularity::OneHour => 60 * 60,
+            Granularity::TwoHours => 2 * 60 * 60,
+            Granularity::SixHours => 6 * 60 * 60,
+            Granularity::OneDay => 24 * 60 * 60,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Granularity::OneMinute => "ONE_MINUTE",
+            Granularity::FiveMinutes => "FIVE_MINUTE",
+            Granularity::FifteenMinutes => "FIFTEEN_MINUTE",
+            Granularity::ThirtyMinutes => "THIRTY_MINUTE",
+            Granularity::OneHour => "ONE_HOUR",
+            Granularity::TwoHours => "TWO_HOUR",
+            Granularity::SixHours => "SIX_HOUR",
+            Granularity::OneDay => "ONE_DAY",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Candle {
+    /// Start of the interval.
+    #[serde(deserialize_with = "unix_time")]
+    pub start: DateTime,
+    pub low: BigDecimal,
+    pub high: BigDecimal,
+    pub open: BigDecimal,
+    pub close: BigDecimal,
+    pub volume: BigDecimal,
+}
+
+#[derive(Deserialize, Debug)]
+struct Candles {
+    candles: Vec<Candle>,
+}
+
+/// A trade of a product on the exchange.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub trade_id: String,
+    pub product_id: String,
+    pub price: BigDecimal,
+    pub size: BigDecimal,
+    pub time: DateTime,
+    /// Side of the taker.
+    pub side: OrderSide,
+}
+
+#[derive(Deserialize, Debug)]
+struct MarketTrades {
+    trades: Vec<Trade>,
+}
+
+/// Deserializes seconds since the epoch, sent as a string.
+fn unix_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<DateTime, D::Error> {
+    let seconds: i64 = String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)?;
+    chrono::DateTime::from_timestamp(seconds, 0)
+        .ok_or_else(|| serde::de::Error::custom("timestamp out of range"))
+}
+
+impl Client {
+    ///
+    /// **Get product candles**
+    ///
+    /// Get rates for a single product by product ID, grouped in buckets, between `start` and
+    /// `end`. At most [`MAX_CANDLES`] are returned, newest first.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_getpublicproductcandles
+    ///
+    pub async fn candles(
+        &self,
+        product: &str,
+        granularity: Granularity,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<Vec<Candle>> {
+        self.candles_with_meta(product, granularity, start, end)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`candles`](Self::candles), also returning the response metadata.
+    pub async fn candles_with_meta(
+        &self,
+        product: &str,
+        granularity: Granularity,
+        start: DateTime,
+        end: DateTime,
+    ) -> Result<ApiResponse<Vec<Candle>>> {
+        let uri = candles_uri(product, granularity, start, end);
+        let response = self.make_request::<Candles>(self.request(&uri)).await?;
+        Ok(ApiResponse {
+            data: response.data.candles,
+            meta: response.meta,
+        })
+    }
+
+    ///
+    /// **Get market trades**
+    ///
+    /// Get the latest `limit` trades of a product, newest first.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_getpublicmarkettrades
+    ///
+    pub async fn market_trades(&self, product: &str, limit: u32) -> Result<Vec<Trade>> {
+        self.market_trades_with_meta(product, limit)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`market_trades`](Self::market_trades), also returning the response metadata.
+    pub async fn market_trades_with_meta(
+        &self,
+        product: &str,
+        limit: u32,
+    ) -> Result<ApiResponse<Vec<Trade>>> {
+        let uri = market_trades_uri(product, limit);
+        let response = self
+            .make_request::<MarketTrades>(self.request(&uri))
+            .await?;
+        Ok(ApiResponse {
+            data: response.data.trades,
+            meta: response.meta,
+        })
+    }
+
+    /// Fetches the candles of `product` from `start` up to `end` in as many requests as the
+    /// [`MAX_CANDLES`] cap requires, yielding each chunk oldest first. Requests are paced by the
+    /// client's rate limiter. Candles are never repeated across chunks, and intervals without
+    /// trades have no candle. A [`Shutdown`](crate::Shutdown) ends it after the current chunk.
+    pub fn fetch_candles_range<'a>(
+        &'a self,
+        product: &str,
+        granularity: Granularity,
+        start: DateTime,
+        end: DateTime,
+    ) -> impl Stream<Item = Result<Vec<Candle>>> + 'a {
+        let product = product.to_string();
+        let chunk = chrono::Duration::seconds(granularity.seconds() * MAX_CANDLES);
+        try_stream! {
+            let mut chunk_start = start;
+            let mut last: Option<DateTime> = None;
+            while chunk_start < end {
+                let chunk_end = (chunk_start + chunk).min(end);
+                let mut candles = self
+                    .candles(&product, granularity, chunk_start, chunk_end)
+                    .await?;
+                candles.sort_by_key(|candle| candle.start);
+                candles.dedup_by_key(|candle| candle.start);
+                candles.retain(|candle| {
+                    candle.start >= start
+                        && candle.start < end
+                        && last.is_none_or(|last| candle.start > last)
+                });
+                if let Some(candle) = candles.last() {
+                    last = Some(candle.start);
+                }
+                yield candles;
+                chunk_start = chunk_end;
+                if !self.pace(None).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn candles_uri(
+    product: &str,
+    granularity: Granularity,
+    start: DateTime,
+    end: DateTime,
+) -> String {
+    format!(
+        "/api/v3/brokerage/market/products/{}/candles?start={}&end={}&granularity={}",
+        product,
+        start.timestamp(),
+        end.timestamp(),
+        granularity.as_str(),
+    )
+}
+
+pub(crate) fn market_trades_uri(product: &str, limit: u32) -> String {
+    format!("/api/v3/brokerage/market/products/{product}/ticker?limit={limit}")
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::TryStreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn candles(starts: &[i64]) -> String {
+        let candles: Vec<_> = starts
+            .iter()
+            .map(|start| {
+                format!(
+                    r#"{{"start":"{start}","low":"1","high":"2","open":"1","close":"2","volume":"10"}}"#
+                )
+            })
+            .collect();
+        format!(r#"{{"candles":[{}]}}"#, candles.join(","))
+    }
+
+    #[tokio::test]
+    async fn test_fetch_candles_range() {
+        let hour = 3600;
+        let start = chrono::DateTime::from_timestamp(0, 0).unwrap();
+        let end = chrono::DateTime::from_timestamp(400 * hour, 0).unwrap();
+        let mock = MockTransport::new();
+        mock.push_json(
+            &format!(
+                "/api/v3/brokerage/market/products/BTC-USD/candles?start=0&end={}&granularity=ONE_HOUR",
+                300 * hour
+            ),
+            &candles(&[300 * hour, 299 * hour, hour, 0]),
+        )
+        .push_json(
+            &format!(
+                "/api/v3/brokerage/market/products/BTC-USD/candles?start={}&end={}&granularity=ONE_HOUR",
+                300 * hour,
+                400 * hour
+            ),
+            &candles(&[400 * hour, 350 * hour, 300 * hour]),
+        );
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+
+        let series: Vec<Candle> = client
+            .fetch_candles_range("BTC-USD", Granularity::OneHour, start, end)
+            .try_concat()
+            .await
+            .unwrap();
+        let starts: Vec<_> = series.iter().map(|c| c.start.timestamp() / hour).collect();
+        assert_eq!(starts, [0, 1, 299, 300, 350]);
+        assert_eq!(mock.requests().len(), 2);
+    }
+}
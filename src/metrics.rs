@@ -0,0 +1,54 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{Method, StatusCode};
+
+/// Receives measurements about the requests made by a client, e.g. to feed Prometheus or
+/// StatsD.
+///
+/// `request_started` and `request_finished` are called once per HTTP request, so a request that
+/// is retried reports every attempt. `status` is `None` when no response was received.
+pub trait Metrics: Send + Sync {
+    fn request_started(&self, _method: &Method, _path: &str) {}
+
+    fn request_finished(
+        &self,
+        _method: &Method,
+        _path: &str,
+        _status: Option<StatusCode>,
+        _latency: Duration,
+    ) {
+    }
+
+    fn rate_limited(&self, _path: &str, _retry_after: Option<Duration>) {}
+
+    fn retry(&self, _method: &Method, _path: &str, _attempt: u32, _delay: Duration) {}
+}
+
+impl<M: Metrics + ?Sized> Metrics for Arc<M> {
+    fn request_started(&self, method: &Method, path: &str) {
+        (**self).request_started(method, path)
+    }
+
+    fn request_finished(
+        &self,
+        method: &Method,
+        path: &str,
+        status: Option<StatusCode>,
+        latency: Duration,
+    ) {
+        (**self).request_finished(method, path, status, latency)
+    }
+
+    fn rate_limited(&self, path: &str, retry_after: Option<Duration>) {
+        (**self).rate_limited(path, retry_after)
+    }
+
+    fn retry(&self, method: &Method, path: &str, attempt: u32, delay: Duration) {
+        (**self).retry(method, path, attempt, delay)
+    }
+}
+
+pub(crate) struct NoMetrics;
+
+impl Metrics for NoMetrics {}
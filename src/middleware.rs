@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::{response, Request};
+
+/// Hook into the requests sent and responses received by a client.
+///
+/// Middleware runs in the order it was added. `on_request` sees each request after it has been
+/// signed, so it may add or replace headers, and runs again for every retry. `on_response` sees
+/// the status, headers and raw body of every response before it is parsed.
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, _request: &mut Request<Bytes>) {}
+
+    fn on_response(&self, _response: &response::Parts, _body: &[u8]) {}
+}
+
+impl<M: Middleware + ?Sized> Middleware for Arc<M> {
+    fn on_request(&self, request: &mut Request<Bytes>) {
+        (**self).on_request(request)
+    }
+
+    fn on_response(&self, response: &response::Parts, body: &[u8]) {
+        (**self).on_response(response, body)
+    }
+}
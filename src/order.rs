@@ -0,0 +1,240 @@
+//! Enums describing orders, shared by the order endpoints, fills and trades of every API.
+//!
+//! The Advanced Trade and Prime APIs spell values in upper case, e.g. `GOOD_UNTIL_CANCELLED`,
+//! and the Exchange API in lower case or with abbreviations, e.g. `GTC`. The enums accept
+//! either spelling and are sent the way the API an order goes to expects. Statuses and types
+//! that are added to the APIs later are received as `Unknown` instead of failing.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Spellings of the values of an enum.
+pub(crate) trait Names: Sized + Copy + 'static {
+    const ALL: &'static [Self];
+    /// Value of names not in [`ALL`](Self::ALL), `None` if they are an error.
+    const FALLBACK: Option<Self>;
+
+    /// Name used by the Advanced Trade and Prime APIs.
+    fn name(self) -> &'static str;
+
+    /// Name used by the Exchange API.
+    fn exchange_name(self) -> &'static str;
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|value| {
+                name.eq_ignore_ascii_case(value.name())
+                    || name.eq_ignore_ascii_case(value.exchange_name())
+            })
+            .or(Self::FALLBACK)
+    }
+}
+
+struct NameVisitor<T>(PhantomData<T>);
+
+impl<T: Names> Visitor<'_> for NameVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = T::ALL.iter().map(|value| value.name()).collect();
+        write!(f, "one of {}", names.join(", "))
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<T, E> {
+        T::parse(name).ok_or_else(|| E::invalid_value(de::Unexpected::Str(name), &self))
+    }
+}
+
+/// Serializes `value` with its Exchange API name.
+#[cfg(feature = "exchange")]
+pub(crate) fn exchange<T: Names, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(value.exchange_name())
+}
+
+/// Serializes `value`, if any, with its Exchange API name.
+#[cfg(feature = "exchange")]
+pub(crate) fn exchange_option<T: Names, S: Serializer>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.serialize_str(value.exchange_name()),
+        None => serializer.serialize_none(),
+    }
+}
+
+macro_rules! names {
+    (
+        $type:ident,
+        $fallback:expr,
+        { $($variant:ident => ($name:literal, $exchange:literal),)* }
+    ) => {
+        impl Names for $type {
+            const ALL: &'static [Self] = &[$($type::$variant,)*];
+            const FALLBACK: Option<Self> = $fallback;
+
+            fn name(self) -> &'static str {
+                match self {
+                    $($type::$variant => $name,)*
+                }
+            }
+
+            fn exchange_name(self) -> &'static str {
+                match self {
+                    $($type::$variant => $exchange,)*
+                }
+            }
+        }
+
+        impl $type {
+            /// The name of the value in the Advanced Trade and Prime APIs.
+            pub fn as_str(self) -> &'static str {
+                self.name()
+            }
+        }
+
+        impl fmt::Display for $type {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.name())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_str(NameVisitor(PhantomData))
+            }
+        }
+    };
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+names!(OrderSide, None, {
+    Buy => ("BUY", "buy"),
+    Sell => ("SELL", "sell"),
+});
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum OrderType {
+    Limit,
+    Market,
+    Stop,
+    StopLimit,
+    Twap,
+    Vwap,
+    Block,
+    Rfq,
+    Unknown,
+}
+
+names!(OrderType, Some(OrderType::Unknown), {
+    Limit => ("LIMIT", "limit"),
+    Market => ("MARKET", "market"),
+    Stop => ("STOP", "stop"),
+    StopLimit => ("STOP_LIMIT", "stop_limit"),
+    Twap => ("TWAP", "twap"),
+    Vwap => ("VWAP", "vwap"),
+    Block => ("BLOCK", "block"),
+    Rfq => ("RFQ", "rfq"),
+    Unknown => ("UNKNOWN_ORDER_TYPE", "unknown"),
+});
+
+/// Status of an order. Advanced Trade and Prime orders end up `Filled`, `Cancelled`, `Expired`
+/// or `Failed`, Exchange orders `Done` or `Rejected`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum OrderStatus {
+    Pending,
+    Queued,
+    Received,
+    Open,
+    Active,
+    CancelQueued,
+    Filled,
+    Cancelled,
+    Expired,
+    Failed,
+    Done,
+    Rejected,
+    Unknown,
+}
+
+names!(OrderStatus, Some(OrderStatus::Unknown), {
+    Pending => ("PENDING", "pending"),
+    Queued => ("QUEUED", "queued"),
+    Received => ("RECEIVED", "received"),
+    Open => ("OPEN", "open"),
+    Active => ("ACTIVE", "active"),
+    CancelQueued => ("CANCEL_QUEUED", "cancel_queued"),
+    Filled => ("FILLED", "filled"),
+    Cancelled => ("CANCELLED", "cancelled"),
+    Expired => ("EXPIRED", "expired"),
+    Failed => ("FAILED", "failed"),
+    Done => ("DONE", "done"),
+    Rejected => ("REJECTED", "rejected"),
+    Unknown => ("UNKNOWN_ORDER_STATUS", "unknown"),
+});
+
+/// How long an order stays on the book.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TimeInForce {
+    GoodTilCancelled,
+    /// Until the expiry time given with the order.
+    GoodTilTime,
+    /// Fills what it can right away and cancels the rest.
+    ImmediateOrCancel,
+    /// Fills completely right away or not at all.
+    FillOrKill,
+}
+
+names!(TimeInForce, None, {
+    GoodTilCancelled => ("GOOD_UNTIL_CANCELLED", "GTC"),
+    GoodTilTime => ("GOOD_UNTIL_DATE_TIME", "GTT"),
+    ImmediateOrCancel => ("IMMEDIATE_OR_CANCEL", "IOC"),
+    FillOrKill => ("FILL_OR_KILL", "FOK"),
+});
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spellings() {
+        let parse = |json: &str| serde_json::from_str::<TimeInForce>(json).unwrap();
+        assert_eq!(parse(r#""GTC""#), TimeInForce::GoodTilCancelled);
+        assert_eq!(
+            parse(r#""GOOD_UNTIL_CANCELLED""#),
+            TimeInForce::GoodTilCancelled
+        );
+        assert_eq!(
+            serde_json::to_string(&TimeInForce::FillOrKill).unwrap(),
+            r#""FILL_OR_KILL""#
+        );
+
+        let status: OrderStatus = serde_json::from_str(r#""done""#).unwrap();
+        assert_eq!(status, OrderStatus::Done);
+        let status: OrderStatus = serde_json::from_str(r#""SETTLING""#).unwrap();
+        assert_eq!(status, OrderStatus::Unknown);
+
+        let side: OrderSide = serde_json::from_str(r#""buy""#).unwrap();
+        assert_eq!(side, OrderSide::Buy);
+        assert!(serde_json::from_str::<OrderSide>(r#""hold""#).is_err());
+    }
+}
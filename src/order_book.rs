@@ -0,0 +1,262 @@
+//! Level 2 order book kept up to date from snapshots and updates, with analytics that are
+//! maintained as levels change instead of being recomputed from a copy of the book.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::order_book::Side;
+//! use coinbase_rs::{Client, MAIN_URL};
+//!
+//! let client = Client::new(MAIN_URL);
+//! let mut book = client.product_book("BTC-USD", Some(100)).await?;
+//! println!("spread {:?}, mid {:?}", book.spread(), book.mid_price());
+//! book.update(Side::Bid, "64000.01".parse().unwrap(), "0.5".parse().unwrap());
+//! println!("imbalance {:?}", book.imbalance());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use bigdecimal::{BigDecimal, Signed, Zero};
+
+use crate::{ApiResponse, Client, Result};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+/// Aggregated size at every price of one product.
+#[derive(Clone, Debug)]
+pub struct OrderBook {
+    product: String,
+    bids: BTreeMap<BigDecimal, BigDecimal>,
+    asks: BTreeMap<BigDecimal, BigDecimal>,
+    bid_volume: BigDecimal,
+    ask_volume: BigDecimal,
+}
+
+impl OrderBook {
+    pub fn new(product: &str) -> Self {
+        Self {
+            product: product.to_string(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            bid_volume: BigDecimal::zero(),
+            ask_volume: BigDecimal::zero(),
+        }
+    }
+
+    pub fn product(&self) -> &str {
+        &self.product
+    }
+
+    /// Sets the size at `price`, removing the level when `size` is zero.
+    pub fn update(&mut self, side: Side, price: BigDecimal, size: BigDecimal) {
+        let (levels, volume) = match side {
+            Side::Bid => (&mut self.bids, &mut self.bid_volume),
+            Side::Ask => (&mut self.asks, &mut self.ask_volume),
+        };
+        let previous = if size.is_positive() {
+            *volume = &*volume + &size;
+            levels.insert(price, size)
+        } else {
+            levels.remove(&price)
+        };
+        if let Some(previous) = previous {
+            *volume = &*volume - &previous;
+        }
+    }
+
+    /// Removes all levels, e.g. before applying a new snapshot.
+    pub fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.bid_volume = BigDecimal::zero();
+        self.ask_volume = BigDecimal::zero();
+    }
+
+    /// Levels of `side`, best price first.
+    pub fn levels(&self, side: Side) -> Box<dyn Iterator<Item = (&BigDecimal, &BigDecimal)> + '_> {
+        match side {
+            Side::Bid => Box::new(self.bids.iter().rev()),
+            Side::Ask => Box::new(self.asks.iter()),
+        }
+    }
+
+    /// Highest bid price and its size.
+    pub fn best_bid(&self) -> Option<(&BigDecimal, &BigDecimal)> {
+        self.bids.iter().next_back()
+    }
+
+    /// Lowest ask price and its size.
+    pub fn best_ask(&self) -> Option<(&BigDecimal, &BigDecimal)> {
+        self.asks.iter().next()
+    }
+
+    pub fn spread(&self) -> Option<BigDecimal> {
+        Some(self.best_ask()?.0 - self.best_bid()?.0)
+    }
+
+    pub fn mid_price(&self) -> Option<BigDecimal> {
+        Some((self.best_ask()?.0 + self.best_bid()?.0) / BigDecimal::from(2))
+    }
+
+    /// Total size on `side`.
+    pub fn volume(&self, side: Side) -> &BigDecimal {
+        match side {
+            Side::Bid => &self.bid_volume,
+            Side::Ask => &self.ask_volume,
+        }
+    }
+
+    /// Average price of filling `size` against `side`, walking from the best price. `None` if
+    /// the side doesn't hold `size`.
+    pub fn vwap(&self, side: Side, size: &BigDecimal) -> Option<BigDecimal> {
+        if !size.is_positive() {
+            return None;
+        }
+        let mut remaining = size.clone();
+        let mut cost = BigDecimal::zero();
+        for (price, available) in self.levels(side) {
+            let filled = if available < &remaining {
+                available
+            } else {
+                &remaining
+            };
+            cost = &cost + price * filled;
+            remaining = &remaining - filled;
+            if remaining.is_zero() {
+                return Some(cost / size);
+            }
+        }
+        None
+    }
+
+    /// Difference between bid and ask volume relative to their sum, from -1 when there are only
+    /// asks to 1 when there are only bids.
+    pub fn imbalance(&self) -> Option<BigDecimal> {
+        imbalance(&self.bid_volume, &self.ask_volume)
+    }
+
+    /// Like [`imbalance`](Self::imbalance), only counting the best `levels` of each side.
+    pub fn imbalance_within(&self, levels: usize) -> Option<BigDecimal> {
+        let bids: BigDecimal = self.levels(Side::Bid).take(levels).map(|(_, s)| s).sum();
+        let asks: BigDecimal = self.levels(Side::Ask).take(levels).map(|(_, s)| s).sum();
+        imbalance(&bids, &asks)
+    }
+}
+
+fn imbalance(bids: &BigDecimal, asks: &BigDecimal) -> Option<BigDecimal> {
+    let total = bids + asks;
+    if total.is_zero() {
+        return None;
+    }
+    Some((bids - asks) / total)
+}
+
+#[derive(Deserialize, Debug)]
+struct ProductBook {
+    pricebook: PriceBook,
+}
+
+#[derive(Deserialize, Debug)]
+struct PriceBook {
+    product_id: String,
+    bids: Vec<Level>,
+    asks: Vec<Level>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Level {
+    price: BigDecimal,
+    size: BigDecimal,
+}
+
+impl Client {
+    ///
+    /// **Get product book**
+    ///
+    /// Get a snapshot of the bids and asks of a product, limited to the best `limit` levels of
+    /// each side.
+    ///
+    /// https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_getpublicproductbook
+    ///
+    pub async fn product_book(&self, product: &str, limit: Option<u32>) -> Result<OrderBook> {
+        self.product_book_with_meta(product, limit)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`product_book`](Self::product_book), also returning the response metadata.
+    pub async fn product_book_with_meta(
+        &self,
+        product: &str,
+        limit: Option<u32>,
+    ) -> Result<ApiResponse<OrderBook>> {
+        let uri = product_book_uri(product, limit);
+        let response = self.make_request::<ProductBook>(self.request(&uri)).await?;
+        let pricebook = response.data.pricebook;
+        let mut book = OrderBook::new(&pricebook.product_id);
+        for level in pricebook.bids {
+            book.update(Side::Bid, level.price, level.size);
+        }
+        for level in pricebook.asks {
+            book.update(Side::Ask, level.price, level.size);
+        }
+        Ok(ApiResponse {
+            data: book,
+            meta: response.meta,
+        })
+    }
+}
+
+pub(crate) fn product_book_uri(product: &str, limit: Option<u32>) -> String {
+    let mut uri = format!("/api/v3/brokerage/market/product_book?product_id={product}");
+    if let Some(limit) = limit {
+        uri += &format!("&limit={limit}");
+    }
+    uri
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn decimal(value: &str) -> BigDecimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_product_book() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/api/v3/brokerage/market/product_book?product_id=BTC-USD&limit=2",
+            r#"{"pricebook":{"product_id":"BTC-USD",
+                "bids":[{"price":"99","size":"2"},{"price":"98","size":"4"}],
+                "asks":[{"price":"101","size":"1"},{"price":"103","size":"3"}],
+                "time":"2024-01-01T00:00:00Z"}}"#,
+        );
+        let client = Client::new(MAIN_URL).with_transport(mock);
+        let mut book = client.product_book("BTC-USD", Some(2)).await.unwrap();
+
+        assert_eq!(book.product(), "BTC-USD");
+        assert_eq!(book.spread(), Some(decimal("2")));
+        assert_eq!(book.mid_price(), Some(decimal("100")));
+        assert_eq!(book.vwap(Side::Ask, &decimal("2")), Some(decimal("102")));
+        assert_eq!(book.vwap(Side::Ask, &decimal("5")), None);
+        assert_eq!(book.imbalance(), Some(decimal("0.2")));
+        assert_eq!(book.imbalance_within(1), Some(decimal("1") / decimal("3")));
+
+        book.update(Side::Bid, decimal("98"), decimal("0"));
+        book.update(Side::Ask, decimal("101"), decimal("2"));
+        book.update(Side::Bid, decimal("100"), decimal("1"));
+        assert_eq!(book.volume(Side::Bid), &decimal("3"));
+        assert_eq!(book.volume(Side::Ask), &decimal("5"));
+        assert_eq!(book.spread(), Some(decimal("1")));
+        assert_eq!(book.best_bid(), Some((&decimal("100"), &decimal("1"))));
+    }
+}
@@ -0,0 +1,505 @@
+//! Order state transitions of the Exchange API, reconciled from snapshots of the orders.
+//!
+//! Every poll lists the open orders, and the orders created since the previous poll that are
+//! already done, and compares them with the previous snapshot. Orders that left the open
+//! listing without showing up as done are fetched one by one for their final state, and are
+//! kept until that succeeds. A failed poll is retried from the previous snapshot, so it delays
+//! events but loses none: every order that was filled, also one placed and filled between two
+//! polls, yields its final state.
+//!
+//! The stream is coarse-grained: it reports the difference between two polls, not every
+//! transition in between. An open order that is partly filled and then done between two polls
+//! yields a single [`Done`](OrderEvent::Done), and several fills between two polls a single
+//! [`Updated`](OrderEvent::Updated). An order placed and canceled between two polls before any
+//! fill yields nothing, as the Exchange deletes such orders. The individual fills are listed by
+//! [`fills`](ExchangeClient::fills).
+//!
+//! The websocket user channel is not subscribed to, so events arrive at most once per poll
+//! rather than as they happen.
+//!
+//! ```no_run
+//! # async fn run() {
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::exchange::ExchangeClient;
+//! use coinbase_rs::order_events::{OrderEvent, OrderEvents};
+//! use futures::stream::StreamExt;
+//!
+//! let exchange = ExchangeClient::new("<key>", "<secret>", "<passphrase>");
+//! let events = OrderEvents::new(Duration::from_secs(5)).watch(&exchange);
+//! futures::pin_mut!(events);
+//! while let Some(event) = events.next().await {
+//!     match event {
+//!         Ok(OrderEvent::Done(order)) => println!("{} {:?}", order.id, order.status),
+//!         Ok(event) => println!("{event:?}"),
+//!         Err(error) => eprintln!("poll failed: {error}"),
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_stream::stream;
+use bigdecimal::{BigDecimal, Zero};
+use futures::stream::{Stream, TryStreamExt};
+use http::StatusCode;
+
+use crate::exchange::{ExchangeClient, ListOrdersParams, NewOrder, Order};
+use crate::order::OrderStatus;
+use crate::{CBError, DateTime, Result};
+
+/// How far the clock of the Exchange may be behind the local one, by which the listings of
+/// done orders reach back.
+const CLOCK_SKEW: chrono::Duration = chrono::Duration::minutes(1);
+
+/// A change of an order between two snapshots.
+#[derive(Clone, Debug)]
+pub enum OrderEvent {
+    /// An order that wasn't open at the previous snapshot, e.g. one just placed.
+    Opened(Order),
+    /// The status or filled size of an open order changed.
+    Updated { previous: OrderStatus, order: Order },
+    /// An order is no longer open, with its final state.
+    Done(Order),
+    /// An order is no longer open and no longer exists, i.e. it was canceled before any fill.
+    Canceled { order_id: String },
+}
+
+/// Polls the open and recently done orders and yields an [`OrderEvent`] for every change
+/// between two polls.
+#[derive(Clone, Debug)]
+pub struct OrderEvents {
+    interval: Duration,
+    params: ListOrdersParams,
+}
+
+impl OrderEvents {
+    /// Creates a watcher polling the open orders of every product every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            params: ListOrdersParams::new(),
+        }
+    }
+
+    /// Polls the orders listed with `params` instead, e.g. of a single product.
+    pub fn params(self, params: ListOrdersParams) -> Self {
+        let mut _self = self;
+        _self.params = params;
+        _self
+    }
+
+    /// Yields events until dropped or the [`Shutdown`](crate::Shutdown) of `client` is
+    /// triggered. The first snapshot only records the open orders. Failed
+    /// polls are yielded as errors and polling goes on from the last snapshot.
+    pub fn watch<'a>(
+        &self,
+        client: &'a ExchangeClient,
+    ) -> impl Stream<Item = Result<OrderEvent>> + 'a {
+        let interval = self.interval;
+        let params = self.params.clone();
+        stream! {
+            let mut open: Option<HashMap<String, Order>> = None;
+            // Start of the last successful poll.
+            let mut since = chrono::Utc::now();
+            // Orders reported done, by creation time, so overlapping listings don't repeat them.
+            let mut reported: HashMap<String, DateTime> = HashMap::new();
+            loop {
+                let started = chrono::Utc::now();
+                let snapshot = client.orders(&params).try_concat().await;
+                match (snapshot, open.as_mut()) {
+                    (Err(error), _) => yield Err(error),
+                    (Ok(snapshot), None) => {
+                        open = Some(
+                            snapshot
+                                .into_iter()
+                                .map(|order| (order.id.clone(), order))
+                                .collect(),
+                        );
+                        since = started;
+                    }
+                    (Ok(snapshot), Some(open)) => {
+                        let done = params.done_since(since - CLOCK_SKEW);
+                        let done: Vec<Order> = match client.orders(&done).try_concat().await {
+                            Ok(done) => done,
+                            Err(error) => {
+                                yield Err(error);
+                                if !client.client.idle(interval).await {
+                                    break;
+                                }
+                                continue;
+                            }
+                        };
+
+                        let mut listed = HashMap::new();
+                        for order in snapshot {
+                            match open.remove(&order.id) {
+                                None => yield Ok(OrderEvent::Opened(order.clone())),
+                                Some(previous) if changed(&previous, &order) => {
+                                    yield Ok(OrderEvent::Updated {
+                                        previous: previous.status,
+                                        order: order.clone(),
+                                    })
+                                }
+                                Some(_) => {}
+                            }
+                            listed.insert(order.id.clone(), order);
+                        }
+                        // Orders done since the previous poll, also ones never seen open. An
+                        // order still listed as open is left to the next poll.
+                        for order in done {
+                            if listed.contains_key(&order.id)
+                                || reported.contains_key(&order.id)
+                            {
+                                continue;
+                            }
+                            open.remove(&order.id);
+                            reported.insert(order.id.clone(), order.created_at);
+                            yield Ok(OrderEvent::Done(order));
+                        }
+                        // Whatever is left wasn't listed, its final state is fetched.
+                        for (id, previous) in std::mem::replace(open, listed) {
+                            match client.order(&id).await {
+                                Ok(order) if is_open(&order) => {
+                                    open.insert(id, order);
+                                }
+                                Ok(order) => {
+                                    reported.insert(id, order.created_at);
+                                    yield Ok(OrderEvent::Done(order));
+                                }
+                                Err(error) if is_not_found(&error) => {
+                                    yield Ok(OrderEvent::Canceled { order_id: id })
+                                }
+                                Err(error) => {
+                                    open.insert(id, previous);
+                                    yield Err(error);
+                                }
+                            }
+                        }
+                        since = started;
+                        reported.retain(|_, created_at| *created_at >= since - CLOCK_SKEW);
+                    }
+                }
+                if !client.client.idle(interval).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Outcome of an order placed by [`OrderEvents::execute`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionReport {
+    pub order_id: String,
+    pub status: OrderStatus,
+    /// Amount of the base currency bought or sold.
+    pub filled_size: BigDecimal,
+    /// Quote currency paid or received per unit of the base currency, `None` without fills.
+    pub average_price: Option<BigDecimal>,
+    pub fees: BigDecimal,
+    /// Whether an unfilled remainder was canceled at the deadline.
+    pub canceled: bool,
+}
+
+impl ExecutionReport {
+    fn new(order: &Order, canceled: bool) -> Self {
+        let filled_size = order.filled_size.clone().unwrap_or_default();
+        let average_price = match &order.executed_value {
+            Some(value) if !filled_size.is_zero() => Some(value / &filled_size),
+            _ => None,
+        };
+        Self {
+            order_id: order.id.clone(),
+            status: order.status,
+            filled_size,
+            average_price,
+            fees: order.fill_fees.clone().unwrap_or_default(),
+            canceled,
+        }
+    }
+}
+
+impl OrderEvents {
+    /// Places `order` and follows it every `interval` until it is done. If it is still open
+    /// `deadline` after being placed, the unfilled remainder is canceled.
+    ///
+    /// Failed polls are retried until the deadline, and an order still open when polling
+    /// fails for good is canceled all the same. An error is only returned if the order may
+    /// still be open, i.e. if canceling it failed too. A report whose final state couldn't be
+    /// fetched after canceling reflects the last successful poll.
+    pub async fn execute(
+        &self,
+        client: &ExchangeClient,
+        order: &NewOrder,
+        deadline: Duration,
+    ) -> Result<ExecutionReport> {
+        let started = crate::rt::Instant::now();
+        let mut last = client.place_order(order).await?;
+        loop {
+            if !is_open(&last) {
+                return Ok(ExecutionReport::new(&last, false));
+            }
+            let remaining = deadline.saturating_sub(started.elapsed());
+            if remaining.is_zero() {
+                break;
+            }
+            crate::rt::sleep(self.interval.min(remaining)).await;
+            match client.order(&last.id).await {
+                Ok(order) => last = order,
+                // Orders canceled before any fill are deleted.
+                Err(error) if is_not_found(&error) => {
+                    last.status = OrderStatus::Done;
+                    return Ok(ExecutionReport::new(&last, true));
+                }
+                // Polled again until the deadline, then canceled.
+                Err(_) => {}
+            }
+        }
+
+        let cancel = client.cancel_order(&last.id).await;
+        match client.order(&last.id).await {
+            Ok(order) if is_open(&order) => match cancel {
+                Err(error) => Err(error),
+                Ok(_) => Ok(ExecutionReport::new(&order, true)),
+            },
+            // Filled while being canceled.
+            Ok(order) => Ok(ExecutionReport::new(&order, cancel.is_ok())),
+            Err(error) if is_not_found(&error) => {
+                last.status = OrderStatus::Done;
+                Ok(ExecutionReport::new(&last, true))
+            }
+            Err(error) => match cancel {
+                Ok(_) => {
+                    last.status = OrderStatus::Done;
+                    Ok(ExecutionReport::new(&last, true))
+                }
+                // Neither canceled nor known to be done.
+                Err(_) => Err(error),
+            },
+        }
+    }
+}
+
+fn changed(previous: &Order, order: &Order) -> bool {
+    previous.status != order.status || previous.filled_size != order.filled_size
+}
+
+fn is_open(order: &Order) -> bool {
+    matches!(
+        order.status,
+        OrderStatus::Pending | OrderStatus::Received | OrderStatus::Open | OrderStatus::Active
+    )
+}
+
+fn is_not_found(error: &CBError) -> bool {
+    matches!(
+        error,
+        CBError::Coinbase { status, .. } | CBError::Status { status, .. }
+            if *status == StatusCode::NOT_FOUND
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::StreamExt;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{Client, MAIN_URL};
+
+    fn order(id: &str, status: &str, filled_size: &str) -> String {
+        format!(
+            r#"{{"id":"{id}","product_id":"BTC-USD","side":"buy","type":"limit",
+                "price":"30000","size":"0.01","time_in_force":"GTC","post_only":false,
+                "created_at":"2024-01-01T00:00:00Z","status":"{status}",
+                "filled_size":"{filled_size}","executed_value":"0","fill_fees":"0",
+                "settled":false}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_order_events() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/orders?limit=100",
+            &format!("[{},{}]", order("a", "open", "0"), order("b", "open", "0")),
+        )
+        // b is filled partly, a disappears and c is placed.
+        .push_json(
+            "/orders?limit=100",
+            &format!(
+                "[{},{}]",
+                order("c", "open", "0"),
+                order("b", "open", "0.005")
+            ),
+        )
+        .push_json("/orders", "[]")
+        .push_status("/orders/a", StatusCode::BAD_GATEWAY, "")
+        // a is still gone, b filled and c was canceled.
+        .push_json("/orders?limit=100", "[]")
+        .push_json("/orders", "[]")
+        .push_json("/orders/a", &order("a", "done", "0.01"))
+        .push_json("/orders/b", &order("b", "done", "0.01"))
+        .push_status(
+            "/orders/c",
+            StatusCode::NOT_FOUND,
+            r#"{"message":"NotFound"}"#,
+        );
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_retry_policy(crate::retry::NoRetry)
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", "c2VjcmV0", "passphrase");
+
+        let events: Vec<_> = OrderEvents::new(Duration::ZERO)
+            .watch(&exchange)
+            .take(6)
+            .collect()
+            .await;
+        assert!(matches!(&events[0], Ok(OrderEvent::Opened(order)) if order.id == "c"));
+        assert!(matches!(
+            &events[1],
+            Ok(OrderEvent::Updated { previous: OrderStatus::Open, order }) if order.id == "b"
+        ));
+        assert!(events[2].is_err());
+        let mut rest: Vec<String> = events[3..]
+            .iter()
+            .map(|event| match event.as_ref().unwrap() {
+                OrderEvent::Done(order) => format!("done {}", order.id),
+                OrderEvent::Canceled { order_id } => format!("canceled {order_id}"),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        rest.sort();
+        assert_eq!(rest, ["canceled c", "done a", "done b"]);
+    }
+
+    #[tokio::test]
+    async fn test_done_between_polls() {
+        let now = chrono::Utc::now().to_rfc3339();
+        let recent = |id: &str| order(id, "done", "0.01").replace("2024-01-01T00:00:00Z", &now);
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/orders?limit=100",
+            &format!("[{}]", order("a", "open", "0")),
+        )
+        // x is placed and filled between two polls.
+        .push_json(
+            "/orders?limit=100",
+            &format!("[{}]", order("a", "open", "0")),
+        )
+        .push_json("/orders", &format!("[{}]", recent("x")))
+        // a is filled, x is listed again.
+        .push_json("/orders?limit=100", "[]")
+        .push_json("/orders", &format!("[{},{}]", recent("x"), recent("a")));
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_retry_policy(crate::retry::NoRetry)
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", "c2VjcmV0", "passphrase");
+
+        let events: Vec<_> = OrderEvents::new(Duration::ZERO)
+            .watch(&exchange)
+            .take(2)
+            .collect()
+            .await;
+        assert!(matches!(&events[0], Ok(OrderEvent::Done(order)) if order.id == "x"));
+        assert!(matches!(&events[1], Ok(OrderEvent::Done(order)) if order.id == "a"));
+
+        // The final state of a came with the listing, so it isn't fetched.
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 5);
+        let query = requests[2].uri().query().unwrap();
+        assert!(query.contains("status=done&start_date="));
+        assert!(mock.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_execute() {
+        let partly = r#"{"id":"a","product_id":"BTC-USD","side":"buy","type":"limit",
+            "price":"30000","size":"0.01","time_in_force":"GTC","post_only":false,
+            "created_at":"2024-01-01T00:00:00Z","status":"open","filled_size":"0.004",
+            "executed_value":"119.8","fill_fees":"0.6","settled":false}"#;
+        let mock = MockTransport::new();
+        mock.push_json("/orders", &order("a", "pending", "0"))
+            .push_json("/orders/a", &order("a", "open", "0"))
+            .push_json("/orders/a", partly)
+            .push_json("/orders/a", r#""a""#)
+            .push_json("/orders/a", &partly.replace(r#""open""#, r#""done""#));
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", "c2VjcmV0", "passphrase");
+
+        let order = NewOrder::limit(
+            crate::order::OrderSide::Buy,
+            "BTC-USD",
+            "30000".parse().unwrap(),
+            "0.01".parse().unwrap(),
+        );
+        let report = OrderEvents::new(Duration::from_secs(1))
+            .execute(&exchange, &order, Duration::from_millis(1500))
+            .await
+            .unwrap();
+        assert_eq!(report.status, OrderStatus::Done);
+        assert!(report.canceled);
+        assert_eq!(report.filled_size, "0.004".parse().unwrap());
+        assert_eq!(report.average_price, Some("29950".parse().unwrap()));
+        assert_eq!(report.fees, "0.6".parse().unwrap());
+
+        let methods: Vec<_> = mock.requests().iter().map(|r| r.method().clone()).collect();
+        assert_eq!(methods[3], http::Method::DELETE);
+        assert!(mock.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_execute_failed_polls() {
+        let mock = MockTransport::new();
+        mock.push_json("/orders", &order("a", "pending", "0"));
+        for _ in 0..4 {
+            mock.push_status("/orders/a", StatusCode::SERVICE_UNAVAILABLE, "");
+        }
+        mock.push_json("/orders/a", r#""a""#).push_status(
+            "/orders/a",
+            StatusCode::SERVICE_UNAVAILABLE,
+            "",
+        );
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_retry_policy(crate::retry::NoRetry)
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", "c2VjcmV0", "passphrase");
+
+        let new_order = NewOrder::limit(
+            crate::order::OrderSide::Buy,
+            "BTC-USD",
+            "30000".parse().unwrap(),
+            "0.01".parse().unwrap(),
+        );
+        // Every poll fails, yet the order is canceled at the deadline.
+        let report = OrderEvents::new(Duration::from_secs(1))
+            .execute(&exchange, &new_order, Duration::from_secs(4))
+            .await
+            .unwrap();
+        assert!(report.canceled);
+        assert_eq!(report.order_id, "a");
+
+        let methods: Vec<_> = mock.requests().iter().map(|r| r.method().clone()).collect();
+        assert_eq!(methods[5], http::Method::DELETE);
+        assert!(mock.is_empty());
+
+        // An order that may still be open is reported as an error.
+        mock.push_json("/orders", &order("b", "pending", "0"))
+            .push_status("/orders/b", StatusCode::SERVICE_UNAVAILABLE, "")
+            .push_status("/orders/b", StatusCode::SERVICE_UNAVAILABLE, "")
+            .push_status("/orders/b", StatusCode::SERVICE_UNAVAILABLE, "");
+        let result = OrderEvents::new(Duration::from_secs(1))
+            .execute(&exchange, &new_order, Duration::from_secs(1))
+            .await;
+        assert!(result.is_err());
+        assert!(mock.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+//! OpenTelemetry spans of the HTTP requests sent by a client, compiled down to nothing unless
+//! the `otel` feature is enabled.
+//!
+//! Every attempt at sending a request gets a client span, a child of the current context, using
+//! the globally installed tracer provider. The span context is injected into the request headers
+//! with the global propagator, so traces continue through proxies and gateways recording them.
+
+use bytes::Bytes;
+use http::{Request, StatusCode};
+#[cfg(feature = "otel")]
+use opentelemetry::{
+    global,
+    propagation::Injector,
+    trace::{SpanKind, Status, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+
+use crate::CBError;
+
+/// Name of the tracer creating the spans.
+#[cfg(feature = "otel")]
+const TRACER: &str = "coinbase-rs";
+
+/// Span covering one attempt at sending a request.
+pub(crate) struct HttpSpan {
+    #[cfg(feature = "otel")]
+    cx: Context,
+}
+
+impl HttpSpan {
+    #[cfg_attr(not(feature = "otel"), allow(unused_variables))]
+    pub(crate) fn start(request: &mut Request<Bytes>) -> Self {
+        #[cfg(feature = "otel")]
+        {
+            let uri = request.uri();
+            let host = uri.host().unwrap_or_default().to_string();
+            let tracer = global::tracer(TRACER);
+            let span = tracer
+                .span_builder(request.method().to_string())
+                .with_kind(SpanKind::Client)
+                .with_attributes([
+                    KeyValue::new("http.method", request.method().to_string()),
+                    KeyValue::new(
+                        "http.url",
+                        format!(
+                            "{}://{}{}",
+                            uri.scheme_str().unwrap_or("https"),
+                            host,
+                            uri.path()
+                        ),
+                    ),
+                    KeyValue::new("net.peer.name", host),
+                    KeyValue::new("coinbase.endpoint", uri.path().to_string()),
+                ])
+                .start_with_context(&tracer, &Context::current());
+            let cx = Context::current_with_span(span);
+            global::get_text_map_propagator(|propagator| {
+                propagator.inject_context(&cx, &mut HeaderInjector(request.headers_mut()))
+            });
+            Self { cx }
+        }
+        #[cfg(not(feature = "otel"))]
+        Self {}
+    }
+
+    /// Ends the span with the status of the response, or the error no response came with.
+    #[cfg_attr(not(feature = "otel"), allow(unused_variables))]
+    pub(crate) fn finish(self, status: std::result::Result<StatusCode, &CBError>) {
+        #[cfg(feature = "otel")]
+        {
+            let span = self.cx.span();
+            match status {
+                Ok(status) => {
+                    span.set_attribute(KeyValue::new("http.status_code", status.as_u16() as i64));
+                    if status.is_client_error() || status.is_server_error() {
+                        span.set_status(Status::error(status.to_string()));
+                    }
+                }
+                Err(error) => span.set_status(Status::error(error.to_string())),
+            }
+            span.end();
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+struct HeaderInjector<'a>(&'a mut http::HeaderMap);
+
+#[cfg(feature = "otel")]
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            http::HeaderName::from_bytes(key.as_bytes()),
+            http::HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod test {
+    use opentelemetry::propagation::{text_map_propagator::FieldIter, TextMapPropagator};
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{Client, MAIN_URL};
+
+    /// Propagates a fixed header, since the no-op tracer of the API crate has no span context.
+    #[derive(Debug)]
+    struct FixedPropagator;
+
+    impl TextMapPropagator for FixedPropagator {
+        fn inject_context(&self, _cx: &Context, injector: &mut dyn Injector) {
+            injector.set(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".into(),
+            );
+        }
+
+        fn extract_with_context(
+            &self,
+            cx: &Context,
+            _extractor: &dyn opentelemetry::propagation::Extractor,
+        ) -> Context {
+            cx.clone()
+        }
+
+        fn fields(&self) -> FieldIter<'_> {
+            FieldIter::new(&[])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_context_propagation() {
+        global::set_text_map_propagator(FixedPropagator);
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/prices/BTC-USD/spot",
+            r#"{"data":{"amount":"1020.25","currency":"USD"}}"#,
+        );
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+        client.spot_price("BTC-USD", None).await.unwrap();
+
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].headers()["traceparent"],
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+        );
+    }
+}
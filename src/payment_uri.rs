@@ -0,0 +1,188 @@
+//! Payment URIs for deposit addresses, the content of the QR codes wallets scan.
+
+use bigdecimal::{BigDecimal, Signed};
+
+use crate::private::Address;
+
+/// Decimal places of one ether in wei.
+const WEI_EXPONENT: i64 = 18;
+
+impl Address {
+    /// A URI asking a wallet to pay `amount` of `currency`, or any amount, to this address:
+    /// [BIP 21](https://github.com/bitcoin/bips/blob/master/bip-0021.mediawiki) for Bitcoin and
+    /// its forks, [EIP 681](https://eips.ethereum.org/EIPS/eip-681) for ether,
+    /// [SEP 7](https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0007.md) for
+    /// Stellar and the Ripple and Solana Pay schemes, with the destination tag or memo of the
+    /// address. `None` for networks without a scheme, and for tokens on Ethereum, whose URIs
+    /// need the token contract.
+    ///
+    /// ```
+    /// # fn uri(address: coinbase_rs::private::Address) {
+    /// let uri = address.payment_uri("BTC", Some(&"0.01".parse().unwrap()));
+    /// // e.g. Some("bitcoin:3PMTBpYKdrZ6XAoqrUq9Ac5rFNzFd36PZz?amount=0.01")
+    /// # }
+    /// ```
+    pub fn payment_uri(&self, currency: &str, amount: Option<&BigDecimal>) -> Option<String> {
+        let amount = amount.filter(|amount| amount.is_positive());
+        let mut query: Vec<(&str, String)> = Vec::new();
+        let network = self.network.to_ascii_lowercase();
+        let uri = match network.as_str() {
+            "bitcoin" | "bitcoincash" | "litecoin" | "dogecoin" => {
+                let prefix = format!("{network}:");
+                let address = self.address.strip_prefix(&prefix).unwrap_or(&self.address);
+                if let Some(amount) = amount {
+                    query.push(("amount", amount.to_string()));
+                }
+                if let Some(name) = &self.name {
+                    query.push(("label", name.clone()));
+                }
+                format!("{prefix}{address}")
+            }
+            "ethereum" => {
+                if !currency.eq_ignore_ascii_case("ETH") {
+                    return None;
+                }
+                if let Some(amount) = amount {
+                    let wei = (amount * BigDecimal::new(1.into(), -WEI_EXPONENT)).with_scale(0);
+                    query.push(("value", wei.to_string()));
+                }
+                format!("ethereum:{}", self.address)
+            }
+            "ripple" => {
+                if let Some(amount) = amount {
+                    query.push(("amount", amount.to_string()));
+                }
+                if let Some(tag) = &self.destination_tag {
+                    query.push(("dt", tag.clone()));
+                }
+                format!("ripple:{}", self.address)
+            }
+            "stellar" => {
+                query.push(("destination", self.address.clone()));
+                if let Some(amount) = amount {
+                    query.push(("amount", amount.to_string()));
+                }
+                if let Some(memo) = &self.destination_tag {
+                    let memo_type = if memo.bytes().all(|b| b.is_ascii_digit()) {
+                        "MEMO_ID"
+                    } else {
+                        "MEMO_TEXT"
+                    };
+                    query.push(("memo", memo.clone()));
+                    query.push(("memo_type", memo_type.to_string()));
+                }
+                "web+stellar:pay".to_string()
+            }
+            "solana" => {
+                if let Some(amount) = amount {
+                    query.push(("amount", amount.to_string()));
+                }
+                if let Some(memo) = &self.destination_tag {
+                    query.push(("memo", memo.clone()));
+                }
+                format!("solana:{}", self.address)
+            }
+            _ => return None,
+        };
+        if query.is_empty() {
+            return Some(uri);
+        }
+        let query: Vec<String> = query
+            .iter()
+            .map(|(key, value)| format!("{key}={}", encode(value)))
+            .collect();
+        Some(format!("{uri}?{}", query.join("&")))
+    }
+}
+
+/// Percent-encodes everything but unreserved characters.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn address(network: &str, address: &str, name: Option<&str>, tag: Option<&str>) -> Address {
+        Address {
+            id: "dd3183eb-af1d-5f5d-a90d-cbff946435ff".to_string(),
+            address: address.to_string(),
+            name: name.map(str::to_string),
+            created_at: None,
+            updated_at: None,
+            network: network.to_string(),
+            destination_tag: tag.map(str::to_string),
+            resource: "address".to_string(),
+            resource_path: "/".to_string(),
+        }
+    }
+
+    fn decimal(value: &str) -> BigDecimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_payment_uri() {
+        let btc = address(
+            "bitcoin",
+            "3PMTBpYKdrZ6XAoqrUq9Ac5rFNzFd36PZz",
+            Some("Tip jar"),
+            None,
+        );
+        assert_eq!(
+            btc.payment_uri("BTC", Some(&decimal("0.01"))).unwrap(),
+            "bitcoin:3PMTBpYKdrZ6XAoqrUq9Ac5rFNzFd36PZz?amount=0.01&label=Tip%20jar"
+        );
+        let bch = address("bitcoincash", "bitcoincash:qpm2qsznhks23z7629", None, None);
+        assert_eq!(
+            bch.payment_uri("BCH", None).unwrap(),
+            "bitcoincash:qpm2qsznhks23z7629"
+        );
+
+        let eth = address(
+            "ethereum",
+            "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359",
+            None,
+            None,
+        );
+        assert_eq!(
+            eth.payment_uri("ETH", Some(&decimal("2.014"))).unwrap(),
+            "ethereum:0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359?value=2014000000000000000"
+        );
+        assert_eq!(eth.payment_uri("USDC", Some(&decimal("1"))), None);
+
+        let xrp = address(
+            "ripple",
+            "rw2ciyaNshpHe7bCHo4bRWq6pqqynnWKQg",
+            None,
+            Some("1234"),
+        );
+        assert_eq!(
+            xrp.payment_uri("XRP", Some(&decimal("25"))).unwrap(),
+            "ripple:rw2ciyaNshpHe7bCHo4bRWq6pqqynnWKQg?amount=25&dt=1234"
+        );
+        let xlm = address(
+            "stellar",
+            "GAHK7EEG2WWHVKDNT4CEQFZGKF2LGDSW",
+            None,
+            Some("4816"),
+        );
+        assert_eq!(
+            xlm.payment_uri("XLM", None).unwrap(),
+            "web+stellar:pay?destination=GAHK7EEG2WWHVKDNT4CEQFZGKF2LGDSW&memo=4816&memo_type=MEMO_ID"
+        );
+        assert_eq!(
+            address("cardano", "addr1", None, None).payment_uri("ADA", None),
+            None
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Value of all accounts in a single currency.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::{Client, MAIN_URL};
+//!
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! let portfolio = client.portfolio("USD").await?;
+//! for asset in &portfolio.assets {
+//!     println!("{} {} = {:?} USD", asset.amount, asset.currency, asset.value);
+//! }
+//! println!("total {} USD", portfolio.total);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use bigdecimal::{BigDecimal, Signed, Zero};
+use futures::stream::TryStreamExt;
+
+use crate::private::Account;
+use crate::{Client, Result};
+
+/// Decimal places values are rounded to.
+const VALUE_SCALE: i64 = 8;
+
+/// Holdings of all accounts valued in one currency.
+#[derive(Clone, Debug)]
+pub struct Portfolio {
+    /// Currency the values are expressed in.
+    pub currency: String,
+    /// Sum of the values of all priced assets.
+    pub total: BigDecimal,
+    /// Assets with a non-zero balance, most valuable first and unpriced ones last.
+    pub assets: Vec<AssetValue>,
+}
+
+/// Balance of one currency summed over all accounts holding it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetValue {
+    pub currency: String,
+    pub amount: BigDecimal,
+    /// Value of `amount` in the portfolio's currency, `None` if there is no exchange rate for it.
+    pub value: Option<BigDecimal>,
+}
+
+impl Client {
+    ///
+    /// **Portfolio value**
+    ///
+    /// Lists all accounts and values their balances in `currency` (e.g. the user's native
+    /// currency) using a single request for exchange rates. Values are rounded to 8 decimal
+    /// places.
+    ///
+    pub async fn portfolio(&self, currency: &str) -> Result<Portfolio> {
+        let accounts: Vec<Account> = self.accounts().try_concat().await?;
+        let rates = self.exchange_rates(currency).await?.rates;
+        Ok(Portfolio::value(currency, &accounts, &rates))
+    }
+}
+
+impl Portfolio {
+    /// Values `accounts` with `rates`, the amount of each currency worth one unit of `currency`.
+    fn value(currency: &str, accounts: &[Account], rates: &HashMap<String, BigDecimal>) -> Self {
+        let mut amounts: BTreeMap<&str, BigDecimal> = BTreeMap::new();
+        for account in accounts {
+            let amount = amounts
+                .entry(account.balance.currency.as_str())
+                .or_insert_with(BigDecimal::zero);
+            *amount = &*amount + &account.balance.amount;
+        }
+
+        let mut total = BigDecimal::zero();
+        let mut assets: Vec<AssetValue> = amounts
+            .into_iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(asset, amount)| {
+                let value = if asset == currency {
+                    Some(amount.clone())
+                } else {
+                    rates
+                        .get(asset)
+                        .filter(|rate| rate.is_positive())
+                        .map(|rate| &amount / rate)
+                };
+                if let Some(value) = &value {
+                    total = &total + value;
+                }
+                AssetValue {
+                    currency: asset.to_string(),
+                    amount,
+                    value: value.map(|value| value.round(VALUE_SCALE)),
+                }
+            })
+            .collect();
+        assets.sort_by(|a, b| b.value.cmp(&a.value));
+
+        Portfolio {
+            currency: currency.to_string(),
+            total: total.round(VALUE_SCALE),
+            assets,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn account(currency: &str, amount: &str) -> String {
+        format!(
+            r#"{{"id":"{currency}-{amount}","type":"wallet","created_at":null,"updated_at":null,
+                "resource":"account","resource_path":"/","name":"{currency} Wallet",
+                "primary":false,
+                "currency":{{"code":"{currency}","name":"{currency}","exponent":8,"type":"crypto"}},
+                "balance":{{"amount":"{amount}","currency":"{currency}"}},
+                "allow_deposits":true,"allow_withdrawals":true}}"#
+        )
+    }
+
+    fn decimal(value: &str) -> BigDecimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_portfolio() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/accounts",
+            &format!(
+                r#"{{"pagination":{{"limit":3,"order":"asc","next_uri":"/v2/accounts?starting_after=ETH"}},
+                    "data":[{},{},{}]}}"#,
+                account("BTC", "0.5"),
+                account("ETH", "3"),
+                account("DOGE", "0"),
+            ),
+        )
+        .push_json(
+            "/v2/accounts?starting_after=ETH",
+            &format!(
+                r#"{{"data":[{},{},{}]}}"#,
+                account("BTC", "0.25"),
+                account("USD", "10.5"),
+                account("XYZ", "7"),
+            ),
+        )
+        .push_json(
+            "/v2/exchange-rates?currency=USD",
+            r#"{"data":{"currency":"USD","rates":{"BTC":"0.00002","ETH":"0.0004","USD":"1"}}}"#,
+        );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+
+        let portfolio = client.portfolio("USD").await.unwrap();
+        assert_eq!(portfolio.total, decimal("45010.5"));
+        let assets: Vec<_> = portfolio
+            .assets
+            .iter()
+            .map(|asset| (asset.currency.as_str(), asset.value.clone()))
+            .collect();
+        assert_eq!(
+            assets,
+            [
+                ("BTC", Some(decimal("37500"))),
+                ("ETH", Some(decimal("7500"))),
+                ("USD", Some(decimal("10.5"))),
+                ("XYZ", None),
+            ]
+        );
+        assert_eq!(mock.requests().len(), 3);
+    }
+}
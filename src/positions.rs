@@ -0,0 +1,334 @@
+//! Positions and profit and loss of Exchange products, built from the fills of their orders.
+//!
+//! A [`PositionTracker`] polls the fills of each product, applies the new ones to an average
+//! cost position and marks it against the product ticker. The positions can be read at any
+//! time while the stream yields every position that changed.
+//!
+//! ```no_run
+//! # async fn run() {
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::exchange::ExchangeClient;
+//! use coinbase_rs::positions::PositionTracker;
+//! use futures::stream::StreamExt;
+//!
+//! let exchange = ExchangeClient::new("<key>", "<secret>", "<passphrase>");
+//! let tracker = PositionTracker::new(&["BTC-USD", "ETH-USD"], Duration::from_secs(10));
+//! let updates = tracker.watch(&exchange);
+//! futures::pin_mut!(updates);
+//! while let Some(update) = updates.next().await {
+//!     match update {
+//!         Ok(position) => println!("{} {:?}", position.product_id, position.unrealized_pnl()),
+//!         Err(error) => eprintln!("poll failed: {error}"),
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_stream::stream;
+use bigdecimal::{BigDecimal, Signed, Zero};
+use futures::stream::{Stream, StreamExt};
+
+use crate::exchange::{ExchangeClient, Fill, ListFillsParams};
+use crate::order::OrderSide;
+use crate::Result;
+
+/// Holdings of a product under the average cost method.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub product_id: String,
+    /// Amount of the base currency held, negative when short.
+    pub size: BigDecimal,
+    /// Average price the open size was entered at, `None` when flat.
+    pub average_entry_price: Option<BigDecimal>,
+    /// Profit or loss, in the quote currency, of the size closed so far, before fees.
+    pub realized_pnl: BigDecimal,
+    /// Fees paid on all fills, in the quote currency.
+    pub fees: BigDecimal,
+    /// Last price the position was marked at.
+    pub mark_price: Option<BigDecimal>,
+    /// Trade id of the last fill applied, later fills have higher ids.
+    pub last_trade_id: Option<u64>,
+}
+
+impl Position {
+    /// Creates a flat position.
+    pub fn new(product_id: &str) -> Self {
+        Self {
+            product_id: product_id.to_string(),
+            size: BigDecimal::zero(),
+            average_entry_price: None,
+            realized_pnl: BigDecimal::zero(),
+            fees: BigDecimal::zero(),
+            mark_price: None,
+            last_trade_id: None,
+        }
+    }
+
+    /// Adds a fill to the position. Fills must be applied oldest first, fills of other
+    /// products or already applied are ignored. Returns whether the fill was applied.
+    pub fn apply(&mut self, fill: &Fill) -> bool {
+        if fill.product_id != self.product_id
+            || self.last_trade_id.is_some_and(|last| fill.trade_id <= last)
+        {
+            return false;
+        }
+        let size = match fill.side {
+            OrderSide::Buy => fill.size.clone(),
+            OrderSide::Sell => -&fill.size,
+        };
+        match &self.average_entry_price {
+            Some(average) if self.size.signum() != size.signum() => {
+                let closed = self.size.abs().min(size.abs());
+                self.realized_pnl += (&fill.price - average) * closed * self.size.signum();
+                let previous = self.size.signum();
+                self.size += size;
+                if self.size.is_zero() {
+                    self.average_entry_price = None;
+                } else if self.size.signum() != previous {
+                    self.average_entry_price = Some(fill.price.clone());
+                }
+            }
+            average => {
+                let cost = match average {
+                    Some(average) => average * self.size.abs(),
+                    None => BigDecimal::zero(),
+                };
+                self.size += size;
+                let average = (cost + &fill.price * fill.size.abs()) / self.size.abs();
+                self.average_entry_price = Some(average);
+            }
+        }
+        self.fees += &fill.fee;
+        self.last_trade_id = Some(fill.trade_id);
+        true
+    }
+
+    /// Marks the position at `price`, e.g. of the last trade.
+    pub fn mark(&mut self, price: BigDecimal) {
+        self.mark_price = Some(price);
+    }
+
+    /// Profit or loss, in the quote currency, of the open size at the mark price. Zero when
+    /// flat and `None` before the position was marked.
+    pub fn unrealized_pnl(&self) -> Option<BigDecimal> {
+        let mark = self.mark_price.as_ref()?;
+        Some(match &self.average_entry_price {
+            Some(average) => (mark - average) * &self.size,
+            None => BigDecimal::zero(),
+        })
+    }
+}
+
+/// Keeps the positions of a set of products up to date, see the [module documentation](self).
+/// Clones share the positions.
+#[derive(Clone, Debug)]
+pub struct PositionTracker {
+    interval: Duration,
+    products: Vec<String>,
+    positions: Arc<Mutex<HashMap<String, Position>>>,
+}
+
+impl PositionTracker {
+    /// Creates a tracker of flat positions in `products`, polling every `interval`.
+    pub fn new(products: &[&str], interval: Duration) -> Self {
+        Self {
+            interval,
+            products: products.iter().map(|product| product.to_string()).collect(),
+            positions: Arc::new(Mutex::new(
+                products
+                    .iter()
+                    .map(|product| (product.to_string(), Position::new(product)))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// The current position in `product`, `None` if it isn't tracked.
+    pub fn position(&self, product: &str) -> Option<Position> {
+        self.positions.lock().unwrap().get(product).cloned()
+    }
+
+    /// The current positions, in the order the products were given.
+    pub fn positions(&self) -> Vec<Position> {
+        let positions = self.positions.lock().unwrap();
+        self.products
+            .iter()
+            .filter_map(|product| positions.get(product).cloned())
+            .collect()
+    }
+
+    /// Polls the fills and tickers until dropped or the [`Shutdown`](crate::Shutdown) of
+    /// `client` is triggered, yielding a position whenever a fill was
+    /// applied to it or its mark price moved. The first poll applies the whole fill history.
+    /// Failed polls are yielded as errors and polling goes on with the next product.
+    pub fn watch<'a>(
+        &self,
+        client: &'a ExchangeClient,
+    ) -> impl Stream<Item = Result<Position>> + 'a {
+        let tracker = self.clone();
+        stream! {
+            loop {
+                for product in &tracker.products {
+                    let last = tracker.position(product).and_then(|p| p.last_trade_id);
+                    let fills = match new_fills(client, product, last).await {
+                        Ok(fills) => fills,
+                        Err(error) => {
+                            yield Err(error);
+                            continue;
+                        }
+                    };
+                    let ticker = client.ticker(product).await;
+                    let update = {
+                        let mut positions = tracker.positions.lock().unwrap();
+                        let position = positions
+                            .entry(product.clone())
+                            .or_insert_with(|| Position::new(product));
+                        let mut changed = false;
+                        for fill in &fills {
+                            changed |= position.apply(fill);
+                        }
+                        if let Ok(ticker) = &ticker {
+                            changed |= position.mark_price.as_ref() != Some(&ticker.price);
+                            position.mark(ticker.price.clone());
+                        }
+                        changed.then(|| position.clone())
+                    };
+                    if let Some(position) = update {
+                        yield Ok(position);
+                    }
+                    if let Err(error) = ticker {
+                        yield Err(error);
+                    }
+                }
+                if !client.client.idle(tracker.interval).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Fills of `product` after the trade `last`, oldest first. The listing is newest first, so
+/// only pages until the first known fill are fetched.
+async fn new_fills(client: &ExchangeClient, product: &str, last: Option<u64>) -> Result<Vec<Fill>> {
+    let pages = client.fills(&ListFillsParams::product(product));
+    futures::pin_mut!(pages);
+    let mut fills = Vec::new();
+    while let Some(page) = pages.next().await {
+        let page = page?;
+        let known = page
+            .iter()
+            .any(|fill| last.is_some_and(|last| fill.trade_id <= last));
+        fills.extend(
+            page.into_iter()
+                .filter(|fill| last.is_none_or(|last| fill.trade_id > last)),
+        );
+        if known {
+            break;
+        }
+    }
+    fills.sort_by_key(|fill| fill.trade_id);
+    Ok(fills)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::{Client, MAIN_URL};
+
+    fn fill(trade_id: u64, side: &str, price: &str, size: &str) -> String {
+        format!(
+            r#"{{"trade_id":{trade_id},"order_id":"o{trade_id}","product_id":"BTC-USD",
+                "side":"{side}","price":"{price}","size":"{size}","fee":"1",
+                "liquidity":"T","created_at":"2024-01-01T00:00:00Z","settled":true}}"#
+        )
+    }
+
+    fn ticker(price: &str) -> String {
+        format!(
+            r#"{{"trade_id":1,"price":"{price}","size":"0.1","bid":"{price}","ask":"{price}",
+                "volume":"100","time":"2024-01-01T00:00:00Z"}}"#
+        )
+    }
+
+    #[test]
+    fn test_position() {
+        let apply = |position: &mut Position, json: &str| {
+            position.apply(&serde_json::from_str(json).unwrap())
+        };
+        let mut position = Position::new("BTC-USD");
+        assert!(apply(&mut position, &fill(1, "buy", "100", "2")));
+        assert!(apply(&mut position, &fill(2, "buy", "130", "1")));
+        assert_eq!(position.average_entry_price, Some("110".parse().unwrap()));
+        assert!(!apply(&mut position, &fill(2, "buy", "130", "1")));
+
+        // Closes 3 at a gain of 10 each and goes short 1 at 120.
+        assert!(apply(&mut position, &fill(3, "sell", "120", "4")));
+        assert_eq!(position.size, "-1".parse().unwrap());
+        assert_eq!(position.average_entry_price, Some("120".parse().unwrap()));
+        assert_eq!(position.realized_pnl, "30".parse().unwrap());
+        assert_eq!(position.fees, "3".parse().unwrap());
+
+        assert_eq!(position.unrealized_pnl(), None);
+        position.mark("125".parse().unwrap());
+        assert_eq!(position.unrealized_pnl(), Some("-5".parse().unwrap()));
+
+        assert!(apply(&mut position, &fill(4, "buy", "125", "1")));
+        assert!(position.size.is_zero());
+        assert_eq!(position.average_entry_price, None);
+        assert_eq!(position.realized_pnl, "25".parse().unwrap());
+        assert_eq!(position.unrealized_pnl(), Some(BigDecimal::zero()));
+    }
+
+    #[tokio::test]
+    async fn test_watch() {
+        let fills = "/fills?limit=100&product_id=BTC-USD";
+        let mock = MockTransport::new();
+        mock.push_json(
+            fills,
+            &format!(
+                "[{},{}]",
+                fill(2, "buy", "130", "1"),
+                fill(1, "buy", "100", "2")
+            ),
+        )
+        .push_json("/products/BTC-USD/ticker", &ticker("120"))
+        // Nothing changed.
+        .push_json(fills, &format!("[{}]", fill(2, "buy", "130", "1")))
+        .push_json("/products/BTC-USD/ticker", &ticker("120"))
+        .push_json(
+            fills,
+            &format!(
+                "[{},{}]",
+                fill(3, "sell", "140", "1"),
+                fill(2, "buy", "130", "1")
+            ),
+        )
+        .push_json("/products/BTC-USD/ticker", &ticker("140"));
+        let client = Client::new(MAIN_URL)
+            .with_rate_limit(None)
+            .with_transport(mock.clone());
+        let exchange = ExchangeClient::with_client(&client, "key", "c2VjcmV0", "passphrase");
+
+        let tracker = PositionTracker::new(&["BTC-USD"], Duration::ZERO);
+        let updates: Vec<Position> = tracker
+            .watch(&exchange)
+            .take(2)
+            .map(|update| update.unwrap())
+            .collect()
+            .await;
+        assert_eq!(updates[0].size, "3".parse().unwrap());
+        assert_eq!(updates[0].unrealized_pnl(), Some("30".parse().unwrap()));
+        assert_eq!(updates[1].size, "2".parse().unwrap());
+        assert_eq!(updates[1].realized_pnl, "30".parse().unwrap());
+        assert_eq!(updates[1].unrealized_pnl(), Some("60".parse().unwrap()));
+        assert_eq!(tracker.positions(), updates[1..]);
+        assert!(mock.is_empty());
+    }
+}
@@ -0,0 +1,619 @@
+//! Coinbase Prime, the institutional custody and trading API.
+//!
+//! Requests are signed with a Prime API key, its secret and its passphrase, and sent through a
+//! [`Client`] so they share its transport, retries, rate limiting and errors. Everything is
+//! scoped to a portfolio; listings are followed page by page with their cursor.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::prime::{NewOrder, OrderSide, PrimeClient};
+//! use futures::stream::TryStreamExt;
+//!
+//! let prime = PrimeClient::new("<key>", "<secret>", "<passphrase>");
+//! for portfolio in prime.portfolios().await? {
+//!     let wallets: Vec<_> = prime.wallets(&portfolio.id).try_concat().await?;
+//!     println!("{}: {} wallets", portfolio.name, wallets.len());
+//! }
+//! let order = NewOrder::market(OrderSide::Buy, "BTC-USD", "0.01".parse().unwrap());
+//! let order_id = prime.place_order("<portfolio>", &order).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use async_stream::try_stream;
+use bigdecimal::BigDecimal;
+use futures::stream::Stream;
+use hmac::{Hmac, Mac};
+use http::Method;
+use sha2::Sha256;
+use uritemplate::UriTemplate;
+use uuid::Uuid;
+
+pub use crate::order::{OrderSide, OrderStatus, OrderType, TimeInForce};
+use crate::{request, ApiResponse, CBError, Client, DateTime, Result};
+
+/// Base URL of the Prime API.
+pub const PRIME_URL: &str = "https://api.prime.coinbase.com";
+
+/// Most items returned by a single page of a listing.
+const PAGE_LIMIT: u32 = 100;
+
+/// Client for the Prime API.
+#[derive(Clone)]
+pub struct PrimeClient {
+    client: Client,
+}
+
+impl PrimeClient {
+    /// Creates a client with the default transport and settings.
+    pub fn new(key: &str, secret: &str, passphrase: &str) -> Self {
+        Self::with_client(&Client::new(PRIME_URL), key, secret, passphrase)
+    }
+
+    /// Creates a client sending requests through `client`, sharing its transport, retry
+    /// policy, rate limiter, middleware and metrics. A `client` pointed at [`MAIN_URL`] sends
+    /// requests to [`PRIME_URL`]; any other URL is kept, e.g. a test server. Prime has no
+    /// sandbox, so a client for [`SANDBOX_URL`] keeps it and its requests fail rather than
+    /// placing real orders.
+    ///
+    /// [`MAIN_URL`]: crate::MAIN_URL
+    /// [`SANDBOX_URL`]: crate::SANDBOX_URL
+    pub fn with_client(client: &Client, key: &str, secret: &str, passphrase: &str) -> Self {
+        let mut client = client
+            .clone()
+            .with_prime_credentials(key, secret, passphrase);
+        if client.uri == crate::MAIN_URL {
+            client.uri = PRIME_URL.to_string();
+        }
+        Self { client }
+    }
+
+    ///
+    /// **List portfolios**
+    ///
+    /// Lists the portfolios the API key has access to.
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_getportfolios
+    ///
+    pub async fn portfolios(&self) -> Result<Vec<Portfolio>> {
+        #[derive(Deserialize)]
+        struct Portfolios {
+            portfolios: Vec<Portfolio>,
+        }
+        let response: Portfolios = self.get("/v1/portfolios").await?;
+        Ok(response.portfolios)
+    }
+
+    ///
+    /// **List portfolio wallets**
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_getwallets
+    ///
+    pub fn wallets<'a>(
+        &'a self,
+        portfolio_id: &str,
+    ) -> impl Stream<Item = Result<Vec<Wallet>>> + 'a {
+        let uri = UriTemplate::new("/v1/portfolios/{portfolio}/wallets")
+            .set("portfolio", portfolio_id)
+            .build();
+        self.list(uri, Vec::new())
+    }
+
+    ///
+    /// **List portfolio orders**
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_getorders
+    ///
+    pub fn orders<'a>(
+        &'a self,
+        portfolio_id: &str,
+        params: &ListOrdersParams,
+    ) -> impl Stream<Item = Result<Vec<Order>>> + 'a {
+        let uri = UriTemplate::new("/v1/portfolios/{portfolio}/orders")
+            .set("portfolio", portfolio_id)
+            .build();
+        self.list(uri, params.query())
+    }
+
+    ///
+    /// **Get order by order ID**
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_getorder
+    ///
+    pub async fn order(&self, portfolio_id: &str, order_id: &str) -> Result<Order> {
+        #[derive(Deserialize)]
+        struct OrderResponse {
+            order: Order,
+        }
+        let uri = UriTemplate::new("/v1/portfolios/{portfolio}/orders/{order}")
+            .set("portfolio", portfolio_id)
+            .set("order", order_id)
+            .build();
+        let response: OrderResponse = self.get(&uri).await?;
+        Ok(response.order)
+    }
+
+    ///
+    /// **Create order**
+    ///
+    /// Places an order in the portfolio and returns its id. The order carries a client order
+    /// id, so a retried placement can be matched up with an order that was already accepted.
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_createorder
+    ///
+    pub async fn place_order(&self, portfolio_id: &str, order: &NewOrder) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Placed {
+            order_id: String,
+        }
+        let uri = UriTemplate::new("/v1/portfolios/{portfolio}/order")
+            .set("portfolio", portfolio_id)
+            .build();
+        let request = self
+            .client
+            .authenticated_request(&uri)
+            .method(Method::POST)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(order)?);
+        let response = self.client.make_request::<Placed>(request).await?;
+        Ok(response.data.order_id)
+    }
+
+    ///
+    /// **Cancel order**
+    ///
+    /// Returns the id of the canceled order.
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_cancelorder
+    ///
+    pub async fn cancel_order(&self, portfolio_id: &str, order_id: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Canceled {
+            id: String,
+        }
+        let uri = UriTemplate::new("/v1/portfolios/{portfolio}/orders/{order}/cancel")
+            .set("portfolio", portfolio_id)
+            .set("order", order_id)
+            .build();
+        let request = self.client.authenticated_request(&uri).method(Method::POST);
+        let response = self.client.make_request::<Canceled>(request).await?;
+        Ok(response.data.id)
+    }
+
+    ///
+    /// **List portfolio allocations**
+    ///
+    /// Lists the allocations of orders placed in the portfolio to its sub-portfolios.
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_getportfolioallocations
+    ///
+    pub fn allocations<'a>(
+        &'a self,
+        portfolio_id: &str,
+    ) -> impl Stream<Item = Result<Vec<Allocation>>> + 'a {
+        let uri = UriTemplate::new("/v1/portfolios/{portfolio}/allocations")
+            .set("portfolio", portfolio_id)
+            .build();
+        self.list(uri, Vec::new())
+    }
+
+    ///
+    /// **Get allocation by ID**
+    ///
+    /// https://docs.cdp.coinbase.com/prime/reference/primerestapi_getallocation
+    ///
+    pub async fn allocation(&self, portfolio_id: &str, allocation_id: &str) -> Result<Allocation> {
+        #[derive(Deserialize)]
+        struct AllocationResponse {
+            allocation: Allocation,
+        }
+        let uri = UriTemplate::new("/v1/portfolios/{portfolio}/allocations/{allocation}")
+            .set("portfolio", portfolio_id)
+            .set("allocation", allocation_id)
+            .build();
+        let response: AllocationResponse = self.get(&uri).await?;
+        Ok(response.allocation)
+    }
+
+    async fn get<U: serde::de::DeserializeOwned>(&self, uri: &str) -> Result<U> {
+        let request = self.client.authenticated_request(uri);
+        Ok(self.client.make_request(request).await?.data)
+    }
+
+    /// Pages through a listing until its pagination reports no next page.
+    fn list<'a, U>(
+        &'a self,
+        path: String,
+        query: Vec<(String, String)>,
+    ) -> impl Stream<Item = Result<Vec<U>>> + 'a
+    where
+        U: serde::de::DeserializeOwned + 'a,
+    {
+        try_stream! {
+            let mut cursor: Option<String> = None;
+            loop {
+                let mut pairs = vec![("limit".to_string(), PAGE_LIMIT.to_string())];
+                pairs.extend(query.iter().cloned());
+                if let Some(cursor) = &cursor {
+                    pairs.push(("cursor".to_string(), cursor.clone()));
+                }
+                let uri = UriTemplate::new(&format!("{path}{{?query*}}"))
+                    .set("query", pairs)
+                    .build();
+                let request = self.client.authenticated_request(&uri);
+                let ApiResponse { data: page, meta } =
+                    self.client.make_page_request::<Page<U>>(request).await?;
+                yield page.items;
+                match page
+                    .pagination
+                    .next_cursor
+                    .filter(|cursor| page.pagination.has_next && !cursor.is_empty())
+                {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+                if !self.client.pace(meta.rate_limit.page_delay()).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Adds the Prime API signature headers to `request`: the base64 encoded HMAC-SHA256, keyed
+/// with `secret`, of the timestamp, method, path without the query and body.
+/// The timestamp is taken by a clock `clock_offset` ahead of the local one.
+pub(crate) fn sign(
+    request: request::Builder,
+    key: &str,
+    secret: &str,
+    passphrase: &str,
+    clock_offset: chrono::Duration,
+) -> Result<request::Builder> {
+    let timestamp = (chrono::Utc::now() + clock_offset).timestamp().to_string();
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes())
+        .map_err(|_| CBError::InvalidCredentials("empty secret".to_string()))?;
+    mac.input(timestamp.as_bytes());
+    mac.input(request.method_ref().as_str().as_bytes());
+    mac.input(request.uri_ref().path().as_bytes());
+    mac.input(request.body_ref());
+    let signature = base64::encode(&mac.result().code());
+    Ok(request
+        .header("X-CB-ACCESS-KEY", key)
+        .header("X-CB-ACCESS-PASSPHRASE", passphrase)
+        .header("X-CB-ACCESS-SIGNATURE", &signature)
+        .header("X-CB-ACCESS-TIMESTAMP", &timestamp))
+}
+
+#[derive(Deserialize, Debug)]
+struct Page<T> {
+    #[serde(alias = "wallets", alias = "orders", alias = "allocations")]
+    items: Vec<T>,
+    pagination: Pagination,
+}
+
+#[derive(Deserialize, Debug)]
+struct Pagination {
+    next_cursor: Option<String>,
+    #[serde(default)]
+    has_next: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Portfolio {
+    pub id: String,
+    pub name: String,
+    pub entity_id: String,
+    pub organization_id: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Wallet {
+    pub id: String,
+    pub name: String,
+    pub symbol: String,
+    /// `VAULT`, `TRADING`, `WALLET_TYPE_OTHER` or `QC`.
+    pub r#type: String,
+    pub created_at: DateTime,
+    pub address: Option<String>,
+}
+
+/// Filters of an [`orders`](PrimeClient::orders) listing.
+///
+/// ```
+/// use coinbase_rs::prime::{ListOrdersParams, OrderSide, OrderStatus};
+///
+/// let params = ListOrdersParams::new()
+///     .product("BTC-USD")
+///     .status(OrderStatus::Filled)
+///     .side(OrderSide::Buy);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ListOrdersParams {
+    product_ids: Vec<String>,
+    statuses: Vec<OrderStatus>,
+    side: Option<OrderSide>,
+    start_date: Option<DateTime>,
+    end_date: Option<DateTime>,
+}
+
+impl ListOrdersParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists orders of `product`, e.g. `BTC-USD`, in addition to the products given before.
+    pub fn product(self, product: &str) -> Self {
+        let mut _self = self;
+        _self.product_ids.push(product.to_string());
+        _self
+    }
+
+    /// Lists orders with `status`, in addition to the statuses given before.
+    pub fn status(self, status: OrderStatus) -> Self {
+        let mut _self = self;
+        _self.statuses.push(status);
+        _self
+    }
+
+    /// Only lists buy or sell orders.
+    pub fn side(self, side: OrderSide) -> Self {
+        let mut _self = self;
+        _self.side = Some(side);
+        _self
+    }
+
+    /// Only lists orders created at or after `start`.
+    pub fn start_date(self, start: DateTime) -> Self {
+        let mut _self = self;
+        _self.start_date = Some(start);
+        _self
+    }
+
+    /// Only lists orders created before `end`.
+    pub fn end_date(self, end: DateTime) -> Self {
+        let mut _self = self;
+        _self.end_date = Some(end);
+        _self
+    }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut query = Vec::new();
+        for product_id in &self.product_ids {
+            query.push(("product_ids".to_string(), product_id.clone()));
+        }
+        for status in &self.statuses {
+            query.push(("order_statuses".to_string(), status.as_str().to_string()));
+        }
+        if let Some(side) = self.side {
+            query.push(("order_side".to_string(), side.as_str().to_string()));
+        }
+        if let Some(start) = self.start_date {
+            query.push(("start_date".to_string(), start.to_rfc3339()));
+        }
+        if let Some(end) = self.end_date {
+            query.push(("end_date".to_string(), end.to_rfc3339()));
+        }
+        query
+    }
+}
+
+/// Parameters of a [`place_order`](PrimeClient::place_order) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct NewOrder {
+    pub product_id: String,
+    pub side: OrderSide,
+    /// Identifier chosen by the client, generated by the constructors.
+    pub client_order_id: String,
+    pub r#type: OrderType,
+    /// Amount of the base currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_quantity: Option<BigDecimal>,
+    /// Amount of the quote currency, for market orders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_value: Option<BigDecimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<BigDecimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+}
+
+impl NewOrder {
+    /// Buys or sells `base_quantity` of `product` at `limit_price` or better.
+    pub fn limit(
+        side: OrderSide,
+        product: &str,
+        limit_price: BigDecimal,
+        base_quantity: BigDecimal,
+    ) -> Self {
+        Self {
+            limit_price: Some(limit_price),
+            base_quantity: Some(base_quantity),
+            ..Self::new(OrderType::Limit, side, product)
+        }
+    }
+
+    /// Buys or sells `base_quantity` of `product` at the best available prices.
+    pub fn market(side: OrderSide, product: &str, base_quantity: BigDecimal) -> Self {
+        Self {
+            base_quantity: Some(base_quantity),
+            ..Self::new(OrderType::Market, side, product)
+        }
+    }
+
+    /// Spends or receives `quote_value` of the quote currency of `product` at the best
+    /// available prices.
+    pub fn market_value(side: OrderSide, product: &str, quote_value: BigDecimal) -> Self {
+        Self {
+            quote_value: Some(quote_value),
+            ..Self::new(OrderType::Market, side, product)
+        }
+    }
+
+    fn new(r#type: OrderType, side: OrderSide, product: &str) -> Self {
+        Self {
+            product_id: product.to_string(),
+            side,
+            client_order_id: Uuid::new_v4().to_string(),
+            r#type,
+            base_quantity: None,
+            quote_value: None,
+            limit_price: None,
+            time_in_force: None,
+        }
+    }
+
+    pub fn time_in_force(self, time_in_force: TimeInForce) -> Self {
+        let mut _self = self;
+        _self.time_in_force = Some(time_in_force);
+        _self
+    }
+
+    /// Replaces the generated client order id, e.g. with one persisted before placing.
+    pub fn client_order_id(self, client_order_id: &str) -> Self {
+        let mut _self = self;
+        _self.client_order_id = client_order_id.to_string();
+        _self
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Order {
+    pub id: String,
+    pub user_id: Option<String>,
+    pub portfolio_id: String,
+    pub product_id: String,
+    pub side: OrderSide,
+    pub client_order_id: Option<String>,
+    pub r#type: OrderType,
+    pub base_quantity: Option<BigDecimal>,
+    pub quote_value: Option<BigDecimal>,
+    pub limit_price: Option<BigDecimal>,
+    pub status: OrderStatus,
+    pub time_in_force: Option<TimeInForce>,
+    pub created_at: DateTime,
+    pub filled_quantity: Option<BigDecimal>,
+    pub filled_value: Option<BigDecimal>,
+    pub average_filled_price: Option<BigDecimal>,
+    pub commission: Option<BigDecimal>,
+}
+
+/// The split of one or more orders across the sub-portfolios of a portfolio.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Allocation {
+    pub root_id: String,
+    pub allocation_id: String,
+    pub user_id: Option<String>,
+    pub product_id: String,
+    pub side: OrderSide,
+    pub avg_price: Option<BigDecimal>,
+    pub base_quantity: Option<BigDecimal>,
+    pub quote_value: Option<BigDecimal>,
+    pub fees_allocated: Option<BigDecimal>,
+    pub status: String,
+    #[serde(default)]
+    pub order_ids: Vec<String>,
+    #[serde(default)]
+    pub destinations: Vec<AllocationLeg>,
+    pub allocation_completed_at: Option<DateTime>,
+}
+
+/// The share of an allocation received by one portfolio.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AllocationLeg {
+    pub leg_id: String,
+    pub portfolio_id: String,
+    pub allocation_base: Option<BigDecimal>,
+    pub allocation_quote: Option<BigDecimal>,
+    pub fees_allocated_leg: Option<BigDecimal>,
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::TryStreamExt;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn order(id: &str) -> String {
+        format!(
+            r#"{{"id":"{id}","user_id":"u1","portfolio_id":"p1","product_id":"BTC-USD",
+                "side":"BUY","client_order_id":"c-{id}","type":"LIMIT","base_quantity":"0.5",
+                "quote_value":null,"limit_price":"30000","status":"OPEN",
+                "time_in_force":"GOOD_UNTIL_CANCELLED","created_at":"2024-01-01T00:00:00Z",
+                "filled_quantity":"0","filled_value":"0","average_filled_price":"0",
+                "commission":"0"}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_orders() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v1/portfolios/p1/orders?limit=100&product_ids=BTC-USD&order_statuses=FILLED",
+            &format!(
+                r#"{{"orders":[{}],"pagination":{{"next_cursor":"c2","sort_direction":"DESC","has_next":true}}}}"#,
+                order("a")
+            ),
+        )
+        .push_json(
+            "/v1/portfolios/p1/orders?limit=100&product_ids=BTC-USD&order_statuses=FILLED&cursor=c2",
+            &format!(
+                r#"{{"orders":[{}],"pagination":{{"next_cursor":"","sort_direction":"DESC","has_next":false}}}}"#,
+                order("b")
+            ),
+        )
+        .push_json("/v1/portfolios/p1/order", r#"{"order_id":"c"}"#);
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+        let prime = PrimeClient::with_client(&client, "key", "secret", "passphrase");
+
+        let params = ListOrdersParams::new()
+            .product("BTC-USD")
+            .status(OrderStatus::Filled);
+        let orders: Vec<Order> = prime.orders("p1", &params).try_concat().await.unwrap();
+        let ids: Vec<_> = orders.iter().map(|o| o.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(orders[0].side, OrderSide::Buy);
+
+        let new = NewOrder::market(OrderSide::Sell, "BTC-USD", "0.5".parse().unwrap());
+        assert_eq!(prime.place_order("p1", &new).await.unwrap(), "c");
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].uri().host(), Some("api.prime.coinbase.com"));
+        let post = &requests[2];
+        let body: Value = serde_json::from_slice(post.body()).unwrap();
+        assert_eq!(body["side"], "SELL");
+        assert_eq!(body["client_order_id"], new.client_order_id.as_str());
+        assert!(body.get("limit_price").is_none());
+
+        // The query is not part of the signed message.
+        let get = &requests[1];
+        let timestamp = get.headers()["x-cb-access-timestamp"].to_str().unwrap();
+        let mut mac = Hmac::<Sha256>::new_varkey(b"secret").unwrap();
+        mac.input(format!("{timestamp}GET/v1/portfolios/p1/orders").as_bytes());
+        assert_eq!(
+            get.headers()["x-cb-access-signature"],
+            base64::encode(&mac.result().code()).as_str()
+        );
+        assert_eq!(get.headers()["x-cb-access-passphrase"], "passphrase");
+    }
+
+    #[tokio::test]
+    async fn test_base_url() {
+        let mock = MockTransport::new();
+        mock.push_json("/v1/portfolios", r#"{"portfolios":[]}"#)
+            .push_json("/v1/portfolios", r#"{"portfolios":[]}"#);
+        for url in [MAIN_URL, crate::SANDBOX_URL] {
+            let client = Client::new(url).with_transport(mock.clone());
+            let prime = PrimeClient::with_client(&client, "key", "secret", "passphrase");
+            prime.portfolios().await.unwrap();
+        }
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].uri().host(), Some("api.prime.coinbase.com"));
+        // Prime has no sandbox, so sandbox clients never reach production.
+        assert_eq!(requests[1].uri().host(), Some("api-sandbox.coinbase.com"));
+    }
+}
@@ -1,147 +1,1575 @@
-use bigdecimal::BigDecimal;
-use futures::stream::Stream;
-use hyper::Uri;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_stream::{stream, try_stream};
+use bigdecimal::{BigDecimal, Signed, Zero};
+use futures::future;
+use futures::stream::{Stream, StreamExt};
+use http::Method;
 use uritemplate::UriTemplate;
 use uuid::Uuid;
 
-use crate::{public::Public, request, DateTime, Result};
+use crate::dedup::DedupPages;
+use crate::public::Response;
+use crate::{amount, ApiResponse, Client, DateTime, IdempotencyKey, Result};
+
+/// Former client of the authenticated endpoints, now a [`Client`] configured with credentials
+/// that it derefs to.
+#[deprecated(note = "use `Client::new_with_auth`")]
+#[derive(Clone)]
+pub struct Private(Client);
+
+#[allow(deprecated)]
+impl Private {
+    pub fn new(uri: &str, key: &str, secret: &str) -> Self {
+        Self(Client::new_with_auth(uri, key, secret))
+    }
+}
+
+#[allow(deprecated)]
+impl std::ops::Deref for Private {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.0
+    }
+}
+
+#[allow(deprecated)]
+impl std::convert::From<Private> for Client {
+    fn from(private: Private) -> Self {
+        private.0
+    }
+}
+
+impl Client {
+    /// Requests the current user and returns how long the round trip took, failing like any
+    /// authenticated endpoint if the credentials are missing, invalid or revoked.
+    pub async fn ping_authenticated(&self) -> Result<Duration> {
+        let started = crate::rt::Instant::now();
+        let request = self.authenticated_request("/v2/user");
+        self.make_request::<serde::de::IgnoredAny>(request).await?;
+        Ok(started.elapsed())
+    }
+
+    ///
+    /// **Show authorization information**
+    ///
+    /// Gets how the current user is authenticated and the scopes granted to the credentials.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-authorization-information
+    ///
+    pub async fn auth_info(&self) -> Result<AuthInfo> {
+        let request = self.authenticated_request("/v2/user/auth");
+        Ok(self
+            .make_request::<Response<AuthInfo>>(request)
+            .await?
+            .data
+            .data)
+    }
+
+    /// Fails with [`CBError::MissingScopes`](crate::CBError::MissingScopes) listing every scope
+    /// of `scopes` the credentials weren't granted, e.g. before attempting a withdrawal.
+    pub async fn require_scopes(&self, scopes: &[Scope]) -> Result<()> {
+        let granted = self.auth_info().await?.scopes;
+        let missing: Vec<Scope> = scopes
+            .iter()
+            .filter(|scope| !granted.iter().any(|granted| granted == scope.as_str()))
+            .copied()
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::CBError::MissingScopes { missing })
+        }
+    }
+
+    ///
+    /// **List accounts**
+    ///
+    /// Lists current user’s accounts to which the authentication method has access to.
+    ///
+    /// https://developers.coinbase.com/api/v2#list-accounts
+    ///
+    pub fn accounts<'a>(&'a self) -> impl Stream<Item = Result<Vec<Account>>> + 'a {
+        self.accounts_with_meta()
+            .map(|page| page.map(|page| page.data))
+            .dedup_pages()
+    }
+
+    /// Like [`accounts`](Self::accounts), also returning the metadata of each page.
+    pub fn accounts_with_meta<'a>(
+        &'a self,
+    ) -> impl Stream<Item = Result<ApiResponse<Vec<Account>>>> + 'a {
+        let uri = UriTemplate::new("/v2/accounts").build();
+        let request = self.authenticated_request(&uri);
+        self.get_stream(request)
+    }
+
+    /// Like [`accounts`](Self::accounts), with the page size, order and starting point given
+    /// by `params`.
+    pub fn accounts_with_params<'a>(
+        &'a self,
+        params: &ListParams,
+    ) -> impl Stream<Item = Result<Vec<Account>>> + 'a {
+        let uri = UriTemplate::new("/v2/accounts{?query*}")
+            .set("query", params.query())
+            .build();
+        let request = self.authenticated_request(&uri);
+        self.get_stream(request)
+            .map(|page| page.map(|page: ApiResponse<Vec<Account>>| page.data))
+            .dedup_pages()
+    }
+
+    /// Like [`accounts`](Self::accounts), only yielding accounts with a nonzero balance. Pages
+    /// left without accounts are skipped.
+    pub fn funded_accounts<'a>(&'a self) -> impl Stream<Item = Result<Vec<Account>>> + 'a {
+        self.accounts().filter_map(|page| {
+            let page = page.map(|mut accounts| {
+                accounts.retain(|account| !account.balance.amount.is_zero());
+                accounts
+            });
+            future::ready(match &page {
+                Ok(accounts) if accounts.is_empty() => None,
+                _ => Some(page),
+            })
+        })
+    }
+
+    ///
+    /// **Show an account**
+    ///
+    /// Show current user’s account.
+    ///
+    /// https://developers.coinbase.com/api/v2#show-an-account
+    ///
+    pub async fn account(&self, account_id: &Uuid) -> Result<Account> {
+        self.account_with_meta(account_id)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`account`](Self::account), also returning the response metadata.
+    pub async fn account_with_meta(&self, account_id: &Uuid) -> Result<ApiResponse<Account>> {
+        let uri = UriTemplate::new("/v2/accounts/{account}")
+            .set("account", account_id.to_string())
+            .build();
+        let request = self.authenticated_request(&uri);
+        let response = self.make_request::<Response<Account>>(request).await?;
+        Ok(ApiResponse {
+            data: response.data.data,
+            meta: response.meta,
+        })
+    }
+
+    /// Polls an account every `interval` and yields its old and new balance whenever the
+    /// balance changes, e.g. to detect deposits. The first poll only records the balance.
+    /// Failed polls are yielded as errors and polling goes on.
+    pub fn balance_stream<'a>(
+        &'a self,
+        account_id: &Uuid,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<BalanceChange>> + 'a {
+        let account_id = *account_id;
+        stream! {
+            let mut last: Option<Balance> = None;
+            loop {
+                match self.account(&account_id).await {
+                    Ok(account) => {
+                        let new = account.balance;
+                        match last.replace(new.clone()) {
+                            Some(old) if old != new => yield Ok(BalanceChange { old, new }),
+                            _ => {}
+                        }
+                    }
+                    Err(error) => yield Err(error),
+                }
+                if !self.idle(interval).await {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Polls the transactions of an address every `interval` and yields a deposit event for
+    /// each transaction received after polling started, and again whenever its status or
+    /// number of confirmations changes. Failed polls are yielded as errors and polling goes on.
+    ///
+    /// Only the newest page of transactions is polled, so `interval` should be short enough
+    /// that fewer than a page of deposits arrives in between.
+    ///
+    /// https://developers.coinbase.com/api/v2#list-address39s-transactions
+    ///
+    pub fn deposit_stream<'a>(
+        &'a self,
+        account_id: &Uuid,
+        address_id: &str,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Deposit>> + 'a {
+        let uri = UriTemplate::new("/v2/accounts/{account}/addresses/{address}/transactions")
+            .set("account", account_id.to_string())
+            .set("address", address_id)
+            .build();
+        stream! {
+            let mut seen: Option<HashMap<Uuid, (DepositStatus, Option<u32>)>> = None;
+            loop {
+                let request = self.authenticated_request(&uri);
+                match self.make_request::<Response<Vec<Transaction>>>(request).await {
+                    Ok(response) => {
+                        let first = seen.is_none();
+                        let seen = seen.get_or_insert_with(HashMap::new);
+                        // Oldest first, so deposits arriving together are yielded in order.
+                        for transaction in response.data.data.into_iter().rev() {
+                            let deposit = Deposit::new(transaction);
+                            let state = (deposit.status.clone(), deposit.confirmations);
+                            let changed = seen.insert(deposit.transaction.id, state.clone())
+                                != Some(state);
+                            if changed && !first {
+                                yield Ok(deposit);
+                            }
+                        }
+                    }
+                    Err(error) => yield Err(error),
+                }
+                if !self.idle(interval).await {
+                    break;
+                }
+            }
+        }
+    }
+
+    ///
+    /// **List transactions**
+    ///
+    /// Lists account’s transactions.
+    ///
+    /// https://developers.coinbase.com/api/v2#list-transactions
+    ///
+    pub fn transactions<'a>(
+        &'a self,
+        account_id: &Uuid,
+    ) -> impl Stream<Item = Result<Vec<Transaction>>> + 'a {
+        self.transactions_with_meta(account_id)
+            .map(|page| page.map(|page| page.data))
+            .dedup_pages()
+    }
+
+    /// Like [`transactions`](Self::transactions), also returning the metadata of each page.
+    pub fn transactions_with_meta<'a>(
+        &'a self,
+        account_id: &Uuid,
+    ) -> impl Stream<Item = Result<ApiResponse<Vec<Transaction>>>> + 'a {
+        let request = self.transactions_request(account_id, &ListParams::new());
+        self.get_stream(request)
+    }
+
+    /// Like [`transactions`](Self::transactions), with the page size, order and starting
+    /// point given by `params`.
+    pub fn transactions_with_params<'a>(
+        &'a self,
+        account_id: &Uuid,
+        params: &ListParams,
+    ) -> impl Stream<Item = Result<Vec<Transaction>>> + 'a {
+        let request = self.transactions_request(account_id, params);
+        self.get_stream(request)
+            .map(|page| page.map(|page: ApiResponse<Vec<Transaction>>| page.data))
+            .dedup_pages()
+    }
+
+    fn transactions_request(
+        &self,
+        account_id: &Uuid,
+        params: &ListParams,
+    ) -> crate::request::Builder {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions{?query*}")
+            .set("account", account_id.to_string())
+            .set("query", params.query())
+            .build();
+        self.authenticated_request(&uri)
+    }
+
+    /// Lists the transactions of an account created from `start` up to but excluding `end`,
+    /// newest first. Either bound may be left open.
+    ///
+    /// The API can't filter by date, so transactions are requested newest first and filtered
+    /// here. Pagination stops at the first page reaching past `start`, so only the pages
+    /// overlapping the range and the ones after it are fetched. Transactions without a creation
+    /// time are skipped.
+    pub fn transactions_between<'a>(
+        &'a self,
+        account_id: &Uuid,
+        start: Option<DateTime>,
+        end: Option<DateTime>,
+    ) -> impl Stream<Item = Result<Vec<Transaction>>> + 'a {
+        let params = ListParams::new().order(Order::Descending);
+        let request = self.transactions_request(account_id, &params);
+        try_stream! {
+            let pages = self
+                .get_stream::<Vec<Transaction>>(request)
+                .map(|page| page.map(|page| page.data))
+                .dedup_pages();
+            futures::pin_mut!(pages);
+            while let Some(page) = pages.next().await {
+                let mut transactions = page?;
+                let past_start = transactions
+                    .iter()
+                    .any(|t| t.created_at.zip(start).is_some_and(|(at, start)| at < start));
+                transactions.retain(|t| {
+                    t.created_at.is_some_and(|at| {
+                        start.is_none_or(|start| at >= start) && end.is_none_or(|end| at < end)
+                    })
+                });
+                if !transactions.is_empty() {
+                    yield transactions;
+                }
+                if past_start {
+                    break;
+                }
+            }
+        }
+    }
+
+    ///
+    /// **List addresses**
+    ///
+    /// Lists addresses for an account.
+    ///
+    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/api-addresses#list-addresses
+    ///
+    pub fn list_addresses<'a>(
+        &'a self,
+        account_id: &Uuid,
+    ) -> impl Stream<Item = Result<Vec<Address>>> + 'a {
+        self.list_addresses_with_meta(account_id)
+            .map(|page| page.map(|page| page.data))
+    }
+
+    /// Like [`list_addresses`](Self::list_addresses), also returning the metadata of each page.
+    pub fn list_addresses_with_meta<'a>(
+        &'a self,
+        account_id: &Uuid,
+    ) -> impl Stream<Item = Result<ApiResponse<Vec<Address>>>> + 'a {
+        let uri = UriTemplate::new("/v2/accounts/{account}/addresses")
+            .set("account", account_id.to_string())
+            .build();
+        let request = self.authenticated_request(&uri);
+        self.get_stream(request)
+    }
+
+    /// Like [`list_addresses`](Self::list_addresses), with the page size, order and starting
+    /// point given by `params`.
+    pub fn list_addresses_with_params<'a>(
+        &'a self,
+        account_id: &Uuid,
+        params: &ListParams,
+    ) -> impl Stream<Item = Result<Vec<Address>>> + 'a {
+        let uri = UriTemplate::new("/v2/accounts/{account}/addresses{?query*}")
+            .set("account", account_id.to_string())
+            .set("query", params.query())
+            .build();
+        let request = self.authenticated_request(&uri);
+        self.get_stream(request)
+            .map(|page| page.map(|page: ApiResponse<Vec<Address>>| page.data))
+    }
+
+    ///
+    /// **Update address**
+    ///
+    /// Renames an address of an account.
+    ///
+    /// https://docs.cdp.coinbase.com/coinbase-app/docs/api-addresses#update-address
+    ///
+    pub async fn update_address(
+        &self,
+        account_id: &Uuid,
+        address_id: &str,
+        name: &str,
+    ) -> Result<Address> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/addresses/{address}")
+            .set("account", account_id.to_string())
+            .set("address", address_id)
+            .build();
+        let body = serde_json::json!({ "name": name });
+        self.send_json(Method::PUT, &uri, &body).await
+    }
+
+    ///
+    /// **Cancel a transaction**
+    ///
+    /// Cancels a transaction that is still pending, such as a vault withdrawal before its
+    /// delay has passed or a money request.
+    ///
+    /// https://developers.coinbase.com/api/v2#cancel-request-money
+    ///
+    pub async fn cancel_transaction(&self, account_id: &Uuid, transaction_id: &Uuid) -> Result<()> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions/{transaction}")
+            .set("account", account_id.to_string())
+            .set("transaction", transaction_id.to_string())
+            .build();
+        let request = self.authenticated_request(&uri).method(Method::DELETE);
+        self.make_request::<()>(request).await?;
+        Ok(())
+    }
+
+    /// Cancels a withdrawal from the vault `account_id` during its delay, see
+    /// [`cancel_transaction`](Self::cancel_transaction).
+    pub async fn cancel_vault_withdrawal(
+        &self,
+        account_id: &Uuid,
+        transaction_id: &Uuid,
+    ) -> Result<()> {
+        self.cancel_transaction(account_id, transaction_id).await
+    }
+
+    ///
+    /// **Send money**
+    ///
+    /// Send funds to a network address for any Coinbase supported asset, or to a Coinbase
+    /// retail user's email address. The request carries an idempotency key, so resending the
+    /// same `SendMoney` never results in a second transfer, and the client's retry policy
+    /// resends it after timeouts and server errors as it would an idempotent request.
+    ///
+    /// When the send names a network, it is first checked against the networks the currency
+    /// supports, see [`check_network`](Self::check_network).
+    ///
+    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/api-transactions#send-money
+    ///
+    pub async fn send_money(&self, account_id: &Uuid, send: &SendMoney) -> Result<Transaction> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/transactions")
+            .set("account", account_id.to_string())
+            .build();
+        if let Some(network) = &send.network {
+            self.check_network(&send.currency, network).await?;
+        }
+        let request = self
+            .authenticated_request(&uri)
+            .method(Method::POST)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(send)?)
+            .idempotency_key();
+        let response = self.make_request::<Response<Transaction>>(request).await?;
+        Ok(response.data.data)
+    }
+
+    ///
+    /// **List notifications**
+    ///
+    /// Lists notifications where the current user was the subscriber, the same payloads that
+    /// are delivered to webhooks.
+    ///
+    /// https://developers.coinbase.com/api/v2#list-notifications
+    ///
+    pub fn notifications<'a>(&'a self) -> impl Stream<Item = Result<Vec<Notification>>> + 'a {
+        self.notifications_with_meta()
+            .map(|page| page.map(|page| page.data))
+    }
+
+    /// Like [`notifications`](Self::notifications), also returning the metadata of each page.
+    pub fn notifications_with_meta<'a>(
+        &'a self,
+    ) -> impl Stream<Item = Result<ApiResponse<Vec<Notification>>>> + 'a {
+        let request = self.authenticated_request("/v2/notifications");
+        self.get_stream(request)
+    }
+
+    /// Like [`notifications`](Self::notifications), with the page size, order and starting
+    /// point given by `params`.
+    pub fn notifications_with_params<'a>(
+        &'a self,
+        params: &ListParams,
+    ) -> impl Stream<Item = Result<Vec<Notification>>> + 'a {
+        let uri = UriTemplate::new("/v2/notifications{?query*}")
+            .set("query", params.query())
+            .build();
+        let request = self.authenticated_request(&uri);
+        self.get_stream(request)
+            .map(|page| page.map(|page: ApiResponse<Vec<Notification>>| page.data))
+    }
+
+    ///
+    /// **List payment methods**
+    ///
+    /// Lists the current user's payment methods with their buy, sell, deposit and withdrawal
+    /// limits.
+    ///
+    /// https://developers.coinbase.com/api/v2#list-payment-methods
+    ///
+    pub fn payment_methods<'a>(&'a self) -> impl Stream<Item = Result<Vec<PaymentMethod>>> + 'a {
+        let request = self.authenticated_request("/v2/payment-methods");
+        self.get_stream(request)
+            .map(|page| page.map(|page: ApiResponse<Vec<PaymentMethod>>| page.data))
+    }
+
+    /// Like [`payment_methods`](Self::payment_methods), with the page size, order and starting
+    /// point given by `params`.
+    pub fn payment_methods_with_params<'a>(
+        &'a self,
+        params: &ListParams,
+    ) -> impl Stream<Item = Result<Vec<PaymentMethod>>> + 'a {
+        let uri = UriTemplate::new("/v2/payment-methods{?query*}")
+            .set("query", params.query())
+            .build();
+        let request = self.authenticated_request(&uri);
+        self.get_stream(request)
+            .map(|page| page.map(|page: ApiResponse<Vec<PaymentMethod>>| page.data))
+    }
+
+    ///
+    /// **Show a payment method**
+    ///
+    /// https://developers.coinbase.com/api/v2#show-a-payment-method
+    ///
+    pub async fn payment_method(&self, payment_method_id: &str) -> Result<PaymentMethod> {
+        let uri = UriTemplate::new("/v2/payment-methods/{id}")
+            .set("id", payment_method_id)
+            .build();
+        let request = self.authenticated_request(&uri);
+        Ok(self
+            .make_request::<Response<PaymentMethod>>(request)
+            .await?
+            .data
+            .data)
+    }
+
+    ///
+    /// **Place buy order**
+    ///
+    /// Buys an amount of the currency of an account, paid with the payment method of the buy or
+    /// the user's default one.
+    ///
+    /// https://developers.coinbase.com/api/v2#place-buy-order
+    ///
+    pub async fn place_buy(&self, account_id: &Uuid, buy: &PlaceBuy) -> Result<Buy> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/buys")
+            .set("account", account_id.to_string())
+            .build();
+        self.post(&uri, buy).await
+    }
+
+    /// Places `buy` without committing it, to show the exact total including fees before the
+    /// user confirms. The quote is executed by [`commit_buy`](Self::commit_buy), or left to
+    /// expire.
+    pub async fn quote_buy(&self, account_id: &Uuid, buy: &PlaceBuy) -> Result<Quote> {
+        let buy = buy.clone().commit(false).quote(false);
+        let uri = UriTemplate::new("/v2/accounts/{account}/buys")
+            .set("account", account_id.to_string())
+            .build();
+        self.post(&uri, &buy).await
+    }
+
+    ///
+    /// **Commit a buy**
+    ///
+    /// Executes a buy placed with [`quote_buy`](Self::quote_buy), at the quoted price.
+    ///
+    /// https://developers.coinbase.com/api/v2#commit-a-buy
+    ///
+    pub async fn commit_buy(&self, account_id: &Uuid, quote: &Quote) -> Result<Buy> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/buys/{buy}/commit")
+            .set("account", account_id.to_string())
+            .set("buy", quote.id.to_string())
+            .build();
+        self.post(&uri, &serde_json::json!({})).await
+    }
+
+    ///
+    /// **Place sell order**
+    ///
+    /// Sells an amount of the currency of an account, paying out to the payment method of the
+    /// sell. An uncommitted sell is executed by [`commit_sell`](Self::commit_sell).
+    ///
+    /// https://developers.coinbase.com/api/v2#place-sell-order
+    ///
+    pub async fn place_sell(&self, account_id: &Uuid, sell: &PlaceSell) -> Result<Sell> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/sells")
+            .set("account", account_id.to_string())
+            .build();
+        self.post(&uri, sell).await
+    }
+
+    ///
+    /// **Commit a sell**
+    ///
+    /// Executes a sell placed with [`commit`](PlaceSell::commit) set to false.
+    ///
+    /// https://developers.coinbase.com/api/v2#commit-a-sell
+    ///
+    pub async fn commit_sell(&self, account_id: &Uuid, sell: &Sell) -> Result<Sell> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/sells/{sell}/commit")
+            .set("account", account_id.to_string())
+            .set("sell", sell.id.to_string())
+            .build();
+        self.post(&uri, &serde_json::json!({})).await
+    }
+
+    ///
+    /// **Withdraw funds**
+    ///
+    /// Withdraws fiat from an account to a payment method, e.g. a bank account.
+    ///
+    /// https://developers.coinbase.com/api/v2#withdraw-funds
+    ///
+    pub async fn withdraw(
+        &self,
+        account_id: &Uuid,
+        withdrawal: &PlaceWithdrawal,
+    ) -> Result<Withdrawal> {
+        let uri = UriTemplate::new("/v2/accounts/{account}/withdrawals")
+            .set("account", account_id.to_string())
+            .build();
+        self.post(&uri, withdrawal).await
+    }
+
+    async fn post<T, U>(&self, uri: &str, body: &T) -> Result<U>
+    where
+        T: serde::Serialize,
+        U: serde::de::DeserializeOwned,
+    {
+        self.send_json(Method::POST, uri, body).await
+    }
+
+    async fn send_json<T, U>(&self, method: Method, uri: &str, body: &T) -> Result<U>
+    where
+        T: serde::Serialize,
+        U: serde::de::DeserializeOwned,
+    {
+        let request = self
+            .authenticated_request(uri)
+            .method(method)
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_vec(body)?);
+        let response = self.make_request::<Response<U>>(request).await?;
+        Ok(response.data.data)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Account {
+    // id appears to be either a UUID or a token name e.g: "LINK"
+    pub id: String,
+
+    pub r#type: String,
+
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
+
+    pub resource: String,
+    pub resource_path: String,
+
+    pub name: String,
+    pub primary: bool,
+
+    pub currency: Currency,
+
+    pub balance: Balance,
+
+    pub allow_deposits: bool,
+    pub allow_withdrawals: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Balance {
+    pub amount: BigDecimal,
+    pub currency: String,
+}
+
+impl Balance {
+    /// The balance rounded to `exponent` decimal places, e.g. its currency's
+    /// [`exponent`](Currency::exponent).
+    pub fn round(&self, exponent: usize) -> Balance {
+        Balance {
+            amount: amount::round(&self.amount, exponent),
+            currency: self.currency.clone(),
+        }
+    }
+
+    /// The balance truncated to `exponent` decimal places, e.g. to send all of it without
+    /// exceeding what is available.
+    pub fn truncate(&self, exponent: usize) -> Balance {
+        Balance {
+            amount: amount::truncate(&self.amount, exponent),
+            currency: self.currency.clone(),
+        }
+    }
+
+    /// The amount with exactly `exponent` decimal places, followed by the currency, e.g.
+    /// `0.50000000 BTC`.
+    pub fn format(&self, exponent: usize) -> String {
+        self.format_with(exponent, &amount::NumberFormat::PLAIN)
+    }
+
+    /// Like [`format`](Self::format), with the separators of a locale, e.g. `1.234,50 EUR`.
+    pub fn format_with(&self, exponent: usize, number_format: &amount::NumberFormat) -> String {
+        format!(
+            "{} {}",
+            amount::format_with(&self.amount, exponent, number_format),
+            self.currency
+        )
+    }
+}
+
+impl Account {
+    /// The balance formatted to the precision of the account's currency.
+    pub fn formatted_balance(&self) -> String {
+        self.balance.format(self.currency.exponent)
+    }
+
+    /// Whether this is a vault, whose withdrawals are delayed and need approval.
+    pub fn is_vault(&self) -> bool {
+        self.r#type == "vault"
+    }
+}
+
+/// Balance of an account before and after it changed, see
+/// [`balance_stream`](Client::balance_stream).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceChange {
+    pub old: Balance,
+    pub new: Balance,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Address {
+    pub id: String,
+    pub address: String,
+    pub name: Option<String>,
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
+    /// Network of the address, missing from the addresses in notifications.
+    #[serde(default)]
+    pub network: String,
+    /// Destination tag or memo deposits to the address must carry, on networks such as XRP
+    /// and Stellar.
+    pub destination_tag: Option<String>,
+    pub resource: String,
+    pub resource_path: String,
+}
+
+/// Paging of a v2 listing, e.g. [`accounts_with_params`](Client::accounts_with_params) or
+/// [`transactions_with_params`](Client::transactions_with_params). Oldest first, a listing can
+/// be followed from the last record seen to sync everything since.
+///
+/// ```
+/// use coinbase_rs::private::{ListParams, Order};
+///
+/// let params = ListParams::new().limit(25).order(Order::Ascending);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ListParams {
+    limit: u32,
+    order: Option<Order>,
+    starting_after: Option<String>,
+    ending_before: Option<String>,
+}
+
+/// The paging of transactions, which came first.
+pub type ListTransactionsParams = ListParams;
+
+impl Default for ListParams {
+    fn default() -> Self {
+        Self {
+            limit: 100,
+            order: None,
+            starting_after: None,
+            ending_before: None,
+        }
+    }
+}
+
+impl ListParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of records per page, between 1 and 100, which is the default.
+    pub fn limit(self, limit: u32) -> Self {
+        let mut _self = self;
+        _self.limit = limit.clamp(1, 100);
+        _self
+    }
+
+    /// Order by creation time, newest first unless given.
+    pub fn order(self, order: Order) -> Self {
+        let mut _self = self;
+        _self.order = Some(order);
+        _self
+    }
+
+    /// Starts the listing after the record with id `id`.
+    pub fn starting_after(self, id: &str) -> Self {
+        let mut _self = self;
+        _self.starting_after = Some(id.to_string());
+        _self
+    }
+
+    /// Ends the listing before the record with id `id`.
+    pub fn ending_before(self, id: &str) -> Self {
+        let mut _self = self;
+        _self.ending_before = Some(id.to_string());
+        _self
+    }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut query = vec![("limit".to_string(), self.limit.to_string())];
+        if let Some(order) = self.order {
+            let order = match order {
+                Order::Ascending => "asc",
+                Order::Descending => "desc",
+            };
+            query.push(("order".to_string(), order.to_string()));
+        }
+        if let Some(id) = &self.starting_after {
+            query.push(("starting_after".to_string(), id.clone()));
+        }
+        if let Some(id) = &self.ending_before {
+            query.push(("ending_before".to_string(), id.clone()));
+        }
+        query
+    }
+}
+
+/// How the current user is authenticated, see [`Client::auth_info`].
+#[derive(Deserialize, Debug)]
+pub struct AuthInfo {
+    /// `oauth` or `api_key`.
+    pub method: String,
+    /// Scopes granted to the credentials, e.g. `wallet:accounts:read`.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+/// Permission of an OAuth token or API key, checked by [`Client::require_scopes`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Scope {
+    AccountsRead,
+    AccountsUpdate,
+    AddressesRead,
+    AddressesCreate,
+    BuysRead,
+    BuysCreate,
+    SellsRead,
+    SellsCreate,
+    DepositsRead,
+    DepositsCreate,
+    WithdrawalsRead,
+    WithdrawalsCreate,
+    TransactionsRead,
+    TransactionsSend,
+    TransactionsTransfer,
+    TransactionsRequest,
+    NotificationsRead,
+    PaymentMethodsRead,
+    UserRead,
+    UserEmail,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::AccountsRead => "wallet:accounts:read",
+            Scope::AccountsUpdate => "wallet:accounts:update",
+            Scope::AddressesRead => "wallet:addresses:read",
+            Scope::AddressesCreate => "wallet:addresses:create",
+            Scope::BuysRead => "wallet:buys:read",
+            Scope::BuysCreate => "wallet:buys:create",
+            Scope::SellsRead => "wallet:sells:read",
+            Scope::SellsCreate => "wallet:sells:create",
+            Scope::DepositsRead => "wallet:deposits:read",
+            Scope::DepositsCreate => "wallet:deposits:create",
+            Scope::WithdrawalsRead => "wallet:withdrawals:read",
+            Scope::WithdrawalsCreate => "wallet:withdrawals:create",
+            Scope::TransactionsRead => "wallet:transactions:read",
+            Scope::TransactionsSend => "wallet:transactions:send",
+            Scope::TransactionsTransfer => "wallet:transactions:transfer",
+            Scope::TransactionsRequest => "wallet:transactions:request",
+            Scope::NotificationsRead => "wallet:notifications:read",
+            Scope::PaymentMethodsRead => "wallet:payment-methods:read",
+            Scope::UserRead => "wallet:user:read",
+            Scope::UserEmail => "wallet:user:email",
+        }
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Transaction {
+    pub id: Uuid,
+
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
+
+    pub r#type: String,
+    pub resource: String,
+    pub resource_path: String,
+    pub status: String,
+    pub amount: Balance,
+    pub native_amount: Balance,
+    #[serde(default)]
+    pub instant_exchange: bool,
+    pub network: Option<Network>,
+    pub from: Option<From>,
+    pub details: TransactionDetails,
+    /// When the funds of a delayed transaction, such as a vault withdrawal, become available.
+    pub available_at: Option<DateTime>,
+}
+
+impl Transaction {
+    /// Where a withdrawal from a vault stands, `None` for other transactions.
+    pub fn vault_withdrawal_status(&self) -> Option<VaultWithdrawalStatus> {
+        if self.r#type != "vault_withdrawal" {
+            return None;
+        }
+        Some(match self.status.as_str() {
+            "waiting_for_signature" => VaultWithdrawalStatus::AwaitingApproval,
+            "pending" | "waiting_for_clearing" => VaultWithdrawalStatus::Delayed,
+            "completed" => VaultWithdrawalStatus::Completed,
+            "canceled" | "cancelled" => VaultWithdrawalStatus::Canceled,
+            "expired" => VaultWithdrawalStatus::Expired,
+            other => VaultWithdrawalStatus::Other(other.to_string()),
+        })
+    }
+}
+
+/// Where a deposit stands, see [`deposit_stream`](Client::deposit_stream).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DepositStatus {
+    /// Seen on the network, waiting for confirmations.
+    Pending,
+    /// Confirmed on the network and credited to the account.
+    Completed,
+    /// Failed, canceled or expired.
+    Failed,
+    Other(String),
+}
+
+/// A transaction received by an address, see [`deposit_stream`](Client::deposit_stream).
+#[derive(Debug)]
+pub struct Deposit {
+    pub status: DepositStatus,
+    /// Network confirmations so far, when the API reports them.
+    pub confirmations: Option<u32>,
+    pub transaction: Transaction,
+}
+
+impl Deposit {
+    fn new(transaction: Transaction) -> Self {
+        let status = match transaction.status.as_str() {
+            "pending" | "waiting_for_clearing" => DepositStatus::Pending,
+            "completed" => DepositStatus::Completed,
+            "failed" | "canceled" | "cancelled" | "expired" => DepositStatus::Failed,
+            other => DepositStatus::Other(other.to_string()),
+        };
+        Self {
+            status,
+            confirmations: transaction
+                .network
+                .as_ref()
+                .and_then(|network| network.confirmations),
+            transaction,
+        }
+    }
+}
+
+/// Stage of a withdrawal from a vault, which has to be approved and then waits out a delay
+/// before the funds move.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VaultWithdrawalStatus {
+    /// Waiting for the approvers to confirm it.
+    AwaitingApproval,
+    /// Approved, waiting until [`available_at`](Transaction::available_at). It can still be
+    /// canceled.
+    Delayed,
+    Completed,
+    Canceled,
+    /// Not approved in time.
+    Expired,
+    Other(String),
+}
+
+/// Parameters of a [`send_money`](Client::send_money) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct SendMoney {
+    r#type: &'static str,
+    /// A network address or the email address of the recipient.
+    pub to: String,
+    pub amount: BigDecimal,
+    pub currency: String,
+    pub description: Option<String>,
+    pub idem: IdempotencyKey,
+    /// Network to send on, for currencies that exist on several, see
+    /// [`supported_networks`](Client::supported_networks).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Whether the recipient address belongs to a financial institution such as another
+    /// exchange, rather than a self-hosted wallet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_financial_institution: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub financial_institution_website: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub travel_rule_data: Option<TravelRule>,
+}
+
+/// Recipient information required by the travel rule for sends in some jurisdictions, e.g. the
+/// EU and Canada.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TravelRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beneficiary_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beneficiary_address: Option<PostalAddress>,
+    /// Name of the financial institution holding the recipient's account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beneficiary_financial_institution: Option<String>,
+    /// Whether the funds are sent to an account of the sender.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_self: Option<bool>,
+}
+
+impl TravelRule {
+    /// Recipient named `name`.
+    pub fn beneficiary(name: &str) -> Self {
+        Self {
+            beneficiary_name: Some(name.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn address(self, address: PostalAddress) -> Self {
+        let mut _self = self;
+        _self.beneficiary_address = Some(address);
+        _self
+    }
+
+    pub fn financial_institution(self, name: &str) -> Self {
+        let mut _self = self;
+        _self.beneficiary_financial_institution = Some(name.to_string());
+        _self
+    }
+
+    pub fn is_self(self, is_self: bool) -> Self {
+        let mut _self = self;
+        _self.is_self = Some(is_self);
+        _self
+    }
+}
 
-pub struct Private {
-    _pub: Public,
-    key: String,
-    secret: String,
+/// Postal address of a travel rule beneficiary.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PostalAddress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address3: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// ISO 3166-1 alpha-2 country code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
 }
 
-impl Private {
-    pub fn new(uri: &str, key: &str, secret: &str) -> Self {
+impl SendMoney {
+    /// Sends `amount` of `currency` to `to` with a freshly generated idempotency key.
+    pub fn new(to: &str, amount: BigDecimal, currency: &str) -> Self {
         Self {
-            _pub: Public::new(uri),
-            key: key.to_string(),
-            secret: secret.to_string(),
+            r#type: "send",
+            to: to.to_string(),
+            amount,
+            currency: currency.to_string(),
+            description: None,
+            idem: IdempotencyKey::default(),
+            network: None,
+            to_financial_institution: None,
+            financial_institution_website: None,
+            travel_rule_data: None,
         }
     }
 
-    ///
-    /// **List accounts**
-    ///
-    /// Lists current user’s accounts to which the authentication method has access to.
-    ///
-    /// https://developers.coinbase.com/api/v2#list-accounts
-    ///
-    pub fn accounts<'a>(&'a self) -> impl Stream<Item = Result<Vec<Account>>> + 'a {
-        let uri = UriTemplate::new("/v2/accounts").build();
-        let request = self.request(&uri);
-        self._pub.get_stream(request)
+    pub fn description(self, description: &str) -> Self {
+        let mut _self = self;
+        _self.description = Some(description.to_string());
+        _self
     }
 
-    ///
-    /// **List transactions**
-    ///
-    /// Lists account’s transactions.
-    ///
-    /// https://developers.coinbase.com/api/v2#list-transactions
-    ///
-    pub fn transactions<'a>(
-        &'a self,
-        account_id: &Uuid,
-    ) -> impl Stream<Item = Result<Vec<Transaction>>> + 'a {
-        let limit = 100;
-        let uri = UriTemplate::new("/v2/accounts/{account}/transactions{?query*}")
-            .set("account", account_id.to_string())
-            .set("query", &[("limit", limit.to_string().as_ref())])
-            .build();
-        let request = self.request(&uri);
-        self._pub.get_stream(request)
+    /// Truncates the amount to `exponent` decimal places, the precision of `currency`.
+    pub fn truncate(self, exponent: usize) -> Self {
+        let mut _self = self;
+        _self.amount = amount::truncate(&_self.amount, exponent);
+        _self
     }
 
-    ///
-    /// **List addresses**
-    ///
-    /// Lists addresses for an account.
-    ///
-    /// https://docs.cloud.coinbase.com/sign-in-with-coinbase/docs/api-addresses#list-addresses
-    ///
-    pub fn list_addresses<'a>(
-        &'a self,
-        account_id: &Uuid,
-    ) -> impl Stream<Item = Result<Vec<Address>>> + 'a {
-        let uri = UriTemplate::new("/v2/accounts/{account}/addresses")
-            .set("account", account_id.to_string())
-            .build();
-        let request = self.request(&uri);
-        self._pub.get_stream(request)
+    /// Replaces the generated idempotency key, e.g. with one persisted before the first attempt.
+    pub fn idem<K: Into<IdempotencyKey>>(self, idem: K) -> Self {
+        let mut _self = self;
+        _self.idem = idem.into();
+        _self
     }
 
-    fn request(&self, _uri: &str) -> request::Builder {
-        let uri: Uri = (self._pub.uri.to_string() + _uri).parse().unwrap();
-        request::Builder::new_with_auth(&self.key, &self.secret).uri(uri)
+    /// Sends on `network`, e.g. `base` rather than `ethereum` for USDC.
+    pub fn network(self, network: &str) -> Self {
+        let mut _self = self;
+        _self.network = Some(network.to_string());
+        _self
+    }
+
+    /// Declares the recipient address as belonging to the financial institution at `website`.
+    pub fn financial_institution(self, website: &str) -> Self {
+        let mut _self = self;
+        _self.to_financial_institution = Some(true);
+        _self.financial_institution_website = Some(website.to_string());
+        _self
+    }
+
+    /// Declares the recipient address as a self-hosted wallet.
+    pub fn self_hosted(self) -> Self {
+        let mut _self = self;
+        _self.to_financial_institution = Some(false);
+        _self.financial_institution_website = None;
+        _self
+    }
+
+    /// Attaches travel rule information about the recipient.
+    pub fn travel_rule(self, travel_rule: TravelRule) -> Self {
+        let mut _self = self;
+        _self.travel_rule_data = Some(travel_rule);
+        _self
     }
 }
 
+/// A bank account, card or other way to pay for buys and receive payouts, see
+/// [`payment_methods`](Client::payment_methods).
 #[derive(Deserialize, Debug)]
-pub struct Account {
-    // id appears to be either a UUID or a token name e.g: "LINK"
+pub struct PaymentMethod {
     pub id: String,
-
+    /// Kind of payment method, e.g. `ach_bank_account` or `fiat_account`.
     pub r#type: String,
-
+    pub name: String,
+    pub currency: String,
+    #[serde(default)]
+    pub primary_buy: bool,
+    #[serde(default)]
+    pub primary_sell: bool,
+    #[serde(default)]
+    pub allow_buy: bool,
+    #[serde(default)]
+    pub allow_sell: bool,
+    #[serde(default)]
+    pub allow_deposit: bool,
+    #[serde(default)]
+    pub allow_withdraw: bool,
+    #[serde(default)]
+    pub limits: PaymentMethodLimits,
     pub created_at: Option<DateTime>,
     pub updated_at: Option<DateTime>,
-
     pub resource: String,
     pub resource_path: String,
+}
 
-    pub name: String,
-    pub primary: bool,
+/// What a payment method can still be used for, over one or more rolling periods per use.
+#[derive(Deserialize, Debug, Default)]
+pub struct PaymentMethodLimits {
+    #[serde(default)]
+    pub buy: Vec<Limit>,
+    #[serde(default)]
+    pub instant_buy: Vec<Limit>,
+    #[serde(default)]
+    pub sell: Vec<Limit>,
+    #[serde(default)]
+    pub deposit: Vec<Limit>,
+    #[serde(default)]
+    pub withdraw: Vec<Limit>,
+}
 
-    pub currency: Currency,
+/// Use of a payment method a [`Limit`] applies to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LimitKind {
+    Buy,
+    InstantBuy,
+    Sell,
+    Deposit,
+    Withdraw,
+}
 
-    pub balance: Balance,
+impl std::fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            LimitKind::Buy => "buy",
+            LimitKind::InstantBuy => "instant buy",
+            LimitKind::Sell => "sell",
+            LimitKind::Deposit => "deposit",
+            LimitKind::Withdraw => "withdrawal",
+        })
+    }
+}
 
-    pub allow_deposits: bool,
-    pub allow_withdrawals: bool,
+impl PaymentMethodLimits {
+    pub fn get(&self, kind: LimitKind) -> &[Limit] {
+        match kind {
+            LimitKind::Buy => &self.buy,
+            LimitKind::InstantBuy => &self.instant_buy,
+            LimitKind::Sell => &self.sell,
+            LimitKind::Deposit => &self.deposit,
+            LimitKind::Withdraw => &self.withdraw,
+        }
+    }
+
+    /// Fails with [`CBError::InvalidAmount`](crate::CBError::InvalidAmount) if `amount` is more
+    /// than what remains of any limit of `kind` in the same currency.
+    pub fn check(&self, kind: LimitKind, amount: &Balance) -> Result<()> {
+        let exceeded = self.get(kind).iter().find(|limit| {
+            limit.remaining.currency == amount.currency && limit.remaining.amount < amount.amount
+        });
+        match exceeded {
+            Some(limit) => Err(crate::CBError::InvalidAmount(format!(
+                "{} {} exceeds the remaining {} {} of the {} limit over {} days",
+                amount.amount,
+                amount.currency,
+                limit.remaining.amount,
+                limit.remaining.currency,
+                kind,
+                limit.period_in_days
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Limit of a payment method over a rolling period.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Limit {
+    pub period_in_days: u32,
+    pub total: Balance,
+    pub remaining: Balance,
+}
+
+/// Parameters of a [`place_buy`](Client::place_buy) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlaceBuy {
+    /// Amount to buy, in `currency`.
+    pub amount: BigDecimal,
+    /// Currency of `amount`, either the account's or a fiat currency to spend.
+    pub currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_method: Option<String>,
+    /// Whether the buy is executed immediately rather than only quoted.
+    pub commit: bool,
+    /// Whether only a price quote is returned, without saving the buy.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub quote: bool,
+}
+
+impl PlaceBuy {
+    /// Truncates the amount to `exponent` decimal places, the precision of `currency`, so the
+    /// buy isn't rejected for being too precise.
+    pub fn truncate(self, exponent: usize) -> Self {
+        let mut _self = self;
+        _self.amount = amount::truncate(&_self.amount, exponent);
+        _self
+    }
+
+    /// Buys `amount` of `currency` and commits the buy right away.
+    pub fn new(amount: BigDecimal, currency: &str) -> Self {
+        Self {
+            amount,
+            currency: currency.to_string(),
+            payment_method: None,
+            commit: true,
+            quote: false,
+        }
+    }
+
+    /// Pays with the payment method `id` instead of the default one.
+    pub fn payment_method(self, id: &str) -> Self {
+        let mut _self = self;
+        _self.payment_method = Some(id.to_string());
+        _self
+    }
+
+    pub fn commit(self, commit: bool) -> Self {
+        let mut _self = self;
+        _self.commit = commit;
+        _self
+    }
+
+    /// Only returns a price quote, which can't be committed, see
+    /// [`quote_buy`](Client::quote_buy) for one that can.
+    pub fn quote(self, quote: bool) -> Self {
+        let mut _self = self;
+        _self.quote = quote;
+        _self
+    }
 }
 
+/// An uncommitted buy with its final price, see [`quote_buy`](Client::quote_buy).
 #[derive(Deserialize, Debug)]
-pub struct Balance {
+pub struct Quote {
+    pub id: Uuid,
+    /// Amount bought, in the account's currency.
+    pub amount: Balance,
+    /// Price of `amount` before fees.
+    pub subtotal: Balance,
+    pub fee: Balance,
+    /// What the payment method is charged, fees included.
+    pub total: Balance,
+    pub payment_method: Option<ResourceRef>,
+    pub created_at: Option<DateTime>,
+    /// When the bought funds become available.
+    pub payout_at: Option<DateTime>,
+}
+
+/// Parameters of a [`place_sell`](Client::place_sell) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlaceSell {
+    /// Amount to sell, in `currency`.
     pub amount: BigDecimal,
+    /// Currency of `amount`, either the account's or a fiat currency to receive.
     pub currency: String,
+    /// Payment method the proceeds are paid out to.
+    pub payment_method: String,
+    /// Whether the sell is executed immediately, or waits for
+    /// [`commit_sell`](Client::commit_sell).
+    pub commit: bool,
+}
+
+impl PlaceSell {
+    /// Sells `amount` of `currency`, pays out to the payment method `payment_method` and
+    /// commits the sell right away.
+    pub fn new(amount: BigDecimal, currency: &str, payment_method: &str) -> Self {
+        Self {
+            amount,
+            currency: currency.to_string(),
+            payment_method: payment_method.to_string(),
+            commit: true,
+        }
+    }
+
+    /// Truncates the amount to `exponent` decimal places, the precision of `currency`, so the
+    /// sell isn't rejected for being too precise.
+    pub fn truncate(self, exponent: usize) -> Self {
+        let mut _self = self;
+        _self.amount = amount::truncate(&_self.amount, exponent);
+        _self
+    }
+
+    pub fn commit(self, commit: bool) -> Self {
+        let mut _self = self;
+        _self.commit = commit;
+        _self
+    }
 }
 
 #[derive(Deserialize, Debug)]
-pub struct Address {
-    pub id: String,
-    pub address: String,
-    pub name: Option<String>,
+pub struct Sell {
+    pub id: Uuid,
+    pub status: String,
+    pub resource: String,
+    pub resource_path: String,
+    pub payment_method: Option<ResourceRef>,
+    pub amount: Balance,
+    /// What is paid out, fees deducted.
+    pub total: Option<Balance>,
+    pub subtotal: Option<Balance>,
+    pub fee: Option<Balance>,
+    #[serde(default)]
+    pub committed: bool,
     pub created_at: Option<DateTime>,
     pub updated_at: Option<DateTime>,
-    pub network: String,
+    pub payout_at: Option<DateTime>,
+}
+
+/// Parameters of a [`withdraw`](Client::withdraw) request.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlaceWithdrawal {
+    amount: BigDecimal,
+    currency: String,
+    payment_method: String,
+    commit: bool,
+}
+
+impl PlaceWithdrawal {
+    /// Withdraws `amount` of `currency`, e.g. the [`currency`](Account::currency) of the account
+    /// withdrawn from, to the payment method `payment_method` and commits it right away.
+    ///
+    /// Fails with [`CBError::InvalidAmount`](crate::CBError::InvalidAmount) if `amount` isn't
+    /// positive or has more decimal places than the currency's exponent, see
+    /// [`amount::truncate`].
+    pub fn new(amount: BigDecimal, currency: &Currency, payment_method: &str) -> Result<Self> {
+        if !amount.is_positive() {
+            return Err(crate::CBError::InvalidAmount(format!(
+                "{} {} is not positive",
+                amount, currency.code
+            )));
+        }
+        if !amount::fits(&amount, currency.exponent) {
+            return Err(crate::CBError::InvalidAmount(format!(
+                "{} {} has more than {} decimal places",
+                amount, currency.code, currency.exponent
+            )));
+        }
+        Ok(Self {
+            amount,
+            currency: currency.code.clone(),
+            payment_method: payment_method.to_string(),
+            commit: true,
+        })
+    }
+
+    pub fn amount(&self) -> &BigDecimal {
+        &self.amount
+    }
+
+    pub fn commit(self, commit: bool) -> Self {
+        let mut _self = self;
+        _self.commit = commit;
+        _self
+    }
+}
+
+/// A withdrawal to a payment method, see [`withdraw`](Client::withdraw).
+#[derive(Deserialize, Debug)]
+pub struct Withdrawal {
+    pub id: Uuid,
+    pub status: String,
     pub resource: String,
     pub resource_path: String,
+    pub amount: Balance,
+    pub subtotal: Option<Balance>,
+    pub fee: Option<Balance>,
+    #[serde(default)]
+    pub committed: bool,
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
+    pub payout_at: Option<DateTime>,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct Transaction {
+pub struct Buy {
     pub id: Uuid,
-
+    pub status: String,
+    pub resource: String,
+    pub resource_path: String,
+    pub amount: Balance,
+    pub total: Option<Balance>,
+    pub subtotal: Option<Balance>,
+    pub fee: Option<Balance>,
+    #[serde(default)]
+    pub committed: bool,
     pub created_at: Option<DateTime>,
     pub updated_at: Option<DateTime>,
+    pub payout_at: Option<DateTime>,
+}
 
+/// Event delivered to a webhook, or listed by [`notifications`](Client::notifications).
+#[derive(Deserialize, Debug)]
+pub struct Notification {
+    pub id: Uuid,
+    /// Kind of event, e.g. `wallet:addresses:new-payment`.
     pub r#type: String,
+    /// Resource the event is about, its shape depends on `type`.
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default)]
+    pub additional_data: serde_json::Value,
+    pub user: Option<ResourceRef>,
+    pub account: Option<ResourceRef>,
+    #[serde(default)]
+    pub delivery_attempts: u32,
+    pub delivered_at: Option<DateTime>,
+    pub created_at: Option<DateTime>,
     pub resource: String,
     pub resource_path: String,
-    pub status: String,
+}
+
+impl Notification {
+    /// Deserializes `data` into the model of the resource, e.g. a [`Transaction`].
+    pub fn data_as<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(T::deserialize(&self.data)?)
+    }
+
+    /// The event by its `type`, with `data` deserialized into the model of the resource.
+    /// Types without a model are returned as [`Other`](NotificationEvent::Other).
+    pub fn event(&self) -> Result<NotificationEvent> {
+        Ok(match self.r#type.as_str() {
+            "ping" => NotificationEvent::Ping,
+            "wallet:addresses:new-payment" => NotificationEvent::NewPayment {
+                address: self.data_as()?,
+                payment: serde::Deserialize::deserialize(&self.additional_data)?,
+            },
+            "wallet:buys:created" => NotificationEvent::BuyCreated(self.data_as()?),
+            "wallet:buys:completed" => NotificationEvent::BuyCompleted(self.data_as()?),
+            "wallet:buys:canceled" => NotificationEvent::BuyCanceled(self.data_as()?),
+            "wallet:sells:created" => NotificationEvent::SellCreated(self.data_as()?),
+            "wallet:sells:completed" => NotificationEvent::SellCompleted(self.data_as()?),
+            "wallet:sells:canceled" => NotificationEvent::SellCanceled(self.data_as()?),
+            "wallet:withdrawal:completed" => {
+                NotificationEvent::WithdrawalCompleted(self.data_as()?)
+            }
+            "wallet:withdrawal:canceled" => NotificationEvent::WithdrawalCanceled(self.data_as()?),
+            other => NotificationEvent::Other(other.to_string()),
+        })
+    }
+}
+
+/// What a [`Notification`] is about, see [`Notification::event`].
+#[derive(Debug)]
+pub enum NotificationEvent {
+    /// Sent when a subscription is created, to check that notifications are received.
+    Ping,
+    /// A payment was received by an address.
+    NewPayment {
+        address: Address,
+        payment: AddressPayment,
+    },
+    BuyCreated(Buy),
+    BuyCompleted(Buy),
+    BuyCanceled(Buy),
+    SellCreated(Sell),
+    SellCompleted(Sell),
+    SellCanceled(Sell),
+    WithdrawalCompleted(Withdrawal),
+    WithdrawalCanceled(Withdrawal),
+    /// A type without a model, its payload is in the notification's `data`.
+    Other(String),
+}
+
+/// The payment of a [`NewPayment`](NotificationEvent::NewPayment) event.
+#[derive(Deserialize, Debug)]
+pub struct AddressPayment {
+    /// Hash of the transaction on the network.
+    pub hash: Option<String>,
     pub amount: Balance,
-    pub native_amount: Balance,
-    pub instant_exchange: bool,
-    pub network: Option<Network>,
-    pub from: Option<From>,
-    pub details: TransactionDetails,
+    /// The transaction crediting the payment to the account.
+    pub transaction: Option<ResourceRef>,
+}
+
+/// Reference to another resource by its id.
+#[derive(Deserialize, Debug)]
+pub struct ResourceRef {
+    pub id: String,
+    pub resource: Option<String>,
+    pub resource_path: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Network {
     pub status: String,
+    pub name: Option<String>,
+    pub hash: Option<String>,
+    /// Fee paid to the network, once the transaction was broadcast.
+    pub transaction_fee: Option<Balance>,
+    /// Confirmations of a received transaction.
+    pub confirmations: Option<u32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -166,7 +1594,7 @@ pub struct Currency {
     pub r#type: String,
 }
 
-#[derive(Deserialize, Debug, Eq, PartialEq)]
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Order {
     #[serde(rename = "asc")]
     Ascending,
@@ -308,3 +1736,773 @@ fn test_transactions_deserialize() {
     let transactions: Vec<Transaction> = serde_json::from_slice(input.as_bytes()).unwrap();
     assert_eq!(transactions.len(), 2);
 }
+
+#[test]
+fn test_balance_format() {
+    let balance = Balance {
+        amount: "0.123456789".parse().unwrap(),
+        currency: "BTC".to_string(),
+    };
+    assert_eq!(balance.format(8), "0.12345679 BTC");
+    assert_eq!(balance.round(2).amount, "0.12".parse().unwrap());
+    assert_eq!(balance.round(4).amount, "0.1235".parse().unwrap());
+    assert_eq!(balance.truncate(4).amount, "0.1234".parse().unwrap());
+    let balance = Balance {
+        amount: "1234.5".parse().unwrap(),
+        currency: "EUR".to_string(),
+    };
+    assert_eq!(
+        balance.format_with(2, &amount::NumberFormat::DE),
+        "1.234,50 EUR"
+    );
+    let buy = PlaceBuy::new("10.129".parse().unwrap(), "USD").truncate(2);
+    assert_eq!(buy.amount.to_string(), "10.12");
+}
+
+#[test]
+fn test_send_money_serialize() {
+    let send = SendMoney::new(
+        "1AUJ8z5RuHRTqD1eikyfUUetzGmdWLGkpT",
+        "0.1".parse().unwrap(),
+        "BTC",
+    )
+    .idem("9316dd16-0c05");
+    let json = serde_json::to_value(&send).unwrap();
+    assert_eq!(json["type"], "send");
+    assert_eq!(json["amount"], "0.1");
+    assert_eq!(json["idem"], "9316dd16-0c05");
+
+    let other = SendMoney::new(
+        "1AUJ8z5RuHRTqD1eikyfUUetzGmdWLGkpT",
+        "0.1".parse().unwrap(),
+        "BTC",
+    );
+    assert_ne!(other.idem, send.idem);
+    assert!(json.get("travel_rule_data").is_none());
+
+    let send = other
+        .financial_institution("https://exchange.example")
+        .travel_rule(TravelRule::beneficiary("Jane Doe").address(PostalAddress {
+            city: Some("Berlin".to_string()),
+            country: Some("DE".to_string()),
+            ..PostalAddress::default()
+        }));
+    let json = serde_json::to_value(&send).unwrap();
+    assert_eq!(json["to_financial_institution"], true);
+    assert_eq!(
+        json["travel_rule_data"],
+        serde_json::json!({
+            "beneficiary_name": "Jane Doe",
+            "beneficiary_address": {"city": "Berlin", "country": "DE"}
+        })
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_balance_stream() {
+    use crate::testing::MockTransport;
+
+    let account = |amount: &str| {
+        format!(
+            r#"{{"data":{{"id":"f1bb8f61-7f5d-4f04-9552-bcbafdf856b7","type":"wallet",
+                "created_at":null,"updated_at":null,"resource":"account","resource_path":"/",
+                "name":"BTC Wallet","primary":true,
+                "currency":{{"code":"BTC","name":"Bitcoin","exponent":8,"type":"crypto"}},
+                "balance":{{"amount":"{amount}","currency":"BTC"}},
+                "allow_deposits":true,"allow_withdrawals":true}}}}"#
+        )
+    };
+    let id: Uuid = "f1bb8f61-7f5d-4f04-9552-bcbafdf856b7".parse().unwrap();
+    let mock = MockTransport::new();
+    for amount in ["1.5", "1.50", "2", "2"] {
+        mock.push_json(&format!("/v2/accounts/{id}"), &account(amount));
+    }
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_rate_limit(None)
+        .with_transport(mock);
+
+    let changes: Vec<_> = client
+        .balance_stream(&id, Duration::ZERO)
+        .take(2)
+        .collect()
+        .await;
+    let change = changes[0].as_ref().unwrap();
+    assert_eq!(change.old.amount, "1.5".parse().unwrap());
+    assert_eq!(change.new.amount, "2".parse().unwrap());
+    assert!(changes[1].is_err());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_transactions_between() {
+    use futures::stream::TryStreamExt;
+
+    use crate::testing::MockTransport;
+
+    let page = |dates: &[&str], next_uri: Option<&str>| {
+        let transactions: Vec<_> = dates
+            .iter()
+            .map(|date| {
+                let day = &date[8..];
+                format!(
+                    r#"{{"id":"9dd482e4-d8ce-46f7-a261-281843bd28{day}","type":"buy",
+                        "status":"completed","amount":{{"amount":"1","currency":"BTC"}},
+                        "native_amount":{{"amount":"1","currency":"USD"}},
+                        "created_at":"{date}T12:00:00Z","updated_at":null,
+                        "resource":"transaction","resource_path":"/",
+                        "details":{{"title":"","subtitle":""}}}}"#
+                )
+            })
+            .collect();
+        let next_uri = next_uri.map_or("null".to_string(), |uri| format!("\"{uri}\""));
+        format!(
+            r#"{{"pagination":{{"limit":2,"order":"desc","next_uri":{next_uri}}},"data":[{}]}}"#,
+            transactions.join(",")
+        )
+    };
+    let account = Uuid::nil();
+    let first = format!("/v2/accounts/{account}/transactions?limit=100&order=desc");
+    let second = format!("/v2/accounts/{account}/transactions?order=desc&starting_after=b");
+    let mock = MockTransport::new();
+    mock.push_json(&first, &page(&["2024-01-06", "2024-01-05"], Some(&second)))
+        .push_json(
+            &second,
+            &page(&["2024-01-04", "2024-01-01"], Some("/never")),
+        );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+
+    let day = |day: u32| {
+        chrono::NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    };
+    let transactions: Vec<_> = client
+        .transactions_between(&account, Some(day(2)), Some(day(6)))
+        .try_concat()
+        .await
+        .unwrap();
+    let dates: Vec<_> = transactions
+        .iter()
+        .map(|t| t.created_at.unwrap().date_naive().to_string())
+        .collect();
+    assert_eq!(dates, ["2024-01-05", "2024-01-04"]);
+    assert_eq!(mock.requests().len(), 2);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_funded_accounts() {
+    use futures::stream::TryStreamExt;
+
+    use crate::testing::MockTransport;
+
+    let account = |currency: &str, amount: &str| {
+        format!(
+            r#"{{"id":"{currency}","type":"wallet","created_at":null,"updated_at":null,
+                "resource":"account","resource_path":"/","name":"{currency} Wallet",
+                "primary":false,
+                "currency":{{"code":"{currency}","name":"{currency}","exponent":8,"type":"crypto"}},
+                "balance":{{"amount":"{amount}","currency":"{currency}"}},
+                "allow_deposits":true,"allow_withdrawals":true}}"#
+        )
+    };
+    let mock = MockTransport::new();
+    mock.push_json(
+        "/v2/accounts",
+        &format!(
+            r#"{{"pagination":{{"limit":2,"order":"desc","next_uri":"/v2/accounts?starting_after=ETH"}},
+                "data":[{},{}]}}"#,
+            account("BTC", "0.00000000"),
+            account("ETH", "0")
+        ),
+    )
+    .push_json(
+        "/v2/accounts?starting_after=ETH",
+        &format!(
+            r#"{{"data":[{},{}]}}"#,
+            account("LINK", "0.0"),
+            account("SOL", "1.25")
+        ),
+    );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock);
+
+    let pages: Vec<Vec<Account>> = client.funded_accounts().try_collect().await.unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0][0].id, "SOL");
+}
+
+#[cfg(test)]
+#[test]
+#[allow(deprecated)]
+fn test_private_alias() {
+    let private = Private::new(crate::MAIN_URL, "<key>", "<secret>");
+    assert!(private.has_credentials());
+    let client: Client = private.clone().into();
+    assert_eq!(client.uri, crate::MAIN_URL);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_list_params() {
+    use futures::stream::TryStreamExt;
+
+    use crate::testing::MockTransport;
+
+    let empty = r#"{"pagination":{"limit":25,"order":"asc","next_uri":null},"data":[]}"#;
+    let mock = MockTransport::new();
+    mock.push_json("/v2/accounts?limit=25&order=asc&starting_after=BTC", empty)
+        .push_json("/v2/notifications?limit=100&order=desc", empty);
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+
+    let params = ListParams::new()
+        .limit(25)
+        .order(Order::Ascending)
+        .starting_after("BTC");
+    let accounts: Vec<_> = client
+        .accounts_with_params(&params)
+        .try_concat()
+        .await
+        .unwrap();
+    assert!(accounts.is_empty());
+    let params = ListParams::new().order(Order::Descending);
+    let notifications: Vec<_> = client
+        .notifications_with_params(&params)
+        .try_concat()
+        .await
+        .unwrap();
+    assert!(notifications.is_empty());
+    assert!(mock.is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_send_money_network() {
+    use crate::testing::MockTransport;
+    use crate::CBError;
+
+    let account = Uuid::nil();
+    let networks = r#"{"id":"USDC","name":"USD Coin","status":"online","min_size":"0.000001",
+        "supported_networks":[
+        {"id":"ethereum","name":"Ethereum","status":"online","contract_address":"0xa0b8",
+         "min_withdrawal_amount":1e-06,"max_withdrawal_amount":2000000,
+         "network_confirmations":14,"processing_time_seconds":null},
+        {"id":"base","name":"Base","status":"online","contract_address":"0x8335",
+         "min_withdrawal_amount":0.000001,"max_withdrawal_amount":2000000,
+         "network_confirmations":1,"processing_time_seconds":null}]}"#;
+    let transaction = r#"{"data":{"id":"9dd482e4-d8ce-46f7-a261-281843bd2855","type":"send",
+        "status":"pending","amount":{"amount":"-10","currency":"USDC"},
+        "native_amount":{"amount":"-10","currency":"USD"},"created_at":null,"updated_at":null,
+        "resource":"transaction","resource_path":"/","details":{"title":"","subtitle":""}}}"#;
+    let mock = MockTransport::new();
+    mock.push_json("/currencies/USDC", networks)
+        .push_json("/currencies/USDC", networks)
+        .push_json(&format!("/v2/accounts/{account}/transactions"), transaction);
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+
+    let send = SendMoney::new("0xd8da", "10".parse().unwrap(), "USDC");
+    let error = client
+        .send_money(&account, &send.clone().network("solana"))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        CBError::UnsupportedNetwork { supported, .. } if supported == ["ethereum", "base"]
+    ));
+    client
+        .send_money(&account, &send.network("base"))
+        .await
+        .unwrap();
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].uri().host(), Some("api.exchange.coinbase.com"));
+    let body: serde_json::Value = serde_json::from_slice(requests[2].body()).unwrap();
+    assert_eq!(body["network"], "base");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_cancel_vault_withdrawal() {
+    use crate::testing::MockTransport;
+
+    let transaction: Transaction = serde_json::from_str(
+        r#"{"id":"9dd482e4-d8ce-46f7-a261-281843bd2855","type":"vault_withdrawal",
+            "status":"waiting_for_clearing","amount":{"amount":"-1","currency":"BTC"},
+            "native_amount":{"amount":"-60000","currency":"USD"},
+            "created_at":"2024-01-01T12:00:00Z","updated_at":null,
+            "available_at":"2024-01-03T12:00:00Z","resource":"transaction",
+            "resource_path":"/","details":{"title":"","subtitle":""}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        transaction.vault_withdrawal_status(),
+        Some(VaultWithdrawalStatus::Delayed)
+    );
+    assert_eq!(
+        transaction.available_at.unwrap().to_rfc3339(),
+        "2024-01-03T12:00:00+00:00"
+    );
+
+    let account = Uuid::nil();
+    let mock = MockTransport::new();
+    mock.push(
+        &format!("/v2/accounts/{account}/transactions/{}", transaction.id),
+        http::Response::builder()
+            .status(204)
+            .body(bytes::Bytes::new())
+            .unwrap(),
+    );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+
+    client
+        .cancel_vault_withdrawal(&account, &transaction.id)
+        .await
+        .unwrap();
+    assert_eq!(mock.requests()[0].method(), Method::DELETE);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_update_address() {
+    use crate::testing::MockTransport;
+
+    let account = Uuid::nil();
+    let mock = MockTransport::new();
+    mock.push_json(
+        &format!("/v2/accounts/{account}/addresses/dd3183eb-af1d-5f5d-a90d-cbff946435ff"),
+        r#"{"data":{"id":"dd3183eb-af1d-5f5d-a90d-cbff946435ff",
+            "address":"mswUGcPHp1YnkLCgF1TtoryqSc5E9Q8xFa","name":"Savings",
+            "created_at":"2015-01-31T20:49:02Z","updated_at":"2015-03-31T17:25:29-07:00",
+            "network":"bitcoin","resource":"address",
+            "resource_path":"/v2/accounts/2bbf394c/addresses/dd3183eb"}}"#,
+    );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+
+    let address = client
+        .update_address(&account, "dd3183eb-af1d-5f5d-a90d-cbff946435ff", "Savings")
+        .await
+        .unwrap();
+    assert_eq!(address.name.as_deref(), Some("Savings"));
+    let request = &mock.requests()[0];
+    assert_eq!(request.method(), Method::PUT);
+    assert_eq!(&request.body()[..], br#"{"name":"Savings"}"#);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_ping() {
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    let mock = MockTransport::new();
+    mock.push_json(
+        "/v2/time",
+        r#"{"data":{"iso":"2015-06-23T18:02:51Z","epoch":1435082571}}"#,
+    )
+    .push_json(
+        "/v2/user",
+        r#"{"data":{"id":"9da7a204","name":"User One"}}"#,
+    )
+    .push_status(
+        "/v2/user",
+        http::StatusCode::UNAUTHORIZED,
+        r#"{"errors":[{"id":"invalid_token","message":"The access token is invalid"}]}"#,
+    );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_retry_policy(crate::retry::NoRetry)
+        .with_transport(mock.clone());
+
+    client.ping().await.unwrap();
+    client.ping_authenticated().await.unwrap();
+    assert!(matches!(
+        client.ping_authenticated().await,
+        Err(crate::CBError::Coinbase { status, .. } | crate::CBError::Status { status, .. })
+            if status == http::StatusCode::UNAUTHORIZED
+    ));
+    assert!(Client::new(MAIN_URL).ping_authenticated().await.is_err());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_require_scopes() {
+    use crate::testing::MockTransport;
+
+    let auth = r#"{"data":{"method":"oauth",
+        "scopes":["wallet:user:read","wallet:accounts:read","wallet:withdrawals:read"],
+        "oauth_meta":{}}}"#;
+    let mock = MockTransport::new();
+    mock.push_json("/v2/user/auth", auth)
+        .push_json("/v2/user/auth", auth);
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock);
+
+    client
+        .require_scopes(&[Scope::UserRead, Scope::AccountsRead])
+        .await
+        .unwrap();
+    match client
+        .require_scopes(&[
+            Scope::AccountsRead,
+            Scope::WithdrawalsCreate,
+            Scope::TransactionsSend,
+        ])
+        .await
+    {
+        Err(error @ crate::CBError::MissingScopes { .. }) => assert_eq!(
+            error.to_string(),
+            "missing scopes: wallet:withdrawals:create, wallet:transactions:send"
+        ),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_withdraw() {
+    use crate::testing::MockTransport;
+    use crate::CBError;
+
+    let usd = Currency {
+        code: "USD".to_string(),
+        name: "US Dollar".to_string(),
+        exponent: 2,
+        r#type: "fiat".to_string(),
+    };
+    for amount in ["10.001", "0", "-5"] {
+        assert!(matches!(
+            PlaceWithdrawal::new(amount.parse().unwrap(), &usd, "83562370-3e5c-51db-87da"),
+            Err(CBError::InvalidAmount(_))
+        ));
+    }
+
+    let account = Uuid::nil();
+    let mock = MockTransport::new();
+    mock.push_json(
+        &format!("/v2/accounts/{account}/withdrawals"),
+        r#"{"data":{"id":"67e0eaec-07d7-54c4-a72c-2e92826897df","status":"created",
+            "resource":"withdrawal","resource_path":"/","committed":true,
+            "amount":{"amount":"10.00","currency":"USD"},
+            "fee":{"amount":"0.00","currency":"USD"},
+            "created_at":null,"updated_at":null,"payout_at":null}}"#,
+    );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+    let withdrawal =
+        PlaceWithdrawal::new("10.00".parse().unwrap(), &usd, "83562370-3e5c-51db-87da").unwrap();
+    let withdrawal = client.withdraw(&account, &withdrawal).await.unwrap();
+    assert_eq!(withdrawal.status, "created");
+
+    let body: serde_json::Value = serde_json::from_slice(mock.requests()[0].body()).unwrap();
+    assert_eq!(body["amount"], "10.00");
+    assert_eq!(body["currency"], "USD");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_deposit_stream() {
+    use crate::testing::MockTransport;
+
+    let transaction = |id: &str, status: &str, confirmations: u32| {
+        format!(
+            r#"{{"id":"{id}","type":"send","status":"{status}",
+                "amount":{{"amount":"0.01","currency":"BTC"}},
+                "native_amount":{{"amount":"650","currency":"USD"}},
+                "network":{{"status":"{status}","confirmations":{confirmations}}},
+                "created_at":null,"updated_at":null,"resource":"transaction",
+                "resource_path":"/","details":{{"title":"Received bitcoin","subtitle":""}}}}"#
+        )
+    };
+    let old = "57ffb4ae-0c59-5430-bcd3-3f98f797a66c";
+    let new = "8250fe29-f5ef-5fc5-8302-0fbacf6be51e";
+    let account = Uuid::nil();
+    let path = format!("/v2/accounts/{account}/addresses/dd3183eb/transactions");
+    let mock = MockTransport::new();
+    for page in [
+        vec![transaction(old, "completed", 6)],
+        vec![
+            transaction(new, "pending", 0),
+            transaction(old, "completed", 6),
+        ],
+        vec![
+            transaction(new, "pending", 0),
+            transaction(old, "completed", 6),
+        ],
+        vec![
+            transaction(new, "completed", 3),
+            transaction(old, "completed", 6),
+        ],
+    ] {
+        mock.push_json(&path, &format!(r#"{{"data":[{}]}}"#, page.join(",")));
+    }
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_rate_limit(None)
+        .with_transport(mock);
+
+    let deposits: Vec<_> = client
+        .deposit_stream(&account, "dd3183eb", Duration::ZERO)
+        .take(3)
+        .collect()
+        .await;
+    let deposit = deposits[0].as_ref().unwrap();
+    assert_eq!(deposit.transaction.id.to_string(), new);
+    assert_eq!(deposit.status, DepositStatus::Pending);
+    let deposit = deposits[1].as_ref().unwrap();
+    assert_eq!(deposit.status, DepositStatus::Completed);
+    assert_eq!(deposit.confirmations, Some(3));
+    assert!(deposits[2].is_err());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_quote_buy() {
+    use crate::testing::MockTransport;
+
+    let buy = |committed: bool| {
+        format!(
+            r#"{{"data":{{"id":"a333743d-184a-5b5b-abe8-11612fc44ab5","status":"created",
+                "resource":"buy","resource_path":"/","committed":{committed},
+                "payment_method":{{"id":"83562370-3e5c-51db-87da-752af5ab9559",
+                    "resource":"payment_method"}},
+                "amount":{{"amount":"0.01","currency":"BTC"}},
+                "subtotal":{{"amount":"650.00","currency":"USD"}},
+                "fee":{{"amount":"9.75","currency":"USD"}},
+                "total":{{"amount":"659.75","currency":"USD"}},
+                "created_at":null,"updated_at":null,"payout_at":null}}}}"#
+        )
+    };
+    let account = Uuid::nil();
+    let mock = MockTransport::new();
+    mock.push_json(&format!("/v2/accounts/{account}/buys"), &buy(false))
+        .push_json(
+            &format!("/v2/accounts/{account}/buys/a333743d-184a-5b5b-abe8-11612fc44ab5/commit"),
+            &buy(true),
+        );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+
+    let quote = client
+        .quote_buy(&account, &PlaceBuy::new("0.01".parse().unwrap(), "BTC"))
+        .await
+        .unwrap();
+    assert_eq!(quote.total.amount, "659.75".parse().unwrap());
+    assert_eq!(quote.fee.amount, "9.75".parse().unwrap());
+    let buy = client.commit_buy(&account, &quote).await.unwrap();
+    assert!(buy.committed);
+
+    let body: serde_json::Value = serde_json::from_slice(mock.requests()[0].body()).unwrap();
+    assert_eq!(body["commit"], false);
+    assert!(body.get("quote").is_none());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_place_sell() {
+    use crate::testing::MockTransport;
+
+    let sell = |committed: bool| {
+        format!(
+            r#"{{"data":{{"id":"fb1b8ea5-a1a3-5a1a-8d8f-6b3bd1a5e3f1","status":"created",
+                "resource":"sell","resource_path":"/","committed":{committed},
+                "payment_method":{{"id":"83562370-3e5c-51db-87da-752af5ab9559"}},
+                "amount":{{"amount":"0.01","currency":"BTC"}},
+                "total":{{"amount":"640.25","currency":"USD"}},
+                "fee":{{"amount":"9.75","currency":"USD"}},
+                "created_at":null,"updated_at":null,"payout_at":null}}}}"#
+        )
+    };
+    let account = Uuid::nil();
+    let mock = MockTransport::new();
+    mock.push_json(&format!("/v2/accounts/{account}/sells"), &sell(false))
+        .push_json(
+            &format!("/v2/accounts/{account}/sells/fb1b8ea5-a1a3-5a1a-8d8f-6b3bd1a5e3f1/commit"),
+            &sell(true),
+        );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock.clone());
+
+    let place = PlaceSell::new(
+        "0.0100000001".parse().unwrap(),
+        "BTC",
+        "83562370-3e5c-51db-87da-752af5ab9559",
+    )
+    .truncate(8)
+    .commit(false);
+    let sell = client.place_sell(&account, &place).await.unwrap();
+    assert!(!sell.committed);
+    let sell = client.commit_sell(&account, &sell).await.unwrap();
+    assert!(sell.committed);
+    assert_eq!(sell.total.unwrap().amount, "640.25".parse().unwrap());
+
+    let body: serde_json::Value = serde_json::from_slice(mock.requests()[0].body()).unwrap();
+    assert_eq!(body["amount"], "0.01000000");
+    assert_eq!(
+        body["payment_method"],
+        "83562370-3e5c-51db-87da-752af5ab9559"
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_payment_method_limits() {
+    use crate::testing::MockTransport;
+    use crate::CBError;
+
+    let mock = MockTransport::new();
+    mock.push_json(
+        "/v2/payment-methods/83562370-3e5c-51db-87da-752af5ab9559",
+        r#"{"data":{"id":"83562370-3e5c-51db-87da-752af5ab9559","type":"ach_bank_account",
+            "name":"International Bank *****1111","currency":"USD","primary_buy":true,
+            "primary_sell":true,"allow_buy":true,"allow_sell":true,"allow_deposit":true,
+            "allow_withdraw":true,"instant_buy":false,"instant_sell":false,
+            "created_at":null,"updated_at":null,"resource":"payment_method","resource_path":"/",
+            "limits":{"type":"bank","name":"Bank Account",
+                "buy":[{"period_in_days":7,"total":{"amount":"3000.00","currency":"USD"},
+                    "remaining":{"amount":"1000.00","currency":"USD"}}],
+                "deposit":[{"period_in_days":1,"total":{"amount":"500.00","currency":"USD"},
+                    "remaining":{"amount":"500.00","currency":"USD"}},
+                    {"period_in_days":7,"total":{"amount":"3000.00","currency":"USD"},
+                    "remaining":{"amount":"250.00","currency":"USD"}}]}}}"#,
+    );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_transport(mock);
+    let method = client
+        .payment_method("83562370-3e5c-51db-87da-752af5ab9559")
+        .await
+        .unwrap();
+    assert!(method.allow_withdraw);
+    assert_eq!(method.limits.get(LimitKind::Deposit).len(), 2);
+
+    let usd = |amount: &str| Balance {
+        amount: amount.parse().unwrap(),
+        currency: "USD".to_string(),
+    };
+    method.limits.check(LimitKind::Buy, &usd("1000")).unwrap();
+    assert!(method.limits.check(LimitKind::Sell, &usd("1e6")).is_ok());
+    match method.limits.check(LimitKind::Deposit, &usd("300")) {
+        Err(CBError::InvalidAmount(message)) => assert_eq!(
+            message,
+            "300 USD exceeds the remaining 250.00 USD of the deposit limit over 7 days"
+        ),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[cfg(test)]
+#[tokio::test(start_paused = true)]
+async fn test_send_money_retried() {
+    use crate::testing::MockTransport;
+    use http::StatusCode;
+
+    let account = Uuid::nil();
+    let path = format!("/v2/accounts/{account}/transactions");
+    let mock = MockTransport::new();
+    mock.push_status(&path, StatusCode::SERVICE_UNAVAILABLE, "{}")
+        .push_json(
+            &path,
+            r#"{"data":{"id":"9dd482e4-d8ce-46f7-a261-281843bd2855","type":"send",
+                "status":"pending","amount":{"amount":"-0.1","currency":"BTC"},
+                "native_amount":{"amount":"-6500","currency":"USD"},"created_at":null,
+                "updated_at":null,"resource":"transaction","resource_path":"/",
+                "details":{"title":"","subtitle":""}}}"#,
+        );
+    let client = Client::new(crate::MAIN_URL)
+        .with_oauth_token("<token>")
+        .with_rate_limit(None)
+        .with_transport(mock.clone());
+    let send = SendMoney::new(
+        "1AUJ8z5RuHRTqD1eikyfUUetzGmdWLGkpT",
+        "0.1".parse().unwrap(),
+        "BTC",
+    );
+    client.send_money(&account, &send).await.unwrap();
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    let idem = |request: &http::Request<bytes::Bytes>| {
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        body["idem"].as_str().unwrap().to_string()
+    };
+    assert_eq!(idem(&requests[0]), send.idem.as_str());
+    assert_eq!(idem(&requests[1]), send.idem.as_str());
+}
+
+#[cfg(test)]
+#[test]
+fn test_notification_event() {
+    let notification = |r#type: &str, data: &str, additional_data: &str| -> Notification {
+        serde_json::from_str(&format!(
+            r#"{{"id":"6bf0ca21-0b2f-5e8a-b95e-7bd7eaccc338","type":"{type}",
+                "data":{data},"additional_data":{additional_data},
+                "created_at":"2015-11-10T19:15:06Z","resource":"notification",
+                "resource_path":"/v2/notifications/6bf0ca21-0b2f-5e8a-b95e-7bd7eaccc338"}}"#
+        ))
+        .unwrap()
+    };
+
+    let payment = notification(
+        "wallet:addresses:new-payment",
+        r#"{"id":"a6b4c2df-a62c-5d68-822a-dd4e2102e703",
+            "address":"mpJKwdmJKYjiyfNo26eRp4j6qGwuUUnw9x","name":null,"created_at":null,"updated_at":null,"resource":"address",
+            "resource_path":"/v2/accounts/8d5f086c/addresses/a6b4c2df"}"#,
+        r#"{"hash":"56ca4fc5e5e3e1b3bd1c4d4fb8ae0fb9",
+            "amount":{"amount":"0.01","currency":"BTC"},
+            "transaction":{"id":"9dd482e4-d8ce-46f7-a261-281843bd2855","resource":"transaction"}}"#,
+    );
+    match payment.event().unwrap() {
+        NotificationEvent::NewPayment { address, payment } => {
+            assert_eq!(address.address, "mpJKwdmJKYjiyfNo26eRp4j6qGwuUUnw9x");
+            assert_eq!(payment.amount.amount, "0.01".parse().unwrap());
+            assert_eq!(
+                payment.transaction.unwrap().id,
+                "9dd482e4-d8ce-46f7-a261-281843bd2855"
+            );
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let buy = notification(
+        "wallet:buys:completed",
+        r#"{"id":"9dd482e4-d8ce-46f7-a261-281843bd2855","status":"completed","resource":"buy",
+            "resource_path":"/","amount":{"amount":"1.00000000","currency":"BTC"},
+            "committed":true,"created_at":null,"updated_at":null,"payout_at":null}"#,
+        "{}",
+    );
+    assert!(matches!(
+        buy.event().unwrap(),
+        NotificationEvent::BuyCompleted(Buy {
+            committed: true,
+            ..
+        })
+    ));
+    assert!(matches!(
+        notification("ping", "{}", "{}").event().unwrap(),
+        NotificationEvent::Ping
+    ));
+    assert!(matches!(
+        notification("wallet:orders:paid", "{}", "{}").event().unwrap(),
+        NotificationEvent::Other(r#type) if r#type == "wallet:orders:paid"
+    ));
+}
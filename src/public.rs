@@ -1,32 +1,18 @@
 use std::collections::HashMap;
-use std::thread;
 use std::time::Duration;
 
-use async_stream::try_stream;
+use async_stream::stream;
 use bigdecimal::BigDecimal;
-use futures::stream::Stream;
-use hyper::{client::HttpConnector, Body, Client, Uri};
-use hyper_tls::HttpsConnector;
+use futures::stream::{Stream, StreamExt};
 use uritemplate::UriTemplate;
 
-use crate::{request, CBError, DateTime, Result};
+use crate::{ApiResponse, Client, DateTime, Result};
 
-pub struct Public {
-    pub(crate) uri: String,
-    client: Client<HttpsConnector<HttpConnector>>,
-}
-
-impl Public {
-    pub fn new(uri: &str) -> Self {
-        let https = HttpsConnector::new();
-        let client = Client::builder()
-            .pool_idle_timeout(Duration::from_secs(30))
-            .build::<_, Body>(https);
-        let uri = uri.to_string();
-
-        Self { uri, client }
-    }
+/// Former name of [`Client`], from when clients without credentials had their own type.
+#[deprecated(note = "use `Client`, which also serves the public endpoints")]
+pub type Public = Client;
 
+impl Client {
     ///
     /// **Get currencies**
     ///
@@ -37,6 +23,14 @@ impl Public {
     /// https://developers.coinbase.com/api/v2#currencies
     ///
     pub fn currencies<'a>(&'a self) -> impl Stream<Item = Result<Vec<Currency>>> + 'a {
+        self.currencies_with_meta()
+            .map(|page| page.map(|page| page.data))
+    }
+
+    /// Like [`currencies`](Self::currencies), also returning the metadata of each page.
+    pub fn currencies_with_meta<'a>(
+        &'a self,
+    ) -> impl Stream<Item = Result<ApiResponse<Vec<Currency>>>> + 'a {
         let limit = 100;
         let uri = UriTemplate::new("/v2/currencies{?query*}")
             .set("query", &[("limit", limit.to_string().as_ref())])
@@ -55,10 +49,20 @@ impl Public {
     /// https://developers.coinbase.com/api/v2#exchange-rates
     ///
     pub async fn exchange_rates(&self, currency: &str) -> Result<ExchangeRates> {
-        let uri = UriTemplate::new("/v2/exchange-rates{?query*}")
+        self.exchange_rates_with_meta(currency)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`exchange_rates`](Self::exchange_rates), also returning the response metadata.
+    pub async fn exchange_rates_with_meta(
+        &self,
+        currency: &str,
+    ) -> Result<ApiResponse<ExchangeRates>> {
+        let uri = UriTemplate::new("/v2/exchange-rates{?currency}")
             .set("currency", currency)
             .build();
-        self.get(&uri).await
+        self.get_with_meta(&uri).await
     }
 
     ///
@@ -69,10 +73,17 @@ impl Public {
     /// https://developers.coinbase.com/api/v2#get-buy-price
     ///
     pub async fn buy_price(&self, pair: &str) -> Result<CurrencyPrice> {
+        self.buy_price_with_meta(pair)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`buy_price`](Self::buy_price), also returning the response metadata.
+    pub async fn buy_price_with_meta(&self, pair: &str) -> Result<ApiResponse<CurrencyPrice>> {
         let uri = UriTemplate::new("/v2/prices/{pair}")
             .set("pair", pair)
             .build();
-        self.get(&uri).await
+        self.get_with_meta(&uri).await
     }
 
     ///
@@ -83,7 +94,17 @@ impl Public {
     /// https://developers.coinbase.com/api/v2#get-sell-price
     ///
     pub async fn sell_price(&self, currency_pair: &str) -> Result<CurrencyPrice> {
-        self.get(&format!("/v2/prices/{}/sell", currency_pair))
+        self.sell_price_with_meta(currency_pair)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`sell_price`](Self::sell_price), also returning the response metadata.
+    pub async fn sell_price_with_meta(
+        &self,
+        currency_pair: &str,
+    ) -> Result<ApiResponse<CurrencyPrice>> {
+        self.get_with_meta(&format!("/v2/prices/{}/sell", currency_pair))
             .await
     }
 
@@ -91,94 +112,189 @@ impl Public {
     /// **Get spot price**
     ///
     /// Get the current market price for a currency pair. This is usually somewhere in between the
-    /// buy and sell price.
+    /// buy and sell price. With a `date` the historic price of that day (UTC) is returned.
     ///
     /// https://developers.coinbase.com/api/v2#get-spot-price
     ///
     pub async fn spot_price(
         &self,
         currency_pair: &str,
-        _date: Option<chrono::NaiveDate>,
+        date: Option<chrono::NaiveDate>,
     ) -> Result<CurrencyPrice> {
-        self.get(&format!("/v2/prices/{}/spot", currency_pair))
+        self.spot_price_with_meta(currency_pair, date)
             .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`spot_price`](Self::spot_price), also returning the response metadata.
+    pub async fn spot_price_with_meta(
+        &self,
+        currency_pair: &str,
+        date: Option<chrono::NaiveDate>,
+    ) -> Result<ApiResponse<CurrencyPrice>> {
+        let mut uri = format!("/v2/prices/{}/spot", currency_pair);
+        if let Some(date) = date {
+            uri += &format!("?date={}", date.format("%Y-%m-%d"));
+        }
+        self.get_with_meta(&uri).await
     }
 
     ///
-    /// **Get current time**
+    /// **Get historic prices**
     ///
-    /// Get the API server time.
+    /// Get the spot prices of a currency pair over `period`, oldest first, at a resolution
+    /// depending on the period. Lighter than candles for simple charts.
     ///
-    /// https://developers.coinbase.com/api/v2#time
+    pub async fn historic_prices(
+        &self,
+        currency_pair: &str,
+        period: HistoricPeriod,
+    ) -> Result<HistoricPrices> {
+        self.historic_prices_with_meta(currency_pair, period)
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`historic_prices`](Self::historic_prices), also returning the response metadata.
+    pub async fn historic_prices_with_meta(
+        &self,
+        currency_pair: &str,
+        period: HistoricPeriod,
+    ) -> Result<ApiResponse<HistoricPrices>> {
+        let uri = UriTemplate::new("/v2/prices/{pair}/historic{?period}")
+            .set("pair", currency_pair)
+            .set("period", period.as_str())
+            .build();
+        let mut response: ApiResponse<HistoricPrices> = self.get_with_meta(&uri).await?;
+        response.data.prices.sort_by_key(|price| price.time);
+        Ok(response)
+    }
+
+    /// Polls the spot price of `currency_pair` every `interval`, starting right away, for when
+    /// websockets aren't an option. Failed requests are retried according to the client's retry
+    /// policy; failures left after that are yielded as errors and polling goes on.
     ///
-    pub async fn current_time(&self) -> Result<Time> {
-        self.get("/v2/time").await
+    /// ```no_run
+    /// # async fn run(client: coinbase_rs::Client) {
+    /// use std::time::Duration;
+    ///
+    /// use futures::stream::StreamExt;
+    ///
+    /// let prices = client.spot_price_stream("BTC-USD", Duration::from_secs(10));
+    /// futures::pin_mut!(prices);
+    /// while let Some(Ok(price)) = prices.next().await {
+    ///     println!("BTC is at {}", price.amount);
+    /// }
+    /// # }
+    /// ```
+    pub fn spot_price_stream<'a>(
+        &'a self,
+        currency_pair: &str,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<CurrencyPrice>> + 'a {
+        let currency_pair = currency_pair.to_string();
+        stream! {
+            loop {
+                yield self.spot_price(&currency_pair, None).await;
+                if !self.idle(interval).await {
+                    break;
+                }
+            }
+        }
     }
 
-    pub(crate) async fn make_request<U>(&self, request: request::Builder) -> Result<Response<U>>
-    where
-        U: serde::de::DeserializeOwned,
-    {
-        // TODO: This is for rate limiting purposes, but this is super-hacky
-        thread::sleep(Duration::from_millis(350));
+    ///
+    /// **Get all known currencies**
+    ///
+    /// List the assets with their withdrawal limits and the networks they can be sent on. This
+    /// is served by the Exchange API.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getcurrencies
+    ///
+    pub async fn assets(&self) -> Result<Vec<Asset>> {
+        self.assets_with_meta().await.map(|response| response.data)
+    }
 
-        let request = request.clone().build();
-        let request_future = self.client.request(request);
+    /// Like [`assets`](Self::assets), also returning the response metadata.
+    pub async fn assets_with_meta(&self) -> Result<ApiResponse<Vec<Asset>>> {
+        self.make_request(self.exchange_request("/currencies"))
+            .await
+    }
 
-        let response = request_future.await?;
-        let body = hyper::body::to_bytes(response.into_body()).await?;
+    ///
+    /// **Get a currency**
+    ///
+    /// Get a single asset with its withdrawal limits and the networks it can be sent on. This
+    /// is served by the Exchange API.
+    ///
+    /// https://docs.cdp.coinbase.com/exchange/reference/exchangerestapi_getcurrency
+    ///
+    pub async fn asset(&self, currency: &str) -> Result<Asset> {
+        self.asset_with_meta(currency)
+            .await
+            .map(|response| response.data)
+    }
 
-        match serde_json::from_slice::<Response<U>>(&body) {
-            Ok(body) => Ok(body),
-            Err(e) => match serde_json::from_slice(&body) {
-                Ok(coinbase_err) => Err(CBError::Coinbase(coinbase_err)),
-                Err(_) => Err(CBError::Serde(e)),
-            },
-        }
+    /// Like [`asset`](Self::asset), also returning the response metadata.
+    pub async fn asset_with_meta(&self, currency: &str) -> Result<ApiResponse<Asset>> {
+        let uri = UriTemplate::new("/currencies/{currency}")
+            .set("currency", currency)
+            .build();
+        self.make_request(self.exchange_request(&uri)).await
     }
 
-    pub(crate) fn get_stream<'a, U>(
-        &'a self,
-        request: request::Builder,
-    ) -> impl Stream<Item = Result<U>> + 'a
-    where
-        U: Send + 'static,
-        U: serde::de::DeserializeOwned,
-        U: std::marker::Unpin,
-    {
-        try_stream! {
-            let initial_request = request.clone();
-            let mut result = self.make_request(initial_request).await?;
-            yield result.data;
-
-            while let Some(ref next_uri) = result.pagination.and_then(|p| p.next_uri) {
-                let uri: Uri = (self.uri.to_string() + next_uri).parse().unwrap();
-                let request = request.clone().uri(uri);
-                result = self.make_request(request).await?;
-                yield result.data;
-            }
+    /// The networks `currency` can be deposited and withdrawn on, e.g. `ethereum` and `base`
+    /// for USDC, see [`asset`](Self::asset).
+    pub async fn supported_networks(&self, currency: &str) -> Result<Vec<SupportedNetwork>> {
+        Ok(self.asset(currency).await?.supported_networks)
+    }
+
+    /// Checks that `currency` can be sent on `network`, failing with
+    /// [`CBError::UnsupportedNetwork`](crate::CBError::UnsupportedNetwork) otherwise.
+    pub async fn check_network(&self, currency: &str, network: &str) -> Result<()> {
+        let networks = self.supported_networks(currency).await?;
+        if networks.iter().any(|n| n.id.eq_ignore_ascii_case(network)) {
+            return Ok(());
         }
+        Err(crate::CBError::UnsupportedNetwork {
+            currency: currency.to_string(),
+            network: network.to_string(),
+            supported: networks.into_iter().map(|n| n.id).collect(),
+        })
     }
 
-    async fn get<U>(&self, uri: &str) -> Result<U>
-    where
-        U: Send + 'static,
-        U: serde::de::DeserializeOwned,
-    {
-        let result = self.make_request(self.request(uri)).await?;
-        Ok(result.data)
+    ///
+    /// **Get current time**
+    ///
+    /// Get the API server time.
+    ///
+    /// https://developers.coinbase.com/api/v2#time
+    ///
+    pub async fn current_time(&self) -> Result<Time> {
+        self.current_time_with_meta()
+            .await
+            .map(|response| response.data)
+    }
+
+    /// Like [`current_time`](Self::current_time), also returning the response metadata.
+    pub async fn current_time_with_meta(&self) -> Result<ApiResponse<Time>> {
+        self.get_with_meta("/v2/time").await
     }
 
-    fn request(&self, uri: &str) -> request::Builder {
-        let uri: Uri = (self.uri.to_string() + uri).parse().unwrap();
-        request::Builder::new().uri(uri)
+    /// Requests the server time and returns how long the round trip took, for readiness probes.
+    /// See [`ping_authenticated`](Self::ping_authenticated) to also check the credentials.
+    pub async fn ping(&self) -> Result<Duration> {
+        let started = crate::rt::Instant::now();
+        self.current_time().await?;
+        Ok(started.elapsed())
     }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Time {
-    iso: DateTime,
-    epoch: u64,
+    pub iso: DateTime,
+    /// Seconds since the Unix epoch.
+    pub epoch: u64,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -187,6 +303,12 @@ pub struct Response<U> {
     pub data: U,
 }
 
+impl<U> Response<U> {
+    pub(crate) fn next_uri(&self) -> Option<&str> {
+        self.pagination.as_ref()?.next_uri.as_deref()
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub enum Order {
     #[serde(rename = "asc")]
@@ -214,6 +336,74 @@ pub struct Currency {
     pub min_size: BigDecimal,
 }
 
+/// A currency with what's needed to deposit and withdraw it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Asset {
+    pub id: String,
+    pub name: String,
+    /// `online` when the asset can be traded and moved.
+    pub status: String,
+    pub min_size: BigDecimal,
+    pub max_precision: Option<BigDecimal>,
+    #[serde(default)]
+    pub details: AssetDetails,
+    /// Network sends use when they don't name one.
+    pub default_network: Option<String>,
+    #[serde(default)]
+    pub supported_networks: Vec<SupportedNetwork>,
+}
+
+impl Asset {
+    /// The supported network with the identifier `id`.
+    pub fn network(&self, id: &str) -> Option<&SupportedNetwork> {
+        self.supported_networks
+            .iter()
+            .find(|network| network.id.eq_ignore_ascii_case(id))
+    }
+}
+
+/// Properties of an asset on its default network.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AssetDetails {
+    /// `crypto` or `fiat`.
+    pub r#type: Option<String>,
+    pub symbol: Option<String>,
+    pub network_confirmations: Option<u32>,
+    pub min_withdrawal_amount: Option<BigDecimal>,
+    pub max_withdrawal_amount: Option<BigDecimal>,
+    pub crypto_address_link: Option<String>,
+    pub crypto_transaction_link: Option<String>,
+}
+
+/// A network a currency can be sent on.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SupportedNetwork {
+    /// Identifier to pass as the network of a send, e.g. `ethereum`.
+    pub id: String,
+    pub name: String,
+    /// `online` when deposits and withdrawals are processed.
+    pub status: String,
+    pub contract_address: Option<String>,
+    pub min_withdrawal_amount: Option<BigDecimal>,
+    pub max_withdrawal_amount: Option<BigDecimal>,
+    /// Confirmations after which deposits are credited.
+    pub network_confirmations: Option<u32>,
+    pub processing_time_seconds: Option<u64>,
+    /// Explorer link to an address, with `{{address}}` in place of the address.
+    pub crypto_address_link: Option<String>,
+    /// Explorer link to a transaction, with `{{txId}}` in place of the transaction hash.
+    pub crypto_transaction_link: Option<String>,
+    /// Pattern of the destination tags or memos the network accepts, if it uses them.
+    pub destination_tag_regex: Option<String>,
+}
+
+impl SupportedNetwork {
+    /// Whether deposits and withdrawals on the network are processed.
+    pub fn is_online(&self) -> bool {
+        self.status == "online"
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ExchangeRates {
     pub currency: String,
@@ -226,6 +416,44 @@ pub struct CurrencyPrice {
     pub currency: String,
 }
 
+/// Span covered by [`historic_prices`](Client::historic_prices).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HistoricPeriod {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl HistoricPeriod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HistoricPeriod::Hour => "hour",
+            HistoricPeriod::Day => "day",
+            HistoricPeriod::Week => "week",
+            HistoricPeriod::Month => "month",
+            HistoricPeriod::Year => "year",
+            HistoricPeriod::All => "all",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HistoricPrices {
+    pub base: String,
+    pub currency: String,
+    pub prices: Vec<HistoricPrice>,
+}
+
+/// The spot price at one point in time.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoricPrice {
+    pub price: BigDecimal,
+    pub time: DateTime,
+}
+
 #[cfg(test)]
 mod test {
     use bigdecimal::FromPrimitive;
@@ -299,4 +527,81 @@ mod test {
         );
         assert_eq!(currency_price.currency, "USD");
     }
+
+    #[test]
+    fn test_asset_deserialize() {
+        let input = r#"
+    {
+    "id": "USDC",
+    "name": "USD Coin",
+    "min_size": "0.000001",
+    "status": "online",
+    "message": "",
+    "max_precision": "0.000001",
+    "convertible_to": ["USD"],
+    "details": {
+        "type": "crypto",
+        "symbol": "",
+        "network_confirmations": 14,
+        "sort_order": 2,
+        "crypto_address_link": "https://etherscan.io/token/0xa0b8?a={{address}}",
+        "crypto_transaction_link": "https://etherscan.io/tx/0x{{txId}}",
+        "push_payment_methods": ["crypto"],
+        "min_withdrawal_amount": 1e-06,
+        "max_withdrawal_amount": 20000000
+    },
+    "default_network": "ethereum",
+    "supported_networks": [
+        {
+        "id": "base",
+        "name": "Base",
+        "status": "online",
+        "contract_address": "0x8335",
+        "crypto_address_link": "https://basescan.org/token/0x8335?a={{address}}",
+        "crypto_transaction_link": "https://basescan.org/tx/{{txId}}",
+        "min_withdrawal_amount": 1e-06,
+        "max_withdrawal_amount": 20000000,
+        "network_confirmations": 1,
+        "processing_time_seconds": null,
+        "destination_tag_regex": ""
+        }
+    ]
+    }"#;
+        let asset: Asset = serde_json::from_slice(input.as_bytes()).unwrap();
+        assert_eq!(asset.default_network.as_deref(), Some("ethereum"));
+        assert_eq!(asset.details.network_confirmations, Some(14));
+        let base = asset.network("BASE").unwrap();
+        assert!(base.is_online());
+        assert_eq!(base.network_confirmations, Some(1));
+        assert_eq!(
+            base.min_withdrawal_amount,
+            Some("0.000001".parse().unwrap())
+        );
+        assert!(asset.network("solana").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_historic_prices() {
+        use crate::testing::MockTransport;
+        use crate::MAIN_URL;
+
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/prices/BTC-USD/historic?period=day",
+            r#"{"data":{"base":"BTC","currency":"USD","prices":[
+                {"price":"42100.5","time":"2024-01-01T12:00:00Z"},
+                {"price":"42000","time":"2024-01-01T11:00:00Z"}]}}"#,
+        );
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+
+        let history = client
+            .historic_prices("BTC-USD", HistoricPeriod::Day)
+            .await
+            .unwrap();
+        assert_eq!(history.base, "BTC");
+        assert_eq!(history.currency, "USD");
+        let prices: Vec<String> = history.prices.iter().map(|p| p.price.to_string()).collect();
+        assert_eq!(prices, ["42000", "42100.5"]);
+        assert!(mock.is_empty());
+    }
 }
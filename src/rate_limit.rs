@@ -0,0 +1,285 @@
+use std::time::Duration;
+
+use http::Method;
+use std::sync::{Arc, Mutex};
+
+use crate::rt::{self, Instant};
+
+/// Coinbase allows 10,000 requests per hour for each API key.
+const REQUESTS_PER_HOUR: f64 = 10_000.0;
+
+/// Describes how many requests the client may send.
+///
+/// Requests are admitted at `rate` per second on average, with up to `burst` requests allowed
+/// back to back after a quiet period.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    rate: f64,
+    burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self::per_second(REQUESTS_PER_HOUR / 3600.0).burst(10)
+    }
+}
+
+impl RateLimit {
+    /// Admits `rate` requests per second on average.
+    pub fn per_second(rate: f64) -> Self {
+        Self {
+            rate: rate.max(f64::MIN_POSITIVE),
+            burst: 1,
+        }
+    }
+
+    /// Admits `requests` requests per hour on average.
+    pub fn per_hour(requests: u32) -> Self {
+        Self::per_second(f64::from(requests) / 3600.0)
+    }
+
+    /// Number of requests that may be sent back to back.
+    pub fn burst(self, burst: u32) -> Self {
+        let mut _self = self;
+        _self.burst = burst.max(1);
+        _self
+    }
+}
+
+/// A rate limit budget shared by every client using it.
+///
+/// Clones of a [`Client`](crate::Client) always draw from the budget of the client they were
+/// cloned from. A budget is needed to also share it with clients created separately for the same
+/// API key, e.g. by different components of a service:
+///
+/// ```
+/// use coinbase_rs::{Client, RateBudget, RateLimit, MAIN_URL};
+///
+/// let budget = RateBudget::new(RateLimit::default());
+/// let trading = Client::new(MAIN_URL).with_rate_budget(&budget);
+/// let reporting = Client::new(MAIN_URL).with_rate_budget(&budget);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateBudget {
+    pub(crate) limiter: Arc<RateLimiter>,
+}
+
+impl RateBudget {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(Some(limit))),
+        }
+    }
+}
+
+/// Order in which requests waiting for the rate limiter are sent.
+///
+/// A request is only admitted while no request of a higher priority is waiting, so trading
+/// actions aren't starved behind a long pagination job sharing the limiter. By default requests
+/// changing state (`POST`, `PUT`, `DELETE`, ...) are [`High`](Self::High) and the others
+/// [`Normal`](Self::Normal), see [`Client::with_priority`](crate::Client::with_priority).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Priority {
+    const COUNT: usize = 3;
+
+    pub(crate) fn for_method(method: &Method) -> Self {
+        if method.is_safe() {
+            Priority::Normal
+        } else {
+            Priority::High
+        }
+    }
+}
+
+/// Async token bucket enforcing a `RateLimit`.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: Option<RateLimit>,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// Number of requests waiting for a token, by priority.
+    waiting: [usize; Priority::COUNT],
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: &RateLimit) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.rate).min(f64::from(limit.burst));
+        self.updated = now;
+    }
+}
+
+/// Tolerance for the rounding of refilled tokens.
+const EPSILON: f64 = 1e-9;
+
+impl RateLimiter {
+    pub(crate) fn new(limit: Option<RateLimit>) -> Self {
+        let tokens = limit.map(|l| f64::from(l.burst)).unwrap_or_default();
+        Self {
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens,
+                updated: Instant::now(),
+                waiting: [0; Priority::COUNT],
+            }),
+        }
+    }
+
+    /// Waits until a request of `priority` may be sent.
+    pub(crate) async fn acquire(&self, priority: Priority) {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        let mut waiter: Option<Waiter> = None;
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                bucket.refill(&limit);
+                let ahead: usize = bucket.waiting[priority as usize + 1..].iter().sum();
+                if ahead == 0 && bucket.tokens >= 1.0 - EPSILON {
+                    bucket.tokens -= 1.0;
+                    if let Some(waiter) = waiter.take() {
+                        waiter.done(&mut bucket);
+                    }
+                    return;
+                }
+                if waiter.is_none() {
+                    bucket.waiting[priority as usize] += 1;
+                    waiter = Some(Waiter {
+                        limiter: self,
+                        priority,
+                    });
+                }
+                // Enough time for the waiting requests of higher priority to go first.
+                let missing = 1.0 + ahead as f64 - bucket.tokens;
+                Duration::from_secs_f64(missing.max(EPSILON) / limit.rate)
+            };
+            rt::sleep(wait).await;
+        }
+    }
+}
+
+/// A request counted as waiting, uncounted when it gets its token or gives up.
+struct Waiter<'a> {
+    limiter: &'a RateLimiter,
+    priority: Priority,
+}
+
+impl Waiter<'_> {
+    fn done(self, bucket: &mut Bucket) {
+        bucket.waiting[self.priority as usize] -= 1;
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut bucket) = self.limiter.bucket.lock() {
+            bucket.waiting[self.priority as usize] -= 1;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod test {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_then_rate() {
+        let limiter = RateLimiter::new(Some(RateLimit::per_second(2.0).burst(3)));
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire(Priority::Normal).await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire(Priority::Normal).await;
+        limiter.acquire(Priority::Normal).await;
+        assert_eq!(start.elapsed().as_millis(), 1000);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_unlimited() {
+        let limiter = RateLimiter::new(None);
+        let start = Instant::now();
+        for _ in 0..100 {
+            limiter.acquire(Priority::Low).await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shared_budget() {
+        use crate::testing::MockTransport;
+        use crate::{Client, MAIN_URL};
+
+        let mock = MockTransport::new();
+        let time = r#"{"data":{"iso":"2015-06-23T18:02:51Z","epoch":1435082571}}"#;
+        for _ in 0..4 {
+            mock.push_json("/v2/time", time);
+        }
+        let budget = RateBudget::new(RateLimit::per_second(1.0).burst(2));
+        let first = Client::new(MAIN_URL)
+            .with_transport(mock.clone())
+            .with_rate_budget(&budget);
+        let second = Client::new(MAIN_URL)
+            .with_transport(mock.clone())
+            .with_rate_budget(&first.rate_budget());
+
+        let start = Instant::now();
+        first.current_time().await.unwrap();
+        second.current_time().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        second.clone().current_time().await.unwrap();
+        first.current_time().await.unwrap();
+        assert_eq!(start.elapsed().as_millis(), 2000);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_priority() {
+        let limiter = Arc::new(RateLimiter::new(Some(RateLimit::per_second(1.0))));
+        limiter.acquire(Priority::Normal).await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let spawn = |priority| {
+            let limiter = limiter.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                limiter.acquire(priority).await;
+                order.lock().unwrap().push(priority);
+            })
+        };
+
+        let low = spawn(Priority::Low);
+        tokio::task::yield_now().await;
+        let normal = spawn(Priority::Normal);
+        tokio::task::yield_now().await;
+        let high = spawn(Priority::High);
+        for task in [low, normal, high] {
+            task.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            [Priority::High, Priority::Normal, Priority::Low]
+        );
+
+        // A request that stopped waiting doesn't hold back lower priorities.
+        let start = Instant::now();
+        let _ =
+            tokio::time::timeout(Duration::from_millis(10), limiter.acquire(Priority::High)).await;
+        limiter.acquire(Priority::Low).await;
+        assert!(start.elapsed() <= Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,143 @@
+//! Exchange rates cached for a while, for code converting many amounts.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::rates::Rates;
+//! use coinbase_rs::{Client, MAIN_URL};
+//!
+//! let rates = Rates::new(Client::new(MAIN_URL)).ttl(Duration::from_secs(300));
+//! let eur = rates.convert(&"0.5".parse().unwrap(), "BTC", "EUR").await?;
+//! println!("0.5 BTC is {} EUR", eur.round(2));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bigdecimal::{BigDecimal, One, Zero};
+use futures::lock::Mutex;
+
+use crate::rt::Instant;
+use crate::{CBError, Client, Result};
+
+/// Rates keyed by currency, shared between the cache and its readers.
+type RateTable = Arc<HashMap<String, BigDecimal>>;
+
+/// Exchange rates of one base currency, fetched again once they are older than the TTL.
+pub struct Rates {
+    client: Client,
+    base: String,
+    ttl: Duration,
+    cache: Mutex<Option<(Instant, RateTable)>>,
+}
+
+impl Rates {
+    /// Caches USD rates for a minute.
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            base: "USD".to_string(),
+            ttl: Duration::from_secs(60),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Currency the rates are fetched for. Conversions between any two currencies go through it.
+    pub fn base(self, currency: &str) -> Self {
+        let mut _self = self;
+        _self.base = currency.to_string();
+        _self
+    }
+
+    /// How long fetched rates are used for.
+    pub fn ttl(self, ttl: Duration) -> Self {
+        let mut _self = self;
+        _self.ttl = ttl;
+        _self
+    }
+
+    /// Amount of every currency worth one unit of the base currency. Concurrent callers share a
+    /// single request when the rates have expired.
+    pub async fn rates(&self) -> Result<RateTable> {
+        let mut cache = self.cache.lock().await;
+        if let Some((fetched, rates)) = cache.as_ref() {
+            if fetched.elapsed() < self.ttl {
+                return Ok(rates.clone());
+            }
+        }
+        let rates = Arc::new(self.client.exchange_rates(&self.base).await?.rates);
+        *cache = Some((Instant::now(), rates.clone()));
+        Ok(rates)
+    }
+
+    /// Converts `amount` of `from` to `to` at full precision, failing with
+    /// [`CBError::UnknownCurrency`] if either has no rate.
+    pub async fn convert(&self, amount: &BigDecimal, from: &str, to: &str) -> Result<BigDecimal> {
+        if from == to {
+            return Ok(amount.clone());
+        }
+        let rates = self.rates().await?;
+        let rate = |currency: &str| {
+            if currency == self.base {
+                return Ok(BigDecimal::one());
+            }
+            rates
+                .get(currency)
+                .filter(|rate| !rate.is_zero())
+                .cloned()
+                .ok_or_else(|| CBError::UnknownCurrency(currency.to_string()))
+        };
+        Ok(amount * rate(to)? / rate(from)?)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod test {
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn decimal(value: &str) -> BigDecimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_convert() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/exchange-rates?currency=USD",
+            r#"{"data":{"currency":"USD","rates":{"BTC":"0.00002","EUR":"0.9"}}}"#,
+        )
+        .push_json(
+            "/v2/exchange-rates?currency=USD",
+            r#"{"data":{"currency":"USD","rates":{"BTC":"0.00001","EUR":"0.9"}}}"#,
+        );
+        let rates = Rates::new(Client::new(MAIN_URL).with_transport(mock.clone()));
+
+        let btc = decimal("0.5");
+        assert_eq!(
+            rates.convert(&btc, "BTC", "EUR").await.unwrap(),
+            decimal("22500")
+        );
+        assert_eq!(
+            rates.convert(&btc, "BTC", "USD").await.unwrap(),
+            decimal("25000")
+        );
+        assert!(matches!(
+            rates.convert(&btc, "BTC", "XYZ").await,
+            Err(CBError::UnknownCurrency(currency)) if currency == "XYZ"
+        ));
+        assert_eq!(mock.requests().len(), 1);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(
+            rates.convert(&btc, "BTC", "USD").await.unwrap(),
+            decimal("50000")
+        );
+        assert_eq!(mock.requests().len(), 2);
+    }
+}
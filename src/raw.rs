@@ -0,0 +1,138 @@
+use futures::stream::StreamExt;
+use http::Method;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::public::Response;
+use crate::{request, Client, Result};
+
+impl Client {
+    ///
+    /// **Get raw JSON**
+    ///
+    /// Sends a GET request to `path`, e.g. `/v2/user`, and returns the `data` of the response
+    /// as JSON, for endpoints this crate doesn't model yet. The request is signed when
+    /// credentials are configured. Paginated responses are followed to the last page and the
+    /// `data` arrays of all pages concatenated.
+    ///
+    pub async fn get_raw(&self, path: &str) -> Result<Value> {
+        let mut pages = Box::pin(self.get_stream::<Value>(self.raw_request(path)));
+        let mut data = None;
+        while let Some(page) = pages.next().await {
+            data = Some(match (data, page?.data) {
+                (Some(Value::Array(mut items)), Value::Array(more)) => {
+                    items.extend(more);
+                    Value::Array(items)
+                }
+                (_, page) => page,
+            });
+        }
+        Ok(data.unwrap_or(Value::Null))
+    }
+
+    ///
+    /// **Post raw JSON**
+    ///
+    /// Sends `body` as a POST request to `path` and returns the `data` of the response as JSON,
+    /// for endpoints this crate doesn't model yet. The request is signed when credentials are
+    /// configured.
+    ///
+    pub async fn post_raw(&self, path: &str, body: &Value) -> Result<Value> {
+        self.call(Method::POST, path, Some(body)).await
+    }
+
+    ///
+    /// **Call an endpoint**
+    ///
+    /// Sends a `method` request to `path` with `body` as JSON and deserializes the `data` of the
+    /// response into `T`, so endpoints this crate doesn't model yet can be used with
+    /// user-defined types. Requests are signed when credentials are configured, and retried and
+    /// mapped to errors like those of the modeled endpoints.
+    ///
+    /// ```no_run
+    /// # async fn run(client: coinbase_rs::Client) -> coinbase_rs::Result<()> {
+    /// #[derive(serde::Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// let user: User = client.call(http::Method::GET, "/v2/user", None).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub async fn call<T>(&self, method: Method, path: &str, body: Option<&Value>) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let mut request = self.raw_request(path).method(method);
+        if let Some(body) = body {
+            request = request
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_vec(body)?);
+        }
+        let response = self.make_request::<Response<T>>(request).await?;
+        Ok(response.data.data)
+    }
+
+    fn raw_request(&self, path: &str) -> request::Builder {
+        if self.has_credentials() {
+            self.authenticated_request(path)
+        } else {
+            self.request(path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::Method;
+    use serde_json::json;
+
+    use crate::testing::MockTransport;
+    use crate::{Client, MAIN_URL};
+
+    #[tokio::test]
+    async fn test_get_raw_pages() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/user/things",
+            r#"{"pagination":{"limit":1,"order":"asc","next_uri":"/v2/user/things?starting_after=1"},
+                "data":[{"id":1}]}"#,
+        )
+        .push_json(
+            "/v2/user/things?starting_after=1",
+            r#"{"pagination":{"limit":1,"order":"asc","next_uri":null},"data":[{"id":2}]}"#,
+        )
+        .push_json("/v2/user/things", r#"{"data":{"id":3}}"#);
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+
+        let things = client.get_raw("/v2/user/things").await.unwrap();
+        assert_eq!(things, json!([{ "id": 1 }, { "id": 2 }]));
+        let thing = client
+            .post_raw("/v2/user/things", &json!({ "name": "thing" }))
+            .await
+            .unwrap();
+        assert_eq!(thing, json!({ "id": 3 }));
+        assert_eq!(&mock.requests()[2].body()[..], br#"{"name":"thing"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_call() {
+        #[derive(Deserialize)]
+        struct Thing {
+            id: u32,
+        }
+
+        let mock = MockTransport::new();
+        mock.push_json("/v2/user/things/3", r#"{"data":{"id":3}}"#);
+        let client = Client::new(MAIN_URL).with_transport(mock.clone());
+
+        let thing: Thing = client
+            .call(Method::DELETE, "/v2/user/things/3", None)
+            .await
+            .unwrap();
+        assert_eq!(thing.id, 3);
+        assert_eq!(mock.requests()[0].method(), Method::DELETE);
+    }
+}
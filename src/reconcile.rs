@@ -0,0 +1,299 @@
+//! Reconciliation of a local ledger against what Coinbase reports for a period.
+//!
+//! The ledger is read through the [`Ledger`] trait, implemented over whatever stores the
+//! entries, and matched to the reported entries by id. Every entry only one side has, or
+//! whose amount differs, is reported as a [`Discrepancy`].
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use chrono::{TimeZone, Utc};
+//! use coinbase_rs::reconcile::{Entry, Ledger};
+//! use coinbase_rs::{Client, DateTime, Uuid, MAIN_URL};
+//! use futures::future::BoxFuture;
+//!
+//! struct Books(Vec<Entry>);
+//!
+//! impl Ledger for Books {
+//!     fn entries(
+//!         &self,
+//!         start: DateTime,
+//!         end: DateTime,
+//!     ) -> BoxFuture<'_, coinbase_rs::Result<Vec<Entry>>> {
+//!         let entries = self.0.iter().filter(|e| e.at >= start && e.at < end);
+//!         let entries = entries.cloned().collect();
+//!         Box::pin(async move { Ok(entries) })
+//!     }
+//! }
+//!
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! let account: Uuid = "af6fd33a-e20c-494a-b3f6-f91d204af4b7".parse().unwrap();
+//! let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+//! let end = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+//! let report = client
+//!     .reconcile_transactions(&account, start, end, &Books(Vec::new()))
+//!     .await?;
+//! for discrepancy in &report.discrepancies {
+//!     println!("{discrepancy:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use bigdecimal::BigDecimal;
+use futures::future::BoxFuture;
+use futures::stream::TryStreamExt;
+use uuid::Uuid;
+
+use crate::private::Transaction;
+use crate::{Client, DateTime, Result};
+
+/// A movement of funds, identified by the id Coinbase gave it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub id: String,
+    pub at: DateTime,
+    /// Signed amount, negative for funds leaving the account.
+    pub amount: BigDecimal,
+    /// Currency of the amount, or product for fills.
+    pub currency: String,
+}
+
+impl Entry {
+    fn from_transaction(transaction: &Transaction) -> Option<Self> {
+        Some(Self {
+            id: transaction.id.to_string(),
+            at: transaction.created_at?,
+            amount: transaction.amount.amount.clone(),
+            currency: transaction.amount.currency.clone(),
+        })
+    }
+}
+
+/// Local record of the entries of one account, e.g. a database table.
+pub trait Ledger {
+    /// The entries recorded from `start` up to but excluding `end`. Errors of the store can be
+    /// wrapped in [`CBError::Storage`](crate::CBError::Storage).
+    fn entries(&self, start: DateTime, end: DateTime) -> BoxFuture<'_, Result<Vec<Entry>>>;
+}
+
+/// A difference between the ledger and Coinbase.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discrepancy {
+    /// Reported by Coinbase but not recorded in the ledger.
+    Missing(Entry),
+    /// Recorded in the ledger but not reported by Coinbase.
+    Extra(Entry),
+    /// Recorded with another amount or currency than reported.
+    AmountMismatch { reported: Entry, recorded: Entry },
+}
+
+impl Discrepancy {
+    fn at(&self) -> DateTime {
+        match self {
+            Self::Missing(entry) | Self::Extra(entry) => entry.at,
+            Self::AmountMismatch { reported, .. } => reported.at,
+        }
+    }
+}
+
+/// Outcome of a reconciliation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Reconciliation {
+    /// Number of entries both sides agree on.
+    pub matched: usize,
+    /// The differences, oldest first.
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl Reconciliation {
+    /// Whether the ledger agrees with Coinbase.
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Matches `recorded` entries to `reported` ones by id.
+pub fn reconcile(reported: Vec<Entry>, recorded: Vec<Entry>) -> Reconciliation {
+    let mut recorded: HashMap<String, Entry> = recorded
+        .into_iter()
+        .map(|entry| (entry.id.clone(), entry))
+        .collect();
+    let mut reconciliation = Reconciliation::default();
+    for reported in reported {
+        match recorded.remove(&reported.id) {
+            None => reconciliation
+                .discrepancies
+                .push(Discrepancy::Missing(reported)),
+            Some(recorded)
+                if recorded.amount != reported.amount || recorded.currency != reported.currency =>
+            {
+                reconciliation
+                    .discrepancies
+                    .push(Discrepancy::AmountMismatch { reported, recorded })
+            }
+            Some(_) => reconciliation.matched += 1,
+        }
+    }
+    reconciliation
+        .discrepancies
+        .extend(recorded.into_values().map(Discrepancy::Extra));
+    reconciliation.discrepancies.sort_by_key(Discrepancy::at);
+    reconciliation
+}
+
+impl Client {
+    /// Reconciles `ledger` against the transactions of an account created from `start` up to
+    /// but excluding `end`.
+    pub async fn reconcile_transactions<L: Ledger>(
+        &self,
+        account_id: &Uuid,
+        start: DateTime,
+        end: DateTime,
+        ledger: &L,
+    ) -> Result<Reconciliation> {
+        let transactions: Vec<Transaction> = self
+            .transactions_between(account_id, Some(start), Some(end))
+            .try_concat()
+            .await?;
+        let reported = transactions
+            .iter()
+            .filter_map(Entry::from_transaction)
+            .collect();
+        Ok(reconcile(reported, ledger.entries(start, end).await?))
+    }
+}
+
+#[cfg(feature = "exchange")]
+mod exchange {
+    use super::*;
+    use crate::exchange::{ExchangeClient, Fill, ListFillsParams};
+    use crate::order::OrderSide;
+
+    impl Entry {
+        /// A fill as an entry of its product, with the size negative for sells.
+        pub fn from_fill(fill: &Fill) -> Self {
+            Self {
+                id: fill.trade_id.to_string(),
+                at: fill.created_at,
+                amount: match fill.side {
+                    OrderSide::Buy => fill.size.clone(),
+                    OrderSide::Sell => -&fill.size,
+                },
+                currency: fill.product_id.clone(),
+            }
+        }
+    }
+
+    impl ExchangeClient {
+        /// Reconciles `ledger` against the fills of `products` from `start` up to `end`, with
+        /// fills recorded as by [`Entry::from_fill`].
+        pub async fn reconcile_fills<L: Ledger>(
+            &self,
+            products: &[&str],
+            start: DateTime,
+            end: DateTime,
+            ledger: &L,
+        ) -> Result<Reconciliation> {
+            let mut reported = Vec::new();
+            for product in products {
+                let params = ListFillsParams::product(product)
+                    .start_date(start)
+                    .end_date(end);
+                let fills: Vec<Fill> = self.fills(&params).try_concat().await?;
+                reported.extend(fills.iter().map(Entry::from_fill));
+            }
+            Ok(reconcile(reported, ledger.entries(start, end).await?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    struct Books(Vec<Entry>);
+
+    impl Ledger for Books {
+        fn entries(&self, _: DateTime, _: DateTime) -> BoxFuture<'_, Result<Vec<Entry>>> {
+            Box::pin(futures::future::ready(Ok(self.0.clone())))
+        }
+    }
+
+    fn entry(id: &str, day: u32, amount: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            at: Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+            amount: amount.parse().unwrap(),
+            currency: "BTC".to_string(),
+        }
+    }
+
+    fn transaction(id: &str, day: u32, amount: &str) -> String {
+        format!(
+            r#"{{"id":"{id}","type":"send","status":"completed",
+                "amount":{{"amount":"{amount}","currency":"BTC"}},
+                "native_amount":{{"amount":"0","currency":"USD"}},
+                "description":null,"created_at":"2024-01-{day:02}T00:00:00Z",
+                "updated_at":"2024-01-{day:02}T00:00:00Z","resource":"transaction",
+                "resource_path":"/v2/accounts/a/transactions/{id}","network":null,
+                "details":{{"title":"","subtitle":""}}}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_transactions() {
+        let ids = [
+            "11111111-1111-1111-1111-111111111111",
+            "22222222-2222-2222-2222-222222222222",
+            "33333333-3333-3333-3333-333333333333",
+            "44444444-4444-4444-4444-444444444444",
+        ];
+        let account: Uuid = "af6fd33a-e20c-494a-b3f6-f91d204af4b7".parse().unwrap();
+        let mock = MockTransport::new();
+        mock.push_json(
+            &format!("/v2/accounts/{account}/transactions"),
+            &format!(
+                r#"{{"pagination":{{"limit":25,"order":"desc","next_uri":null}},
+                    "data":[{},{},{}]}}"#,
+                transaction(ids[2], 20, "-0.5"),
+                transaction(ids[1], 10, "1"),
+                transaction(ids[0], 5, "2"),
+            ),
+        );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_rate_limit(None)
+            .with_transport(mock.clone());
+        let books = Books(vec![
+            entry(ids[0], 5, "2"),
+            entry(ids[1], 10, "1.5"),
+            entry(ids[3], 25, "3"),
+        ]);
+
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let report = client
+            .reconcile_transactions(&account, start, end, &books)
+            .await
+            .unwrap();
+        assert_eq!(report.matched, 1);
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.discrepancies,
+            [
+                Discrepancy::AmountMismatch {
+                    reported: entry(ids[1], 10, "1"),
+                    recorded: entry(ids[1], 10, "1.5"),
+                },
+                Discrepancy::Missing(entry(ids[2], 20, "-0.5")),
+                Discrepancy::Extra(entry(ids[3], 25, "3")),
+            ]
+        );
+    }
+}
@@ -1,16 +1,18 @@
 use std::collections::HashMap;
 use std::result;
 
+use bytes::Bytes;
 use http::{request, Method, Request, Uri, Version};
-use hyper::Body;
 use jwt_simple::prelude::*;
 
+use crate::error::AuthError;
+
 #[derive(Debug)]
 pub struct Error {}
 
 pub type Result<T> = result::Result<T, Error>;
 
-const USER_AGENT: &str = concat!("coinbase-rs/", env!("CARGO_PKG_VERSION"));
+pub(crate) const USER_AGENT: &str = concat!("coinbase-rs/", env!("CARGO_PKG_VERSION"));
 
 #[derive(Clone, Debug, Default)]
 pub struct Parts {
@@ -35,43 +37,103 @@ struct Payload {
 #[derive(Clone, Debug, Default)]
 pub struct Builder {
     auth: Option<(String, String)>,
+    clock_offset: chrono::Duration,
+    authenticated: bool,
+    idempotency_key: bool,
     parts: Parts,
-    body: Vec<u8>,
+    body: Bytes,
 }
 
 impl Builder {
     pub fn new() -> Builder {
         Builder {
             auth: None,
+            clock_offset: chrono::Duration::zero(),
+            authenticated: false,
+            idempotency_key: false,
             parts: Parts {
                 method: Method::GET,
                 uri: "/".parse().unwrap(),
                 version: Version::default(),
                 headers: HashMap::new(),
             },
-            body: Vec::new(),
+            body: Bytes::new(),
         }
     }
 
     pub fn new_with_auth(key: &str, secret: &str) -> Builder {
         Builder {
             auth: Some((key.to_string(), secret.to_string())),
+            clock_offset: chrono::Duration::zero(),
+            authenticated: true,
+            idempotency_key: false,
             parts: Parts {
                 method: Method::GET,
                 uri: "/".parse().unwrap(),
                 version: Version::default(),
                 headers: HashMap::new(),
             },
-            body: Vec::new(),
+            body: Bytes::new(),
         }
     }
 
+    /// Signs the request with `key` and `secret` when it is built.
+    pub fn auth(self, key: &str, secret: &str) -> Builder {
+        let mut _self = self;
+        _self.auth = Some((key.to_string(), secret.to_string()));
+        _self
+    }
+
+    /// Dates the JWT signing the request by a clock `offset` ahead of the local one.
+    pub(crate) fn clock_offset(self, offset: chrono::Duration) -> Builder {
+        let mut _self = self;
+        _self.clock_offset = offset;
+        _self
+    }
+
+    /// Authorizes the request with an OAuth2 access token.
+    pub fn bearer(self, token: &str) -> Builder {
+        self.header("Authorization", &format!("Bearer {token}"))
+    }
+
+    /// Marks the request as targeting an endpoint that needs credentials, so the client signs it
+    /// or refuses to send it.
+    pub(crate) fn authenticated(self) -> Builder {
+        let mut _self = self;
+        _self.authenticated = true;
+        _self
+    }
+
+    pub(crate) fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    /// Marks the body as carrying an idempotency key, so resending the request can't repeat
+    /// its effect even though its method isn't idempotent.
+    pub(crate) fn idempotency_key(self) -> Builder {
+        let mut _self = self;
+        _self.idempotency_key = true;
+        _self
+    }
+
+    pub(crate) fn has_idempotency_key(&self) -> bool {
+        self.idempotency_key
+    }
+
     pub fn method(self, method: Method) -> Builder {
         let mut _self = self;
         _self.parts.method = method;
         _self
     }
 
+    pub fn method_ref(&self) -> &Method {
+        &self.parts.method
+    }
+
+    pub fn uri_ref(&self) -> &Uri {
+        &self.parts.uri
+    }
+
     pub fn uri(self, uri: Uri) -> Builder {
         let mut _self = self;
         _self.parts.uri = uri;
@@ -90,70 +152,97 @@ impl Builder {
         _self
     }
 
-    pub fn body(self, body: &Vec<u8>) -> Builder {
+    #[cfg(any(feature = "exchange", feature = "prime"))]
+    pub(crate) fn body_ref(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Sets the body, taking ownership so it is never copied, also not when the request is
+    /// cloned to be retried.
+    pub fn body(self, body: impl Into<Bytes>) -> Builder {
         let mut _self = self;
-        _self.body = body.clone();
+        _self.body = body.into();
         _self
     }
 
-    pub fn build(self) -> Request<Body> {
-        let _self = if let Some((ref key, ref secret)) = self.auth {
-            let path = format!(
-                "{}{}",
-                self.parts.uri.host().unwrap(),
-                self.parts.uri.path_and_query().unwrap(),
-            );
-            let token = Self::token(key, secret, &self.parts.method, &path);
-            let bearer = format!("Bearer {token}");
-            self.clone()
-                .header("User-Agent", USER_AGENT)
-                .header("Content-Type", "text/plain; charset=utf-8")
-                .header("Authorization", &bearer)
-        } else {
-            self
-        };
+    /// Builds the request, signing it if credentials were given.
+    ///
+    /// # Panics
+    ///
+    /// If the request can't be signed, see [`try_build`](Self::try_build).
+    pub fn build(self) -> Request<Bytes> {
+        self.try_build()
+            .unwrap_or_else(|error| panic!("failed to sign the request: {error}"))
+    }
+
+    /// Builds the request, failing if it can't be signed with the credentials given, or if its
+    /// URI has no host to sign for.
+    pub fn try_build(self) -> result::Result<Request<Bytes>, AuthError> {
+        let mut _self = self;
+        if let Some((key, secret)) = _self.auth.take() {
+            let uri = &_self.parts.uri;
+            let host = uri
+                .host()
+                .ok_or_else(|| AuthError::RelativeUri(uri.to_string()))?;
+            let path = match uri.path_and_query() {
+                Some(path) => format!("{host}{path}"),
+                None => format!("{host}{}", uri.path()),
+            };
+            let method = &_self.parts.method;
+            let token = Self::token(&key, &secret, method, &path, _self.clock_offset)?;
+            _self = _self.header("Authorization", &format!("Bearer {token}"));
+            if !_self.has_header("User-Agent") {
+                _self = _self.header("User-Agent", USER_AGENT);
+            }
+            if !_self.has_header("Content-Type") {
+                _self = _self.header("Content-Type", "text/plain; charset=utf-8");
+            }
+        }
         let mut builder = request::Builder::new()
             .method(_self.parts.method)
             .uri(_self.parts.uri);
         for (key, value) in _self.parts.headers {
             builder = builder.header(&key, &value);
         }
-        builder.body(_self.body.into()).unwrap()
+        Ok(builder.body(_self.body).unwrap())
     }
 
-    fn token(key_name: &str, secret: &str, method: &Method, path: &str) -> String {
-        let pkey = match elliptic_curve::SecretKey::<p256::NistP256>::from_sec1_pem(secret) {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!("Failed to load private key from pem: {e}");
-                return String::default();
-            }
-        };
-        let key_pair = match jwt_simple::prelude::ES256KeyPair::from_bytes(&pkey.to_bytes()) {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!("Failed to load key_pair from bytes: {e}");
-                return String::default();
-            }
-        };
+    fn has_header(&self, key: &str) -> bool {
+        self.parts
+            .headers
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case(key))
+    }
+
+    fn token(
+        key_name: &str,
+        secret: &str,
+        method: &Method,
+        path: &str,
+        clock_offset: chrono::Duration,
+    ) -> result::Result<String, AuthError> {
+        let pkey = elliptic_curve::SecretKey::<p256::NistP256>::from_sec1_pem(secret)
+            .map_err(|e| AuthError::InvalidPem(e.to_string()))?;
+        let key_pair = jwt_simple::prelude::ES256KeyPair::from_bytes(&pkey.to_bytes())
+            .map_err(|e| AuthError::InvalidKey(e.to_string()))?;
         let key_pair = key_pair.with_key_id(key_name);
         let payload = Payload {
             uri: format!("{} {}", method.as_str(), path),
         };
-        let mut claims = jwt_simple::claims::Claims::with_custom_claims(
-            payload,
-            coarsetime::Duration::from_secs(120),
-        )
-        .with_issuer("cdp".to_string())
-        .with_subject(key_name);
+        let valid_for = coarsetime::Duration::from_secs(120);
+        let mut claims = jwt_simple::claims::Claims::with_custom_claims(payload, valid_for)
+            .with_issuer("cdp".to_string())
+            .with_subject(key_name);
+        if !clock_offset.is_zero() {
+            let now = (chrono::Utc::now() + clock_offset).timestamp().max(0) as u64;
+            let now = coarsetime::Duration::from_secs(now);
+            claims.issued_at = Some(now);
+            claims.invalid_before = Some(now);
+            claims.expires_at = Some(now + valid_for);
+        }
         claims.create_nonce();
-        let token = match key_pair.sign(claims) {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!("Failed to sign claims: {e}");
-                return String::default();
-            }
-        };
-        token.to_string()
+        key_pair
+            .sign(claims)
+            .map_err(|e| AuthError::Signing(e.to_string()))
     }
 }
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use http::{HeaderMap, StatusCode};
+
+const REQUEST_ID: &str = "cb-request-id";
+const RATE_LIMIT_LIMIT: &str = "x-ratelimit-limit";
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+const AFTER: &str = "cb-after";
+
+/// Pages of listings are paced once less than this fraction of the window's requests is left.
+const PACE_BELOW: u32 = 4;
+
+/// A parsed response body together with metadata taken from the response headers.
+#[derive(Debug)]
+pub struct ApiResponse<T> {
+    pub data: T,
+    pub meta: ResponseMeta,
+}
+
+/// Metadata about a response, returned by the `*_with_meta` methods.
+#[derive(Clone, Debug)]
+pub struct ResponseMeta {
+    pub status: StatusCode,
+    /// Identifier Coinbase assigned to the request, useful when contacting support.
+    pub request_id: Option<String>,
+    pub rate_limit: RateLimitStatus,
+    /// Cursor of the next page of an Exchange API listing, from the `CB-AFTER` header.
+    pub after: Option<String>,
+}
+
+/// Rate limit state reported by the server, each field is `None` when the header is missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Requests allowed in the current window.
+    pub limit: Option<u32>,
+    /// Requests left in the current window.
+    pub remaining: Option<u32>,
+    /// Unix timestamp at which the window resets.
+    pub reset: Option<u64>,
+}
+
+impl RateLimitStatus {
+    /// How long to wait before requesting the next page of a listing, so that the requests left
+    /// are spread over the rest of the window instead of running into 429s. `None` while more
+    /// than a quarter of the window's requests are left, or when the server doesn't report them.
+    pub fn page_delay(&self) -> Option<Duration> {
+        self.page_delay_at(chrono::Utc::now().timestamp())
+    }
+
+    fn page_delay_at(&self, now: i64) -> Option<Duration> {
+        let remaining = self.remaining?;
+        if self
+            .limit
+            .is_some_and(|limit| remaining >= limit / PACE_BELOW)
+        {
+            return None;
+        }
+        let window = i64::try_from(self.reset?).ok()? - now;
+        if window <= 0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            window as f64 / f64::from(remaining.saturating_add(1)),
+        ))
+    }
+}
+
+impl ResponseMeta {
+    pub(crate) fn new(status: StatusCode, headers: &HeaderMap) -> Self {
+        Self {
+            status,
+            request_id: header(headers, REQUEST_ID)
+                .or_else(|| header(headers, "x-request-id"))
+                .map(String::from),
+            rate_limit: RateLimitStatus {
+                limit: header(headers, RATE_LIMIT_LIMIT).and_then(|v| v.parse().ok()),
+                remaining: header(headers, RATE_LIMIT_REMAINING).and_then(|v| v.parse().ok()),
+                reset: header(headers, RATE_LIMIT_RESET).and_then(|v| v.parse().ok()),
+            },
+            after: header(headers, AFTER).map(String::from),
+        }
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(str::trim)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_response_meta() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID, "2f7a9f5c".parse().unwrap());
+        headers.insert(RATE_LIMIT_LIMIT, "10000".parse().unwrap());
+        headers.insert(RATE_LIMIT_REMAINING, "9998".parse().unwrap());
+        headers.insert(RATE_LIMIT_RESET, "bogus".parse().unwrap());
+
+        let meta = ResponseMeta::new(StatusCode::OK, &headers);
+        assert_eq!(meta.request_id.as_deref(), Some("2f7a9f5c"));
+        assert_eq!(
+            meta.rate_limit,
+            RateLimitStatus {
+                limit: Some(10000),
+                remaining: Some(9998),
+                reset: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_page_delay() {
+        let status = |remaining, reset| RateLimitStatus {
+            limit: Some(100),
+            remaining: Some(remaining),
+            reset: Some(reset),
+        };
+        assert_eq!(status(50, 1060).page_delay_at(1000), None);
+        assert_eq!(
+            status(9, 1060).page_delay_at(1000),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(
+            status(0, 1060).page_delay_at(1000),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(status(0, 990).page_delay_at(1000), None);
+        assert_eq!(RateLimitStatus::default().page_delay_at(1000), None);
+    }
+}
@@ -0,0 +1,433 @@
+//! Retrying failed requests.
+//!
+//! A client asks its [`RetryPolicy`] after every failed attempt whether and when to try again.
+//! [`Exponential`] backoff is the default, [`DecorrelatedJitter`] spreads retries of many
+//! clients further apart and [`NoRetry`] returns every error right away. Other strategies, e.g.
+//! one giving up once a latency budget is spent, implement the trait themselves.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use coinbase_rs::retry::{Attempt, RetryPolicy};
+//! use coinbase_rs::{CBError, Client, MAIN_URL};
+//!
+//! /// Retries timeouts once, immediately.
+//! struct RetryTimeoutOnce;
+//!
+//! impl RetryPolicy for RetryTimeoutOnce {
+//!     fn decide(&self, attempt: &Attempt, error: &CBError) -> Option<Duration> {
+//!         let timeout = matches!(error, CBError::Timeout);
+//!         (timeout && attempt.number() == 1).then_some(Duration::ZERO)
+//!     }
+//! }
+//!
+//! let client = Client::new(MAIN_URL).with_retry_policy(RetryTimeoutOnce);
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{Method, StatusCode};
+use rand::Rng;
+
+use crate::transport::TransportErrorKind;
+use crate::CBError;
+
+/// Decides whether a failed request is sent again.
+pub trait RetryPolicy: Send + Sync {
+    /// Returns how long to wait before retrying the request that failed with `error`, or `None`
+    /// if the error should be returned to the caller.
+    fn decide(&self, attempt: &Attempt, error: &CBError) -> Option<Duration>;
+}
+
+impl<P: RetryPolicy + ?Sized> RetryPolicy for Arc<P> {
+    fn decide(&self, attempt: &Attempt, error: &CBError) -> Option<Duration> {
+        (**self).decide(attempt, error)
+    }
+}
+
+/// A failed attempt at sending a request.
+#[derive(Clone, Debug)]
+pub struct Attempt<'a> {
+    number: u32,
+    method: &'a Method,
+    previous_delay: Option<Duration>,
+    idempotency_key: bool,
+}
+
+impl<'a> Attempt<'a> {
+    pub(crate) fn new(number: u32, method: &'a Method, previous_delay: Option<Duration>) -> Self {
+        Self {
+            number,
+            method,
+            previous_delay,
+            idempotency_key: false,
+        }
+    }
+
+    pub(crate) fn with_idempotency_key(self, idempotency_key: bool) -> Self {
+        let mut _self = self;
+        _self.idempotency_key = idempotency_key;
+        _self
+    }
+
+    /// Number of the attempt, starting at 1.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// HTTP method of the request.
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
+    /// Delay before this attempt, `None` for the first one.
+    pub fn previous_delay(&self) -> Option<Duration> {
+        self.previous_delay
+    }
+
+    /// Whether the request carries an idempotency key, e.g. a send, so Coinbase executes it
+    /// once however often it is sent.
+    pub fn has_idempotency_key(&self) -> bool {
+        self.idempotency_key
+    }
+}
+
+/// Never retries.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+    fn decide(&self, _attempt: &Attempt, _error: &CBError) -> Option<Duration> {
+        None
+    }
+}
+
+/// Which failures may be retried, shared by the built-in backoff policies. Requests with other
+/// methods are retried too if they carry an idempotency key.
+#[derive(Clone, Debug)]
+struct Conditions {
+    max_attempts: u32,
+    methods: Vec<Method>,
+    statuses: Vec<StatusCode>,
+}
+
+impl Default for Conditions {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            methods: vec![
+                Method::GET,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PUT,
+                Method::DELETE,
+            ],
+            statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+}
+
+impl Conditions {
+    /// Waits `backoff` before retrying a transient error, or the `Retry-After` delay of a 429
+    /// response if it has one.
+    fn decide(
+        &self,
+        attempt: &Attempt,
+        error: &CBError,
+        backoff: impl FnOnce() -> Duration,
+    ) -> Option<Duration> {
+        let resendable = self.methods.contains(attempt.method) || attempt.idempotency_key;
+        if attempt.number >= self.max_attempts || !resendable {
+            return None;
+        }
+        match error {
+            CBError::RateLimited {
+                retry_after: Some(retry_after),
+            } if self.statuses.contains(&StatusCode::TOO_MANY_REQUESTS) => Some(*retry_after),
+            _ if self.is_transient(error) => Some(backoff()),
+            _ => None,
+        }
+    }
+
+    fn is_transient(&self, error: &CBError) -> bool {
+        match error {
+            CBError::Http(e) => matches!(
+                e.kind(),
+                TransportErrorKind::Connect
+                    | TransportErrorKind::Timeout
+                    | TransportErrorKind::Incomplete
+            ),
+            CBError::Timeout => true,
+            CBError::RateLimited { .. } => self.statuses.contains(&StatusCode::TOO_MANY_REQUESTS),
+            CBError::Coinbase { status, .. } | CBError::Status { status, .. } => {
+                self.statuses.contains(status)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Retries with exponential backoff, the default policy.
+///
+/// The n-th retry waits `base_delay * 2^(n-1)`, capped at `max_delay` and reduced by a random
+/// amount of up to `jitter` times the delay. A `Retry-After` delay sent with a 429 response
+/// takes precedence over the computed backoff.
+#[derive(Clone, Debug)]
+pub struct Exponential {
+    conditions: Conditions,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+}
+
+impl Default for Exponential {
+    fn default() -> Self {
+        Self {
+            conditions: Conditions::default(),
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(10),
+            jitter: 0.5,
+        }
+    }
+}
+
+impl Exponential {
+    /// Total number of attempts, including the first one.
+    pub fn max_attempts(self, max_attempts: u32) -> Self {
+        let mut _self = self;
+        _self.conditions.max_attempts = max_attempts.max(1);
+        _self
+    }
+
+    /// Delay before the first retry, doubled for every following one.
+    pub fn base_delay(self, base_delay: Duration) -> Self {
+        let mut _self = self;
+        _self.base_delay = base_delay;
+        _self
+    }
+
+    /// Upper bound for the computed backoff.
+    pub fn max_delay(self, max_delay: Duration) -> Self {
+        let mut _self = self;
+        _self.max_delay = max_delay;
+        _self
+    }
+
+    /// Fraction of the delay, between 0 and 1, that is randomly subtracted from it.
+    pub fn jitter(self, jitter: f64) -> Self {
+        let mut _self = self;
+        _self.jitter = jitter.clamp(0.0, 1.0);
+        _self
+    }
+
+    /// HTTP methods that are safe to retry.
+    pub fn methods(self, methods: &[Method]) -> Self {
+        let mut _self = self;
+        _self.conditions.methods = methods.to_vec();
+        _self
+    }
+
+    /// Response statuses that are considered transient.
+    pub fn statuses(self, statuses: &[StatusCode]) -> Self {
+        let mut _self = self;
+        _self.conditions.statuses = statuses.to_vec();
+        _self
+    }
+}
+
+impl RetryPolicy for Exponential {
+    fn decide(&self, attempt: &Attempt, error: &CBError) -> Option<Duration> {
+        self.conditions.decide(attempt, error, || {
+            let exponent = (attempt.number - 1).min(31);
+            let delay = self
+                .base_delay
+                .saturating_mul(1 << exponent)
+                .min(self.max_delay);
+            let jitter = rand::thread_rng().gen_range(0.0..=self.jitter);
+            delay.mul_f64(1.0 - jitter)
+        })
+    }
+}
+
+/// Retries with "decorrelated jitter" backoff.
+///
+/// Every retry waits a random delay between `base_delay` and three times the previous delay,
+/// capped at `max_delay`. Delays grow about as fast as with [`Exponential`] backoff, but clients
+/// failing at the same moment don't keep retrying in lockstep. A `Retry-After` delay sent with a
+/// 429 response takes precedence.
+#[derive(Clone, Debug)]
+pub struct DecorrelatedJitter {
+    conditions: Conditions,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl Default for DecorrelatedJitter {
+    fn default() -> Self {
+        Self {
+            conditions: Conditions::default(),
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl DecorrelatedJitter {
+    /// Total number of attempts, including the first one.
+    pub fn max_attempts(self, max_attempts: u32) -> Self {
+        let mut _self = self;
+        _self.conditions.max_attempts = max_attempts.max(1);
+        _self
+    }
+
+    /// Shortest delay between attempts.
+    pub fn base_delay(self, base_delay: Duration) -> Self {
+        let mut _self = self;
+        _self.base_delay = base_delay;
+        _self
+    }
+
+    /// Upper bound for the computed backoff.
+    pub fn max_delay(self, max_delay: Duration) -> Self {
+        let mut _self = self;
+        _self.max_delay = max_delay;
+        _self
+    }
+
+    /// HTTP methods that are safe to retry.
+    pub fn methods(self, methods: &[Method]) -> Self {
+        let mut _self = self;
+        _self.conditions.methods = methods.to_vec();
+        _self
+    }
+
+    /// Response statuses that are considered transient.
+    pub fn statuses(self, statuses: &[StatusCode]) -> Self {
+        let mut _self = self;
+        _self.conditions.statuses = statuses.to_vec();
+        _self
+    }
+}
+
+impl RetryPolicy for DecorrelatedJitter {
+    fn decide(&self, attempt: &Attempt, error: &CBError) -> Option<Duration> {
+        self.conditions.decide(attempt, error, || {
+            let previous = attempt.previous_delay.unwrap_or(self.base_delay);
+            let upper = previous.saturating_mul(3).max(self.base_delay);
+            let delay = rand::thread_rng().gen_range(self.base_delay..=upper);
+            delay.min(self.max_delay)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unavailable() -> CBError {
+        CBError::Status {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            body: String::new(),
+        }
+    }
+
+    fn attempt(number: u32) -> Attempt<'static> {
+        Attempt::new(number, &Method::GET, None)
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = Exponential::default()
+            .max_attempts(5)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(300))
+            .jitter(0.0);
+        let error = unavailable();
+        let delays: Vec<_> = (1..=5)
+            .map(|number| policy.decide(&attempt(number), &error))
+            .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(300)),
+                Some(Duration::from_millis(300)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        let policy = Exponential::default()
+            .base_delay(Duration::from_millis(100))
+            .jitter(1.0);
+        for _ in 0..100 {
+            let delay = policy.decide(&attempt(1), &unavailable()).unwrap();
+            assert!(delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_decorrelated_jitter() {
+        let policy = DecorrelatedJitter::default()
+            .max_attempts(10)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_secs(1));
+        for _ in 0..100 {
+            let first = policy.decide(&attempt(1), &unavailable()).unwrap();
+            assert!(first >= Duration::from_millis(100) && first <= Duration::from_millis(300));
+
+            let previous = Attempt::new(2, &Method::GET, Some(Duration::from_millis(200)));
+            let second = policy.decide(&previous, &unavailable()).unwrap();
+            assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(600));
+
+            let previous = Attempt::new(3, &Method::GET, Some(Duration::from_millis(900)));
+            let third = policy.decide(&previous, &unavailable()).unwrap();
+            assert!(third <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn test_non_retryable() {
+        let policy = Exponential::default();
+        let post = Attempt::new(1, &Method::POST, None);
+        assert_eq!(policy.decide(&post, &unavailable()), None);
+        let send = post.clone().with_idempotency_key(true);
+        assert!(policy.decide(&send, &unavailable()).is_some());
+
+        let not_found = CBError::Status {
+            status: StatusCode::NOT_FOUND,
+            body: String::new(),
+        };
+        assert_eq!(policy.decide(&attempt(1), &not_found), None);
+        assert_eq!(NoRetry.decide(&attempt(1), &unavailable()), None);
+        assert_eq!(
+            DecorrelatedJitter::default().decide(&post, &unavailable()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_retry_after_takes_precedence() {
+        let error = CBError::RateLimited {
+            retry_after: Some(Duration::from_secs(7)),
+        };
+        assert_eq!(
+            Exponential::default().decide(&attempt(1), &error),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            DecorrelatedJitter::default().decide(&attempt(1), &error),
+            Some(Duration::from_secs(7))
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Timers of the async runtime: tokio's with the `tokio` feature, so that they follow its
+//! paused clock in tests, and otherwise ones driven by a timer thread that work on any runtime,
+//! or by the browser's `setTimeout` on `wasm32`.
+
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(all(not(feature = "tokio"), not(target_arch = "wasm32")))]
+pub(crate) use std::time::Instant;
+#[cfg(feature = "tokio")]
+pub(crate) use tokio::time::Instant;
+#[cfg(all(not(feature = "tokio"), target_arch = "wasm32"))]
+pub(crate) use web_time::Instant;
+
+#[cfg(feature = "tokio")]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(not(feature = "tokio"))]
+pub(crate) async fn sleep(duration: Duration) {
+    futures_timer::Delay::new(duration).await
+}
+
+/// Runs `future` to completion, returning `None` if it takes longer than `duration`.
+#[cfg(feature = "tokio")]
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    tokio::time::timeout(duration, future).await.ok()
+}
+
+/// Runs `future` to completion, returning `None` if it takes longer than `duration`.
+#[cfg(not(feature = "tokio"))]
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    use futures::future::{self, Either};
+
+    futures::pin_mut!(future);
+    match future::select(future, futures_timer::Delay::new(duration)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
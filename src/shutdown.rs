@@ -0,0 +1,198 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_stream::stream;
+use futures::channel::oneshot;
+use futures::future::{self, Either, FutureExt, Shared};
+use futures::stream::{Stream, StreamExt};
+
+use crate::{CBError, Result};
+
+/// Asks the streams of the clients given it to wind down, e.g. when a service is terminated.
+///
+/// Once [triggered](Self::trigger), a paginated stream finishes the request in flight, yields
+/// its page and ends instead of fetching the next one, and a polling stream yields what its
+/// current poll found and ends instead of sleeping until the next one. Nothing is cut off
+/// mid-page, and dropping the stream after it ended loses no data. Clones share the signal.
+///
+/// ```no_run
+/// # async fn run() -> coinbase_rs::Result<()> {
+/// use coinbase_rs::{Client, Shutdown, MAIN_URL};
+/// use futures::stream::StreamExt;
+///
+/// let shutdown = Shutdown::new();
+/// let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>").with_shutdown(&shutdown);
+///
+/// let signal = shutdown.clone();
+/// tokio::spawn(async move {
+///     tokio::signal::ctrl_c().await.unwrap();
+///     signal.trigger();
+/// });
+///
+/// let pages = client.accounts();
+/// futures::pin_mut!(pages);
+/// while let Some(page) = pages.next().await {
+///     println!("{} accounts", page?.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    /// Dropped to trigger the shutdown.
+    sender: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+    receiver: Shared<oneshot::Receiver<()>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        let (sender, receiver) = oneshot::channel();
+        Self {
+            sender: Arc::new(Mutex::new(Some(sender))),
+            receiver: receiver.shared(),
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signals every stream watching this shutdown, or a clone of it, to end.
+    pub fn trigger(&self) {
+        self.sender.lock().unwrap().take();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.sender.lock().unwrap().is_none()
+    }
+
+    /// Completes once the shutdown is triggered.
+    pub async fn triggered(&self) {
+        let _ = self.receiver.clone().await;
+    }
+
+    /// Sleeps for `duration` unless triggered first. Returns whether the sleep completed.
+    pub(crate) async fn sleep(&self, duration: Duration) -> bool {
+        if self.is_triggered() {
+            return false;
+        }
+        let sleep = crate::rt::sleep(duration);
+        let triggered = self.triggered();
+        futures::pin_mut!(sleep, triggered);
+        matches!(future::select(sleep, triggered).await, Either::Left(_))
+    }
+}
+
+/// Aborts a stream of results, e.g. a listing, that takes too long or is no longer wanted,
+/// unlike a [`Shutdown`] also in the middle of a request. The stream then yields a last
+/// [`CBError::Cancelled`] or [`CBError::Timeout`] and ends, instead of leaving the task
+/// waiting for a stuck page.
+///
+/// ```no_run
+/// # async fn run() -> coinbase_rs::Result<()> {
+/// use std::time::Duration;
+///
+/// use coinbase_rs::{CancellableStream, Client, Shutdown, MAIN_URL};
+/// use futures::stream::TryStreamExt;
+///
+/// let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+/// let cancel = Shutdown::new();
+/// let accounts: Vec<_> = client
+///     .accounts()
+///     .deadline(Duration::from_secs(30))
+///     .cancel_on(cancel.triggered())
+///     .try_concat()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait CancellableStream<T>: Stream<Item = Result<T>> + Sized {
+    /// Ends the stream with [`CBError::Cancelled`] once `signal` completes, dropping the
+    /// request in flight. A [`Shutdown`] can serve as the signal through
+    /// [`triggered`](Shutdown::triggered), and so can e.g. a tokio `CancellationToken` through
+    /// its `cancelled_owned`.
+    fn cancel_on<F: Future>(self, signal: F) -> impl Stream<Item = Result<T>> {
+        until(self, signal, || CBError::Cancelled)
+    }
+
+    /// Ends the stream with [`CBError::Timeout`] once `timeout` has passed since it was first
+    /// polled, dropping the request in flight.
+    fn deadline(self, timeout: Duration) -> impl Stream<Item = Result<T>> {
+        until(self, crate::rt::sleep(timeout), || CBError::Timeout)
+    }
+}
+
+impl<T, S: Stream<Item = Result<T>>> CancellableStream<T> for S {}
+
+fn until<T, S, F>(inner: S, signal: F, error: fn() -> CBError) -> impl Stream<Item = Result<T>>
+where
+    S: Stream<Item = Result<T>>,
+    F: Future,
+{
+    stream! {
+        futures::pin_mut!(inner, signal);
+        loop {
+            match future::select(inner.next(), signal.as_mut()).await {
+                Either::Left((Some(item), _)) => yield item,
+                Either::Left((None, _)) => break,
+                Either::Right(_) => {
+                    yield Err(error());
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown() {
+        let shutdown = Shutdown::new();
+        assert!(!shutdown.is_triggered());
+        assert!(shutdown.sleep(Duration::from_secs(1)).await);
+
+        let signal = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            signal.trigger();
+        });
+        let started = tokio::time::Instant::now();
+        assert!(!shutdown.sleep(Duration::from_secs(60)).await);
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+        assert!(shutdown.is_triggered());
+        shutdown.triggered().await;
+        assert!(!shutdown.sleep(Duration::from_secs(1)).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancellable_stream() {
+        fn assert_send<T: Send>(_: &T) {}
+
+        let stuck = futures::stream::iter([Ok(1)]).chain(futures::stream::pending());
+        let started = tokio::time::Instant::now();
+        let items: Vec<Result<u32>> = stuck.deadline(Duration::from_secs(5)).collect().await;
+        assert!(matches!(items[..], [Ok(1), Err(CBError::Timeout)]));
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+
+        let cancel = Shutdown::new();
+        let signal = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            signal.trigger();
+        });
+        let stuck = futures::stream::pending::<Result<u32>>().cancel_on(cancel.triggered());
+        assert_send(&stuck);
+        let items: Vec<_> = stuck.collect().await;
+        assert!(matches!(items[..], [Err(CBError::Cancelled)]));
+
+        let done = futures::stream::iter([Ok(1), Ok(2)]).deadline(Duration::from_secs(5));
+        let items: Vec<Result<u32>> = done.collect().await;
+        assert_eq!(items.len(), 2);
+    }
+}
@@ -0,0 +1,346 @@
+//! Ready-made [`Sink`]s storing synced data in SQLite, behind the `sqlite` feature, or
+//! Postgres, behind the `postgres` feature.
+//!
+//! Both use the same schema, created by `create_tables` if it doesn't exist yet. Amounts are
+//! stored as text to keep their precision and times as RFC 3339 text:
+//!
+//! ```sql
+//! CREATE TABLE coinbase_accounts (
+//!     id TEXT PRIMARY KEY,
+//!     name TEXT NOT NULL,
+//!     type TEXT NOT NULL,
+//!     currency TEXT NOT NULL,
+//!     balance TEXT NOT NULL,
+//!     created_at TEXT,
+//!     updated_at TEXT
+//! );
+//! CREATE TABLE coinbase_transactions (
+//!     id TEXT PRIMARY KEY,
+//!     account_id TEXT NOT NULL,
+//!     type TEXT NOT NULL,
+//!     status TEXT NOT NULL,
+//!     amount TEXT NOT NULL,
+//!     currency TEXT NOT NULL,
+//!     native_amount TEXT NOT NULL,
+//!     native_currency TEXT NOT NULL,
+//!     description TEXT NOT NULL,
+//!     created_at TEXT,
+//!     updated_at TEXT
+//! );
+//! CREATE TABLE coinbase_sync_cursors (
+//!     account_id TEXT PRIMARY KEY,
+//!     cursor TEXT NOT NULL
+//! );
+//! ```
+//!
+//! ```no_run
+//! # #[cfg(feature = "sqlite")]
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::sql::SqliteSink;
+//! use coinbase_rs::{Client, MAIN_URL};
+//! use sqlx::SqlitePool;
+//!
+//! let pool = SqlitePool::connect("sqlite://coinbase.db?mode=rwc").await.unwrap();
+//! let sink = SqliteSink::new(pool);
+//! sink.create_tables().await?;
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! client.sync(&sink).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::future::BoxFuture;
+
+use crate::private::{Account, Transaction};
+use crate::sync::Sink;
+use crate::{CBError, DateTime, Result};
+
+const SCHEMA: [&str; 3] = [
+    "CREATE TABLE IF NOT EXISTS coinbase_accounts (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        type TEXT NOT NULL,
+        currency TEXT NOT NULL,
+        balance TEXT NOT NULL,
+        created_at TEXT,
+        updated_at TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS coinbase_transactions (
+        id TEXT PRIMARY KEY,
+        account_id TEXT NOT NULL,
+        type TEXT NOT NULL,
+        status TEXT NOT NULL,
+        amount TEXT NOT NULL,
+        currency TEXT NOT NULL,
+        native_amount TEXT NOT NULL,
+        native_currency TEXT NOT NULL,
+        description TEXT NOT NULL,
+        created_at TEXT,
+        updated_at TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS coinbase_sync_cursors (
+        account_id TEXT PRIMARY KEY,
+        cursor TEXT NOT NULL
+    )",
+];
+
+const UPSERT_ACCOUNT: &str = "INSERT INTO coinbase_accounts
+        (id, name, type, currency, balance, created_at, updated_at)
+    VALUES (?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT (id) DO UPDATE SET
+        name = excluded.name,
+        type = excluded.type,
+        currency = excluded.currency,
+        balance = excluded.balance,
+        created_at = excluded.created_at,
+        updated_at = excluded.updated_at";
+
+const UPSERT_TRANSACTION: &str = "INSERT INTO coinbase_transactions
+        (id, account_id, type, status, amount, currency, native_amount, native_currency,
+         description, created_at, updated_at)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT (id) DO UPDATE SET
+        account_id = excluded.account_id,
+        type = excluded.type,
+        status = excluded.status,
+        amount = excluded.amount,
+        currency = excluded.currency,
+        native_amount = excluded.native_amount,
+        native_currency = excluded.native_currency,
+        description = excluded.description,
+        created_at = excluded.created_at,
+        updated_at = excluded.updated_at";
+
+const LOAD_CURSOR: &str = "SELECT cursor FROM coinbase_sync_cursors WHERE account_id = ?";
+
+const SAVE_CURSOR: &str = "INSERT INTO coinbase_sync_cursors (account_id, cursor) VALUES (?, ?)
+    ON CONFLICT (account_id) DO UPDATE SET cursor = excluded.cursor";
+
+fn storage(error: sqlx::Error) -> CBError {
+    CBError::Storage(Box::new(error))
+}
+
+fn time(time: Option<DateTime>) -> Option<String> {
+    time.map(|time| time.to_rfc3339())
+}
+
+/// Replaces the `?` placeholders of `sql` with the numbered ones of Postgres.
+#[cfg(feature = "postgres")]
+fn numbered(sql: &str) -> String {
+    let mut n = 0;
+    sql.chars()
+        .map(|c| match c {
+            '?' => {
+                n += 1;
+                format!("${n}")
+            }
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+macro_rules! sql_sink {
+    ($(#[$meta:meta])* $name:ident, $pool:ty, $sql:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+
+            /// Creates the tables of the [module documentation](self) unless they exist.
+            pub async fn create_tables(&self) -> Result<()> {
+                for statement in SCHEMA {
+                    sqlx::query(statement)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(storage)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl Sink for $name {
+            fn upsert_accounts<'a>(&'a self, accounts: &'a [Account]) -> BoxFuture<'a, Result<()>> {
+                Box::pin(async move {
+                    let sql = $sql(UPSERT_ACCOUNT);
+                    let mut tx = self.pool.begin().await.map_err(storage)?;
+                    for account in accounts {
+                        sqlx::query(&sql)
+                            .bind(&account.id)
+                            .bind(&account.name)
+                            .bind(&account.r#type)
+                            .bind(&account.currency.code)
+                            .bind(account.balance.amount.to_string())
+                            .bind(time(account.created_at))
+                            .bind(time(account.updated_at))
+                            .execute(&mut *tx)
+                            .await
+                            .map_err(storage)?;
+                    }
+                    tx.commit().await.map_err(storage)
+                })
+            }
+
+            fn upsert_transactions<'a>(
+                &'a self,
+                account_id: &'a str,
+                transactions: &'a [Transaction],
+            ) -> BoxFuture<'a, Result<()>> {
+                Box::pin(async move {
+                    let sql = $sql(UPSERT_TRANSACTION);
+                    let mut tx = self.pool.begin().await.map_err(storage)?;
+                    for transaction in transactions {
+                        sqlx::query(&sql)
+                            .bind(transaction.id.to_string())
+                            .bind(account_id)
+                            .bind(&transaction.r#type)
+                            .bind(&transaction.status)
+                            .bind(transaction.amount.amount.to_string())
+                            .bind(&transaction.amount.currency)
+                            .bind(transaction.native_amount.amount.to_string())
+                            .bind(&transaction.native_amount.currency)
+                            .bind(&transaction.details.title)
+                            .bind(time(transaction.created_at))
+                            .bind(time(transaction.updated_at))
+                            .execute(&mut *tx)
+                            .await
+                            .map_err(storage)?;
+                    }
+                    tx.commit().await.map_err(storage)
+                })
+            }
+
+            fn load_cursor<'a>(
+                &'a self,
+                account_id: &'a str,
+            ) -> BoxFuture<'a, Result<Option<String>>> {
+                Box::pin(async move {
+                    let sql = $sql(LOAD_CURSOR);
+                    sqlx::query_scalar(&sql)
+                        .bind(account_id)
+                        .fetch_optional(&self.pool)
+                        .await
+                        .map_err(storage)
+                })
+            }
+
+            fn save_cursor<'a>(
+                &'a self,
+                account_id: &'a str,
+                cursor: &'a str,
+            ) -> BoxFuture<'a, Result<()>> {
+                Box::pin(async move {
+                    let sql = $sql(SAVE_CURSOR);
+                    sqlx::query(&sql)
+                        .bind(account_id)
+                        .bind(cursor)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(storage)?;
+                    Ok(())
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "sqlite")]
+sql_sink!(
+    /// Stores synced data in a SQLite database.
+    SqliteSink,
+    sqlx::SqlitePool,
+    |sql: &'static str| sql
+);
+
+#[cfg(feature = "postgres")]
+sql_sink!(
+    /// Stores synced data in a Postgres database.
+    PostgresSink,
+    sqlx::PgPool,
+    numbered
+);
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn test_numbered() {
+        assert_eq!(
+            super::numbered("SELECT a FROM t WHERE b = ? AND c = ?"),
+            "SELECT a FROM t WHERE b = $1 AND c = $2"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_sink() {
+        use sqlx::sqlite::SqlitePoolOptions;
+
+        use super::*;
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let sink = SqliteSink::new(pool.clone());
+        sink.create_tables().await.unwrap();
+        sink.create_tables().await.unwrap();
+
+        let account: Account = serde_json::from_str(
+            r#"{"id":"af6fd33a-e20c-494a-b3f6-f91d204af4b7","type":"wallet",
+                "created_at":null,"updated_at":null,"resource":"account","resource_path":"/",
+                "name":"BTC Wallet","primary":true,
+                "currency":{"code":"BTC","name":"Bitcoin","exponent":8,"type":"crypto"},
+                "balance":{"amount":"0.12345678","currency":"BTC"},
+                "allow_deposits":true,"allow_withdrawals":true}"#,
+        )
+        .unwrap();
+        let transaction = |status: &str| -> Transaction {
+            serde_json::from_str(&format!(
+                r#"{{"id":"9dd482e4-d8ce-46f7-a261-281843bd2855","type":"buy",
+                    "status":"{status}","amount":{{"amount":"0.1","currency":"BTC"}},
+                    "native_amount":{{"amount":"3000.00","currency":"USD"}},
+                    "created_at":"2024-01-01T00:00:00Z","updated_at":null,
+                    "resource":"transaction","resource_path":"/",
+                    "details":{{"title":"Bought Bitcoin","subtitle":""}}}}"#
+            ))
+            .unwrap()
+        };
+
+        let accounts = [account];
+        sink.upsert_accounts(&accounts).await.unwrap();
+        sink.upsert_accounts(&accounts).await.unwrap();
+        let account = &accounts[0];
+        sink.upsert_transactions(&account.id, &[transaction("pending")])
+            .await
+            .unwrap();
+        sink.upsert_transactions(&account.id, &[transaction("completed")])
+            .await
+            .unwrap();
+        assert_eq!(sink.load_cursor(&account.id).await.unwrap(), None);
+        sink.save_cursor(&account.id, "a").await.unwrap();
+        sink.save_cursor(&account.id, "b").await.unwrap();
+        assert_eq!(
+            sink.load_cursor(&account.id).await.unwrap(),
+            Some("b".to_string())
+        );
+
+        let balance: String = sqlx::query_scalar("SELECT balance FROM coinbase_accounts")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(balance, "0.12345678");
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT status, native_amount FROM coinbase_transactions")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(rows, [("completed".to_string(), "3000.00".to_string())]);
+    }
+}
@@ -0,0 +1,334 @@
+//! Incremental sync of accounts and transactions into a store of the user's choice.
+//!
+//! The store implements [`Sink`]. [`Client::sync`] then upserts the accounts and, for every
+//! account, the transactions created after the cursor the sink saved last time, saving the new
+//! cursor after each page so an interrupted sync resumes where it stopped.
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use std::collections::HashMap;
+//! use std::sync::Mutex;
+//!
+//! use coinbase_rs::private::{Account, Transaction};
+//! use coinbase_rs::sync::Sink;
+//! use coinbase_rs::{Client, MAIN_URL};
+//! use futures::future::{ready, BoxFuture};
+//!
+//! #[derive(Default)]
+//! struct Store {
+//!     cursors: Mutex<HashMap<String, String>>,
+//! }
+//!
+//! impl Sink for Store {
+//!     fn upsert_accounts<'a>(
+//!         &'a self,
+//!         accounts: &'a [Account],
+//!     ) -> BoxFuture<'a, coinbase_rs::Result<()>> {
+//!         println!("{} accounts", accounts.len());
+//!         Box::pin(ready(Ok(())))
+//!     }
+//!
+//!     fn upsert_transactions<'a>(
+//!         &'a self,
+//!         account_id: &'a str,
+//!         transactions: &'a [Transaction],
+//!     ) -> BoxFuture<'a, coinbase_rs::Result<()>> {
+//!         println!("{} new transactions in {account_id}", transactions.len());
+//!         Box::pin(ready(Ok(())))
+//!     }
+//!
+//!     fn load_cursor<'a>(
+//!         &'a self,
+//!         account_id: &'a str,
+//!     ) -> BoxFuture<'a, coinbase_rs::Result<Option<String>>> {
+//!         let cursor = self.cursors.lock().unwrap().get(account_id).cloned();
+//!         Box::pin(ready(Ok(cursor)))
+//!     }
+//!
+//!     fn save_cursor<'a>(
+//!         &'a self,
+//!         account_id: &'a str,
+//!         cursor: &'a str,
+//!     ) -> BoxFuture<'a, coinbase_rs::Result<()>> {
+//!         let mut cursors = self.cursors.lock().unwrap();
+//!         cursors.insert(account_id.to_string(), cursor.to_string());
+//!         Box::pin(ready(Ok(())))
+//!     }
+//! }
+//!
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! let report = client.sync(&Store::default()).await?;
+//! println!("synced {} transactions", report.transactions);
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::future::BoxFuture;
+use futures::stream::{StreamExt, TryStreamExt};
+use uuid::Uuid;
+
+use crate::private::{Account, ListParams, Order, Transaction};
+use crate::{Client, Result};
+
+/// Where synced data is stored. Errors of the store can be wrapped in
+/// [`CBError::Storage`](crate::CBError::Storage).
+pub trait Sink {
+    /// Inserts or updates `accounts`, by their id.
+    fn upsert_accounts<'a>(&'a self, accounts: &'a [Account]) -> BoxFuture<'a, Result<()>>;
+
+    /// Inserts or updates transactions of an account, by their id, oldest first.
+    fn upsert_transactions<'a>(
+        &'a self,
+        account_id: &'a str,
+        transactions: &'a [Transaction],
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// The cursor last saved for an account, `None` if it was never synced.
+    fn load_cursor<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<Option<String>>>;
+
+    /// Saves the cursor of an account, the id of the newest transaction upserted before the
+    /// oldest one that isn't final yet, e.g. pending.
+    fn save_cursor<'a>(&'a self, account_id: &'a str, cursor: &'a str)
+        -> BoxFuture<'a, Result<()>>;
+}
+
+/// What a [`sync`](Client::sync) stored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncReport {
+    pub accounts: usize,
+    /// Transactions upserted over all accounts, new ones and ones that weren't final yet when
+    /// last synced.
+    pub transactions: usize,
+}
+
+impl Client {
+    /// Pulls the accounts and the transactions created since the last sync into `sink`.
+    /// Accounts without a UUID, which have no transactions listing, are only upserted.
+    ///
+    /// The cursor stops before the oldest transaction that isn't final, so it and the ones
+    /// after it are fetched and upserted again by every sync until it completes, fails or is
+    /// canceled, and the sink ends up with its final status.
+    pub async fn sync<S: Sink>(&self, sink: &S) -> Result<SyncReport> {
+        let accounts: Vec<Account> = self.accounts().try_concat().await?;
+        sink.upsert_accounts(&accounts).await?;
+        let mut report = SyncReport {
+            accounts: accounts.len(),
+            transactions: 0,
+        };
+        for account in &accounts {
+            let Ok(id) = account.id.parse::<Uuid>() else {
+                continue;
+            };
+            let mut params = ListParams::new().order(Order::Ascending);
+            if let Some(cursor) = sink.load_cursor(&account.id).await? {
+                params = params.starting_after(&cursor);
+            }
+            let pages = self.transactions_with_params(&id, &params);
+            futures::pin_mut!(pages);
+            // Whether every transaction so far is final, so the cursor may move past it.
+            let mut settled = true;
+            while let Some(page) = pages.next().await {
+                let transactions = page?;
+                if transactions.is_empty() {
+                    continue;
+                }
+                sink.upsert_transactions(&account.id, &transactions).await?;
+                report.transactions += transactions.len();
+                if !settled {
+                    continue;
+                }
+                let last_final = transactions.iter().take_while(|t| is_final(t)).last();
+                if let Some(last) = last_final {
+                    sink.save_cursor(&account.id, &last.id.to_string()).await?;
+                }
+                settled = transactions.iter().all(is_final);
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Whether a transaction's status can't change anymore.
+fn is_final(transaction: &Transaction) -> bool {
+    matches!(
+        transaction.status.as_str(),
+        "completed" | "failed" | "canceled" | "cancelled" | "expired"
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use futures::future::ready;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    const ACCOUNT: &str = "af6fd33a-e20c-494a-b3f6-f91d204af4b7";
+
+    #[derive(Default)]
+    struct Store {
+        accounts: Mutex<Vec<String>>,
+        transactions: Mutex<Vec<String>>,
+        cursors: Mutex<HashMap<String, String>>,
+    }
+
+    impl Sink for Store {
+        fn upsert_accounts<'a>(&'a self, accounts: &'a [Account]) -> BoxFuture<'a, Result<()>> {
+            let mut stored = self.accounts.lock().unwrap();
+            stored.extend(accounts.iter().map(|account| account.id.clone()));
+            Box::pin(ready(Ok(())))
+        }
+
+        fn upsert_transactions<'a>(
+            &'a self,
+            _: &'a str,
+            transactions: &'a [Transaction],
+        ) -> BoxFuture<'a, Result<()>> {
+            let mut stored = self.transactions.lock().unwrap();
+            stored.extend(transactions.iter().map(|t| t.id.to_string()));
+            Box::pin(ready(Ok(())))
+        }
+
+        fn load_cursor<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<Option<String>>> {
+            Box::pin(ready(Ok(self
+                .cursors
+                .lock()
+                .unwrap()
+                .get(account_id)
+                .cloned())))
+        }
+
+        fn save_cursor<'a>(
+            &'a self,
+            account_id: &'a str,
+            cursor: &'a str,
+        ) -> BoxFuture<'a, Result<()>> {
+            let mut cursors = self.cursors.lock().unwrap();
+            cursors.insert(account_id.to_string(), cursor.to_string());
+            Box::pin(ready(Ok(())))
+        }
+    }
+
+    fn transactions(ids: &[(&str, &str)]) -> String {
+        let transactions: Vec<String> = ids
+            .iter()
+            .map(|(id, status)| {
+                format!(
+                    r#"{{"id":"{id}","type":"buy","status":"{status}",
+                        "amount":{{"amount":"1","currency":"BTC"}},
+                        "native_amount":{{"amount":"1","currency":"USD"}},
+                        "created_at":null,"updated_at":null,"resource":"transaction",
+                        "resource_path":"/","details":{{"title":"","subtitle":""}}}}"#
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"pagination":{{"limit":100,"order":"asc","next_uri":null}},"data":[{}]}}"#,
+            transactions.join(",")
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sync() {
+        let accounts = format!(
+            r#"{{"pagination":{{"limit":25,"order":"desc","next_uri":null}},"data":[
+                {{"id":"{ACCOUNT}","type":"wallet","created_at":null,"updated_at":null,
+                "resource":"account","resource_path":"/","name":"BTC Wallet","primary":true,
+                "currency":{{"code":"BTC","name":"Bitcoin","exponent":8,"type":"crypto"}},
+                "balance":{{"amount":"2","currency":"BTC"}},
+                "allow_deposits":true,"allow_withdrawals":true}},
+                {{"id":"LINK","type":"wallet","created_at":null,"updated_at":null,
+                "resource":"account","resource_path":"/","name":"LINK Wallet","primary":false,
+                "currency":{{"code":"LINK","name":"Chainlink","exponent":8,"type":"crypto"}},
+                "balance":{{"amount":"0","currency":"LINK"}},
+                "allow_deposits":true,"allow_withdrawals":true}}]}}"#
+        );
+        let first = "11111111-1111-1111-1111-111111111111";
+        let second = "22222222-2222-2222-2222-222222222222";
+        let listing = format!("/v2/accounts/{ACCOUNT}/transactions?limit=100&order=asc");
+        let mock = MockTransport::new();
+        mock.push_json("/v2/accounts", &accounts)
+            .push_json(
+                &listing,
+                &transactions(&[(first, "completed"), (second, "completed")]),
+            )
+            .push_json("/v2/accounts", &accounts)
+            .push_json(
+                &format!("{listing}&starting_after={second}"),
+                &transactions(&[]),
+            );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+        let store = Store::default();
+
+        let report = client.sync(&store).await.unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                accounts: 2,
+                transactions: 2
+            }
+        );
+        assert_eq!(*store.transactions.lock().unwrap(), [first, second]);
+        assert_eq!(store.cursors.lock().unwrap()[ACCOUNT], second);
+
+        let report = client.sync(&store).await.unwrap();
+        assert_eq!(report.transactions, 0);
+        assert_eq!(store.cursors.lock().unwrap()[ACCOUNT], second);
+        assert!(mock.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_pending() {
+        let accounts = format!(
+            r#"{{"pagination":{{"limit":25,"order":"desc","next_uri":null}},"data":[
+                {{"id":"{ACCOUNT}","type":"wallet","created_at":null,"updated_at":null,
+                "resource":"account","resource_path":"/","name":"BTC Wallet","primary":true,
+                "currency":{{"code":"BTC","name":"Bitcoin","exponent":8,"type":"crypto"}},
+                "balance":{{"amount":"2","currency":"BTC"}},
+                "allow_deposits":true,"allow_withdrawals":true}}]}}"#
+        );
+        let first = "11111111-1111-1111-1111-111111111111";
+        let second = "22222222-2222-2222-2222-222222222222";
+        let third = "33333333-3333-3333-3333-333333333333";
+        let listing = format!("/v2/accounts/{ACCOUNT}/transactions?limit=100&order=asc");
+        let mock = MockTransport::new();
+        mock.push_json("/v2/accounts", &accounts)
+            .push_json(
+                &listing,
+                &transactions(&[
+                    (first, "completed"),
+                    (second, "pending"),
+                    (third, "completed"),
+                ]),
+            )
+            .push_json("/v2/accounts", &accounts)
+            .push_json(
+                &format!("{listing}&starting_after={first}"),
+                &transactions(&[(second, "completed"), (third, "completed")]),
+            );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+        let store = Store::default();
+
+        // The cursor stays before the pending transaction.
+        client.sync(&store).await.unwrap();
+        assert_eq!(store.cursors.lock().unwrap()[ACCOUNT], first);
+
+        // Which is fetched again once it completed.
+        let report = client.sync(&store).await.unwrap();
+        assert_eq!(report.transactions, 2);
+        assert_eq!(
+            *store.transactions.lock().unwrap(),
+            [first, second, third, second, third]
+        );
+        assert_eq!(store.cursors.lock().unwrap()[ACCOUNT], third);
+        assert!(mock.is_empty());
+    }
+}
@@ -0,0 +1,506 @@
+//! Cost basis and realized gains of an account, matching every disposal to the lots it was
+//! acquired in.
+//!
+//! Transactions are classified by their type: buys, sells, conversions and fills acquire and
+//! dispose, rewards and interest acquire at their market value, and moves such as sends, vault
+//! withdrawals and transfers to the Exchange take lots out of the account without realizing a
+//! gain. Only the network fee of a send is a disposal. Exchange fills are recorded with their
+//! fees through [`CostBasis::record_fill`].
+//!
+//! ```no_run
+//! # async fn run() -> coinbase_rs::Result<()> {
+//! use coinbase_rs::tax::LotMethod;
+//! use coinbase_rs::{Client, Uuid, MAIN_URL};
+//!
+//! let client = Client::new_with_auth(MAIN_URL, "<key>", "<secret>");
+//! let account: Uuid = "af6fd33a-e20c-494a-b3f6-f91d204af4b7".parse().unwrap();
+//! let basis = client.cost_basis(&account, "USD", LotMethod::Fifo).await?;
+//! println!("realized {} USD", basis.realized_gain());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, Signed, Zero};
+use futures::stream::TryStreamExt;
+use uuid::Uuid;
+
+use crate::journal::TransactionKind;
+use crate::private::Transaction;
+use crate::{Client, DateTime, Result};
+
+/// Order in which disposals consume open lots.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LotMethod {
+    /// First in, first out: the oldest lot is disposed of first.
+    Fifo,
+    /// Last in, first out: the newest lot is disposed of first.
+    Lifo,
+    /// Highest in, first out: the lot with the highest unit cost is disposed of first.
+    Hifo,
+}
+
+/// Amount acquired at once and not disposed of yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lot {
+    pub acquired: DateTime,
+    pub amount: BigDecimal,
+    /// Fiat value per unit when acquired.
+    pub unit_cost: BigDecimal,
+}
+
+/// Part of a disposal matched against a single lot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disposal {
+    /// When the lot was acquired, `None` for amounts without a known acquisition, which have a
+    /// cost of zero.
+    pub acquired: Option<DateTime>,
+    pub disposed: DateTime,
+    pub amount: BigDecimal,
+    /// Fiat cost basis of `amount`.
+    pub cost: BigDecimal,
+    /// Fiat value of `amount` when disposed of.
+    pub proceeds: BigDecimal,
+}
+
+impl Disposal {
+    /// Realized gain, negative for a loss.
+    pub fn gain(&self) -> BigDecimal {
+        &self.proceeds - &self.cost
+    }
+}
+
+/// Open lots and disposals of a single asset.
+///
+/// Acquisitions and disposals must be recorded in chronological order.
+#[derive(Clone, Debug)]
+pub struct CostBasis {
+    method: LotMethod,
+    lots: Vec<Lot>,
+    disposals: Vec<Disposal>,
+}
+
+impl CostBasis {
+    pub fn new(method: LotMethod) -> Self {
+        Self {
+            method,
+            lots: Vec::new(),
+            disposals: Vec::new(),
+        }
+    }
+
+    /// Opens a lot of `amount` valued at `unit_price` per unit.
+    pub fn acquire(&mut self, time: DateTime, amount: BigDecimal, unit_price: BigDecimal) {
+        self.acquire_with_fee(time, amount, unit_price, BigDecimal::zero());
+    }
+
+    /// Opens a lot of `amount` bought at `unit_price` per unit, adding the fiat `fee` paid to
+    /// its cost.
+    pub fn acquire_with_fee(
+        &mut self,
+        time: DateTime,
+        amount: BigDecimal,
+        unit_price: BigDecimal,
+        fee: BigDecimal,
+    ) {
+        if amount.is_positive() {
+            let unit_cost = unit_price + fee / &amount;
+            self.lots.push(Lot {
+                acquired: time,
+                amount,
+                unit_cost,
+            });
+        }
+    }
+
+    /// Disposes of `amount` valued at `unit_price` per unit, consuming open lots in the order of
+    /// the [`LotMethod`].
+    pub fn dispose(&mut self, time: DateTime, amount: BigDecimal, unit_price: BigDecimal) {
+        self.dispose_with_fee(time, amount, unit_price, BigDecimal::zero());
+    }
+
+    /// Disposes of `amount` sold at `unit_price` per unit, deducting the fiat `fee` paid from
+    /// the proceeds.
+    pub fn dispose_with_fee(
+        &mut self,
+        time: DateTime,
+        amount: BigDecimal,
+        unit_price: BigDecimal,
+        fee: BigDecimal,
+    ) {
+        if !amount.is_positive() {
+            return;
+        }
+        let unit_price = unit_price - fee / &amount;
+        for (acquired, amount, unit_cost) in self.take(amount) {
+            self.disposals.push(Disposal {
+                acquired,
+                disposed: time,
+                cost: &amount * unit_cost,
+                proceeds: &amount * &unit_price,
+                amount,
+            });
+        }
+    }
+
+    /// Takes `amount` out of the account without realizing a gain, e.g. when it is sent to
+    /// another wallet of the user, consuming open lots in the order of the [`LotMethod`].
+    pub fn withdraw(&mut self, amount: BigDecimal) {
+        self.take(amount);
+    }
+
+    /// Records a transaction valued at `unit_price` per unit, as classified in the [module
+    /// docs](self). Transactions that haven't completed are ignored.
+    ///
+    /// Incoming sends and transfers open a lot at `unit_price`, since the cost they had where
+    /// they came from isn't known.
+    pub fn record(&mut self, transaction: &Transaction, unit_price: BigDecimal) {
+        let Some(time) = transaction.created_at else {
+            return;
+        };
+        if transaction.status != "completed" {
+            return;
+        }
+        let amount = &transaction.amount.amount;
+        if amount.is_positive() {
+            self.acquire(time, amount.clone(), unit_price);
+            return;
+        }
+        let amount = amount.abs();
+        match TransactionKind::parse(&transaction.r#type) {
+            TransactionKind::Send
+            | TransactionKind::Request
+            | TransactionKind::Transfer
+            | TransactionKind::FiatDeposit
+            | TransactionKind::FiatWithdrawal
+            | TransactionKind::ExchangeDeposit
+            | TransactionKind::ExchangeWithdrawal
+            | TransactionKind::VaultWithdrawal => {
+                // The network fee is spent, what's left of the amount arrives elsewhere.
+                let fee = transaction
+                    .network
+                    .as_ref()
+                    .and_then(|network| network.transaction_fee.as_ref())
+                    .filter(|fee| fee.currency == transaction.amount.currency)
+                    .map(|fee| fee.amount.clone())
+                    .filter(|fee| fee.is_positive() && *fee <= amount)
+                    .unwrap_or_default();
+                self.withdraw(&amount - &fee);
+                self.dispose(time, fee, unit_price);
+            }
+            _ => self.dispose(time, amount, unit_price),
+        }
+    }
+
+    /// Takes `amount` out of the open lots, returning when each part was acquired and its unit
+    /// cost. A part without open lots left has no known acquisition and costs nothing.
+    fn take(&mut self, amount: BigDecimal) -> Vec<(Option<DateTime>, BigDecimal, BigDecimal)> {
+        let mut parts = Vec::new();
+        let mut remaining = amount;
+        while remaining.is_positive() {
+            let Some(index) = self.next_lot() else {
+                parts.push((None, remaining, BigDecimal::zero()));
+                break;
+            };
+            let lot = &mut self.lots[index];
+            let amount = if lot.amount <= remaining {
+                lot.amount.clone()
+            } else {
+                remaining.clone()
+            };
+            lot.amount = &lot.amount - &amount;
+            remaining = &remaining - &amount;
+            parts.push((Some(lot.acquired), amount, lot.unit_cost.clone()));
+            if lot.amount.is_zero() {
+                self.lots.remove(index);
+            }
+        }
+        parts
+    }
+
+    /// Lots still held, in the order they were acquired.
+    pub fn lots(&self) -> &[Lot] {
+        &self.lots
+    }
+
+    pub fn disposals(&self) -> &[Disposal] {
+        &self.disposals
+    }
+
+    /// Amount still held.
+    pub fn holdings(&self) -> BigDecimal {
+        self.lots.iter().map(|lot| &lot.amount).sum()
+    }
+
+    /// Sum of the gains of all disposals, negative for a net loss.
+    pub fn realized_gain(&self) -> BigDecimal {
+        self.disposals.iter().map(Disposal::gain).sum()
+    }
+
+    fn next_lot(&self) -> Option<usize> {
+        match self.method {
+            LotMethod::Fifo => (!self.lots.is_empty()).then_some(0),
+            LotMethod::Lifo => self.lots.len().checked_sub(1),
+            // Ties go to the oldest lot: `max_by` returns the last maximum of the reversed lots.
+            LotMethod::Hifo => self
+                .lots
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by(|(_, a), (_, b)| a.unit_cost.cmp(&b.unit_cost))
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+#[cfg(feature = "exchange")]
+mod exchange {
+    use super::*;
+    use crate::exchange::{ExchangeClient, Fill, ListFillsParams};
+    use crate::order::OrderSide;
+
+    impl CostBasis {
+        /// Records a fill of an Exchange order: buys acquire and sells dispose of its size at
+        /// its price, with its fee added to the cost or deducted from the proceeds. Prices and
+        /// fees are in the quote currency of the product, which is what gains are counted in.
+        pub fn record_fill(&mut self, fill: &Fill) {
+            let (size, price, fee) = (fill.size.clone(), fill.price.clone(), fill.fee.clone());
+            match fill.side {
+                OrderSide::Buy => self.acquire_with_fee(fill.created_at, size, price, fee),
+                OrderSide::Sell => self.dispose_with_fee(fill.created_at, size, price, fee),
+            }
+        }
+    }
+
+    impl ExchangeClient {
+        ///
+        /// **Cost basis of fills**
+        ///
+        /// Replays the fills of `product`, e.g. `BTC-USD`, in chronological order and returns
+        /// the lots still held and the gains realized under `method`, in the quote currency.
+        ///
+        pub async fn cost_basis(&self, product: &str, method: LotMethod) -> Result<CostBasis> {
+            let params = ListFillsParams::product(product);
+            let mut fills: Vec<Fill> = self.fills(&params).try_concat().await?;
+            fills.sort_by_key(|fill| (fill.created_at, fill.trade_id));
+            let mut basis = CostBasis::new(method);
+            for fill in &fills {
+                basis.record_fill(fill);
+            }
+            Ok(basis)
+        }
+    }
+}
+
+impl Client {
+    ///
+    /// **Cost basis**
+    ///
+    /// Replays the completed transactions of an account in chronological order, valuing each at
+    /// the historic spot price in `fiat` on its day, and returns the lots still held and the
+    /// gains realized under `method`.
+    ///
+    pub async fn cost_basis(
+        &self,
+        account_id: &Uuid,
+        fiat: &str,
+        method: LotMethod,
+    ) -> Result<CostBasis> {
+        let mut transactions: Vec<Transaction> = self.transactions(account_id).try_concat().await?;
+        transactions.retain(|t| t.status == "completed" && t.created_at.is_some());
+        transactions.sort_by_key(|t| t.created_at);
+
+        let mut prices = HashMap::new();
+        let mut basis = CostBasis::new(method);
+        for transaction in &transactions {
+            let currency = transaction.amount.currency.as_str();
+            let date = transaction.created_at.unwrap().date_naive();
+            let price = match prices.get(&(currency, date)) {
+                Some(price) => BigDecimal::clone(price),
+                None => {
+                    let pair = format!("{currency}-{fiat}");
+                    let price = self.spot_price(&pair, Some(date)).await?.amount;
+                    prices.insert((currency, date), price.clone());
+                    price
+                }
+            };
+            basis.record(transaction, price);
+        }
+        Ok(basis)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::testing::MockTransport;
+    use crate::MAIN_URL;
+
+    fn day(day: u32) -> DateTime {
+        chrono::Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()
+    }
+
+    fn decimal(value: &str) -> BigDecimal {
+        value.parse().unwrap()
+    }
+
+    fn gains(method: LotMethod) -> (BigDecimal, BigDecimal) {
+        let mut basis = CostBasis::new(method);
+        basis.acquire(day(1), decimal("1"), decimal("100"));
+        basis.acquire(day(2), decimal("1"), decimal("300"));
+        basis.acquire(day(3), decimal("1"), decimal("200"));
+        basis.dispose(day(4), decimal("1.5"), decimal("250"));
+        (basis.realized_gain(), basis.holdings())
+    }
+
+    #[test]
+    fn test_lot_methods() {
+        assert_eq!(gains(LotMethod::Fifo), (decimal("125"), decimal("1.5")));
+        assert_eq!(gains(LotMethod::Lifo), (decimal("25"), decimal("1.5")));
+        assert_eq!(gains(LotMethod::Hifo), (decimal("-25"), decimal("1.5")));
+    }
+
+    #[test]
+    fn test_dispose_without_lots() {
+        let mut basis = CostBasis::new(LotMethod::Fifo);
+        basis.acquire(day(1), decimal("1"), decimal("100"));
+        basis.dispose(day(2), decimal("2"), decimal("150"));
+
+        let disposals = basis.disposals();
+        assert_eq!(disposals.len(), 2);
+        assert_eq!(disposals[1].acquired, None);
+        assert_eq!(disposals[1].gain(), decimal("150"));
+        assert!(basis.lots().is_empty());
+    }
+
+    #[test]
+    fn test_fees() {
+        let mut basis = CostBasis::new(LotMethod::Fifo);
+        basis.acquire_with_fee(day(1), decimal("2"), decimal("100"), decimal("4"));
+        assert_eq!(basis.lots()[0].unit_cost, decimal("102"));
+        basis.dispose_with_fee(day(2), decimal("1"), decimal("150"), decimal("3"));
+        assert_eq!(basis.disposals()[0].proceeds, decimal("147"));
+        assert_eq!(basis.realized_gain(), decimal("45"));
+    }
+
+    #[test]
+    fn test_self_transfer() {
+        let transaction = |r#type: &str, amount: &str, fee: Option<&str>| {
+            let mut transaction = crate::fixtures::transaction()
+                .r#type(r#type)
+                .amount(amount, "BTC")
+                .created_at(day(2))
+                .build();
+            transaction.network = fee.map(|fee| crate::private::Network {
+                status: "confirmed".to_string(),
+                name: None,
+                hash: None,
+                transaction_fee: Some(crate::private::Balance {
+                    amount: decimal(fee),
+                    currency: "BTC".to_string(),
+                }),
+                confirmations: None,
+            });
+            transaction
+        };
+        let mut basis = CostBasis::new(LotMethod::Fifo);
+        basis.acquire(day(1), decimal("2"), decimal("100"));
+
+        // Sent to a wallet of the user: only the network fee is disposed of.
+        basis.record(&transaction("send", "-0.6", Some("0.1")), decimal("150"));
+        assert_eq!(basis.disposals().len(), 1);
+        assert_eq!(basis.disposals()[0].amount, decimal("0.1"));
+        assert_eq!(basis.realized_gain(), decimal("5"));
+        assert_eq!(basis.holdings(), decimal("1.4"));
+
+        basis.record(
+            &transaction("exchange_deposit", "-0.4", None),
+            decimal("150"),
+        );
+        assert_eq!(basis.disposals().len(), 1);
+        assert_eq!(basis.holdings(), decimal("1"));
+
+        basis.record(&transaction("sell", "-1", None), decimal("150"));
+        assert_eq!(basis.realized_gain(), decimal("55"));
+        assert!(basis.lots().is_empty());
+    }
+
+    #[cfg(feature = "exchange")]
+    #[test]
+    fn test_record_fill() {
+        let fill = |side: &str, size: &str, price: &str, fee: &str| -> crate::exchange::Fill {
+            serde_json::from_str(&format!(
+                r#"{{"trade_id":1,"order_id":"a","product_id":"BTC-USD","side":"{side}",
+                    "price":"{price}","size":"{size}","fee":"{fee}","liquidity":"T",
+                    "created_at":"2024-01-01T00:00:00Z","settled":true}}"#
+            ))
+            .unwrap()
+        };
+        let mut basis = CostBasis::new(LotMethod::Fifo);
+        basis.record_fill(&fill("buy", "1", "100", "1"));
+        basis.record_fill(&fill("sell", "1", "120", "1.2"));
+        assert_eq!(basis.realized_gain(), decimal("17.8"));
+    }
+
+    #[tokio::test]
+    async fn test_cost_basis() {
+        let transaction = |id: &str, status: &str, amount: &str, date: &str| {
+            format!(
+                r#"{{"id":"{id}","type":"buy","status":"{status}",
+                    "amount":{{"amount":"{amount}","currency":"BTC"}},
+                    "native_amount":{{"amount":"0","currency":"USD"}},
+                    "created_at":"{date}T12:00:00Z","updated_at":null,
+                    "resource":"transaction","resource_path":"/",
+                    "details":{{"title":"","subtitle":""}}}}"#
+            )
+        };
+        let account = Uuid::nil();
+        let mock = MockTransport::new();
+        mock.push_json(
+            &format!("/v2/accounts/{account}/transactions?limit=100"),
+            &format!(
+                r#"{{"data":[{},{},{}]}}"#,
+                transaction(
+                    "9dd482e4-d8ce-46f7-a261-281843bd2855",
+                    "completed",
+                    "-0.5",
+                    "2024-01-02"
+                ),
+                transaction(
+                    "c1c413d1-acf8-4fcb-a8ed-4e2e4820c6f0",
+                    "pending",
+                    "3",
+                    "2024-01-01"
+                ),
+                transaction(
+                    "c1c413d1-acf8-4fcb-a8ed-4e2e4820c6f1",
+                    "completed",
+                    "1",
+                    "2024-01-01"
+                ),
+            ),
+        )
+        .push_json(
+            "/v2/prices/BTC-USD/spot?date=2024-01-01",
+            r#"{"data":{"amount":"100","currency":"USD"}}"#,
+        )
+        .push_json(
+            "/v2/prices/BTC-USD/spot?date=2024-01-02",
+            r#"{"data":{"amount":"140","currency":"USD"}}"#,
+        );
+        let client = Client::new(MAIN_URL)
+            .with_oauth_token("<token>")
+            .with_transport(mock.clone());
+
+        let basis = client
+            .cost_basis(&account, "USD", LotMethod::Fifo)
+            .await
+            .unwrap();
+        assert_eq!(basis.holdings(), decimal("0.5"));
+        assert_eq!(basis.realized_gain(), decimal("20"));
+        assert_eq!(mock.requests().len(), 3);
+    }
+}
@@ -0,0 +1,573 @@
+//! Helpers for testing code built on this crate without touching the network.
+//!
+//! ```
+//! # use coinbase_rs::{testing::MockTransport, Client, MAIN_URL};
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let mock = MockTransport::new();
+//! mock.push_json(
+//!     "/v2/prices/BTC-USD/spot",
+//!     r#"{"data":{"amount":"1020.25","currency":"USD"}}"#,
+//! );
+//!
+//! let client = Client::new(MAIN_URL).with_transport(mock.clone());
+//! let price = client.spot_price("BTC-USD", None).await.unwrap();
+//! assert_eq!(price.amount.to_string(), "1020.25");
+//! assert_eq!(mock.requests()[0].uri().path(), "/v2/prices/BTC-USD/spot");
+//! # }
+//! ```
+//!
+//! A [`Cassette`] records real responses to a fixture file once and replays them in later runs,
+//! and a [`FaultInjector`] makes any transport slow or unreliable to test error handling.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http::{header, HeaderMap, HeaderName, Request, Response, StatusCode};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::compression;
+use crate::transport::{HttpTransport, TransportError, TransportErrorKind};
+
+/// Headers whose values are replaced by [`REDACTED`] in recorded fixtures.
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "cb-access-key",
+    "cb-access-sign",
+    "cb-access-passphrase",
+    "cookie",
+    "set-cookie",
+];
+
+/// Placeholder stored instead of credentials.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Transport answering requests with responses queued in advance.
+///
+/// Responses are queued per path and returned in order. A response queued for a path with a
+/// query string, such as the `next_uri` of a page, only answers that exact request; otherwise
+/// the query is ignored. Requests nothing was queued for are answered with a 404 in the format
+/// of Coinbase errors. Clones share their queues and recorded requests.
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    responses: HashMap<String, VecDeque<Response<Bytes>>>,
+    requests: Vec<Request<Bytes>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `response` for the next request to `path`.
+    pub fn push(&self, path: &str, response: Response<Bytes>) -> &Self {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .responses
+            .entry(path.to_string())
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// Queues a successful JSON response for the next request to `path`.
+    pub fn push_json(&self, path: &str, body: &str) -> &Self {
+        self.push_status(path, StatusCode::OK, body)
+    }
+
+    /// Queues a JSON response with the given status for the next request to `path`.
+    pub fn push_status(&self, path: &str, status: StatusCode, body: &str) -> &Self {
+        self.push(path, json_response(status, body))
+    }
+
+    /// Returns the requests sent so far, oldest first.
+    pub fn requests(&self) -> Vec<Request<Bytes>> {
+        let inner = self.inner.lock().unwrap();
+        inner.requests.iter().map(clone_request).collect()
+    }
+
+    /// Whether every queued response has been consumed.
+    pub fn is_empty(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.responses.values().all(VecDeque::is_empty)
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn send(
+        &self,
+        request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>> {
+        let mut inner = self.inner.lock().unwrap();
+        let uri = request.uri().clone();
+        let response = [uri.path_and_query().map(|p| p.as_str()), Some(uri.path())]
+            .into_iter()
+            .flatten()
+            .find_map(|key| inner.responses.get_mut(key)?.pop_front())
+            .unwrap_or_else(|| {
+                let message = format!("no response queued for {}", uri.path());
+                let body = serde_json::json!({ "message": message });
+                json_response(StatusCode::NOT_FOUND, &body.to_string())
+            });
+        inner.requests.push(request);
+        Box::pin(futures::future::ready(Ok(response)))
+    }
+}
+
+fn clone_request(request: &Request<Bytes>) -> Request<Bytes> {
+    let mut clone = Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+fn json_response(status: StatusCode, body: &str) -> Response<Bytes> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Bytes::copy_from_slice(body.as_bytes()))
+        .unwrap()
+}
+
+/// VCR-style transport recording interactions to a fixture file, or replaying them from it.
+///
+/// In record mode requests are sent with another transport and every interaction is appended to
+/// the file as JSON, with compressed bodies decoded and credentials replaced by [`REDACTED`]. In
+/// replay mode each request is answered with the first interaction not replayed yet with the same
+/// method, path and query, regardless of the host, so a run is deterministic and offline.
+pub struct Cassette {
+    path: PathBuf,
+    recorder: Option<Arc<dyn HttpTransport>>,
+    state: Mutex<Tape>,
+}
+
+#[derive(Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    played: Vec<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedRequest {
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Cassette {
+    /// Sends requests with `transport` and records them to `path`, replacing its content.
+    pub fn record<T: HttpTransport + 'static>(path: impl AsRef<Path>, transport: T) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            recorder: Some(Arc::new(transport)),
+            state: Mutex::new(Tape::default()),
+        }
+    }
+
+    /// Answers requests with the interactions recorded in `path`.
+    pub fn replay(path: impl AsRef<Path>) -> io::Result<Self> {
+        let interactions: Vec<Interaction> = serde_json::from_slice(&fs::read(&path)?)?;
+        let played = vec![false; interactions.len()];
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            recorder: None,
+            state: Mutex::new(Tape {
+                interactions,
+                played,
+            }),
+        })
+    }
+
+    /// Whether every recorded interaction has been replayed.
+    pub fn is_exhausted(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .played
+            .iter()
+            .all(|played| *played)
+    }
+
+    async fn record_one(
+        &self,
+        transport: &dyn HttpTransport,
+        request: Request<Bytes>,
+    ) -> Result<Response<Bytes>, TransportError> {
+        let recorded = RecordedRequest {
+            method: request.method().to_string(),
+            uri: path_and_query(&request),
+            headers: redact(request.headers()),
+            body: String::from_utf8_lossy(request.body()).into_owned(),
+        };
+        let (mut parts, body) = transport.send(request).await?.into_parts();
+        let body = compression::decompress(&mut parts, body)
+            .map_err(|e| TransportError::new(TransportErrorKind::Other, e))?;
+        let interaction = Interaction {
+            request: recorded,
+            response: RecordedResponse {
+                status: parts.status.as_u16(),
+                headers: redact(&parts.headers),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            },
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.interactions.push(interaction);
+        state.played.push(true);
+        let json = serde_json::to_vec_pretty(&state.interactions)
+            .map_err(|e| TransportError::new(TransportErrorKind::Other, e))?;
+        fs::write(&self.path, json)
+            .map_err(|e| TransportError::new(TransportErrorKind::Other, e))?;
+        Ok(Response::from_parts(parts, body))
+    }
+
+    fn replay_one(&self, request: &Request<Bytes>) -> Result<Response<Bytes>, TransportError> {
+        let method = request.method().as_str();
+        let uri = path_and_query(request);
+        let mut state = self.state.lock().unwrap();
+        let Tape {
+            interactions,
+            played,
+        } = &mut *state;
+        let index = (0..interactions.len())
+            .find(|&i| {
+                !played[i]
+                    && interactions[i].request.method == method
+                    && interactions[i].request.uri == uri
+            })
+            .ok_or_else(|| {
+                let message = format!("no recorded interaction for {} {}", method, uri);
+                TransportError::new(TransportErrorKind::Other, message)
+            })?;
+        played[index] = true;
+
+        let recorded = &interactions[index].response;
+        let mut builder = Response::builder().status(recorded.status);
+        for (name, value) in &recorded.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(Bytes::from(recorded.body.clone()))
+            .map_err(|e| TransportError::new(TransportErrorKind::Other, e))
+    }
+}
+
+impl HttpTransport for Cassette {
+    fn send(
+        &self,
+        request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>> {
+        Box::pin(async move {
+            match &self.recorder {
+                Some(transport) => self.record_one(transport.as_ref(), request).await,
+                None => self.replay_one(&request),
+            }
+        })
+    }
+}
+
+fn path_and_query(request: &Request<Bytes>) -> String {
+    let uri = request.uri();
+    uri.path_and_query()
+        .map_or_else(|| uri.path().to_string(), |p| p.to_string())
+}
+
+fn redact(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = if is_sensitive(name) {
+                REDACTED
+            } else {
+                value.to_str().ok()?
+            };
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn is_sensitive(name: &HeaderName) -> bool {
+    SENSITIVE_HEADERS.contains(&name.as_str())
+}
+
+/// Transport injecting latencies and failures into the requests sent with another transport.
+///
+/// Each request independently gets delayed with the probability given to
+/// [`latency`](Self::latency), then fails with the probabilities given to
+/// [`connection_reset`](Self::connection_reset), [`rate_limited`](Self::rate_limited) and
+/// [`server_error`](Self::server_error), or else is sent with the wrapped transport. Faults are
+/// drawn from a seeded random generator, so the same seed and requests fail the same way in
+/// every run.
+///
+/// ```
+/// use coinbase_rs::testing::{FaultInjector, MockTransport};
+/// use coinbase_rs::{Client, MAIN_URL};
+///
+/// let faults = FaultInjector::new(MockTransport::new())
+///     .seed(7)
+///     .rate_limited(0.1)
+///     .server_error(0.05);
+/// let client = Client::new(MAIN_URL).with_transport(faults);
+/// ```
+pub struct FaultInjector {
+    transport: Arc<dyn HttpTransport>,
+    latency: (f64, Duration),
+    connection_reset: f64,
+    rate_limited: f64,
+    server_error: f64,
+    rng: Mutex<StdRng>,
+}
+
+/// Fault drawn for one request.
+enum Fault {
+    ConnectionReset,
+    RateLimited,
+    ServerError,
+}
+
+impl FaultInjector {
+    /// Wraps `transport` without injecting anything until rates are set.
+    pub fn new<T: HttpTransport + 'static>(transport: T) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            latency: (0.0, Duration::ZERO),
+            connection_reset: 0.0,
+            rate_limited: 0.0,
+            server_error: 0.0,
+            rng: Mutex::new(StdRng::seed_from_u64(0)),
+        }
+    }
+
+    /// Seed of the random generator drawing the faults, 0 by default.
+    pub fn seed(self, seed: u64) -> Self {
+        let mut _self = self;
+        _self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        _self
+    }
+
+    /// Delays the given fraction of requests by `delay`.
+    pub fn latency(self, rate: f64, delay: Duration) -> Self {
+        let mut _self = self;
+        _self.latency = (rate.clamp(0.0, 1.0), delay);
+        _self
+    }
+
+    /// Fails the given fraction of requests as if the connection was reset.
+    pub fn connection_reset(self, rate: f64) -> Self {
+        let mut _self = self;
+        _self.connection_reset = rate.clamp(0.0, 1.0);
+        _self
+    }
+
+    /// Answers the given fraction of requests with a 429, without a `Retry-After` header.
+    pub fn rate_limited(self, rate: f64) -> Self {
+        let mut _self = self;
+        _self.rate_limited = rate.clamp(0.0, 1.0);
+        _self
+    }
+
+    /// Answers the given fraction of requests with a 503.
+    pub fn server_error(self, rate: f64) -> Self {
+        let mut _self = self;
+        _self.server_error = rate.clamp(0.0, 1.0);
+        _self
+    }
+
+    /// Draws whether a request is delayed and how it fails.
+    fn draw(&self) -> (Option<Duration>, Option<Fault>) {
+        let mut rng = self.rng.lock().unwrap();
+        let (rate, delay) = self.latency;
+        let delay = rng.gen_bool(rate).then_some(delay);
+        let sample: f64 = rng.gen();
+        let faults = [
+            (self.connection_reset, Fault::ConnectionReset),
+            (self.rate_limited, Fault::RateLimited),
+            (self.server_error, Fault::ServerError),
+        ];
+        let mut threshold = 0.0;
+        let fault = faults.into_iter().find_map(|(rate, fault)| {
+            threshold += rate;
+            (sample < threshold).then_some(fault)
+        });
+        (delay, fault)
+    }
+}
+
+impl HttpTransport for FaultInjector {
+    fn send(
+        &self,
+        request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>> {
+        let (delay, fault) = self.draw();
+        Box::pin(async move {
+            if let Some(delay) = delay {
+                crate::rt::sleep(delay).await;
+            }
+            match fault {
+                Some(Fault::ConnectionReset) => Err(TransportError::new(
+                    TransportErrorKind::Incomplete,
+                    io::Error::from(io::ErrorKind::ConnectionReset),
+                )),
+                Some(Fault::RateLimited) => Ok(json_response(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    r#"{"message":"injected rate limit"}"#,
+                )),
+                Some(Fault::ServerError) => Ok(json_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    r#"{"message":"injected server error"}"#,
+                )),
+                None => self.transport.send(request).await,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use http::Method;
+
+    use crate::retry::NoRetry;
+    use crate::{CBError, Client, MAIN_URL};
+
+    #[tokio::test]
+    async fn test_pages_and_errors() {
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/currencies",
+            r#"{"pagination":{"limit":1,"order":"asc","next_uri":"/v2/currencies?starting_after=BTC"},
+                "data":[{"id":"BTC","name":"Bitcoin","min_size":"0.00000001"}]}"#,
+        )
+        .push_json(
+            "/v2/currencies?starting_after=BTC",
+            r#"{"pagination":{"limit":1,"order":"asc","next_uri":null},
+                "data":[{"id":"ETH","name":"Ether","min_size":"0.00000001"}]}"#,
+        );
+
+        let client = Client::new(MAIN_URL)
+            .with_retry_policy(NoRetry)
+            .with_transport(mock.clone());
+        let pages: Vec<_> = client.currencies().try_collect().await.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1][0].id, "ETH");
+        assert!(mock.is_empty());
+        assert_eq!(mock.requests().len(), 2);
+
+        match client.current_time().await {
+            Err(CBError::Coinbase { status, error }) => {
+                assert_eq!(status, StatusCode::NOT_FOUND);
+                assert_eq!(error.to_string(), "no response queued for /v2/time");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cassette_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("coinbase-cassette-{}.json", std::process::id()));
+        let mock = MockTransport::new();
+        mock.push_json(
+            "/v2/prices/BTC-USD/spot",
+            r#"{"data":{"amount":"1020.25","currency":"USD"}}"#,
+        );
+
+        let recorder = Cassette::record(&path, mock);
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://api.coinbase.com/v2/prices/BTC-USD/spot")
+            .header(header::AUTHORIZATION, "Bearer secret-token")
+            .body(Bytes::new())
+            .unwrap();
+        recorder.send(request).await.unwrap();
+        let fixture = fs::read_to_string(&path).unwrap();
+        assert!(!fixture.contains("secret-token"));
+        assert!(fixture.contains(REDACTED));
+
+        let cassette = Cassette::replay(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let client = Client::new("https://api.sandbox.coinbase.com")
+            .with_retry_policy(NoRetry)
+            .with_transport(cassette);
+        let price = client.spot_price("BTC-USD", None).await.unwrap();
+        assert_eq!(price.currency, "USD");
+        assert!(matches!(
+            client.spot_price("BTC-USD", None).await,
+            Err(CBError::Http(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fault_injection() {
+        let spot = r#"{"data":{"amount":"1020.25","currency":"USD"}}"#;
+        let outcomes = |seed| async move {
+            let mock = MockTransport::new();
+            for _ in 0..50 {
+                mock.push_json("/v2/prices/BTC-USD/spot", spot);
+            }
+            let faults = FaultInjector::new(mock)
+                .seed(seed)
+                .latency(0.5, Duration::from_millis(100))
+                .connection_reset(0.2)
+                .rate_limited(0.2)
+                .server_error(0.2);
+            let client = Client::new(MAIN_URL)
+                .with_retry_policy(NoRetry)
+                .with_rate_limit(None)
+                .with_transport(faults);
+            let mut outcomes = Vec::new();
+            for _ in 0..50 {
+                outcomes.push(match client.spot_price("BTC-USD", None).await {
+                    Ok(_) => "ok",
+                    Err(CBError::Http(e)) if e.kind() == TransportErrorKind::Incomplete => "reset",
+                    Err(CBError::RateLimited { retry_after: None }) => "429",
+                    Err(CBError::Coinbase { status, .. })
+                        if status == StatusCode::SERVICE_UNAVAILABLE =>
+                    {
+                        "503"
+                    }
+                    other => panic!("unexpected {:?}", other),
+                });
+            }
+            outcomes
+        };
+
+        let start = crate::rt::Instant::now();
+        let first = outcomes(42).await;
+        assert!(start.elapsed() >= Duration::from_millis(1000));
+        assert_eq!(first, outcomes(42).await);
+        for outcome in ["ok", "reset", "429", "503"] {
+            assert!(first.contains(&outcome), "no {} in {:?}", outcome, first);
+        }
+    }
+}
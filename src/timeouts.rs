@@ -0,0 +1,89 @@
+use std::future::Future;
+use std::time::Duration;
+
+use crate::{CBError, Result};
+
+/// Limits on how long the client waits for the server.
+///
+/// `connect` bounds establishing a TCP connection, `request` bounds a single HTTP request
+/// including reading its body, and `total` bounds a call including all of its retries. A
+/// request that runs out of time fails with `CBError::Timeout`; those exceeding `connect` or
+/// `request` are retried according to the retry policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeouts {
+    pub(crate) connect: Option<Duration>,
+    pub(crate) request: Option<Duration>,
+    pub(crate) total: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Some(Duration::from_secs(10)),
+            request: Some(Duration::from_secs(30)),
+            total: None,
+        }
+    }
+}
+
+impl Timeouts {
+    /// No limits at all.
+    pub fn none() -> Self {
+        Self {
+            connect: None,
+            request: None,
+            total: None,
+        }
+    }
+
+    pub fn connect(self, connect: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.connect = connect;
+        _self
+    }
+
+    pub fn request(self, request: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.request = request;
+        _self
+    }
+
+    pub fn total(self, total: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.total = total;
+        _self
+    }
+}
+
+/// Runs `future` to completion, failing with `CBError::Timeout` if it takes longer than
+/// `duration`.
+pub(crate) async fn with_timeout<T, F>(duration: Option<Duration>, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match duration {
+        Some(duration) => crate::rt::timeout(duration, future)
+            .await
+            .unwrap_or(Err(CBError::Timeout)),
+        None => future.await,
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod test {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_with_timeout() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        };
+        let result = with_timeout(Some(Duration::from_secs(1)), slow).await;
+        assert!(matches!(result, Err(CBError::Timeout)));
+
+        let fast = async { Ok(42) };
+        let result = with_timeout(Some(Duration::from_secs(1)), fast).await;
+        assert_eq!(result.unwrap(), 42);
+    }
+}
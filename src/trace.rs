@@ -0,0 +1,83 @@
+//! Instrumentation of requests, compiled down to nothing unless the `tracing` feature is
+//! enabled.
+
+use std::time::Duration;
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+use http::{Method, StatusCode, Uri};
+
+use crate::CBError;
+
+/// Span covering one logical request, including all of its retries.
+pub(crate) struct RequestSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+impl RequestSpan {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(method: &Method, uri: &Uri) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(
+                "coinbase.request",
+                method = %method,
+                path = uri.path(),
+                status = tracing::field::Empty,
+                retries = tracing::field::Empty,
+                duration_ms = tracing::field::Empty,
+            ),
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn status(&self, status: StatusCode) {
+        #[cfg(feature = "tracing")]
+        self.span.record("status", status.as_u16());
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn retry(&self, attempt: u32, delay: Duration, error: &CBError) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            parent: &self.span,
+            attempt,
+            delay_ms = delay.as_millis() as u64,
+            error = %error,
+            "retrying request",
+        );
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn finish(&self, attempts: u32) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("retries", attempts - 1);
+            self.span
+                .record("duration_ms", self.start.elapsed().as_millis() as u64);
+        }
+    }
+}
+
+/// Records that a pagination stream fetched another page.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn page(number: usize, next_uri: Option<&str>) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(page = number, next_uri, "fetched page");
+}
+
+/// Records that a resumable stream requests a page again after a transient error.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn resume(delay: Duration, error: &CBError) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        delay_ms = delay.as_millis() as u64,
+        error = %error,
+        "requesting page again",
+    );
+}
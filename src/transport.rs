@@ -0,0 +1,486 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http::{Request, Response};
+#[cfg(feature = "tokio")]
+use http_body_util::{BodyExt, Full};
+#[cfg(feature = "tokio")]
+use hyper_tls::HttpsConnector;
+#[cfg(feature = "tokio")]
+use hyper_util::client::legacy::{
+    connect::{Connect, HttpConnector},
+    Client,
+};
+#[cfg(feature = "tokio")]
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use thiserror::Error;
+
+#[cfg(feature = "tokio")]
+pub use native_tls::{Certificate, Identity, Protocol};
+
+use crate::proxy::Proxy;
+#[cfg(feature = "tokio")]
+use crate::proxy::ProxyConnector;
+
+/// Sends HTTP requests on behalf of a client.
+///
+/// The crate signs requests, enforces rate limits, timeouts and retries, and parses responses;
+/// a transport only has to deliver a request and return the complete response. Implement this
+/// to run the client on an HTTP stack other than the default [`HyperTransport`].
+pub trait HttpTransport: Send + Sync {
+    fn send(
+        &self,
+        request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>>;
+}
+
+impl<T: HttpTransport + ?Sized> HttpTransport for Arc<T> {
+    fn send(
+        &self,
+        request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>> {
+        (**self).send(request)
+    }
+}
+
+/// What went wrong while talking to the server, used to decide whether a request is retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransportErrorKind {
+    /// No connection could be established, so the request was never sent.
+    Connect,
+    /// The transport gave up waiting for the server.
+    Timeout,
+    /// The connection was closed before the response was complete.
+    Incomplete,
+    Other,
+}
+
+impl fmt::Display for TransportErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TransportErrorKind::Connect => "connection failed",
+            TransportErrorKind::Timeout => "timed out",
+            TransportErrorKind::Incomplete => "connection closed before the response completed",
+            TransportErrorKind::Other => "transport failed",
+        })
+    }
+}
+
+/// Error returned by an [`HttpTransport`].
+#[derive(Debug, Error)]
+#[error("{kind}")]
+pub struct TransportError {
+    kind: TransportErrorKind,
+    #[source]
+    source: Box<dyn StdError + Send + Sync>,
+}
+
+impl TransportError {
+    pub fn new<E>(kind: TransportErrorKind, source: E) -> Self
+    where
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        Self {
+            kind,
+            source: source.into(),
+        }
+    }
+
+    pub fn kind(&self) -> TransportErrorKind {
+        self.kind
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<hyper_util::client::legacy::Error> for TransportError {
+    fn from(error: hyper_util::client::legacy::Error) -> Self {
+        let kind = if error.is_connect() {
+            TransportErrorKind::Connect
+        } else {
+            match error
+                .source()
+                .and_then(|e| e.downcast_ref::<hyper::Error>())
+            {
+                Some(e) if e.is_timeout() => TransportErrorKind::Timeout,
+                Some(e) if e.is_incomplete_message() => TransportErrorKind::Incomplete,
+                _ => TransportErrorKind::Other,
+            }
+        };
+        Self::new(kind, error)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<hyper::Error> for TransportError {
+    fn from(error: hyper::Error) -> Self {
+        let kind = if error.is_timeout() {
+            TransportErrorKind::Timeout
+        } else if error.is_incomplete_message() {
+            TransportErrorKind::Incomplete
+        } else {
+            TransportErrorKind::Other
+        };
+        Self::new(kind, error)
+    }
+}
+
+/// Connection settings of the default [`HyperTransport`].
+///
+/// By default HTTP/2 is negotiated with servers that support it, so concurrent requests share a
+/// single TLS connection, and idle connections are kept for 90 seconds so that paginated
+/// requests reuse them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+pub struct ConnectionOptions {
+    http2: bool,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: usize,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            http2: true,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            pool_max_idle_per_host: 16,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            http2_keep_alive_interval: None,
+        }
+    }
+}
+
+impl ConnectionOptions {
+    /// Whether HTTP/2 is offered during the TLS handshake, otherwise HTTP/1.1 is always used.
+    pub fn http2(self, http2: bool) -> Self {
+        let mut _self = self;
+        _self.http2 = http2;
+        _self
+    }
+
+    /// How long an idle connection is kept in the pool, `None` keeps it indefinitely.
+    pub fn pool_idle_timeout(self, timeout: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.pool_idle_timeout = timeout;
+        _self
+    }
+
+    /// Maximum number of idle connections kept per host.
+    pub fn pool_max_idle_per_host(self, max: usize) -> Self {
+        let mut _self = self;
+        _self.pool_max_idle_per_host = max;
+        _self
+    }
+
+    /// Interval of TCP keepalive probes on idle connections, `None` disables them.
+    pub fn tcp_keepalive(self, interval: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.tcp_keepalive = interval;
+        _self
+    }
+
+    /// Interval of HTTP/2 pings keeping connections alive, `None` disables them.
+    pub fn http2_keep_alive_interval(self, interval: Option<Duration>) -> Self {
+        let mut _self = self;
+        _self.http2_keep_alive_interval = interval;
+        _self
+    }
+}
+
+/// TLS settings of the default [`HyperTransport`].
+///
+/// By default the system trust store is used and no client certificate is presented.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Default)]
+pub struct TlsOptions {
+    root_certificates: Vec<Certificate>,
+    identity: Option<Identity>,
+    min_protocol_version: Option<Protocol>,
+    danger_accept_invalid_certs: bool,
+}
+
+#[cfg(feature = "tokio")]
+impl fmt::Debug for TlsOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsOptions")
+            .field("root_certificates", &self.root_certificates.len())
+            .field("identity", &self.identity.is_some())
+            .field("min_protocol_version", &self.min_protocol_version)
+            .field(
+                "danger_accept_invalid_certs",
+                &self.danger_accept_invalid_certs,
+            )
+            .finish()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl TlsOptions {
+    /// Trusts `certificate` in addition to the system trust store.
+    pub fn root_certificate(self, certificate: Certificate) -> Self {
+        let mut _self = self;
+        _self.root_certificates.push(certificate);
+        _self
+    }
+
+    /// Presents `identity` as client certificate, for mutual TLS.
+    pub fn identity(self, identity: Identity) -> Self {
+        let mut _self = self;
+        _self.identity = Some(identity);
+        _self
+    }
+
+    /// Oldest TLS version accepted, `None` uses the TLS library's default.
+    pub fn min_protocol_version(self, version: Option<Protocol>) -> Self {
+        let mut _self = self;
+        _self.min_protocol_version = version;
+        _self
+    }
+
+    /// Accepts any server certificate, including expired and self-signed ones. Only meant for
+    /// test servers.
+    pub fn danger_accept_invalid_certs(self, accept: bool) -> Self {
+        let mut _self = self;
+        _self.danger_accept_invalid_certs = accept;
+        _self
+    }
+}
+
+/// The default transport, built on hyper and hyper-util's pooled client with native TLS.
+///
+/// The connector is a type parameter so that connections can be made by other means, e.g. to a
+/// test server on a unix socket, through a custom DNS resolver or with client certificates.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug)]
+pub struct HyperTransport<C = HttpsConnector<ProxyConnector>> {
+    client: Client<C, Full<Bytes>>,
+}
+
+#[cfg(feature = "tokio")]
+impl Default for HyperTransport {
+    fn default() -> Self {
+        Self::new(
+            None,
+            None,
+            &ConnectionOptions::default(),
+            &TlsOptions::default(),
+        )
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl HyperTransport {
+    /// Creates a transport giving up on connecting after `connect_timeout` and tunnelling
+    /// connections through `proxy` if given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system TLS library can't be initialized.
+    pub fn new(
+        connect_timeout: Option<Duration>,
+        proxy: Option<Proxy>,
+        options: &ConnectionOptions,
+        tls: &TlsOptions,
+    ) -> Self {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(connect_timeout);
+        http.set_keepalive(options.tcp_keepalive);
+        let connector = ProxyConnector::new(http, proxy);
+
+        let alpn: &[&str] = if options.http2 {
+            &["h2", "http/1.1"]
+        } else {
+            &["http/1.1"]
+        };
+        let mut builder = native_tls::TlsConnector::builder();
+        builder
+            .request_alpns(alpn)
+            .min_protocol_version(tls.min_protocol_version)
+            .danger_accept_invalid_certs(tls.danger_accept_invalid_certs);
+        for certificate in &tls.root_certificates {
+            builder.add_root_certificate(certificate.clone());
+        }
+        if let Some(identity) = &tls.identity {
+            builder.identity(identity.clone());
+        }
+        let tls = builder.build().expect("failed to initialize TLS");
+        let https = HttpsConnector::from((connector, tls.into()));
+
+        Self::with_connector(https, options)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<C> HyperTransport<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// Creates a transport opening connections with `connector`, pooled according to
+    /// `options`. The connector is responsible for TLS.
+    pub fn with_connector(connector: C, options: &ConnectionOptions) -> Self {
+        let mut builder = Client::builder(TokioExecutor::new());
+        builder
+            .timer(TokioTimer::new())
+            .pool_timer(TokioTimer::new())
+            .pool_idle_timeout(options.pool_idle_timeout)
+            .pool_max_idle_per_host(options.pool_max_idle_per_host);
+        if options.http2 {
+            builder
+                .http2_keep_alive_interval(options.http2_keep_alive_interval)
+                .http2_keep_alive_while_idle(options.http2_keep_alive_interval.is_some());
+        }
+        Self::from_client(builder.build(connector))
+    }
+
+    /// Creates a transport sending requests with an already configured client.
+    pub fn from_client(client: Client<C, Full<Bytes>>) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<C> HttpTransport for HyperTransport<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    fn send(
+        &self,
+        request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>> {
+        Box::pin(async move {
+            let response = self.client.request(request.map(Full::new)).await?;
+            let (parts, body) = response.into_parts();
+            let body = body.collect().await?.to_bytes();
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+/// Creates the transport of a client not given one, [`HyperTransport`] with the given settings.
+#[cfg(feature = "tokio")]
+pub(crate) fn default_transport(
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    options: &ConnectionOptions,
+    tls: &TlsOptions,
+) -> Arc<dyn HttpTransport> {
+    Arc::new(HyperTransport::new(connect_timeout, proxy, options, tls))
+}
+
+/// Creates the transport of a client not given one. Without the `tokio` feature there is no
+/// default transport, so every request fails until one is supplied.
+#[cfg(not(feature = "tokio"))]
+pub(crate) fn default_transport(
+    _connect_timeout: Option<Duration>,
+    _proxy: Option<Proxy>,
+    _options: &ConnectionOptions,
+) -> Arc<dyn HttpTransport> {
+    Arc::new(NoTransport)
+}
+
+/// Transport failing every request, used until a client has a transport.
+pub(crate) struct NoTransport;
+
+impl HttpTransport for NoTransport {
+    fn send(
+        &self,
+        _request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>> {
+        let error = TransportError::new(
+            TransportErrorKind::Other,
+            "no transport configured, enable the `tokio` feature or supply one with `with_transport`",
+        );
+        Box::pin(futures::future::ready(Err(error)))
+    }
+}
+
+/// Transport sending requests with a [`reqwest::Client`], so its connection pool and
+/// configuration can be shared with the rest of an application.
+#[cfg(feature = "reqwest")]
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for TransportError {
+    fn from(error: reqwest::Error) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if error.is_connect() {
+            return Self::new(TransportErrorKind::Connect, error);
+        }
+        let kind = if error.is_timeout() {
+            TransportErrorKind::Timeout
+        } else if error.is_body() {
+            TransportErrorKind::Incomplete
+        } else {
+            TransportErrorKind::Other
+        };
+        Self::new(kind, error)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpTransport for ReqwestTransport {
+    fn send(
+        &self,
+        request: Request<Bytes>,
+    ) -> BoxFuture<'_, Result<Response<Bytes>, TransportError>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            Box::pin(reqwest_send(self.client.clone(), request))
+        }
+        // Futures of the fetch API are not `Send`, so they run on the browser's event loop and
+        // only the response crosses back.
+        #[cfg(target_arch = "wasm32")]
+        {
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            let client = self.client.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = sender.send(reqwest_send(client, request).await);
+            });
+            Box::pin(async move {
+                receiver.await.unwrap_or_else(|_| {
+                    Err(TransportError::new(
+                        TransportErrorKind::Incomplete,
+                        "request was dropped",
+                    ))
+                })
+            })
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+async fn reqwest_send(
+    client: reqwest::Client,
+    request: Request<Bytes>,
+) -> Result<Response<Bytes>, TransportError> {
+    let request = reqwest::Request::try_from(request)?;
+    let response = client.execute(request).await?;
+    let mut builder = Response::builder().status(response.status());
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        builder = builder.version(response.version());
+    }
+    if let Some(headers) = builder.headers_mut() {
+        *headers = response.headers().clone();
+    }
+    let body = response.bytes().await?;
+    builder
+        .body(body)
+        .map_err(|e| TransportError::new(TransportErrorKind::Other, e))
+}
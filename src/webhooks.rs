@@ -0,0 +1,140 @@
+//! Verification of webhook notifications.
+//!
+//! Coinbase signs the body of every notification with its private key and sends the base64
+//! encoded signature in the `CB-SIGNATURE` header. The matching public key is published at
+//! <https://www.coinbase.com/coinbase.pub>.
+//!
+//! ```no_run
+//! use coinbase_rs::private::NotificationEvent;
+//! use coinbase_rs::webhooks::WebhookVerifier;
+//!
+//! # fn handle(signature: &str, body: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+//! let verifier = WebhookVerifier::new(&std::fs::read_to_string("coinbase.pub")?)?;
+//! let notification = verifier.verify(signature, body)?;
+//! if let NotificationEvent::NewPayment { payment, .. } = notification.event()? {
+//!     println!("received {} {}", payment.amount.amount, payment.amount.currency);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::sha2::Sha256;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use thiserror::Error;
+
+use crate::private::Notification;
+
+/// Name of the header carrying the signature of a notification.
+pub const SIGNATURE_HEADER: &str = "CB-SIGNATURE";
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("invalid public key: {0}")]
+    InvalidKey(String),
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("invalid notification")]
+    Payload(#[from] serde_json::Error),
+}
+
+/// Checks that notifications were sent by Coinbase before deserializing them.
+#[derive(Clone, Debug)]
+pub struct WebhookVerifier {
+    key: VerifyingKey<Sha256>,
+}
+
+impl WebhookVerifier {
+    /// Creates a verifier trusting the PEM encoded RSA public key `pem`.
+    pub fn new(pem: &str) -> Result<Self, WebhookError> {
+        let key = RsaPublicKey::from_public_key_pem(pem)
+            .map_err(|e| WebhookError::InvalidKey(e.to_string()))?;
+        Ok(Self {
+            key: VerifyingKey::new(key),
+        })
+    }
+
+    /// Verifies the `CB-SIGNATURE` header value `signature` against the raw request `body`.
+    pub fn verify_signature(&self, signature: &str, body: &[u8]) -> Result<(), WebhookError> {
+        let signature =
+            base64::decode(signature.trim()).map_err(|_| WebhookError::InvalidSignature)?;
+        let signature = Signature::try_from(signature.as_slice())
+            .map_err(|_| WebhookError::InvalidSignature)?;
+        self.key
+            .verify(body, &signature)
+            .map_err(|_| WebhookError::InvalidSignature)
+    }
+
+    /// Verifies the signature of `body` and deserializes the notification it carries.
+    pub fn verify(&self, signature: &str, body: &[u8]) -> Result<Notification, WebhookError> {
+        self.verify_signature(signature, body)?;
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::pkcs8::{EncodePublicKey, LineEnding};
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::RsaPrivateKey;
+
+    use super::*;
+    use crate::private::Transaction;
+
+    const BODY: &str = r#"{
+  "id": "6bf0ca21-0b2f-5e8a-b95e-7bd7eaccc338",
+  "type": "wallet:buys:completed",
+  "data": {
+    "id": "9dd482e4-d8ce-46f7-a261-281843bd2855",
+    "type": "buy",
+    "status": "completed",
+    "amount": {"amount": "1.00000000", "currency": "BTC"},
+    "native_amount": {"amount": "10.00", "currency": "USD"},
+    "created_at": "2015-03-26T13:42:00-07:00",
+    "updated_at": "2015-03-26T15:55:45-07:00",
+    "resource": "transaction",
+    "resource_path": "/v2/accounts/af6fd33a-e20c-494a-b3f6-f91d204af4b7/transactions/9dd482e4-d8ce-46f7-a261-281843bd2855",
+    "details": {"title": "Bought bitcoin", "subtitle": "using Capital One Bank"}
+  },
+  "user": {"id": "f01c821e-bb35-555f-a4da-548672963119", "resource": "user", "resource_path": "/v2/users/f01c821e-bb35-555f-a4da-548672963119"},
+  "account": {"id": "8d5f086c-d7d5-58ee-890e-c09b3d8d4434", "resource": "account", "resource_path": "/v2/accounts/8d5f086c-d7d5-58ee-890e-c09b3d8d4434"},
+  "delivery_attempts": 0,
+  "created_at": "2015-11-10T19:15:06Z",
+  "resource": "notification",
+  "resource_path": "/v2/notifications/6bf0ca21-0b2f-5e8a-b95e-7bd7eaccc338"
+}"#;
+
+    #[test]
+    fn test_verify() {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let pem = private_key
+            .to_public_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+        let signature = SigningKey::<Sha256>::new(private_key).sign(BODY.as_bytes());
+        let signature = base64::encode(&signature.to_bytes());
+        let verifier = WebhookVerifier::new(&pem).unwrap();
+
+        let notification = verifier.verify(&signature, BODY.as_bytes()).unwrap();
+        assert_eq!(notification.r#type, "wallet:buys:completed");
+        let transaction: Transaction = notification.data_as().unwrap();
+        assert_eq!(transaction.status, "completed");
+
+        let tampered = BODY.replace("1.00000000", "9.00000000");
+        assert!(matches!(
+            verifier.verify(&signature, tampered.as_bytes()),
+            Err(WebhookError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verifier.verify("not base64!", BODY.as_bytes()),
+            Err(WebhookError::InvalidSignature)
+        ));
+        assert!(matches!(
+            WebhookVerifier::new("nope"),
+            Err(WebhookError::InvalidKey(_))
+        ));
+    }
+}